@@ -0,0 +1,71 @@
+/*
+ * C interface to the rs2 CCSDS Reed-Solomon decoder.
+ *
+ * Build the library with
+ * `cargo rustc --lib --release --features ffi --crate-type cdylib,staticlib`
+ * and link against librs2.so or librs2.a. See src/ffi.rs for details.
+ */
+#ifndef RS2_H
+#define RS2_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RS2_SUCCESS 0
+#define RS2_ERR_NULL -1
+#define RS2_ERR_INVALID_ARG -2
+
+#define RS2_MAX_INTERLEAVE 8
+
+typedef enum {
+    RS2_STATE_OK = 0,
+    RS2_STATE_CORRECTED = 1,
+    RS2_STATE_UNCORRECTABLE = 2,
+    RS2_STATE_NOT_PERFORMED = 3,
+} rs2_state;
+
+typedef enum {
+    RS2_REASON_NONE = 0,
+    RS2_REASON_TOO_MANY_ERRORS = 1,
+    RS2_REASON_LOCATOR_DEGREE_MISMATCH = 2,
+    RS2_REASON_ZERO_ERROR_MAGNITUDE_DENOMINATOR = 3,
+    RS2_REASON_RESIDUAL_SYNDROME = 4,
+    RS2_REASON_INVALID_LENGTH = 5,
+    RS2_REASON_INVALID_INTERLEAVE = 6,
+    RS2_REASON_INVALID_ERASURES = 7,
+    RS2_REASON_INVALID_VIRTUAL_FILL = 8,
+    RS2_REASON_ERROR_IN_VIRTUAL_FILL = 9,
+    RS2_REASON_UNDETECTABLE_INTERLEAVE = 10,
+    RS2_REASON_PARITY_MISMATCH = 11,
+} rs2_reason;
+
+typedef struct {
+    rs2_state state;
+    int32_t num_corrected;
+    rs2_reason reason;
+} rs2_result;
+
+typedef struct {
+    uint8_t interleave;
+    rs2_result messages[RS2_MAX_INTERLEAVE];
+} rs2_codeblock_result;
+
+/* Correct a 255 byte RS(255,223) message. output must be 255 bytes and may equal input. */
+int rs2_correct_message(const uint8_t *input, size_t len, uint8_t *output, rs2_result *result);
+
+/* Correct an interleaved codeblock of len = interleave * 255 bytes. */
+int rs2_correct_codeblock(const uint8_t *input, size_t len, uint8_t interleave, uint8_t *output,
+                          rs2_codeblock_result *result);
+
+/* Encode 223 bytes of data into a 255 byte RS(255,223) message. */
+int rs2_encode_message(const uint8_t *data, size_t len, uint8_t *output);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RS2_H */
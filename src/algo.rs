@@ -0,0 +1,480 @@
+//! Building blocks of the RS decoder, for custom decode flows such as erasure
+//! handling or diagnostics.
+//!
+//! All functions work on messages in conventional basis; see [crate::dual_basis]. The
+//! complete decode performed by [crate::Code::correct_message] is:
+//!
+//! ```
+//! use rs2::algo::{calc_syndromes, correct_errata, find_error_locator, find_errors};
+//! use rs2::{dual_basis, Code};
+//!
+//! let code = Code::E16;
+//! let mut msg = dual_basis::to_conv(&code.encode_message(&[7u8; 223]).unwrap());
+//! let expected = msg.clone();
+//! msg[10] ^= 0x42;
+//! msg[200] ^= 0x01;
+//!
+//! let synd = calc_syndromes(&msg, code.parity_len(), code.fcr());
+//! let errloc = find_error_locator(&synd, code.parity_len(), 0);
+//! let mut errpos = vec![0i32; errloc.len() - 1];
+//! assert_eq!(find_errors(&errloc.reversed(), &mut errpos), errpos.len());
+//! correct_errata(&mut msg, &synd, &errpos, code.fcr()).unwrap();
+//!
+//! assert_eq!(msg, expected);
+//! ```
+use crate::{gf, UncorrectableReason, N, PARITY_LEN};
+
+/// Maximum number of coefficients of any polynomial used while decoding. The errata
+/// locator has at most [PARITY_LEN] roots and the syndrome polynomial has
+/// [PARITY_LEN] + 1 coefficients.
+const MAX_POLY: usize = PARITY_LEN + 2;
+
+/// Fixed capacity polynomial, highest degree coefficient first, allowing the decoder
+/// to work entirely on the stack. Coefficients are accessed by dereferencing to a
+/// slice.
+#[derive(Debug, Clone, Copy)]
+pub struct Poly {
+    coef: [u8; MAX_POLY],
+    len: usize,
+}
+
+impl Poly {
+    /// Create a polynomial from coefficients, highest degree first.
+    ///
+    /// # Panics
+    /// If there are more than [PARITY_LEN] + 2 coefficients.
+    pub fn new(coef: &[u8]) -> Self {
+        let mut p = Poly::zeros(coef.len());
+        p.coef[..coef.len()].copy_from_slice(coef);
+        p
+    }
+
+    pub(crate) fn zeros(len: usize) -> Self {
+        Poly {
+            coef: [0u8; MAX_POLY],
+            len,
+        }
+    }
+
+    /// Multiply by x and add `c`.
+    pub(crate) fn push(&mut self, c: u8) {
+        self.coef[self.len] = c;
+        self.len += 1;
+    }
+
+    /// The polynomial with coefficients in the opposite order, i.e., lowest degree
+    /// first.
+    pub fn reversed(&self) -> Self {
+        let mut p = *self;
+        p.coef[..p.len].reverse();
+        p
+    }
+
+    /// Multiply by `x` in place.
+    pub(crate) fn scale_in_place(&mut self, x: u8) {
+        for c in self.coef[..self.len].iter_mut() {
+            *c = gf::mult(*c, x);
+        }
+    }
+
+    /// Add `other` multiplied by `x` in place, without a temporary for the product.
+    pub(crate) fn add_scaled(&mut self, other: &Poly, x: u8) {
+        if other.len > self.len {
+            let shift = other.len - self.len;
+            self.coef.copy_within(..self.len, shift);
+            self.coef[..shift].fill(0);
+            self.len = other.len;
+        }
+        let offset = self.len - other.len;
+        for (c, o) in self.coef[offset..self.len].iter_mut().zip(other.iter()) {
+            *c ^= gf::mult(*o, x);
+        }
+    }
+
+    /// Multiply by `a * x + 1` in place.
+    pub(crate) fn mult_linear(&mut self, a: u8) {
+        self.push(0);
+        for k in (1..self.len).rev() {
+            self.coef[k] = gf::mult(self.coef[k], a) ^ self.coef[k - 1];
+        }
+        self.coef[0] = gf::mult(self.coef[0], a);
+    }
+
+    /// Product modulo x^n, i.e., only the lowest `n` coefficients of the product.
+    pub(crate) fn mult_mod_xn(&self, other: &Poly, n: usize) -> Self {
+        let len = self.len + other.len - 1;
+        let mut p = Poly::zeros(n);
+        for (k, c) in p.coef[..n].iter_mut().enumerate() {
+            let m = len - n + k;
+            for (i, a) in self.iter().enumerate() {
+                if m >= i && m - i < other.len {
+                    *c ^= gf::mult(*a, other[m - i]);
+                }
+            }
+        }
+        p
+    }
+
+    pub(crate) fn trim_leading_zeros(&mut self) {
+        let zeros = self.iter().take_while(|c| **c == 0).count();
+        self.coef.copy_within(zeros..self.len, 0);
+        self.len -= zeros;
+    }
+}
+
+impl core::ops::Deref for Poly {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.coef[..self.len]
+    }
+}
+
+/// Correct `input` in place given the syndromes, as from [calc_syndromes], and the
+/// message positions of all errata, as from [find_errors] plus any erasures, using
+/// Forney's algorithm.
+///
+/// `input` must be at most 255 symbols, `synd` at most [PARITY_LEN] + 1 syndromes as
+/// produced by [calc_syndromes], and `errpos` at most [PARITY_LEN] positions within
+/// `input`, otherwise `input` is not modified and an error is returned.
+pub fn correct_errata(
+    input: &mut [u8],
+    synd: &[u8],
+    errpos: &[i32],
+    fcr: i32,
+) -> Result<(), UncorrectableReason> {
+    if input.len() > N as usize {
+        return Err(UncorrectableReason::InvalidLength {
+            got: input.len(),
+            want: N as usize,
+        });
+    }
+    if synd.len() > PARITY_LEN + 1 {
+        return Err(UncorrectableReason::InvalidLength {
+            got: synd.len(),
+            want: PARITY_LEN + 1,
+        });
+    }
+    let valid = |p: &i32| (0..input.len() as i32).contains(p);
+    if errpos.len() > PARITY_LEN || !errpos.iter().all(valid) {
+        return Err(UncorrectableReason::InvalidErasures);
+    }
+
+    let mut coef_pos = [0i32; PARITY_LEN];
+    let coef_pos = &mut coef_pos[..errpos.len()];
+    for (i, p) in errpos.iter().enumerate() {
+        coef_pos[i] = input.len() as i32 - 1 - p;
+    }
+
+    let errloc = find_errata_locator(coef_pos);
+    let mut rev_synd = Poly::new(synd);
+    rev_synd.coef[..synd.len()].reverse();
+    let erreval = find_error_evaluator(&rev_synd, &errloc, errloc.len() - 1);
+
+    let mut x = [0u8; PARITY_LEN];
+    let x = &mut x[..coef_pos.len()];
+    for (i, p) in coef_pos.iter().enumerate() {
+        x[i] = gf::exp(p - N as i32);
+    }
+
+    let mut magnitudes = [0u8; PARITY_LEN];
+    for (i, xi) in x.iter().enumerate() {
+        let xi_inv = gf::inv(*xi);
+        let mut errloc_prime = 1u8;
+        for (j, xj) in x.iter().enumerate() {
+            if j != i {
+                errloc_prime = gf::mult(errloc_prime, 1 ^ gf::mult(xi_inv, *xj));
+            }
+        }
+
+        let mut y = gf::poly_eval(&erreval, xi_inv);
+        y = gf::mult(gf::pow(*xi, 1i32.wrapping_sub(fcr)), y);
+
+        if errloc_prime == 0 {
+            return Err(UncorrectableReason::ZeroErrorMagnitudeDenominator);
+        }
+
+        magnitudes[i] = gf::div(y, errloc_prime);
+    }
+
+    for (p, e) in errpos.iter().zip(magnitudes.iter()) {
+        input[*p as usize] ^= e;
+    }
+    Ok(())
+}
+
+fn find_errata_locator(errpos: &[i32]) -> Poly {
+    let mut errloc = Poly::new(&[1]);
+    for p in errpos.iter() {
+        errloc.mult_linear(gf::exp(*p));
+    }
+    errloc
+}
+
+fn find_error_evaluator(synd: &Poly, errloc: &Poly, n: usize) -> Poly {
+    synd.mult_mod_xn(errloc, n + 1)
+}
+
+/// Chien search for the message positions of the roots of the (reversed) error
+/// locator, writing them to `errpos`, and returning the total number of roots found,
+/// which may be more than the space available in `errpos`.
+///
+/// A locator of degree `d` that does not have exactly `d` roots indicates more errors
+/// than can be corrected.
+pub fn find_errors(errloc: &[u8], errpos: &mut [i32]) -> usize {
+    // a nonzero locator of degree d has at most d roots, so the search stops once they
+    // are all found
+    let degree = errloc.len().saturating_sub(1);
+    let nonzero = errloc.iter().any(|c| *c != 0);
+    let mut found = 0;
+    gf::find_roots(errloc, |i| {
+        if found < errpos.len() {
+            errpos[found] = N as i32 - 1 - i as i32;
+        }
+        found += 1;
+        !(nonzero && found == degree)
+    });
+    found
+}
+
+/// Berlekamp-Massey computation of the error locator polynomial from the syndromes,
+/// or the Forney syndromes if there are erasures, where `erase_count` is the number
+/// of erasures. The degree of the locator, i.e., its length less one, is the number
+/// of errors, excluding erasures.
+///
+/// At most [PARITY_LEN] iterations are done, and no more than the number of
+/// syndromes provided.
+pub fn find_error_locator(synd: &[u8], parity_len: usize, erase_count: usize) -> Poly {
+    find_error_locator_with(synd, parity_len, erase_count, |_, _, _| {})
+}
+
+/// [find_error_locator], calling `step` with the iteration, its discrepancy, and the
+/// locator after each iteration, e.g., to trace the decode of an uncorrectable
+/// message.
+pub fn find_error_locator_with(
+    synd: &[u8],
+    parity_len: usize,
+    erase_count: usize,
+    mut step: impl FnMut(usize, u8, &[u8]),
+) -> Poly {
+    let mut errloc = Poly::new(&[1]);
+    let mut oldloc = Poly::new(&[1]);
+    let synd_shift = synd.len().saturating_sub(parity_len);
+    let iterations = parity_len
+        .saturating_sub(erase_count)
+        .min(synd.len() - synd_shift)
+        .min(PARITY_LEN);
+    // After iteration i both locators have at most i + 2 coefficients, so every index
+    // below is in bounds and the locators stay within their capacity.
+    for i in 0..iterations {
+        let k = i + synd_shift;
+        let mut delta = synd[k];
+        for j in 1..errloc.len() {
+            delta ^= gf::mult(errloc[errloc.len() - j - 1], synd[k - j]);
+        }
+        oldloc.push(0);
+        if delta != 0 {
+            if oldloc.len() > errloc.len() {
+                core::mem::swap(&mut errloc, &mut oldloc);
+                errloc.scale_in_place(delta);
+                oldloc.scale_in_place(gf::inv(delta));
+            }
+            errloc.add_scaled(&oldloc, delta);
+        }
+        step(i, delta, &errloc);
+    }
+
+    errloc.trim_leading_zeros();
+    errloc
+}
+
+/// Forney syndromes, i.e., the syndromes with the erasures at message positions `pos`
+/// removed, for a message of `nmess` symbols. Syndromes beyond the [PARITY_LEN] + 1
+/// produced by [calc_syndromes] are ignored.
+pub fn forney_syndromes(synd: &[u8], pos: &[i32], nmess: i32) -> Poly {
+    let synd = synd.get(1..).unwrap_or_default();
+    let mut fsynd = Poly::new(&synd[..synd.len().min(PARITY_LEN)]);
+    for p in pos.iter() {
+        let x = gf::exp(nmess.wrapping_sub(1).wrapping_sub(*p));
+        for j in 0..fsynd.len().saturating_sub(1) {
+            fsynd.coef[j] = gf::mult(fsynd[j], x) ^ fsynd[j + 1];
+        }
+    }
+    fsynd
+}
+
+/// Polynomial of degree at most [PARITY_LEN] with its coefficients lowest degree
+/// first, padded with zeros, as used by the fixed latency functions
+/// [find_error_locator_fixed] and [correct_errors_fixed].
+pub type FixedPoly = [u8; PARITY_LEN + 1];
+
+fn eval_fixed(p: &FixedPoly, x: u8) -> u8 {
+    p.iter().rev().fold(0, |y, c| gf::mult(y, x) ^ c)
+}
+
+/// Berlekamp-Massey computation of the error locator like [find_error_locator], but
+/// doing the same operations for all syndromes of the same length. Erasures are not
+/// supported.
+pub fn find_error_locator_fixed(synd: &[u8], parity_len: usize) -> FixedPoly {
+    let parity_len = parity_len.min(PARITY_LEN).min(synd.len().saturating_sub(1));
+    let mut errloc = [0u8; PARITY_LEN + 1];
+    errloc[0] = 1;
+    let mut oldloc = errloc;
+    let mut len = 0;
+    for r in 0..parity_len {
+        let mut delta = 0;
+        for j in 0..=r {
+            delta ^= gf::mult(errloc[j], synd[r + 1 - j]);
+        }
+        // both candidates for the next old locator are computed so the work done does
+        // not depend on delta
+        oldloc.copy_within(..PARITY_LEN, 1);
+        oldloc[0] = 0;
+        let mut next = errloc;
+        for (c, b) in next.iter_mut().zip(oldloc.iter()) {
+            *c ^= gf::mult(delta, *b);
+        }
+        let delta_inv = gf::inv(if delta == 0 { 1 } else { delta });
+        let mut scaled = errloc;
+        for c in scaled.iter_mut() {
+            *c = gf::mult(*c, delta_inv);
+        }
+        if delta != 0 && 2 * len <= r {
+            oldloc = scaled;
+            len = r + 1 - len;
+        }
+        errloc = next;
+    }
+    errloc
+}
+
+/// Correct a message in conventional basis in place given its syndromes, as from
+/// [calc_syndromes], and the error locator from [find_error_locator_fixed], returning
+/// the number of roots of the locator.
+///
+/// The locator is evaluated at every message position at its full length, and the
+/// error magnitude is computed for as many roots as the code can correct whether or
+/// not there are that many, so the operations done do not depend on the number or
+/// positions of the errors.
+pub fn correct_errors_fixed(
+    input: &mut [u8; N as usize],
+    synd: &[u8],
+    errloc: &FixedPoly,
+    fcr: i32,
+) -> Result<usize, UncorrectableReason> {
+    let len = synd.len().clamp(1, PARITY_LEN + 1);
+
+    // Chien search, where the locator taken highest degree first is its reverse
+    let mut evals = [0u8; N as usize];
+    gf::poly_eval_many(&errloc[..len], &gf::POWERS, &mut evals);
+    let mut roots = 0;
+    let mut slots = [(0usize, 0u8); PARITY_LEN / 2 + 1];
+    for (i, y) in evals.iter().enumerate() {
+        let is_root = *y == 0;
+        // every position is written so only the used slots depend on the roots
+        slots[roots.min(PARITY_LEN / 2)] = (N as usize - 1 - i, 0u8.wrapping_sub(is_root as u8));
+        roots += is_root as usize;
+    }
+
+    // evaluator, i.e., synd * errloc mod x^len
+    let mut erreval = [0u8; PARITY_LEN + 1];
+    for k in 0..len.min(synd.len()) {
+        for j in 0..=k {
+            erreval[k] ^= gf::mult(errloc[j], synd[k - j]);
+        }
+    }
+    // formal derivative, where only the odd degree terms remain in GF(2^8)
+    let mut deriv = [0u8; PARITY_LEN + 1];
+    for k in (0..PARITY_LEN).step_by(2) {
+        deriv[k] = errloc[k + 1];
+    }
+
+    // Forney's algorithm for the first (len - 1) / 2 roots, where unused slots have a
+    // zero mask
+    let mut zero_denominator = false;
+    for (pos, mask) in slots[..(len - 1) / 2].iter() {
+        let x = gf::exp((N as usize - 1 - pos) as i32);
+        let x_inv = gf::inv(x);
+        let denom = eval_fixed(&deriv, x_inv);
+        let magnitude = gf::mult(
+            gf::pow(x, 2i32.wrapping_sub(fcr)),
+            gf::div(
+                eval_fixed(&erreval, x_inv),
+                if denom == 0 { 1 } else { denom },
+            ),
+        );
+        zero_denominator |= *mask != 0 && denom == 0;
+        input[*pos] ^= magnitude & mask;
+    }
+    if zero_denominator {
+        return Err(UncorrectableReason::ZeroErrorMagnitudeDenominator);
+    }
+    Ok(roots)
+}
+
+/// Syndromes of a message in conventional basis for a code with `parity_len` check
+/// symbols and first consecutive root `fcr`, as given by [crate::Code::parity_len]
+/// and [crate::Code::fcr]. The first coefficient is always zero, followed by the `parity_len`
+/// syndromes, all of which are zero if the message is a valid codeword.
+///
+/// A `parity_len` of more than [PARITY_LEN] is treated as [PARITY_LEN].
+pub fn calc_syndromes(input: &[u8], parity_len: usize, fcr: i32) -> Poly {
+    let parity_len = parity_len.min(PARITY_LEN);
+    let mut synd = Poly::zeros(parity_len + 1);
+    let mut roots = [0u8; PARITY_LEN];
+    for (i, r) in roots[..parity_len].iter_mut().enumerate() {
+        *r = gf::exp((i as i32).wrapping_add(fcr));
+    }
+    gf::poly_eval_many(input, &roots[..parity_len], &mut synd.coef[1..=parity_len]);
+    synd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::FIXTURE_MSG;
+    use crate::{dual_basis, Code};
+
+    #[test]
+    fn test_poly_in_place() {
+        let p = Poly::new(&[3, 0, 7]);
+
+        let mut q = p;
+        q.mult_linear(5);
+        assert_eq!(&q[..], &[gf::mult(3, 5), 3, gf::mult(7, 5), 7]);
+
+        let mut q = p;
+        q.add_scaled(&Poly::new(&[1, 2]), 9);
+        assert_eq!(&q[..], &[3, 9, 7 ^ gf::mult(2, 9)]);
+        let mut q = Poly::new(&[4]);
+        q.add_scaled(&p, 2);
+        assert_eq!(&q[..], &[gf::mult(3, 2), 0, gf::mult(7, 2) ^ 4]);
+    }
+
+    #[test]
+    fn test_erasure_flow() {
+        let code = Code::E16;
+        let expected = dual_basis::to_conv(FIXTURE_MSG);
+        let mut msg = expected.clone();
+        // 2 erasures and 1 error
+        msg[3] ^= 0x11;
+        msg[4] ^= 0x22;
+        msg[100] ^= 0x33;
+        let erasures = [3, 4];
+
+        let synd = calc_syndromes(&msg, code.parity_len(), code.fcr());
+        let fsynd = forney_syndromes(&synd, &erasures, msg.len() as i32);
+        let errloc = find_error_locator(&fsynd, code.parity_len(), erasures.len());
+        assert_eq!(errloc.len() - 1, 1);
+        let mut errpos = [0i32; 1];
+        assert_eq!(find_errors(&errloc.reversed(), &mut errpos), 1);
+        assert_eq!(errpos, [100]);
+
+        let errata = [3, 4, 100];
+        correct_errata(&mut msg, &synd, &errata, code.fcr()).unwrap();
+
+        assert_eq!(msg, expected);
+        let synd = calc_syndromes(&msg, code.parity_len(), code.fcr());
+        assert!(synd.iter().all(|x| *x == 0));
+    }
+}
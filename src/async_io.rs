@@ -0,0 +1,626 @@
+//! Async adapters for correcting codeblocks and CADUs read from a
+//! [tokio::io::AsyncRead] source, e.g., a socket. Requires the `async` feature.
+//!
+//! ```no_run
+//! use rs2::async_io::AsyncPipeline;
+//! use rs2::pipeline::Config;
+//! use tokio::io::AsyncRead;
+//!
+//! async fn run(stream: impl AsyncRead + Unpin) -> std::io::Result<()> {
+//!     let mut pipeline = AsyncPipeline::new(stream, Config::default());
+//!     while let Some(frame) = pipeline.next_frame().await {
+//!         println!("{:?}", frame?.states);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [AsyncPipeline] only reads when the next event is awaited, so a slow consumer
+//! slows the reads. [AsyncStagedPipeline] runs the stages concurrently, connected by
+//! bounded queues: each stage waits for room in its output queue before producing
+//! more, so memory use is bounded by the [QueueDepths] however slow the consumer is,
+//! and the occupancy of each queue is reported by [AsyncStagedPipeline::queues].
+//!
+//! With the `stream` feature both pipelines are a `futures_core::Stream` of events,
+//! and `frames` gives a stream of only the decoded frames, so they compose with
+//! stream combinators and other async libraries.
+//!
+//! ```
+//! # #[cfg(feature = "stream")]
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! use futures_util::TryStreamExt;
+//! use rs2::async_io::AsyncPipeline;
+//! use rs2::pipeline::Config;
+//!
+//! let stream = rs2::gen::cadus(&Config::default(), b"hello world", 0);
+//! let frames: Vec<_> = AsyncPipeline::new(&stream[..], Config::default())
+//!     .frames()
+//!     .try_collect()
+//!     .await
+//!     .unwrap();
+//! assert_eq!(&frames[0].data.as_ref().unwrap()[..11], b"hello world");
+//! # });
+//! ```
+use std::future::{poll_fn, Future};
+use std::io::{ErrorKind, Result};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::task::JoinHandle;
+
+use crate::pipeline::{Config, Event, Frame, QueueDepths};
+use crate::reader::partial_block;
+use crate::sync::{self, Scanner, Step};
+use crate::{Code, Codeblock, N};
+
+const READ_SIZE: usize = 8192;
+
+/// Async equivalent of [crate::reader::RsReader].
+pub struct AsyncRsReader<R> {
+    reader: R,
+    interleave: u8,
+    code: Code,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRsReader<R> {
+    /// Create a reader for codeblocks of `interleave` messages using RS(255,223).
+    pub fn new(reader: R, interleave: u8) -> Self {
+        Self::with_code(reader, interleave, Code::E16)
+    }
+
+    pub fn with_code(reader: R, interleave: u8, code: Code) -> Self {
+        AsyncRsReader {
+            reader,
+            interleave,
+            code,
+            done: false,
+        }
+    }
+
+    async fn read_full(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut total = 0;
+        while total < buf.len() {
+            match self.reader.read(&mut buf[total..]).await {
+                Ok(0) => break,
+                Ok(n) => total += n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(total)
+    }
+
+    /// Read and correct the next codeblock, returning None at the end of the stream.
+    pub async fn next(&mut self) -> Option<Result<Codeblock>> {
+        if self.done {
+            return None;
+        }
+        let mut buf = vec![0u8; self.interleave as usize * N as usize];
+        let zult = match self.read_full(&mut buf).await {
+            Ok(n) if n == buf.len() => {
+                return Some(Ok(self.code.correct_codeblock(&buf, self.interleave)))
+            }
+            Ok(0) => None,
+            Ok(n) => Some(Err(partial_block(n, buf.len()))),
+            Err(err) => Some(Err(err)),
+        };
+        self.done = true;
+        zult
+    }
+}
+
+/// Async equivalent of [crate::pipeline::Pipeline].
+pub struct AsyncPipeline<R> {
+    reader: R,
+    scanner: Scanner,
+    config: Config,
+}
+
+impl<R: AsyncRead + Unpin> AsyncPipeline<R> {
+    /// # Panics
+    /// If [Config::asm] is empty.
+    pub fn new(reader: R, config: Config) -> Self {
+        AsyncPipeline {
+            reader,
+            scanner: Scanner::new(&config.asm, config.block_len(), config.lock)
+                .with_threshold(config.asm_threshold),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Read until the next event, returning None at the end of the stream.
+    pub async fn next(&mut self) -> Option<Result<Event>> {
+        poll_fn(|cx| self.poll_event(cx)).await
+    }
+
+    fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Event>>> {
+        let mut tmp = [0u8; READ_SIZE];
+        loop {
+            match self.scanner.next_event() {
+                Step::Event(event) => {
+                    return Poll::Ready(Some(Ok(Event::from_sync(&self.config, event, None))))
+                }
+                Step::Done => return Poll::Ready(None),
+                Step::NeedData => {
+                    let mut buf = ReadBuf::new(&mut tmp);
+                    match ready!(Pin::new(&mut self.reader).poll_read(cx, &mut buf)) {
+                        Ok(()) => self.scanner.push(buf.filled()),
+                        Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                        Err(err) => return Poll::Ready(Some(Err(err))),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Read until the next decoded frame, ignoring all other events.
+    pub async fn next_frame(&mut self) -> Option<Result<Frame>> {
+        loop {
+            match self.next().await? {
+                Ok(Event::Frame(frame)) => return Some(Ok(frame)),
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Stream only the decoded frames, ignoring all other events. Requires the
+    /// `stream` feature.
+    #[cfg(feature = "stream")]
+    pub fn frames(self) -> impl Stream<Item = Result<Frame>> + Unpin {
+        Frames { events: self }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<R: AsyncRead + Unpin> Stream for AsyncPipeline<R> {
+    type Item = Result<Event>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_event(cx)
+    }
+}
+
+/// Occupancy of a queue between the stages of an [AsyncStagedPipeline].
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct QueueStats {
+    /// Number of items queued.
+    pub len: usize,
+    /// Largest number of items queued so far.
+    pub max_len: usize,
+    /// Maximum number of items queued before the producing stage waits; see
+    /// [QueueDepths].
+    pub capacity: usize,
+}
+
+/// [QueueStats] of each queue of an [AsyncStagedPipeline], named like [QueueDepths].
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct QueueMetrics {
+    /// Sync events waiting to be derandomized.
+    pub sync: QueueStats,
+    /// Derandomized events waiting to be corrected.
+    pub derandomize: QueueStats,
+    /// Decoded events waiting to be consumed.
+    pub output: QueueStats,
+}
+
+/// Number of items in a queue, counted once there is room to send each item and after
+/// each is received, so it never underflows.
+#[derive(Debug)]
+struct Gauge {
+    len: AtomicUsize,
+    max_len: AtomicUsize,
+    capacity: usize,
+}
+
+impl Gauge {
+    fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Gauge {
+            len: AtomicUsize::new(0),
+            max_len: AtomicUsize::new(0),
+            capacity,
+        })
+    }
+
+    fn inc(&self) {
+        let len = self.len.fetch_add(1, Ordering::Relaxed) + 1;
+        self.max_len.fetch_max(len, Ordering::Relaxed);
+    }
+
+    fn dec(&self) {
+        self.len.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> QueueStats {
+        QueueStats {
+            len: self.len.load(Ordering::Relaxed),
+            max_len: self.max_len.load(Ordering::Relaxed),
+            capacity: self.capacity,
+        }
+    }
+}
+
+/// A bounded queue to the next stage.
+struct Queue<T> {
+    tx: Sender<T>,
+    gauge: Arc<Gauge>,
+}
+
+impl<T> Queue<T> {
+    /// Wait for room and send `item`, returning false if the next stage has stopped.
+    async fn send(&self, item: T) -> bool {
+        let Ok(permit) = self.tx.reserve().await else {
+            return false;
+        };
+        self.gauge.inc();
+        permit.send(item);
+        true
+    }
+
+    /// [Queue::send] from a blocking thread of the runtime.
+    fn blocking_send(&self, item: T) -> bool {
+        tokio::runtime::Handle::current().block_on(self.send(item))
+    }
+}
+
+fn queue<T>(depth: usize) -> (Queue<T>, Receiver<T>, Arc<Gauge>) {
+    let (tx, rx) = channel(depth);
+    let gauge = Gauge::new(depth);
+    let queue = Queue {
+        tx,
+        gauge: gauge.clone(),
+    };
+    (queue, rx, gauge)
+}
+
+/// Async equivalent of [crate::pipeline::ThreadedPipeline], where synchronization
+/// runs as a task, and derandomization and RS correction each run on a blocking
+/// thread, connected by bounded queues.
+///
+/// Events are produced in the same order as [AsyncPipeline]. Dropping the pipeline
+/// cancels the read and stops the other stages once they next try to send.
+pub struct AsyncStagedPipeline {
+    rx: Receiver<Result<Event>>,
+    gauges: [Arc<Gauge>; 3],
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl AsyncStagedPipeline {
+    /// Start the stages, with up to `depths` events queued after each.
+    ///
+    /// # Panics
+    /// If not called from within a tokio runtime, [Config::asm] is empty, or any depth
+    /// is 0.
+    pub fn new<R>(reader: R, config: Config, depths: QueueDepths) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let (sync_tx, mut sync_rx, sync_gauge) = queue(depths.sync);
+        let (derand_tx, mut derand_rx, derand_gauge) = queue(depths.derandomize);
+        let (out_tx, rx, out_gauge) = queue(depths.output);
+
+        let scanner = Scanner::new(&config.asm, config.block_len(), config.lock)
+            .with_threshold(config.asm_threshold);
+        let randomizer = config.derandomize.then_some(config.randomizer);
+        let config = Config {
+            derandomize: false,
+            ..config
+        };
+        let (sync_gauge_rx, derand_gauge_rx) = (sync_gauge.clone(), derand_gauge.clone());
+        let tasks = vec![
+            tokio::spawn(synchronize(reader, scanner, sync_tx)),
+            tokio::task::spawn_blocking(move || {
+                while let Some(event) = sync_rx.blocking_recv() {
+                    sync_gauge_rx.dec();
+                    let event = event.map(|mut event| {
+                        if let (sync::Event::Cadu(cadu), Some(randomizer)) =
+                            (&mut event, randomizer)
+                        {
+                            randomizer.apply(&mut cadu.data);
+                        }
+                        event
+                    });
+                    if !derand_tx.blocking_send(event) {
+                        return;
+                    }
+                }
+            }),
+            tokio::task::spawn_blocking(move || {
+                while let Some(event) = derand_rx.blocking_recv() {
+                    derand_gauge_rx.dec();
+                    let event = event.map(|event| Event::from_sync(&config, event, None));
+                    if !out_tx.blocking_send(event) {
+                        return;
+                    }
+                }
+            }),
+        ];
+        AsyncStagedPipeline {
+            rx,
+            gauges: [sync_gauge, derand_gauge, out_gauge],
+            tasks,
+        }
+    }
+
+    /// Wait for the next event, returning None once all stages have finished.
+    ///
+    /// # Panics
+    /// If any stage panicked.
+    pub async fn next(&mut self) -> Option<Result<Event>> {
+        poll_fn(|cx| self.poll_event(cx)).await
+    }
+
+    fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Event>>> {
+        if let Some(event) = ready!(self.rx.poll_recv(cx)) {
+            self.gauges[2].dec();
+            return Poll::Ready(Some(event));
+        }
+        // all stages have finished, so surface any panic rather than silently ending
+        // the stream
+        while let Some(task) = self.tasks.first_mut() {
+            let result = ready!(Pin::new(task).poll(cx));
+            self.tasks.remove(0);
+            if let Err(err) = result {
+                if err.is_panic() {
+                    std::panic::resume_unwind(err.into_panic());
+                }
+            }
+        }
+        Poll::Ready(None)
+    }
+
+    /// Wait for the next decoded frame, ignoring all other events.
+    pub async fn next_frame(&mut self) -> Option<Result<Frame>> {
+        loop {
+            match self.next().await? {
+                Ok(Event::Frame(frame)) => return Some(Ok(frame)),
+                Ok(_) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Stream only the decoded frames, ignoring all other events. Requires the
+    /// `stream` feature.
+    #[cfg(feature = "stream")]
+    pub fn frames(self) -> impl Stream<Item = Result<Frame>> + Unpin {
+        Frames { events: self }
+    }
+
+    /// The current occupancy of each queue.
+    pub fn queues(&self) -> QueueMetrics {
+        let [sync, derandomize, output] = &self.gauges;
+        QueueMetrics {
+            sync: sync.stats(),
+            derandomize: derandomize.stats(),
+            output: output.stats(),
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl Stream for AsyncStagedPipeline {
+    type Item = Result<Event>;
+
+    /// See [AsyncStagedPipeline::next].
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_event(cx)
+    }
+}
+
+/// The decoded frames of a stream of events.
+#[cfg(feature = "stream")]
+struct Frames<S> {
+    events: S,
+}
+
+#[cfg(feature = "stream")]
+impl<S: Stream<Item = Result<Event>> + Unpin> Stream for Frames<S> {
+    type Item = Result<Frame>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match ready!(Pin::new(&mut self.events).poll_next(cx)) {
+                Some(Ok(Event::Frame(frame))) => return Poll::Ready(Some(Ok(frame))),
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl Drop for AsyncStagedPipeline {
+    fn drop(&mut self) {
+        // the read may never complete, e.g., on an idle socket
+        if let Some(task) = self.tasks.first() {
+            task.abort();
+        }
+    }
+}
+
+/// Read and synchronize CADUs, sending the events to `tx` until the end of the
+/// stream, the first read error, or the next stage stopping.
+async fn synchronize<R: AsyncRead + Unpin>(
+    mut reader: R,
+    mut scanner: Scanner,
+    tx: Queue<Result<sync::Event>>,
+) {
+    let mut tmp = vec![0u8; READ_SIZE];
+    loop {
+        let item = match scanner.next_event() {
+            Step::Event(event) => Ok(event),
+            Step::Done => return,
+            Step::NeedData => match reader.read(&mut tmp).await {
+                Ok(n) => {
+                    scanner.push(&tmp[..n]);
+                    continue;
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => Err(err),
+            },
+        };
+        let failed = item.is_err();
+        if !tx.send(item).await || failed {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pn;
+    use crate::tests::{interleave, FIXTURE_MSG};
+    use crate::RSState;
+
+    #[tokio::test]
+    async fn test_async_rsreader() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[0] ^= 0x01;
+        let mut input = interleave(&[FIXTURE_MSG, &corrupt]);
+        input.extend(&FIXTURE_MSG[..10]);
+
+        let mut reader = AsyncRsReader::new(&input[..], 2);
+
+        let block = reader.next().await.unwrap().unwrap();
+        assert_eq!(block.states, vec![RSState::Ok, RSState::Corrected(1)]);
+        let err = reader.next().await.unwrap().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_async_pipeline() {
+        let mut block = FIXTURE_MSG.to_vec();
+        pn::randomize(&mut block);
+        let mut input = vec![0u8; 5];
+        for _ in 0..2 {
+            input.extend(crate::sync::ASM);
+            input.extend(&block);
+        }
+        let config = Config {
+            interleave: 1,
+            ..Default::default()
+        };
+
+        let mut pipeline = AsyncPipeline::new(&input[..], config);
+
+        assert_eq!(
+            pipeline.next().await.unwrap().unwrap(),
+            Event::Skipped(0..5)
+        );
+        for offset in [5, 264] {
+            let frame = pipeline.next_frame().await.unwrap().unwrap();
+            assert_eq!(frame.offset, offset);
+            assert_eq!(frame.data.unwrap(), FIXTURE_MSG[..223]);
+        }
+        assert!(pipeline.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_async_staged_pipeline() {
+        let config = Config {
+            interleave: 1,
+            ..Default::default()
+        };
+        let mut input = vec![0u8; 5];
+        input.extend(crate::gen::cadus(&config, &[3u8; 223 * 20], 0));
+        input[5 + 4 + 100] ^= 0x01;
+        let expected = crate::pipeline::Pipeline::new(&input[..], config.clone())
+            .collect::<Result<Vec<Event>>>()
+            .unwrap();
+        let depths = QueueDepths {
+            sync: 1,
+            derandomize: 2,
+            output: 1,
+        };
+
+        let mut pipeline = AsyncStagedPipeline::new(std::io::Cursor::new(input), config, depths);
+
+        // without a consumer every queue fills, and no more is produced
+        let full = |q: QueueMetrics| {
+            [q.sync, q.derandomize, q.output]
+                .iter()
+                .all(|s| s.len == s.capacity)
+        };
+        for _ in 0..500 {
+            if full(pipeline.queues()) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let queues = pipeline.queues();
+        assert!(full(queues), "{queues:?}");
+        assert_eq!(queues.derandomize.max_len, 2);
+        assert_eq!(queues.output.max_len, 1);
+
+        let mut events = Vec::new();
+        while let Some(event) = pipeline.next().await {
+            events.push(event.unwrap());
+        }
+        assert_eq!(events, expected);
+        let queues = pipeline.queues();
+        assert_eq!((queues.sync.len, queues.output.len), (0, 0));
+        assert_eq!(queues.sync.capacity, 1);
+    }
+
+    #[tokio::test]
+    async fn test_async_staged_pipeline_drop() {
+        // a reader that never completes is cancelled
+        let (reader, _writer) = tokio::io::duplex(64);
+        let mut pipeline =
+            AsyncStagedPipeline::new(reader, Config::default(), QueueDepths::default());
+        let next = tokio::time::timeout(std::time::Duration::from_millis(10), pipeline.next());
+        assert!(next.await.is_err());
+        drop(pipeline);
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn test_streams() {
+        use futures_util::{StreamExt, TryStreamExt};
+
+        let config = Config {
+            interleave: 2,
+            ..Default::default()
+        };
+        let mut input = vec![0u8; 3];
+        input.extend(crate::gen::cadus(&config, &[9u8; 446 * 5], 0));
+        input[3 + 4 + 7] ^= 0x10;
+        let expected = crate::pipeline::Pipeline::new(&input[..], config.clone())
+            .collect::<Result<Vec<Event>>>()
+            .unwrap();
+
+        let events: Vec<Event> = AsyncPipeline::new(&input[..], config.clone())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(events, expected);
+        let reader = std::io::Cursor::new(input.clone());
+        let staged = AsyncStagedPipeline::new(reader, config.clone(), QueueDepths::default());
+        assert_eq!(staged.try_collect::<Vec<Event>>().await.unwrap(), expected);
+
+        let corrected: Vec<u64> = AsyncPipeline::new(&input[..], config.clone())
+            .frames()
+            .map(|frame| frame.unwrap().corrected_bits)
+            .collect()
+            .await;
+        assert_eq!(corrected, [1, 0, 0, 0, 0]);
+        let reader = std::io::Cursor::new(input);
+        let frames = AsyncStagedPipeline::new(reader, config, QueueDepths::default()).frames();
+        assert_eq!(frames.count().await, 5);
+    }
+}
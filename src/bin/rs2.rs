@@ -0,0 +1,1026 @@
+//! Command line interface for CCSDS Reed-Solomon decoding.
+//!
+//! ```text
+//! rs2 decode --interleave 4 input.dat output.dat
+//! rs2 serve --listen 0.0.0.0:5000 --forward downstream:6000
+//! ```
+use std::fs::File;
+use std::io::{
+    self, BufRead, BufReader, BufWriter, ErrorKind, Read, Result, Seek, SeekFrom, Write,
+};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use rs2::checkpoint::Checkpointer;
+use rs2::follow::FollowReader;
+use rs2::gen::CaduWriter;
+use rs2::pipeline::{Config, Event, IdleFilter, Pipeline};
+use rs2::quicklook::{Quicklook, QuicklookSummary, Unsampled};
+use rs2::reader::RsReader;
+use rs2::service::{Service, WriteSink};
+use rs2::sim::Injector;
+use rs2::stats::{DecodeSummary, ErrorHistogram, SliceStats};
+use rs2::sync::{self, LockConfig, Synchronizer};
+use rs2::{gen, interleave, pn, Code, Detection, RSState, N};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Correct a file of CADUs, or raw codeblocks, writing the corrected transfer
+    /// frames without check symbols. Uncorrectable frames are dropped.
+    Decode(DecodeArgs),
+    /// Listen for TCP connections carrying CADU streams, forwarding the corrected
+    /// transfer frames of each connection to a file, stdout, or a downstream TCP
+    /// server.
+    Serve(ServeArgs),
+    /// Check a file of CADUs, or raw codeblocks, for errors without correcting them,
+    /// counting the codeblocks that are clean, have errors, or likely have more
+    /// errors than can be corrected.
+    Check(CheckArgs),
+    /// Encode payload data into CADUs, or raw codeblocks, that decode back to the
+    /// payload, padding the last transfer frame with the fill byte.
+    Encode(EncodeArgs),
+    /// Corrupt a file of CADUs, or raw codeblocks, with a fixed number of random symbol
+    /// errors in each RS message, leaving the ASMs intact.
+    Corrupt(CorruptArgs),
+    /// Print decode quality totals for each file of CADUs, or raw codeblocks, exiting
+    /// with status 2 if the percentage of uncorrectable frames in any file exceeds
+    /// the threshold.
+    Report(ReportArgs),
+    /// Estimate the decode quality of a large file of CADUs quickly, fully decoding
+    /// only every Nth CADU and checking the others for errors.
+    Quicklook(QuicklookArgs),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CodeArg {
+    /// RS(255,223)
+    E16,
+    /// RS(255,239)
+    E8,
+}
+
+impl From<CodeArg> for Code {
+    fn from(code: CodeArg) -> Self {
+        match code {
+            CodeArg::E16 => Code::E16,
+            CodeArg::E8 => Code::E8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum RandomizerArg {
+    /// 255 bit sequence, h(x) = x^8 + x^7 + x^5 + x^3 + 1
+    Pn255,
+    /// 131071 bit sequence, h(x) = x^17 + x^14 + 1
+    Pn131071,
+}
+
+impl From<RandomizerArg> for pn::Sequence {
+    fn from(seq: RandomizerArg) -> Self {
+        match seq {
+            RandomizerArg::Pn255 => pn::Sequence::Pn255,
+            RandomizerArg::Pn131071 => pn::Sequence::Pn131071,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct CodeblockArgs {
+    /// Number of interleaved RS messages in each codeblock.
+    #[arg(
+        short,
+        long,
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u8).range(1..=rs2::MAX_INTERLEAVE as i64),
+    )]
+    interleave: u8,
+    /// RS code used to encode the codeblocks.
+    #[arg(long, value_enum, default_value_t = CodeArg::E16)]
+    code: CodeArg,
+}
+
+#[derive(clap::Args)]
+struct PipelineArgs {
+    #[command(flatten)]
+    codeblock: CodeblockArgs,
+    /// CADUs are not randomized.
+    #[arg(long)]
+    no_derandomize: bool,
+    /// Pseudo-random sequence used to randomize the CADUs.
+    #[arg(long, value_enum, default_value_t = RandomizerArg::Pn255)]
+    randomizer: RandomizerArg,
+    /// Maximum number of bit errors allowed in an ASM.
+    #[arg(long, default_value_t = 0)]
+    asm_threshold: u32,
+    /// Maximum number of errors corrected in each RS message, below what the code can
+    /// correct, to lower the chance of miscorrections. Messages with more errors are
+    /// uncorrectable.
+    #[arg(long)]
+    max_errors: Option<usize>,
+    /// Skip decoding CADUs whose transfer frame is on virtual channel 63, i.e., only
+    /// idle data, counting them separately.
+    #[arg(long)]
+    skip_idle: bool,
+    /// Output the frames of CADUs with uncorrectable messages as received, with the
+    /// correctable messages corrected, rather than dropping them.
+    #[arg(long)]
+    best_effort: bool,
+}
+
+impl PipelineArgs {
+    fn config(&self) -> Config {
+        Config {
+            interleave: self.codeblock.interleave,
+            derandomize: !self.no_derandomize,
+            randomizer: self.randomizer.into(),
+            asm_threshold: self.asm_threshold,
+            code: self.codeblock.code.into(),
+            max_errors: self.max_errors,
+            idle: self.skip_idle.then_some(IdleFilter::Vcid(63)),
+            best_effort: self.best_effort,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct DecodeArgs {
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+    /// Input is back-to-back codeblocks without ASMs or randomization.
+    #[arg(long, conflicts_with_all = ["no_derandomize", "asm_threshold"])]
+    raw: bool,
+    /// Keep reading the input as it grows, e.g., while a pass is being recorded,
+    /// following truncation and rotation.
+    #[arg(long)]
+    follow: bool,
+    /// With --follow, finish once the input has not grown for this many seconds.
+    #[arg(long, requires = "follow")]
+    idle_timeout: Option<f64>,
+    /// Write corrected CADUs, with the ASM reattached and the check symbols
+    /// re-derived, rather than transfer frames.
+    #[arg(long, conflicts_with = "raw")]
+    cadus: bool,
+    /// Save the progress to this file periodically, resuming from it if it exists,
+    /// i.e., continuing the input from where a previous run stopped and appending to
+    /// its output.
+    #[arg(long, conflicts_with_all = ["raw", "follow"])]
+    checkpoint: Option<PathBuf>,
+    input: PathBuf,
+    output: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct CheckArgs {
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+    /// Input is back-to-back codeblocks without ASMs or randomization.
+    #[arg(long, conflicts_with_all = ["no_derandomize", "asm_threshold"])]
+    raw: bool,
+    input: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct EncodeArgs {
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+    /// Output back-to-back codeblocks without ASMs or randomization.
+    #[arg(long, conflicts_with_all = ["no_derandomize", "asm_threshold"])]
+    raw: bool,
+    /// Append a CRC-16 FECF to each transfer frame.
+    #[arg(long)]
+    fecf: bool,
+    /// Byte used to pad the last transfer frame.
+    #[arg(long, default_value_t = 0)]
+    fill: u8,
+    input: PathBuf,
+    output: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct CorruptArgs {
+    #[command(flatten)]
+    codeblock: CodeblockArgs,
+    /// Input is back-to-back codeblocks without ASMs.
+    #[arg(long)]
+    raw: bool,
+    /// Number of distinct symbols corrupted in each RS message.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(0..=255))]
+    errors_per_block: u8,
+    /// Seed for the random error positions and values. The same seed always produces
+    /// the same output.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    input: PathBuf,
+    output: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct ReportArgs {
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+    /// Input is back-to-back codeblocks without ASMs or randomization.
+    #[arg(long, conflicts_with_all = ["no_derandomize", "asm_threshold"])]
+    raw: bool,
+    /// Maximum percentage of uncorrectable frames allowed in each file.
+    #[arg(long, default_value_t = 0.0)]
+    max_uncorrectable: f64,
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct QuicklookArgs {
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+    /// Fully decode one of every this many CADUs.
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+    every: u64,
+    /// Only count the CADUs that are not decoded, rather than checking them for
+    /// errors.
+    #[arg(long)]
+    skip_unsampled: bool,
+    input: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct ServeArgs {
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+    /// Address to listen on, e.g., 0.0.0.0:5000.
+    #[arg(long)]
+    listen: String,
+    /// Track sync lock, extracting CADUs through short ASM dropouts.
+    #[arg(long)]
+    lock: bool,
+    /// Forward frames to a TCP server at this address rather than writing them to
+    /// the output.
+    #[arg(long, conflicts_with = "output")]
+    forward: Option<String>,
+    /// File to append frames to. Defaults to stdout.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Counts {
+    frames: usize,
+    uncorrectable_frames: usize,
+    skipped_bytes: u64,
+    summary: DecodeSummary,
+    histogram: ErrorHistogram,
+    slices: SliceStats,
+}
+
+impl Counts {
+    fn add(&mut self, states: &[RSState], corrected_bits: u64) {
+        self.frames += 1;
+        self.summary.corrected_bits += corrected_bits;
+        if states
+            .iter()
+            .any(|s| matches!(s, RSState::Uncorrectable(_)))
+        {
+            self.uncorrectable_frames += 1;
+        }
+        for state in states {
+            self.summary.add(state, N as usize);
+            self.histogram.add(state);
+        }
+        self.slices.add(states);
+    }
+}
+
+fn decode<R: Read, W: Write>(args: &DecodeArgs, reader: R, writer: &mut W) -> Result<Counts> {
+    decode_stream(
+        args.pipeline.config(),
+        args.raw,
+        args.cadus,
+        reader,
+        writer,
+        None,
+    )
+}
+
+/// Decode CADUs, or raw codeblocks if `raw`, writing the transfer frames, or the
+/// corrected CADUs if `cadus`. CADUs are recorded to `checkpointer`, if any, with the
+/// writer flushed before each save.
+fn decode_stream<R: Read, W: Write>(
+    config: Config,
+    raw: bool,
+    cadus: bool,
+    reader: R,
+    writer: &mut W,
+    mut checkpointer: Option<&mut Checkpointer>,
+) -> Result<Counts> {
+    let mut counts = Counts::default();
+
+    if raw {
+        let frame_len = config.frame_len();
+        let reader = RsReader::with_code(reader, config.interleave, config.code)
+            .max_errors(config.max_errors.unwrap_or(usize::MAX));
+        for block in reader {
+            let block = block?;
+            counts.add(&block.states, block.corrected_bits);
+            if let Some(data) = block.message {
+                writer.write_all(&data[..frame_len])?;
+            }
+        }
+        return Ok(counts);
+    }
+
+    let cadu_len = (config.asm.len() + config.block_len()) as u64;
+    let mut cadu_writer = CaduWriter::new(writer, config.clone());
+    for event in Pipeline::new(reader, config) {
+        let event = event?;
+        let mut written = 0;
+        match &event {
+            Event::Frame(frame) if frame.idle => {
+                counts.frames += 1;
+                counts.summary.idle_frames += 1;
+            }
+            Event::Frame(frame) => {
+                counts.add(&frame.states, frame.corrected_bits);
+                if cadus {
+                    if cadu_writer.write_frame(frame)? {
+                        written = cadu_len;
+                    }
+                } else if let Some(data) = &frame.data {
+                    cadu_writer.get_mut().write_all(data)?;
+                    written = data.len() as u64;
+                }
+            }
+            Event::Skipped(range) => counts.skipped_bytes += range.end - range.start,
+            Event::Locked(_) | Event::Unlocked(_) => {}
+        }
+        if let Some(checkpointer) = checkpointer.as_deref_mut() {
+            checkpointer.add(&event, written);
+            if checkpointer.is_due() {
+                cadu_writer.get_mut().flush()?;
+                checkpointer.save()?;
+            }
+        }
+    }
+    if let Some(checkpointer) = checkpointer {
+        cadu_writer.get_mut().flush()?;
+        checkpointer.save()?;
+    }
+    Ok(counts)
+}
+
+/// Decode like [decode], resuming from and saving progress to the checkpoint file,
+/// returning the counts of all runs.
+fn decode_resumable(args: &DecodeArgs, path: &Path) -> Result<Counts> {
+    let config = args.pipeline.config();
+    let mut checkpointer = Checkpointer::open(path, &config)?;
+    let start = checkpointer.checkpoint().clone();
+    let mut input = File::open(&args.input)?;
+    input.seek(SeekFrom::Start(start.offset))?;
+    let mut output = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&args.output)?;
+    output.set_len(start.output_len)?;
+    output.seek(SeekFrom::End(0))?;
+
+    let mut writer = BufWriter::new(output);
+    let mut counts = decode_stream(
+        config,
+        false,
+        args.cadus,
+        BufReader::new(input),
+        &mut writer,
+        Some(&mut checkpointer),
+    )?;
+    counts.frames += start.frames as usize;
+    counts.skipped_bytes += start.skipped_bytes;
+    counts.summary.merge(&start.summary);
+    Ok(counts)
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct CheckCounts {
+    codeblocks: u64,
+    clean: u64,
+    dirty: u64,
+    uncorrectable: u64,
+    skipped_bytes: u64,
+}
+
+impl CheckCounts {
+    /// Classify a codeblock using [Code::detect_errors] for each message, skipping the
+    /// error magnitudes and correction.
+    fn add(&mut self, code: Code, interleave: u8, block: &[u8]) {
+        self.codeblocks += 1;
+        let mut dirty = false;
+        for msg in interleave::deinterleave(block, interleave) {
+            match code.detect_errors(&msg.to_vec()) {
+                Detection::Clean => {}
+                Detection::Correctable(_) => dirty = true,
+                Detection::Uncorrectable(_) => {
+                    self.uncorrectable += 1;
+                    return;
+                }
+            }
+        }
+        match dirty {
+            true => self.dirty += 1,
+            false => self.clean += 1,
+        }
+    }
+}
+
+fn check<R: BufRead>(args: &CheckArgs, mut reader: R) -> Result<CheckCounts> {
+    let config = args.pipeline.config();
+    let mut counts = CheckCounts::default();
+
+    if args.raw {
+        let mut block = vec![0u8; config.block_len()];
+        while !reader.fill_buf()?.is_empty() {
+            reader.read_exact(&mut block)?;
+            counts.add(config.code, config.interleave, &block);
+        }
+        return Ok(counts);
+    }
+
+    let sync = Synchronizer::with_asm(reader, &config.asm, config.block_len())
+        .threshold(config.asm_threshold);
+    for event in sync {
+        match event? {
+            sync::Event::Cadu(mut cadu) => {
+                if config.derandomize {
+                    config.randomizer.apply(&mut cadu.data);
+                }
+                counts.add(config.code, config.interleave, &cadu.data);
+            }
+            sync::Event::Skipped(range) => counts.skipped_bytes += range.end - range.start,
+            sync::Event::Locked(_) | sync::Event::Unlocked(_) => {}
+        }
+    }
+    Ok(counts)
+}
+
+/// Read until `buf` is full or the stream ends, returning the number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
+
+/// Encode the payload read from `reader`, returning the number of transfer frames.
+fn encode<R: Read, W: Write>(args: &EncodeArgs, mut reader: R, writer: &mut W) -> Result<u64> {
+    let config = Config {
+        fecf: args.fecf,
+        derandomize: !args.raw && !args.pipeline.no_derandomize,
+        ..args.pipeline.config()
+    };
+    let mut chunk = vec![0u8; gen::payload_len(&config)];
+    let mut frames = 0;
+    loop {
+        let len = read_full(&mut reader, &mut chunk)?;
+        if len == 0 {
+            return Ok(frames);
+        }
+        let cadu = gen::cadus(&config, &chunk[..len], args.fill);
+        match args.raw {
+            true => writer.write_all(&cadu[config.asm.len()..])?,
+            false => writer.write_all(&cadu)?,
+        }
+        frames += 1;
+    }
+}
+
+/// Corrupt the back-to-back CADUs, or codeblocks, read from `reader`, returning the
+/// number of codeblocks corrupted. A trailing partial CADU is copied unchanged.
+fn corrupt<R: Read, W: Write>(args: &CorruptArgs, mut reader: R, writer: &mut W) -> Result<u64> {
+    let interleave = args.codeblock.interleave as usize;
+    let asm_len = if args.raw { 0 } else { sync::ASM.len() };
+    let mut cadu = vec![0u8; asm_len + interleave * N as usize];
+    let mut injector = Injector::new(args.seed);
+    let mut errors = [0u8; N as usize];
+    let mut blocks = 0;
+    loop {
+        let len = read_full(&mut reader, &mut cadu)?;
+        if len < cadu.len() {
+            writer.write_all(&cadu[..len])?;
+            return Ok(blocks);
+        }
+        let block = &mut cadu[asm_len..];
+        for i in 0..interleave {
+            errors.fill(0);
+            injector.symbol_errors(&mut errors, args.errors_per_block as usize);
+            for (j, x) in errors.iter().enumerate() {
+                block[j * interleave + i] ^= x;
+            }
+        }
+        writer.write_all(&cadu)?;
+        blocks += 1;
+    }
+}
+
+fn percent(count: u64, total: u64) -> f64 {
+    match total {
+        0 => 0.0,
+        n => 100.0 * count as f64 / n as f64,
+    }
+}
+
+/// Format the report for a single file.
+fn report_lines(name: &str, counts: &Counts) -> String {
+    let summary = &counts.summary;
+    let messages = summary.total();
+    format!(
+        "{name}: frames={} idle_frames={} uncorrectable_frames={} ({:.2}%) skipped_bytes={}\n\
+         {name}: messages={messages} ok={:.2}% corrected={:.2}% uncorrectable={:.2}% corrected_symbols={} corrected_bits={}\n\
+         {name}: histogram {}\n\
+         {name}: slices {}",
+        counts.frames,
+        summary.idle_frames,
+        counts.uncorrectable_frames,
+        percent(counts.uncorrectable_frames as u64, counts.frames as u64),
+        counts.skipped_bytes,
+        percent(summary.ok, messages),
+        percent(summary.corrected, messages),
+        percent(summary.uncorrectable, messages),
+        summary.corrected_symbols,
+        summary.corrected_bits,
+        counts.histogram,
+        counts.slices,
+    )
+}
+
+/// Print the report for each input, returning whether all were within the threshold.
+fn report(args: &ReportArgs) -> Result<bool> {
+    let mut pass = true;
+    for input in &args.inputs {
+        let reader = BufReader::new(File::open(input)?);
+        let counts = decode_stream(
+            args.pipeline.config(),
+            args.raw,
+            false,
+            reader,
+            &mut io::sink(),
+            None,
+        )?;
+        println!("{}", report_lines(&input.display().to_string(), &counts));
+        let uncorrectable = percent(counts.uncorrectable_frames as u64, counts.frames as u64);
+        if uncorrectable > args.max_uncorrectable {
+            eprintln!(
+                "{}: {uncorrectable:.2}% uncorrectable frames exceeds {}%",
+                input.display(),
+                args.max_uncorrectable
+            );
+            pass = false;
+        }
+    }
+    Ok(pass)
+}
+
+fn quicklook<R: Read>(args: &QuicklookArgs, reader: R) -> Result<QuicklookSummary> {
+    let config = args.pipeline.config();
+    let sync = Synchronizer::with_asm(reader, &config.asm, config.block_len())
+        .threshold(config.asm_threshold);
+    let unsampled = match args.skip_unsampled {
+        true => Unsampled::Skip,
+        false => Unsampled::Check,
+    };
+    let mut quicklook = Quicklook::new(config, args.every).unsampled(unsampled);
+    for event in sync {
+        quicklook.add(event?);
+    }
+    Ok(quicklook.into_summary())
+}
+
+fn serve(args: &ServeArgs) -> Result<()> {
+    let config = Config {
+        lock: args.lock.then(LockConfig::default),
+        ..args.pipeline.config()
+    };
+    let service = Service::bind(&args.listen, config)?;
+    let writer: Box<dyn Write> = match (&args.forward, &args.output) {
+        (Some(addr), _) => Box::new(TcpStream::connect(addr)?),
+        (None, Some(path)) => Box::new(File::options().create(true).append(true).open(path)?),
+        (None, None) => Box::new(std::io::stdout()),
+    };
+    let mut sink = WriteSink::new(BufWriter::new(writer));
+    eprintln!("listening on {}", service.local_addr()?);
+    loop {
+        let (stream, peer) = service.accept()?;
+        eprintln!("{peer}: connected");
+        let stats = service.handle(stream, &mut sink)?;
+        if let Some(err) = &stats.error {
+            eprintln!("{peer}: {err}");
+        }
+        eprintln!(
+            "{peer}: disconnected frames={} skipped_bytes={} {}",
+            stats.frames, stats.skipped_bytes, stats.summary
+        );
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode> {
+    match cli.command {
+        Command::Decode(args) => {
+            let counts = match &args.checkpoint {
+                Some(path) => decode_resumable(&args, path)?,
+                None => {
+                    let reader: Box<dyn Read> = match args.follow {
+                        true => Box::new(
+                            FollowReader::open(&args.input)?
+                                .idle_timeout(args.idle_timeout.map(Duration::from_secs_f64)),
+                        ),
+                        false => Box::new(BufReader::new(File::open(&args.input)?)),
+                    };
+                    let mut writer = BufWriter::new(File::create(&args.output)?);
+                    let counts = decode(&args, reader, &mut writer)?;
+                    writer.flush()?;
+                    counts
+                }
+            };
+            eprintln!(
+                "frames={} skipped_bytes={} {}",
+                counts.frames, counts.skipped_bytes, counts.summary
+            );
+        }
+        Command::Serve(args) => serve(&args)?,
+        Command::Encode(args) => {
+            let reader = BufReader::new(File::open(&args.input)?);
+            let mut writer = BufWriter::new(File::create(&args.output)?);
+            let frames = encode(&args, reader, &mut writer)?;
+            writer.flush()?;
+            eprintln!("frames={frames}");
+        }
+        Command::Report(args) => {
+            if !report(&args)? {
+                return Ok(ExitCode::from(2));
+            }
+        }
+        Command::Corrupt(args) => {
+            let reader = BufReader::new(File::open(&args.input)?);
+            let mut writer = BufWriter::new(File::create(&args.output)?);
+            let blocks = corrupt(&args, reader, &mut writer)?;
+            writer.flush()?;
+            eprintln!("codeblocks={blocks}");
+        }
+        Command::Quicklook(args) => {
+            let summary = quicklook(&args, BufReader::new(File::open(&args.input)?))?;
+            println!("{summary}");
+        }
+        Command::Check(args) => {
+            let counts = check(&args, BufReader::new(File::open(&args.input)?))?;
+            println!(
+                "codeblocks={} clean={} dirty={} uncorrectable={} skipped_bytes={}",
+                counts.codeblocks,
+                counts.clean,
+                counts.dirty,
+                counts.uncorrectable,
+                counts.skipped_bytes
+            );
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(extra: &[&str]) -> DecodeArgs {
+        let mut argv = vec!["rs2", "decode"];
+        argv.extend(extra);
+        argv.extend(["in.dat", "out.dat"]);
+        match Cli::parse_from(argv).command {
+            Command::Decode(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_decode_raw() {
+        // Zero codewords are valid codewords
+        let mut input = vec![0u8; 2 * 255 * 2];
+        input[3] = 0xff;
+        input[600] = 0xff;
+        let mut output = Vec::new();
+
+        let counts = decode(
+            &args(&["--raw", "--interleave", "2"]),
+            &input[..],
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(counts.frames, 2);
+        assert_eq!(counts.skipped_bytes, 0);
+        assert_eq!(counts.summary.ok, 2);
+        assert_eq!(counts.summary.corrected, 2);
+        assert_eq!(counts.summary.corrected_symbols, 2);
+        assert_eq!(output, vec![0u8; 2 * 446]);
+
+        input[5] = 0xff;
+        let mut output = Vec::new();
+        let counts = decode(
+            &args(&["--raw", "--interleave", "2", "--max-errors", "1"]),
+            &input[..],
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(counts.summary.uncorrectable, 1);
+        assert_eq!(output, vec![0u8; 446]);
+    }
+
+    #[test]
+    fn test_decode_cadus() {
+        let mut block = vec![0u8; 255];
+        rs2::pn::randomize(&mut block);
+        let mut input = vec![0x55u8; 3];
+        for _ in 0..2 {
+            input.extend(rs2::sync::ASM);
+            input.extend(&block);
+        }
+        let mut output = Vec::new();
+
+        let counts = decode(&args(&["-i", "1"]), &input[..], &mut output).unwrap();
+
+        assert_eq!(counts.frames, 2);
+        assert_eq!(counts.skipped_bytes, 3);
+        assert_eq!(output, vec![0u8; 2 * 223]);
+    }
+
+    #[test]
+    fn test_args() {
+        let argv = [
+            "rs2",
+            "decode",
+            "--raw",
+            "--no-derandomize",
+            "in.dat",
+            "out.dat",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+
+        let argv = [
+            "rs2",
+            "serve",
+            "--listen",
+            "0.0.0.0:5000",
+            "--lock",
+            "-i",
+            "2",
+        ];
+        let Command::Serve(args) = Cli::parse_from(argv).command else {
+            panic!("expected serve");
+        };
+        assert_eq!(args.listen, "0.0.0.0:5000");
+        assert!(args.lock);
+        assert_eq!(args.pipeline.config().interleave, 2);
+        assert_eq!(args.pipeline.config().idle, None);
+        assert!(!args.pipeline.config().best_effort);
+        let args = self::args(&["--skip-idle", "--best-effort"]);
+        assert_eq!(args.pipeline.config().idle, Some(IdleFilter::Vcid(63)));
+        assert!(args.pipeline.config().best_effort);
+        let argv = [
+            "rs2",
+            "serve",
+            "--listen",
+            ":5000",
+            "--forward",
+            "x:1",
+            "--output",
+            "f",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let config = Config {
+            interleave: 2,
+            ..Default::default()
+        };
+        let payload = vec![0x33u8; 3 * config.frame_len()];
+        let mut input = rs2::gen::cadus(&config, &payload, 0);
+        input[4 + 1000 + 10] ^= 0x01;
+        for x in &mut input[2 * 514 + 4..][..40] {
+            *x ^= 0xff;
+        }
+        let argv = ["rs2", "check", "-i", "2", "in.dat"];
+        let Command::Check(args) = Cli::parse_from(argv).command else {
+            panic!("expected check");
+        };
+
+        let counts = check(&args, &input[..]).unwrap();
+
+        assert_eq!(
+            counts,
+            CheckCounts {
+                codeblocks: 3,
+                clean: 1,
+                dirty: 1,
+                uncorrectable: 1,
+                skipped_bytes: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_quicklook() {
+        let config = Config {
+            interleave: 2,
+            ..Default::default()
+        };
+        let mut input = rs2::gen::cadus(&config, &[0x33u8; 4 * 446], 0);
+        input[4 + 10] ^= 0x01;
+        input[514 + 4 + 10] ^= 0x01;
+        for extra in [&[][..], &["--skip-unsampled"]] {
+            let mut argv = vec!["rs2", "quicklook", "-i", "2", "--every", "2"];
+            argv.extend(extra);
+            argv.push("in.dat");
+            let Command::Quicklook(args) = Cli::parse_from(argv).command else {
+                panic!("expected quicklook");
+            };
+
+            let summary = quicklook(&args, &input[..]).unwrap();
+
+            assert_eq!(summary.cadus, 4);
+            assert_eq!(summary.decoded_frames, 2);
+            assert_eq!(summary.decoded_with_errors, 1);
+            match extra.is_empty() {
+                true => assert_eq!((summary.checked, summary.checked_with_errors), (2, 1)),
+                false => assert_eq!(summary.skipped, 2),
+            }
+        }
+        assert!(Cli::try_parse_from(["rs2", "quicklook", "--every", "0", "in.dat"]).is_err());
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let payload: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        for extra in [&[][..], &["--raw"], &["--no-derandomize", "--code", "e8"]] {
+            let mut argv = vec!["rs2", "encode", "-i", "2", "--fill", "7"];
+            argv.extend(extra);
+            argv.extend(["in.bin", "out.cadu"]);
+            let Command::Encode(args) = Cli::parse_from(argv).command else {
+                panic!("expected encode");
+            };
+            let mut encoded = Vec::new();
+
+            let frames = encode(&args, &payload[..], &mut encoded).unwrap();
+
+            let mut argv = vec!["rs2", "decode", "-i", "2"];
+            argv.extend(extra);
+            let mut output = Vec::new();
+            let counts = decode(&self::args(&argv[2..]), &encoded[..], &mut output).unwrap();
+            assert_eq!(counts.frames as u64, frames, "{extra:?}");
+            assert_eq!(counts.summary.ok, 2 * frames);
+            assert_eq!(&output[..1000], &payload[..]);
+            assert!(output[1000..].iter().all(|&x| x == 7));
+        }
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let config = Config {
+            interleave: 2,
+            ..Default::default()
+        };
+        let mut input = gen::cadus(&config, &[0x42; 1000], 0);
+        input.extend([1, 2, 3]);
+        let argv = [
+            "rs2",
+            "corrupt",
+            "-i",
+            "2",
+            "--errors-per-block",
+            "5",
+            "--seed",
+            "3",
+        ];
+        let Command::Corrupt(corrupt_args) =
+            Cli::parse_from(argv.iter().chain(&["a", "b"])).command
+        else {
+            panic!("expected corrupt");
+        };
+        let mut output = Vec::new();
+
+        let blocks = corrupt(&corrupt_args, &input[..], &mut output).unwrap();
+
+        assert_eq!(blocks, 3);
+        assert_eq!(output.len(), input.len());
+        assert_eq!(&output[output.len() - 3..], &[1, 2, 3]);
+        let diffs = input.iter().zip(&output).filter(|(a, b)| a != b).count();
+        assert_eq!(diffs, 3 * 2 * 5);
+        let mut again = Vec::new();
+        corrupt(&corrupt_args, &input[..], &mut again).unwrap();
+        assert_eq!(again, output);
+
+        // a partial CADU is not decoded, so drop it to decode the last full one
+        let mut decoded = Vec::new();
+        let cadus = &output[..output.len() - 3];
+        let counts = decode(&args(&["-i", "2"]), cadus, &mut decoded).unwrap();
+        assert_eq!(counts.summary.corrected, 6);
+        assert_eq!(counts.summary.corrected_symbols, 30);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut counts = Counts::default();
+        counts.add(&[RSState::Ok, RSState::Corrected(2)], 3);
+        counts.add(
+            &[
+                RSState::Corrected(2),
+                RSState::Uncorrectable(rs2::UncorrectableReason::ResidualSyndrome),
+            ],
+            5,
+        );
+        counts.skipped_bytes = 7;
+
+        assert_eq!(
+            report_lines("f.dat", &counts),
+            "f.dat: frames=2 idle_frames=0 uncorrectable_frames=1 (50.00%) skipped_bytes=7\n\
+             f.dat: messages=4 ok=25.00% corrected=50.00% uncorrectable=25.00% corrected_symbols=4 corrected_bits=8\n\
+             f.dat: histogram 0:1 2:2\n\
+             f.dat: slices corrected_symbols=2,2 uncorrectable=0,1"
+        );
+    }
+
+    #[test]
+    fn test_decode_cadus_output() {
+        let config = Config::default();
+        let clean = gen::cadus(&config, &[0x24; 2000], 0);
+        let mut input = clean.clone();
+        input[50] ^= 0x80;
+        input[1024 + 900] ^= 0x01;
+        let mut output = Vec::new();
+
+        let counts = decode(&args(&["--cadus"]), &input[..], &mut output).unwrap();
+
+        assert_eq!(counts.summary.corrected_symbols, 2);
+        assert_eq!(output, clean);
+    }
+
+    #[test]
+    fn test_decode_resumable() {
+        let dir = std::env::temp_dir().join(format!("rs2-resume-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = Config::default();
+        let payload: Vec<u8> = (0..5 * 892).map(|i| (i % 251) as u8).collect();
+        let stream = gen::cadus(&config, &payload, 0);
+        let (input, output) = (dir.join("in.dat"), dir.join("out.dat"));
+        let state = dir.join("state");
+        let argv = ["rs2", "decode", "--checkpoint"]
+            .map(String::from)
+            .into_iter();
+        let argv = argv.chain([&state, &input, &output].map(|p| p.display().to_string()));
+        let Command::Decode(args) = Cli::parse_from(argv).command else {
+            panic!("expected decode");
+        };
+
+        // a run over the first 3 CADUs, then output written after its last checkpoint
+        std::fs::write(&input, &stream[..3 * 1024]).unwrap();
+        let counts = decode_resumable(&args, &state).unwrap();
+        assert_eq!(counts.frames, 3);
+        File::options()
+            .append(true)
+            .open(&output)
+            .unwrap()
+            .write_all(&[0; 100])
+            .unwrap();
+
+        std::fs::write(&input, &stream).unwrap();
+        let counts = decode_resumable(&args, &state).unwrap();
+
+        assert_eq!(counts.frames, 5);
+        assert_eq!(counts.summary.ok, 20);
+        assert_eq!(std::fs::read(&output).unwrap(), payload);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
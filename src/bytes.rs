@@ -0,0 +1,129 @@
+//! Decoding of CADUs held in reference-counted [Bytes] buffers. Requires the `bytes`
+//! feature.
+//!
+//! Network services often receive CADUs into a [BytesMut], e.g., from a tokio codec.
+//! [decode_cadu] derandomizes and corrects the codeblock in that buffer, and the
+//! transfer frame of the resulting [SharedFrame] is a view of the same memory, so
+//! it can be passed on to further stages without being copied.
+//!
+//! ```
+//! use bytes::BytesMut;
+//! use rs2::bytes::decode_cadu;
+//! use rs2::pipeline::Config;
+//!
+//! let config = Config::default();
+//! let cadu = BytesMut::from(&rs2::gen::cadus(&config, b"hello world", 0)[..]);
+//! let frame = decode_cadu(&config, 0, cadu);
+//! assert_eq!(&frame.data.unwrap()[..11], b"hello world");
+//! ```
+use ::bytes::{Bytes, BytesMut};
+
+use crate::pipeline::{self, Config, Frame};
+use crate::{RSState, UncorrectableReason};
+
+/// A [Frame] whose transfer frame shares the buffer the CADU was received in.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SharedFrame {
+    /// The decode results. Its data is always None, as the transfer frame is in
+    /// [SharedFrame::data].
+    pub frame: Frame,
+    /// The corrected transfer frame without check symbols, like [Frame::data].
+    pub data: Option<Bytes>,
+}
+
+impl From<SharedFrame> for Frame {
+    /// Convert to a [Frame], copying the transfer frame.
+    fn from(shared: SharedFrame) -> Self {
+        Frame {
+            data: shared.data.map(|data| data.to_vec()),
+            ..shared.frame
+        }
+    }
+}
+
+/// Derandomize and correct a CADU, with or without its ASM, in place.
+///
+/// `offset` is reported as the [Frame::offset]. A CADU of the wrong length for
+/// `config` results in a single [UncorrectableReason::InvalidLength] state.
+pub fn decode_cadu(config: &Config, offset: u64, mut cadu: BytesMut) -> SharedFrame {
+    let block_len = config.block_len();
+    if cadu.len() == config.asm.len() + block_len {
+        let _ = cadu.split_to(config.asm.len());
+    }
+    if cadu.len() != block_len {
+        let frame = Frame {
+            offset,
+            data: None,
+            states: vec![RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                got: cadu.len(),
+                want: block_len,
+            })],
+            error_positions: Vec::new(),
+            corrected_bits: 0,
+            fecf_ok: None,
+            inverted: false,
+            idle: false,
+        };
+        return SharedFrame { frame, data: None };
+    }
+    let (frame, output) = pipeline::decode_in_place(config, offset, false, &mut cadu);
+    let data = output.then(|| {
+        cadu.truncate(config.frame_len());
+        cadu.freeze()
+    });
+    SharedFrame { frame, data }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen;
+
+    #[test]
+    fn test_decode_cadu() {
+        let config = Config::default();
+        let payload: Vec<u8> = (0..100).collect();
+        let stream = gen::cadus(&config, &payload, 0);
+        let mut cadu = BytesMut::from(&stream[..]);
+        cadu[10] ^= 0xff;
+        let ptr = cadu[config.asm.len()..].as_ptr();
+
+        let shared = decode_cadu(&config, 7, cadu);
+
+        let data = shared.data.clone().unwrap();
+        assert_eq!(&data[..100], &payload[..]);
+        assert_eq!(data.len(), config.frame_len());
+        // the frame is the buffer the CADU was received in
+        assert_eq!(data.as_ptr(), ptr);
+        assert_eq!(shared.frame.offset, 7);
+        assert_eq!(shared.frame.error_positions, vec![6]);
+        assert!(shared.frame.data.is_none());
+
+        let expected = pipeline::decode(
+            &config,
+            crate::sync::Cadu {
+                offset: 7,
+                data: {
+                    let mut data = stream[config.asm.len()..].to_vec();
+                    data[6] ^= 0xff;
+                    data
+                },
+                flywheel: false,
+                inverted: false,
+            },
+        );
+        assert_eq!(Frame::from(shared), expected);
+
+        // without the ASM, and of the wrong length
+        let shared = decode_cadu(&config, 0, BytesMut::from(&stream[4..]));
+        assert!(shared.data.is_some());
+        let shared = decode_cadu(&config, 0, BytesMut::from(&stream[5..]));
+        assert!(shared.data.is_none());
+        assert!(matches!(
+            shared.frame.states[..],
+            [RSState::Uncorrectable(
+                UncorrectableReason::InvalidLength { .. }
+            )]
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+//! Reed-Solomon stage for the [ccsds] crate's frame decoding. Requires the
+//! `ccsds-interop` feature.
+//!
+//! [ReedSolomon] implements [IntegrityAlgorithm], so it can be given to a
+//! [FrameDecoder](::ccsds::framing::FrameDecoder) in place of the `ccsds` crate's own
+//! RS implementation, correcting the synchronized, derandomized codeblocks with this
+//! crate's decoder and options.
+//!
+//! ```
+//! use ccsds::framing::{read_synchronized_blocks, DefaultDerandomizer, FrameDecoder, Integrity};
+//! use rs2::ccsds::ReedSolomon;
+//! use rs2::pipeline::Config;
+//!
+//! let config = Config::default();
+//! let stream = rs2::gen::cadus(&config, b"hello world", 0);
+//! let blocks: Vec<Vec<u8>> = read_synchronized_blocks(&stream[..], config.block_len())
+//!     .filter_map(Result::ok)
+//!     .collect();
+//! let frames: Vec<_> = FrameDecoder::new()
+//!     .with_integrity(Box::new(ReedSolomon::from(&config)))
+//!     .with_derandomization(Box::new(DefaultDerandomizer))
+//!     .decode(blocks.into_iter())
+//!     .collect();
+//! let frame = frames[0].as_ref().unwrap();
+//! assert_eq!(frame.integrity, Some(Integrity::Ok));
+//! assert_eq!(&frame.frame.data[..11], b"hello world");
+//! ```
+use ::ccsds::framing::{Integrity, IntegrityAlgorithm, VCDUHeader};
+use ::ccsds::{Error, Result};
+
+use crate::pipeline::Config;
+use crate::{Code, RSState, UncorrectableReason, MAX_INTERLEAVE, N};
+
+/// Corrects codeblocks for a `ccsds` [FrameDecoder](::ccsds::framing::FrameDecoder).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ReedSolomon {
+    /// RS code used to encode the codeblocks.
+    pub code: Code,
+    /// Number of interleaved RS messages in each codeblock, 1 to [MAX_INTERLEAVE].
+    pub interleave: u8,
+    /// Maximum number of errors corrected in each RS message; see
+    /// [Config::max_errors].
+    pub max_errors: Option<usize>,
+}
+
+impl ReedSolomon {
+    pub fn new(code: Code, interleave: u8) -> Self {
+        ReedSolomon {
+            code,
+            interleave,
+            max_errors: None,
+        }
+    }
+
+    /// Length of the codeblock given to [IntegrityAlgorithm::perform].
+    pub fn block_len(&self) -> usize {
+        self.interleave as usize * N as usize
+    }
+
+    /// Length of the transfer frame, i.e., the codeblock without the check symbols.
+    pub fn frame_len(&self) -> usize {
+        self.interleave as usize * self.code.data_len()
+    }
+}
+
+impl From<&Config> for ReedSolomon {
+    /// The code, interleave and maximum errors of a [crate::pipeline::Pipeline] config.
+    fn from(config: &Config) -> Self {
+        ReedSolomon {
+            code: config.code,
+            interleave: config.interleave,
+            max_errors: config.max_errors,
+        }
+    }
+}
+
+impl IntegrityAlgorithm for ReedSolomon {
+    /// Correct `cadu_dat`, a derandomized codeblock, returning the transfer frame
+    /// without the check symbols.
+    ///
+    /// Like the `ccsds` crate's own implementation, fill frames are not corrected and
+    /// are [Integrity::Skipped]. If any message is uncorrectable the frame is
+    /// [Integrity::Uncorrectable], with the correctable messages corrected and the
+    /// others as received.
+    ///
+    /// # Errors
+    /// [Error::IntegrityAlgorithm] if the interleave is not in `1..=MAX_INTERLEAVE` or
+    /// `cadu_dat` is not the codeblock length.
+    fn perform(&self, header: &VCDUHeader, cadu_dat: &[u8]) -> Result<(Integrity, Vec<u8>)> {
+        let reason = if !(1..=MAX_INTERLEAVE).contains(&self.interleave) {
+            Some(UncorrectableReason::InvalidInterleave(self.interleave))
+        } else if cadu_dat.len() != self.block_len() {
+            Some(UncorrectableReason::InvalidLength {
+                got: cadu_dat.len(),
+                want: self.block_len(),
+            })
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(Error::IntegrityAlgorithm(reason.to_string()));
+        }
+        if header.vcid == VCDUHeader::FILL {
+            return Ok((Integrity::Skipped, cadu_dat[..self.frame_len()].to_vec()));
+        }
+
+        let mut block = cadu_dat.to_vec();
+        let codeblock = self.code.correct_codeblock_in_place_with_max_errors(
+            &mut block,
+            self.interleave,
+            self.max_errors.unwrap_or(usize::MAX),
+        );
+        let integrity = if codeblock
+            .states
+            .iter()
+            .any(|s| matches!(s, RSState::Uncorrectable(_)))
+        {
+            Integrity::Uncorrectable
+        } else if codeblock.error_positions.is_empty() {
+            Integrity::Ok
+        } else {
+            Integrity::Corrected
+        };
+        block.truncate(self.frame_len());
+        Ok((integrity, block))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen;
+
+    #[test]
+    fn test_perform() {
+        let config = Config {
+            derandomize: false,
+            interleave: 2,
+            ..Config::default()
+        };
+        let rs = ReedSolomon::from(&config);
+        let payload: Vec<u8> = (0..100).collect();
+        let stream = gen::cadus(&config, &payload, 0);
+        let mut block = stream[config.asm.len()..].to_vec();
+        let header = VCDUHeader::decode(&block).unwrap();
+
+        let (integrity, frame) = rs.perform(&header, &block).unwrap();
+        assert_eq!(integrity, Integrity::Ok);
+        assert_eq!(frame.len(), config.frame_len());
+        assert_eq!(&frame[..100], &payload[..]);
+
+        block[51] ^= 0xff;
+        let (integrity, frame) = rs.perform(&header, &block).unwrap();
+        assert_eq!(integrity, Integrity::Corrected);
+        assert_eq!(&frame[..100], &payload[..]);
+
+        // message 0 is uncorrectable, message 1 is still corrected
+        for i in 0..17 {
+            block[2 * i * 10] ^= 0x01;
+        }
+        let (integrity, frame) = rs.perform(&header, &block).unwrap();
+        assert_eq!(integrity, Integrity::Uncorrectable);
+        assert_eq!(frame[51], payload[51]);
+        assert_ne!(frame[20], payload[20]);
+
+        let fill = VCDUHeader {
+            vcid: VCDUHeader::FILL,
+            ..header
+        };
+        let (integrity, _) = rs.perform(&fill, &block).unwrap();
+        assert_eq!(integrity, Integrity::Skipped);
+
+        assert!(rs.perform(&header, &block[1..]).is_err());
+        assert!(ReedSolomon::new(Code::E16, 0)
+            .perform(&header, &block)
+            .is_err());
+    }
+}
@@ -0,0 +1,378 @@
+//! Checkpoints of the progress of long decoding jobs, so that they can be resumed where
+//! a previous run stopped.
+//!
+//! A [Checkpoint] is the input offset reached, the length of the output written up to
+//! it, and the statistics so far, saved to a small text file of `key=value` lines. A
+//! [Checkpointer] records the events of a [crate::pipeline::Pipeline] reading from the
+//! checkpointed offset and saves the checkpoint periodically.
+//!
+//! To resume, seek the input to [Checkpoint::offset], truncate the output to
+//! [Checkpoint::output_len], and decode with the same config as before. The output
+//! must be flushed before each save, so that it is at least as long as recorded.
+//!
+//! ```no_run
+//! use std::fs::{File, OpenOptions};
+//! use std::io::{Seek, SeekFrom, Write};
+//! use rs2::checkpoint::Checkpointer;
+//! use rs2::pipeline::{Config, Event, Pipeline};
+//!
+//! let config = Config::default();
+//! let mut checkpointer = Checkpointer::open("overpass.state", &config).unwrap();
+//! let start = checkpointer.checkpoint().clone();
+//! let mut input = File::open("overpass.dat").unwrap();
+//! input.seek(SeekFrom::Start(start.offset)).unwrap();
+//! let mut output = OpenOptions::new().create(true).write(true).open("frames.dat").unwrap();
+//! output.set_len(start.output_len).unwrap();
+//! output.seek(SeekFrom::End(0)).unwrap();
+//!
+//! for event in Pipeline::new(input, config) {
+//!     let event = event.unwrap();
+//!     let mut written = 0;
+//!     if let Event::Frame(frame) = &event {
+//!         if let Some(data) = &frame.data {
+//!             output.write_all(data).unwrap();
+//!             written = data.len() as u64;
+//!         }
+//!     }
+//!     checkpointer.add(&event, written);
+//!     if checkpointer.is_due() {
+//!         output.flush().unwrap();
+//!         checkpointer.save().unwrap();
+//!     }
+//! }
+//! output.flush().unwrap();
+//! checkpointer.save().unwrap();
+//! ```
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::pipeline::{Config, Event};
+use crate::stats::DecodeSummary;
+
+/// Default time between saves of a [Checkpointer].
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Progress of decoding an input stream.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Checkpoint {
+    /// Input offset to resume from, i.e., the end of the last CADU or skipped bytes
+    /// processed.
+    pub offset: u64,
+    /// Number of bytes of output written for the input before [Checkpoint::offset].
+    pub output_len: u64,
+    /// Number of frames decoded, including idle frames.
+    pub frames: u64,
+    /// Number of bytes skipped by synchronization between CADUs.
+    pub skipped_bytes: u64,
+    /// Results of the messages of the frames.
+    pub summary: DecodeSummary,
+}
+
+impl Checkpoint {
+    /// Load a checkpoint saved with [Checkpoint::save], or None if `path` does not
+    /// exist.
+    ///
+    /// It is an [ErrorKind::InvalidData] error if the file is not a checkpoint.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(text) => text
+                .parse()
+                .map(Some)
+                .map_err(|err| io::Error::new(ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Save to `path`, replacing any previous checkpoint. The checkpoint is written to
+    /// a temporary file which is then renamed, so `path` always holds a complete
+    /// checkpoint even if the process is killed while saving.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(self.to_string().as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+
+    /// The fields by their key in the file.
+    fn fields_mut(&mut self) -> [(&'static str, &mut u64); 12] {
+        let summary = &mut self.summary;
+        [
+            ("offset", &mut self.offset),
+            ("output_len", &mut self.output_len),
+            ("frames", &mut self.frames),
+            ("skipped_bytes", &mut self.skipped_bytes),
+            ("ok", &mut summary.ok),
+            ("corrected", &mut summary.corrected),
+            ("uncorrectable", &mut summary.uncorrectable),
+            ("not_performed", &mut summary.not_performed),
+            ("corrected_symbols", &mut summary.corrected_symbols),
+            ("corrected_bits", &mut summary.corrected_bits),
+            ("idle_frames", &mut summary.idle_frames),
+            ("bytes", &mut summary.bytes),
+        ]
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    /// The file format, one `key=value` line for each field.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = &self.summary;
+        writeln!(f, "offset={}", self.offset)?;
+        writeln!(f, "output_len={}", self.output_len)?;
+        writeln!(f, "frames={}", self.frames)?;
+        writeln!(f, "skipped_bytes={}", self.skipped_bytes)?;
+        writeln!(f, "ok={}", summary.ok)?;
+        writeln!(f, "corrected={}", summary.corrected)?;
+        writeln!(f, "uncorrectable={}", summary.uncorrectable)?;
+        writeln!(f, "not_performed={}", summary.not_performed)?;
+        writeln!(f, "corrected_symbols={}", summary.corrected_symbols)?;
+        writeln!(f, "corrected_bits={}", summary.corrected_bits)?;
+        writeln!(f, "idle_frames={}", summary.idle_frames)?;
+        writeln!(f, "bytes={}", summary.bytes)
+    }
+}
+
+/// Error parsing a [Checkpoint].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CheckpointError {
+    /// A line is not `key=value` with a known key and an integer value.
+    InvalidLine(String),
+    /// The `offset` key is missing.
+    MissingOffset,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLine(line) => write!(f, "invalid checkpoint line {line:?}"),
+            Self::MissingOffset => write!(f, "checkpoint has no offset"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl FromStr for Checkpoint {
+    type Err = CheckpointError;
+
+    /// Parse the [fmt::Display] format. Keys other than `offset` default to 0.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut checkpoint = Checkpoint::default();
+        let mut offset = false;
+        for line in s.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let invalid = || CheckpointError::InvalidLine(line.to_string());
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let mut fields = checkpoint.fields_mut();
+            let (_, field) = fields
+                .iter_mut()
+                .find(|(k, _)| *k == key.trim())
+                .ok_or_else(invalid)?;
+            **field = value.trim().parse().map_err(|_| invalid())?;
+            offset |= key.trim() == "offset";
+        }
+        match offset {
+            true => Ok(checkpoint),
+            false => Err(CheckpointError::MissingOffset),
+        }
+    }
+}
+
+/// Records the events of a [crate::pipeline::Pipeline] in a [Checkpoint], saving it
+/// to a file periodically; see the [module documentation](self).
+#[derive(Debug)]
+pub struct Checkpointer {
+    path: PathBuf,
+    interval: Duration,
+    saved: Instant,
+    cadu_len: u64,
+    // input offset the pipeline was started at
+    base: u64,
+    checkpoint: Checkpoint,
+}
+
+impl Checkpointer {
+    /// Resume from the checkpoint saved at `path`, or start from the beginning if
+    /// there is none, for a pipeline using `config`. See [Checkpoint::load] for the
+    /// errors.
+    pub fn open<P: AsRef<Path>>(path: P, config: &Config) -> io::Result<Self> {
+        let checkpoint = Checkpoint::load(&path)?.unwrap_or_default();
+        Ok(Checkpointer {
+            path: path.as_ref().to_path_buf(),
+            interval: DEFAULT_INTERVAL,
+            saved: Instant::now(),
+            cadu_len: (config.asm.len() + config.block_len()) as u64,
+            base: checkpoint.offset,
+            checkpoint,
+        })
+    }
+
+    /// Set the time between saves, [DEFAULT_INTERVAL] by default.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The progress so far, or of the resumed run if nothing has been added.
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint
+    }
+
+    /// Record an event of a pipeline reading from the offset of the checkpoint the
+    /// checkpointer was opened with, for which `written` bytes of output were written.
+    pub fn add(&mut self, event: &Event, written: u64) {
+        let checkpoint = &mut self.checkpoint;
+        let end = match event {
+            Event::Frame(frame) => {
+                checkpoint.frames += 1;
+                checkpoint.summary.add_frame(frame);
+                frame.offset + self.cadu_len
+            }
+            Event::Skipped(range) => {
+                checkpoint.skipped_bytes += range.end - range.start;
+                range.end
+            }
+            Event::Locked(_) | Event::Unlocked(_) => return,
+        };
+        checkpoint.offset = checkpoint.offset.max(self.base + end);
+        checkpoint.output_len += written;
+    }
+
+    /// Whether the interval has passed since the last save.
+    pub fn is_due(&self) -> bool {
+        self.saved.elapsed() >= self.interval
+    }
+
+    /// Save the checkpoint now. The output must have been flushed.
+    pub fn save(&mut self) -> io::Result<()> {
+        self.checkpoint.save(&self.path)?;
+        self.saved = Instant::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen;
+    use crate::pipeline::Pipeline;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rs2-checkpoint-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn test_checkpoint_format() {
+        let checkpoint = Checkpoint {
+            offset: 1024,
+            output_len: 446,
+            frames: 2,
+            skipped_bytes: 3,
+            summary: DecodeSummary {
+                ok: 3,
+                corrected: 1,
+                corrected_symbols: 2,
+                bytes: 1020,
+                ..Default::default()
+            },
+        };
+        let text = checkpoint.to_string();
+        assert!(text.starts_with("offset=1024\noutput_len=446\nframes=2\n"));
+        assert_eq!(text.parse(), Ok(checkpoint));
+
+        assert_eq!(
+            " offset = 5 \n\nok=1\n".parse::<Checkpoint>().unwrap(),
+            Checkpoint {
+                offset: 5,
+                summary: DecodeSummary {
+                    ok: 1,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            "frames=1".parse::<Checkpoint>(),
+            Err(CheckpointError::MissingOffset)
+        );
+        for line in ["offset", "offset=x", "offset=1\nfoo=2"] {
+            assert!(matches!(
+                line.parse::<Checkpoint>(),
+                Err(CheckpointError::InvalidLine(_))
+            ));
+        }
+
+        let path = temp_path("format.state");
+        assert_eq!(Checkpoint::load(&path).unwrap(), None);
+        fs::write(&path, "nonsense").unwrap();
+        let err = Checkpoint::load(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_checkpointer_resume() {
+        let config = Config {
+            interleave: 1,
+            ..Config::default()
+        };
+        let payload: Vec<u8> = (0..223 * 6).map(|i| i as u8).collect();
+        let mut stream = vec![0x55; 10];
+        stream.extend(gen::cadus(&config, &payload, 0));
+        let path = temp_path("resume.state");
+
+        // stop after the third frame
+        let mut checkpointer = Checkpointer::open(&path, &config)
+            .unwrap()
+            .interval(Duration::ZERO);
+        let mut output = Vec::new();
+        for event in Pipeline::new(&stream[..], config.clone()).take(4) {
+            let event = event.unwrap();
+            let mut written = 0;
+            if let Event::Frame(frame) = &event {
+                let data = frame.data.as_ref().unwrap();
+                output.extend(data);
+                written = data.len() as u64;
+            }
+            checkpointer.add(&event, written);
+            assert!(checkpointer.is_due());
+            checkpointer.save().unwrap();
+        }
+        // a frame whose output was written after the last save
+        output.extend([0u8; 10]);
+        let checkpoint = Checkpoint::load(&path).unwrap().unwrap();
+        assert_eq!(checkpoint.offset, 10 + 3 * 259);
+        assert_eq!(checkpoint.frames, 3);
+        assert_eq!(checkpoint.skipped_bytes, 10);
+        assert_eq!(checkpoint.output_len, 3 * 223);
+
+        let mut checkpointer = Checkpointer::open(&path, &config).unwrap();
+        assert!(!checkpointer.is_due());
+        let start = checkpointer.checkpoint().clone();
+        output.truncate(start.output_len as usize);
+        let input = &stream[start.offset as usize..];
+        for event in Pipeline::new(input, config) {
+            let event = event.unwrap();
+            if let Event::Frame(frame) = &event {
+                output.extend(frame.data.as_ref().unwrap());
+                checkpointer.add(&event, 223);
+            }
+        }
+        checkpointer.save().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(output, payload);
+        let checkpoint = checkpointer.checkpoint();
+        assert_eq!(checkpoint.offset, stream.len() as u64);
+        assert_eq!(checkpoint.frames, 6);
+        assert_eq!(checkpoint.summary.ok, 6);
+        assert_eq!(checkpoint.output_len, payload.len() as u64);
+    }
+}
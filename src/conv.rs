@@ -0,0 +1,162 @@
+//! CCSDS rate 1/2, constraint length 7 convolutional code as documented in section 3
+//! of [131.0-B-5](https://public.ccsds.org/Pubs/131x0b5.pdf), for use as the inner
+//! code of the concatenated coding system with RS as the outer code.
+//!
+//! The connection vectors are `G1 = 1111001` and `G2 = 1011011`, i.e., 171 and 133
+//! octal, with the G2 output inverted. Each data bit produces the G1 symbol followed
+//! by the inverted G2 symbol.
+//!
+//! Symbols are decoded using a Viterbi decoder from either hard decisions, packed 8
+//! per byte MSB first, or soft decisions, one per byte where 0 is a confident 0, 255
+//! is a confident 1 and 128 is an erasure.
+//!
+//! ```
+//! let data = b"hello, spacecraft";
+//! let mut symbols = rs2::conv::encode(data);
+//! symbols[3] ^= 0x10;
+//! assert_eq!(rs2::conv::decode_hard(&symbols), data);
+//! ```
+
+/// Constraint length.
+pub const K: usize = 7;
+
+const STATES: usize = 1 << (K - 1);
+// Connection vectors with the current input as the least significant bit
+const POLY_A: u8 = 0x4f;
+const POLY_B: u8 = 0x6d;
+
+/// Encoder output symbols for the 7 bit register, where bit 0 is the newest bit.
+const fn branch_symbols(reg: u8) -> (u8, u8) {
+    let a = (reg & POLY_A).count_ones() as u8 & 1;
+    let b = (reg & POLY_B).count_ones() as u8 & 1;
+    (a, b ^ 1)
+}
+
+/// Convolutionally encode `data`, starting from the all zeros state, returning 2 bytes
+/// of packed symbols per data byte.
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() * 2);
+    let mut state = 0u8;
+    let mut word = 0u16;
+    for x in data {
+        for i in (0..8).rev() {
+            let reg = (state << 1) | ((x >> i) & 1);
+            let (a, b) = branch_symbols(reg);
+            word = (word << 2) | (a << 1 | b) as u16;
+            state = reg & (STATES as u8 - 1);
+        }
+        out.extend_from_slice(&word.to_be_bytes());
+    }
+    out
+}
+
+/// Decode hard decision symbols packed 8 per byte, MSB first, returning 1 data byte
+/// per 2 symbol bytes. See [decode_soft].
+pub fn decode_hard(symbols: &[u8]) -> Vec<u8> {
+    let soft: Vec<u8> = symbols
+        .iter()
+        .flat_map(|x| {
+            (0..8)
+                .rev()
+                .map(move |i| if (x >> i) & 1 == 1 { 255 } else { 0 })
+        })
+        .collect();
+    decode_soft(&soft)
+}
+
+/// Decode soft decision symbols, 1 per byte, returning 1 data byte per 16 symbols.
+/// Any trailing symbols that do not make up a complete data byte are ignored.
+///
+/// Decoding assumes the encoder started in the all zeros state and traces back from
+/// the most likely final state, so the last few bits are less reliable unless the
+/// data was followed by at least 6 zero bits.
+pub fn decode_soft(symbols: &[u8]) -> Vec<u8> {
+    let nbits = symbols.len() / 16 * 8;
+
+    // expected symbols for each branch, indexed by the 7 bit register
+    let mut expected = [(0u8, 0u8); 2 * STATES];
+    for (reg, e) in expected.iter_mut().enumerate() {
+        let (a, b) = branch_symbols(reg as u8);
+        *e = (a * 255, b * 255);
+    }
+
+    let mut metrics = [u32::MAX / 2; STATES];
+    metrics[0] = 0;
+    // bit s is set if the predecessor of state s had its high bit set
+    let mut decisions = Vec::with_capacity(nbits);
+    for step in symbols[..nbits * 2].chunks_exact(2) {
+        let cost = |(a, b): (u8, u8)| step[0].abs_diff(a) as u32 + step[1].abs_diff(b) as u32;
+        let mut next = [0u32; STATES];
+        let mut decision = 0u64;
+        for (s, m) in next.iter_mut().enumerate() {
+            // The register is the predecessor state followed by the new bit, s & 1.
+            let p0 = s >> 1;
+            let p1 = p0 | (STATES >> 1);
+            let m0 = metrics[p0] + cost(expected[s]);
+            let m1 = metrics[p1] + cost(expected[s | STATES]);
+            if m1 < m0 {
+                *m = m1;
+                decision |= 1 << s;
+            } else {
+                *m = m0;
+            }
+        }
+        let min = *next.iter().min().unwrap();
+        for (m, n) in metrics.iter_mut().zip(next.iter()) {
+            *m = n - min;
+        }
+        decisions.push(decision);
+    }
+
+    let mut state = (0..STATES).min_by_key(|s| metrics[*s]).unwrap_or(0);
+    let mut out = vec![0u8; nbits / 8];
+    for (i, decision) in decisions.iter().enumerate().rev() {
+        out[i / 8] |= ((state & 1) as u8) << (7 - i % 8);
+        state = (state >> 1) | (((decision >> state) & 1) as usize) << (K - 2);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> Vec<u8> {
+        (0..200u32).map(|i| (i * 37 + i / 3) as u8).collect()
+    }
+
+    #[test]
+    fn test_encode() {
+        // A single 1 bit produces the impulse response of the interleaved connection
+        // vectors, with G2 inverted: G1 = 1111001, G2 = 1011011.
+        // 10 11 10 10 01 00 10 01 01 01 01 01 01 01 01 01
+        assert_eq!(encode(&[0x80, 0x00]), vec![0xba, 0x49, 0x55, 0x55]);
+    }
+
+    #[test]
+    fn test_decode_hard() {
+        let data = data();
+        let mut symbols = encode(&data);
+        assert_eq!(decode_hard(&symbols), data);
+
+        // isolated symbol errors are corrected
+        for i in (0..symbols.len()).step_by(5) {
+            symbols[i] ^= 0x04;
+        }
+        assert_eq!(decode_hard(&symbols), data);
+    }
+
+    #[test]
+    fn test_decode_soft_erasures() {
+        let data = data();
+        let mut soft: Vec<u8> = encode(&data)
+            .iter()
+            .flat_map(|x| (0..8).rev().map(move |i| (x >> i & 1) * 200 + 20))
+            .collect();
+        for i in (0..soft.len()).step_by(7) {
+            soft[i] = 128;
+        }
+        assert_eq!(decode_soft(&soft), data);
+        assert_eq!(decode_soft(&soft[..20]).len(), 1);
+    }
+}
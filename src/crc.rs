@@ -0,0 +1,85 @@
+//! CCSDS Frame Error Control Field (FECF) CRC-16 as documented in section 4.1.6 of
+//! [132.0-B-3](https://public.ccsds.org/Pubs/132x0b3.pdf).
+//!
+//! The FECF is the last 2 bytes of a transfer frame and is the CRC-16-CCITT, i.e.,
+//! generator `x^16 + x^12 + x^5 + 1` with the register preset to all ones, of the
+//! rest of the frame. RS can miscorrect a frame with too many errors, so checking
+//! the FECF after correction detects most such frames.
+
+/// Length of the FECF in bytes.
+pub const FECF_LEN: usize = 2;
+
+const POLY: u16 = 0x1021;
+
+const fn gen_table() -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ POLY
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+static TABLE: [u16; 256] = gen_table();
+
+/// Compute the CRC-16 of `data`.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xffffu16;
+    for x in data {
+        crc = (crc << 8) ^ TABLE[((crc >> 8) as u8 ^ x) as usize];
+    }
+    crc
+}
+
+/// Return true if the last [FECF_LEN] bytes of `frame` are the CRC-16 of the rest of
+/// the frame. Frames shorter than the FECF are never valid.
+pub fn verify_fecf(frame: &[u8]) -> bool {
+    if frame.len() < FECF_LEN {
+        return false;
+    }
+    let (data, fecf) = frame.split_at(frame.len() - FECF_LEN);
+    crc16(data) == u16::from_be_bytes([fecf[0], fecf[1]])
+}
+
+/// Write the FECF to the last [FECF_LEN] bytes of `frame`.
+///
+/// # Panics
+/// If `frame` is shorter than [FECF_LEN].
+pub fn set_fecf(frame: &mut [u8]) {
+    let (data, fecf) = frame.split_at_mut(frame.len() - FECF_LEN);
+    fecf.copy_from_slice(&crc16(data).to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc16() {
+        // CRC-16/IBM-3740 check value
+        assert_eq!(crc16(b"123456789"), 0x29b1);
+        assert_eq!(crc16(&[]), 0xffff);
+    }
+
+    #[test]
+    fn test_fecf() {
+        let mut frame = vec![0x55u8; 892];
+        set_fecf(&mut frame);
+        assert!(verify_fecf(&frame));
+
+        frame[100] ^= 0x01;
+        assert!(!verify_fecf(&frame));
+        assert!(!verify_fecf(&[0]));
+    }
+}
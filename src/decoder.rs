@@ -0,0 +1,547 @@
+//! Configurable RS decoding of codeblocks.
+//!
+//! The free functions in the crate root cover the standard CCSDS case of dual basis,
+//! unshortened codeblocks. [RsDecoder] allows each of those choices to be configured.
+//!
+//! ```
+//! use rs2::decoder::{Basis, RsDecoder};
+//!
+//! let decoder = RsDecoder::builder()
+//!     .interleave(2)
+//!     .virtual_fill(23)
+//!     .strip_parity(true)
+//!     .output_basis(Basis::Conventional)
+//!     .build()
+//!     .unwrap();
+//! let block = decoder.decode(&[0u8; 2 * 232]);
+//! assert_eq!(block.message.unwrap().len(), 2 * 200);
+//! ```
+//!
+//! [Decoder] instead corrects individual messages into buffers it owns, so repeated
+//! calls do not allocate.
+use crate::{
+    dual_basis, interleave, valid_erasures, Block, Code, Codeblock, RSState, UncorrectableReason,
+    MAX_INTERLEAVE, N, PARITY_LEN,
+};
+
+/// Symbol representation.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Basis {
+    /// Berlekamp's dual basis, as transmitted per CCSDS.
+    #[default]
+    Dual,
+    /// Conventional polynomial basis.
+    Conventional,
+}
+
+/// Builder for [RsDecoder]; see [RsDecoder::builder].
+#[derive(Debug, Clone)]
+pub struct RsDecoderBuilder {
+    decoder: RsDecoder,
+}
+
+impl RsDecoderBuilder {
+    /// RS code used to encode the codeblocks. Defaults to [Code::E16].
+    pub fn code(mut self, code: Code) -> Self {
+        self.decoder.code = code;
+        self
+    }
+
+    /// Representation of the input symbols. Defaults to [Basis::Dual].
+    pub fn input_basis(mut self, basis: Basis) -> Self {
+        self.decoder.input_basis = basis;
+        self
+    }
+
+    /// Representation of the output symbols. Defaults to [Basis::Dual].
+    pub fn output_basis(mut self, basis: Basis) -> Self {
+        self.decoder.output_basis = basis;
+        self
+    }
+
+    /// Number of interleaved messages in each codeblock. Defaults to 1.
+    pub fn interleave(mut self, interleave: u8) -> Self {
+        self.decoder.interleave = interleave;
+        self
+    }
+
+    /// Number of leading zero symbols of each message that are not transmitted, i.e.,
+    /// the codeblocks are shortened. Defaults to 0.
+    pub fn virtual_fill(mut self, virtual_fill: usize) -> Self {
+        self.decoder.virtual_fill = virtual_fill;
+        self
+    }
+
+    /// Whether the check symbols are removed from the output. Defaults to false.
+    pub fn strip_parity(mut self, strip: bool) -> Self {
+        self.decoder.strip_parity = strip;
+        self
+    }
+
+    /// Whether corrected messages are re-encoded and the resulting check symbols
+    /// compared to the corrected check symbols, marking any mismatch as
+    /// [UncorrectableReason::ParityMismatch]. Defaults to false.
+    ///
+    /// This is an independent cross-check of the decoder using the encoder. A
+    /// miscorrection to a different valid codeword, which is possible when a message
+    /// has more errors than can be corrected, re-encodes consistently and so is not
+    /// detected; a frame check such as [crate::crc] is required for that.
+    pub fn verify_parity(mut self, verify: bool) -> Self {
+        self.decoder.verify_parity = verify;
+        self
+    }
+
+    /// Maximum number of errors corrected in each message, not counting erasures.
+    /// Messages with more are [UncorrectableReason::TooManyErrors]; see
+    /// [Block::limit_errors]. Defaults to as many as the code can correct.
+    pub fn max_errors(mut self, max: usize) -> Self {
+        self.decoder.max_errors = Some(max);
+        self
+    }
+
+    /// Validate the configuration and create the decoder.
+    pub fn build(self) -> Result<RsDecoder, UncorrectableReason> {
+        let decoder = self.decoder;
+        if !(1..=MAX_INTERLEAVE).contains(&decoder.interleave) {
+            return Err(UncorrectableReason::InvalidInterleave(decoder.interleave));
+        }
+        if decoder.virtual_fill > decoder.code.data_len() {
+            return Err(UncorrectableReason::InvalidVirtualFill {
+                got: decoder.virtual_fill,
+                max: decoder.code.data_len(),
+            });
+        }
+        Ok(decoder)
+    }
+}
+
+/// Decoder for codeblocks with a fixed configuration.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RsDecoder {
+    code: Code,
+    input_basis: Basis,
+    output_basis: Basis,
+    interleave: u8,
+    virtual_fill: usize,
+    strip_parity: bool,
+    verify_parity: bool,
+    max_errors: Option<usize>,
+}
+
+impl Default for RsDecoder {
+    /// RS(255,223), dual basis, no interleaving or virtual fill, keeping parity.
+    fn default() -> Self {
+        RsDecoder {
+            code: Code::E16,
+            input_basis: Basis::Dual,
+            output_basis: Basis::Dual,
+            interleave: 1,
+            virtual_fill: 0,
+            strip_parity: false,
+            verify_parity: false,
+            max_errors: None,
+        }
+    }
+}
+
+impl RsDecoder {
+    pub fn builder() -> RsDecoderBuilder {
+        RsDecoderBuilder {
+            decoder: RsDecoder::default(),
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    pub fn interleave(&self) -> u8 {
+        self.interleave
+    }
+
+    /// Length of each transmitted message, i.e., 255 less the virtual fill.
+    pub fn message_len(&self) -> usize {
+        N as usize - self.virtual_fill
+    }
+
+    /// Expected length of each input codeblock.
+    pub fn input_len(&self) -> usize {
+        self.interleave as usize * self.message_len()
+    }
+
+    /// Length of each output codeblock.
+    pub fn output_len(&self) -> usize {
+        let mut len = self.message_len();
+        if self.strip_parity {
+            len -= self.code.parity_len();
+        }
+        self.interleave as usize * len
+    }
+
+    /// Correct a codeblock of [RsDecoder::input_len] bytes. The corrected codeblock is
+    /// [RsDecoder::output_len] bytes, interleaved the same as the input, and in the
+    /// configured output representation.
+    pub fn decode(&self, input: &[u8]) -> Codeblock {
+        if input.len() != self.input_len() {
+            return Codeblock {
+                states: vec![RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                    got: input.len(),
+                    want: self.input_len(),
+                })],
+                message: None,
+                error_positions: Vec::new(),
+                corrected_bits: 0,
+            };
+        }
+
+        let depth = self.interleave as usize;
+        let mut states = Vec::with_capacity(depth);
+        let mut message = vec![0u8; self.output_len()];
+        let mut error_positions = Vec::new();
+        let mut corrected_bits = 0;
+        let mut uncorrectable = false;
+        for (i, msg) in interleave::deinterleave(input, self.interleave).enumerate() {
+            let mut block = self.code.correct_shortened_in_basis(
+                &msg.to_vec(),
+                self.virtual_fill,
+                self.input_basis,
+                self.output_basis,
+            );
+            if let Some(max) = self.max_errors {
+                block = block.limit_errors(max);
+            }
+            if self.verify_parity && matches!(block.state, RSState::Corrected(_)) {
+                self.check_parity(&mut block);
+            }
+            corrected_bits += block.corrected_bits() as u64;
+            match block.message {
+                Some(mut corrected) => {
+                    corrected.truncate(message.len() / depth);
+                    interleave::write_message(&mut message, self.interleave, i, &corrected);
+                }
+                None => uncorrectable = true,
+            }
+            error_positions.extend(block.error_positions.iter().map(|p| p * depth + i));
+            states.push(block.state);
+        }
+        error_positions.sort_unstable();
+
+        Codeblock {
+            states,
+            message: if uncorrectable { None } else { Some(message) },
+            error_positions,
+            corrected_bits,
+        }
+    }
+
+    /// Mark a corrected block uncorrectable if re-encoding does not reproduce it.
+    fn check_parity(&self, block: &mut Block) {
+        let Some(corrected) = &block.message else {
+            return;
+        };
+        let mut full = [0u8; N as usize];
+        full[self.virtual_fill..].copy_from_slice(corrected);
+        if self.output_basis == Basis::Conventional {
+            dual_basis::to_dual_in_place(&mut full);
+        }
+        if !self.code.verify_parity(&full) {
+            *block = Block::uncorrectable(UncorrectableReason::ParityMismatch);
+        }
+    }
+}
+
+/// Message decoder that reuses its buffers between calls.
+///
+/// The decode itself works entirely on the stack, so the allocations made by
+/// [Code::correct_message] are for the returned [Block]. A `Decoder` instead keeps
+/// the corrected message and the corrections in buffers sized for the maximum number
+/// of corrections, so decoding does not allocate.
+///
+/// ```
+/// use rs2::decoder::Decoder;
+/// use rs2::{Code, RSState};
+///
+/// let mut decoder = Decoder::new(Code::E16);
+/// let mut msg = rs2::encode_message(&[1u8; 223]).unwrap();
+/// msg[9] = 0;
+/// assert_eq!(decoder.decode(&msg), RSState::Corrected(1));
+/// assert_eq!(decoder.error_positions(), &[9]);
+/// assert_eq!(decoder.message().unwrap()[9], 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    code: Code,
+    msg: [u8; N as usize],
+    state: RSState,
+    error_positions: Vec<usize>,
+    error_values: Vec<u8>,
+    corrected_erasures: usize,
+}
+
+impl Decoder {
+    pub fn new(code: Code) -> Self {
+        Decoder {
+            code,
+            msg: [0u8; N as usize],
+            state: RSState::NotPerformed,
+            error_positions: Vec::with_capacity(PARITY_LEN),
+            error_values: Vec::with_capacity(PARITY_LEN),
+            corrected_erasures: 0,
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Correct a 255 byte message, returning the resulting state. See
+    /// [Code::correct_message].
+    pub fn decode(&mut self, input: &[u8]) -> RSState {
+        self.decode_with_erasures(input, &[])
+    }
+
+    /// Correct a 255 byte message with erasures, returning the resulting state. See
+    /// [Code::correct_message_with_erasures].
+    pub fn decode_with_erasures(&mut self, input: &[u8], erasures: &[usize]) -> RSState {
+        self.error_positions.clear();
+        self.error_values.clear();
+        self.corrected_erasures = 0;
+        self.state = match <&[u8; N as usize]>::try_from(input) {
+            Err(_) => RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                got: input.len(),
+                want: N as usize,
+            }),
+            Ok(_) if !valid_erasures(erasures, self.code.parity_len()) => {
+                RSState::Uncorrectable(UncorrectableReason::InvalidErasures)
+            }
+            Ok(input) => {
+                self.msg = *input;
+                self.code.correct_in_place(&mut self.msg, erasures)
+            }
+        };
+        if let RSState::Corrected(_) = self.state {
+            for (i, (a, b)) in input.iter().zip(self.msg.iter()).enumerate() {
+                if a != b {
+                    self.error_positions.push(i);
+                    self.error_values.push(a ^ b);
+                    if erasures.contains(&i) {
+                        self.corrected_erasures += 1;
+                    }
+                }
+            }
+        }
+        self.state.clone()
+    }
+
+    /// State of the most recent decode, [RSState::NotPerformed] before the first.
+    pub fn state(&self) -> &RSState {
+        &self.state
+    }
+
+    /// The corrected message of the most recent decode, or None if it was
+    /// uncorrectable.
+    pub fn message(&self) -> Option<&[u8; N as usize]> {
+        match self.state {
+            RSState::Ok | RSState::Corrected(_) => Some(&self.msg),
+            _ => None,
+        }
+    }
+
+    /// Positions of the symbols corrected by the most recent decode, in ascending
+    /// order; see [Block::error_positions].
+    pub fn error_positions(&self) -> &[usize] {
+        &self.error_positions
+    }
+
+    /// Error values of the symbols corrected by the most recent decode; see
+    /// [Block::error_values].
+    pub fn error_values(&self) -> &[u8] {
+        &self.error_values
+    }
+
+    /// Number of the symbols corrected by the most recent decode that were erasures;
+    /// see [Block::corrected_erasures].
+    pub fn corrected_erasures(&self) -> usize {
+        self.corrected_erasures
+    }
+
+    /// Number of bits corrected by the most recent decode; see
+    /// [Block::corrected_bits].
+    pub fn corrected_bits(&self) -> u32 {
+        self.error_values.iter().map(|v| v.count_ones()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{interleave, shifted_generator, FIXTURE_MSG};
+
+    #[test]
+    fn test_default_matches_correct_codeblock() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[3] ^= 0x44;
+        let mut uncorrectable = *FIXTURE_MSG;
+        for x in uncorrectable.iter_mut().take(20) {
+            *x ^= 0xff;
+        }
+
+        for (input, depth) in [
+            (corrupt.to_vec(), 1),
+            (interleave(&[FIXTURE_MSG, &corrupt]), 2),
+            (interleave(&[&corrupt, &uncorrectable, FIXTURE_MSG]), 3),
+        ] {
+            let block = RsDecoder::builder()
+                .interleave(depth)
+                .build()
+                .unwrap()
+                .decode(&input);
+            let expected = crate::correct_codeblock(&input, depth);
+            assert_eq!(block.states, expected.states, "interleave {depth}");
+            assert_eq!(block.message, expected.message, "interleave {depth}");
+            assert_eq!(block.error_positions, expected.error_positions);
+            assert_eq!(block.corrected_bits, expected.corrected_bits);
+        }
+        let block = RsDecoder::builder().build().unwrap().decode(&corrupt);
+        assert_eq!(block.states, vec![RSState::Corrected(1)]);
+        assert_eq!(block.message.unwrap(), FIXTURE_MSG.to_vec());
+    }
+
+    #[test]
+    fn test_max_errors() {
+        let mut input = *FIXTURE_MSG;
+        input[3] ^= 0x44;
+        input[4] ^= 0x44;
+
+        let decoder = RsDecoder::builder().max_errors(1).build().unwrap();
+        assert_eq!(
+            decoder.decode(&input).states,
+            vec![RSState::Uncorrectable(UncorrectableReason::TooManyErrors {
+                found: 2,
+                max: 1
+            })]
+        );
+        let decoder = RsDecoder::builder().max_errors(2).build().unwrap();
+        assert_eq!(decoder.decode(&input).states, vec![RSState::Corrected(2)]);
+    }
+
+    #[test]
+    fn test_conventional_basis() {
+        let mut input = dual_basis::to_conv(FIXTURE_MSG);
+        input[100] ^= 0x01;
+        let decoder = RsDecoder::builder()
+            .input_basis(Basis::Conventional)
+            .output_basis(Basis::Conventional)
+            .verify_parity(true)
+            .build()
+            .unwrap();
+
+        let block = decoder.decode(&input);
+
+        assert_eq!(block.states, vec![RSState::Corrected(1)]);
+        // counted in the input representation
+        assert_eq!(block.corrected_bits, 1);
+        assert_eq!(block.message.unwrap(), dual_basis::to_conv(FIXTURE_MSG));
+    }
+
+    #[test]
+    fn test_shortened_interleaved_strip_parity() {
+        let msg = shifted_generator(Code::E16, 3);
+        let fill = 100;
+        let mut corrupt = msg.clone();
+        corrupt[fill + 20] ^= 0xff;
+        let input = interleave(&[&msg[fill..], &corrupt[fill..]]);
+        let decoder = RsDecoder::builder()
+            .interleave(2)
+            .virtual_fill(fill)
+            .strip_parity(true)
+            .build()
+            .unwrap();
+        assert_eq!(decoder.input_len(), 310);
+        assert_eq!(decoder.output_len(), 246);
+
+        let block = decoder.decode(&input);
+
+        assert_eq!(block.states, vec![RSState::Ok, RSState::Corrected(1)]);
+        assert_eq!(
+            block.message.unwrap(),
+            interleave(&[&msg[fill..], &msg[fill..]])[..246]
+        );
+    }
+
+    #[test]
+    fn test_verify_parity() {
+        let mut input = interleave(&[FIXTURE_MSG, FIXTURE_MSG]);
+        input[7] ^= 0x20;
+        let decoder = RsDecoder::builder()
+            .interleave(2)
+            .verify_parity(true)
+            .build()
+            .unwrap();
+
+        let block = decoder.decode(&input);
+
+        assert_eq!(block.states, vec![RSState::Ok, RSState::Corrected(1)]);
+        assert!(Code::E16.verify_parity(FIXTURE_MSG));
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[250] ^= 1;
+        assert!(!Code::E16.verify_parity(&corrupt));
+    }
+
+    #[test]
+    fn test_build_invalid() {
+        assert_eq!(
+            RsDecoder::builder().interleave(0).build(),
+            Err(UncorrectableReason::InvalidInterleave(0))
+        );
+        assert_eq!(
+            RsDecoder::builder()
+                .code(Code::E8)
+                .virtual_fill(240)
+                .build(),
+            Err(UncorrectableReason::InvalidVirtualFill { got: 240, max: 239 })
+        );
+        let decoder = RsDecoder::builder().build().unwrap();
+        assert!(decoder.decode(&[0u8; 10]).message.is_none());
+    }
+
+    #[test]
+    fn test_decoder_reuse() {
+        let mut decoder = Decoder::new(Code::E16);
+        assert_eq!(decoder.state(), &RSState::NotPerformed);
+        assert!(decoder.message().is_none());
+        let positions = decoder.error_positions.as_ptr();
+
+        for n in 0..=16 {
+            let mut msg = *FIXTURE_MSG;
+            for x in msg.iter_mut().take(n) {
+                *x ^= 0x5a;
+            }
+            let expected = crate::correct_message(&msg);
+
+            let state = decoder.decode(&msg);
+
+            assert_eq!(state, expected.state);
+            assert_eq!(decoder.message().unwrap(), FIXTURE_MSG);
+            assert_eq!(decoder.error_positions(), expected.error_positions);
+            assert_eq!(decoder.error_values(), expected.error_values);
+            assert_eq!(decoder.corrected_bits(), expected.corrected_bits());
+        }
+        // the buffers were never reallocated
+        assert_eq!(decoder.error_positions.as_ptr(), positions);
+
+        let mut msg = *FIXTURE_MSG;
+        msg[..3].fill(0);
+        msg[200] ^= 0x01;
+        let state = decoder.decode_with_erasures(&msg, &[0, 1, 2, 3]);
+        assert_eq!(state, RSState::Corrected(4));
+        assert_eq!(decoder.corrected_erasures(), 3);
+        assert_eq!(decoder.message().unwrap(), FIXTURE_MSG);
+        assert!(matches!(
+            decoder.decode(&msg[..10]),
+            RSState::Uncorrectable(UncorrectableReason::InvalidLength { .. })
+        ));
+        assert!(decoder.message().is_none());
+        assert!(decoder.error_positions().is_empty());
+    }
+}
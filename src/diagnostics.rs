@@ -0,0 +1,281 @@
+//! Traces of the intermediate decoder state for offline analysis of uncorrectable
+//! messages.
+//!
+//! Decoding does not keep its intermediate results, so all that is known of an
+//! uncorrectable message is its [crate::UncorrectableReason]. [correct_message_traced]
+//! corrects the message as usual and, only if it is uncorrectable, decodes it again
+//! recording every step in a [Trace].
+//!
+//! [correct_message_with_syndromes] and [correct_codeblock_with_syndromes] instead
+//! return just the syndromes of every message, e.g., to study error statistics or to
+//! cross-check against a hardware decoder.
+//!
+//! ```
+//! use rs2::diagnostics::correct_message_traced;
+//! use rs2::{Code, RSState};
+//!
+//! let mut msg = Code::E16.encode_message(&[7u8; 223]).unwrap();
+//! for i in 0..17 {
+//!     msg[i * 15] ^= 0x5a;
+//! }
+//! let (block, trace) = correct_message_traced(Code::E16, &msg);
+//! assert!(matches!(block.state, RSState::Uncorrectable(_)));
+//! let trace = trace.unwrap();
+//! assert_eq!(trace.iterations.len(), 32);
+//! ```
+use crate::algo::{calc_syndromes, correct_errata, find_error_locator_with, find_errors};
+use crate::syndromes::SyndromeAccumulator;
+use crate::{dual_basis, Block, Code, Codeblock, RSState, N};
+
+/// State after one Berlekamp-Massey iteration.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Iteration {
+    /// The discrepancy, where 0 leaves the locator unchanged.
+    pub delta: u8,
+    /// Error locator coefficients after the iteration, highest degree first.
+    pub locator: Vec<u8>,
+}
+
+/// Intermediate state of decoding a single message, in conventional basis.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trace {
+    pub code: Code,
+    /// Result of decoding, the same as [Code::correct_message].
+    pub state: RSState,
+    /// The [Code::parity_len] syndromes of the received message.
+    pub syndromes: Vec<u8>,
+    /// Each Berlekamp-Massey iteration, or empty if the syndromes are all zero.
+    pub iterations: Vec<Iteration>,
+    /// The final error locator, highest degree first, whose degree is the number of
+    /// errors claimed.
+    pub locator: Vec<u8>,
+    /// Message positions of the roots of the locator found by the Chien search, in the
+    /// order found. The message is uncorrectable if there are fewer than the degree of
+    /// the locator.
+    pub error_positions: Vec<usize>,
+    /// Error magnitudes computed by Forney's algorithm for
+    /// [Trace::error_positions], in conventional basis, if the positions were
+    /// consistent with the locator.
+    pub error_values: Option<Vec<u8>>,
+    /// Syndromes of the message after applying [Trace::error_values], which are all
+    /// zero if it was corrected.
+    pub residual_syndromes: Option<Vec<u8>>,
+}
+
+/// Decode `input`, a message of `code` in dual basis, recording the intermediate state
+/// whether or not it is correctable.
+///
+/// Each step is recorded as far as it can be performed, e.g., the Chien search even
+/// for a locator of too high a degree, so it is more work than [Code::correct_message].
+pub fn trace(code: Code, input: &[u8; N as usize]) -> Trace {
+    let parity_len = code.parity_len();
+    let mut msg = *input;
+    let state = code.correct_message_in_place(&mut msg);
+
+    let mut conv = *input;
+    dual_basis::to_conv_in_place(&mut conv);
+    let synd = calc_syndromes(&conv, parity_len, code.fcr());
+    let mut trace = Trace {
+        code,
+        state,
+        syndromes: synd[1..].to_vec(),
+        iterations: Vec::new(),
+        locator: vec![1],
+        error_positions: Vec::new(),
+        error_values: None,
+        residual_syndromes: None,
+    };
+    if synd.iter().all(|x| *x == 0) {
+        return trace;
+    }
+
+    let errloc = find_error_locator_with(&synd, parity_len, 0, |_, delta, locator| {
+        trace.iterations.push(Iteration {
+            delta,
+            locator: locator.to_vec(),
+        })
+    });
+    trace.locator = errloc.to_vec();
+
+    let mut errpos = [0i32; N as usize];
+    let found = find_errors(&errloc.reversed(), &mut errpos);
+    let errpos = &errpos[..found.min(N as usize)];
+    trace.error_positions = errpos.iter().map(|p| *p as usize).collect();
+    if found != errloc.len() - 1 || found * 2 > parity_len {
+        return trace;
+    }
+
+    let received = conv;
+    if correct_errata(&mut conv, &synd, errpos, code.fcr()).is_ok() {
+        let values = errpos
+            .iter()
+            .map(|p| received[*p as usize] ^ conv[*p as usize]);
+        trace.error_values = Some(values.collect());
+        let residual = calc_syndromes(&conv, parity_len, code.fcr());
+        trace.residual_syndromes = Some(residual[1..].to_vec());
+    }
+    trace
+}
+
+/// Correct `input` like [Code::correct_message], also returning a [Trace] if it is
+/// uncorrectable. There is no trace for an input that is not 255 symbols.
+pub fn correct_message_traced(code: Code, input: &[u8]) -> (Block, Option<Trace>) {
+    let block = code.correct_message(input);
+    let trace = match (&block.state, <&[u8; N as usize]>::try_from(input)) {
+        (RSState::Uncorrectable(_), Ok(input)) => Some(trace(code, input)),
+        _ => None,
+    };
+    (block, trace)
+}
+
+/// Traces of the uncorrectable messages of `codeblock`, in interleave order, where the
+/// codeblock has been corrected in place with the resulting `states`, so that its
+/// uncorrectable messages are as received.
+pub(crate) fn trace_uncorrectable(code: Code, codeblock: &[u8], states: &[RSState]) -> Vec<Trace> {
+    let depth = states.len();
+    if codeblock.len() != depth * N as usize {
+        return Vec::new();
+    }
+    let mut msg = [0u8; N as usize];
+    let mut traces = Vec::new();
+    for (i, state) in states.iter().enumerate() {
+        if let RSState::Uncorrectable(_) = state {
+            for (m, x) in msg.iter_mut().zip(codeblock[i..].iter().step_by(depth)) {
+                *m = *x;
+            }
+            traces.push(trace(code, &msg));
+        }
+    }
+    traces
+}
+
+/// The [Code::parity_len] syndromes of `input`, a message of `code` in dual basis,
+/// in conventional basis. Syndrome `j` is the received polynomial evaluated at
+/// `GEN^(fcr + j)`, where `fcr` is [Code::fcr], and all are zero for a codeword.
+pub fn syndromes(code: Code, input: &[u8; N as usize]) -> Vec<u8> {
+    let mut conv = *input;
+    dual_basis::to_conv_in_place(&mut conv);
+    calc_syndromes(&conv, code.parity_len(), code.fcr())[1..].to_vec()
+}
+
+/// Correct `input` like [Code::correct_message], also returning its [syndromes] as
+/// received. There are no syndromes for an input that is not 255 symbols.
+pub fn correct_message_with_syndromes(code: Code, input: &[u8]) -> (Block, Option<Vec<u8>>) {
+    let block = code.correct_message(input);
+    let synd = <&[u8; N as usize]>::try_from(input)
+        .ok()
+        .map(|input| syndromes(code, input));
+    (block, synd)
+}
+
+/// Correct `input` like [Code::correct_codeblock], also returning the [syndromes] of
+/// each message as received, in interleave order. There are no syndromes if the
+/// interleave or input length are invalid.
+pub fn correct_codeblock_with_syndromes(
+    code: Code,
+    input: &[u8],
+    interleave: u8,
+) -> (Codeblock, Vec<Vec<u8>>) {
+    let block = code.correct_codeblock(input, interleave);
+    let mut synd = Vec::new();
+    if let Ok(mut acc) = SyndromeAccumulator::new(code, interleave) {
+        if acc.update(input) == input.len() && acc.is_complete() {
+            synd = (0..interleave as usize)
+                .map(|i| acc.syndromes(i)[1..].to_vec())
+                .collect();
+        }
+    }
+    (block, synd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::FIXTURE_MSG;
+    use crate::UncorrectableReason;
+
+    #[test]
+    fn test_trace_correctable() {
+        let mut input = *FIXTURE_MSG;
+        input[3] ^= 0x10;
+        input[200] ^= 0xff;
+        let trace = trace(Code::E16, &input);
+
+        assert_eq!(trace.state, RSState::Corrected(2));
+        assert_eq!(trace.syndromes.len(), 32);
+        assert_eq!(trace.iterations.len(), 32);
+        assert_eq!(trace.iterations.last().unwrap().locator, trace.locator);
+        assert_eq!(trace.locator.len(), 3);
+        let mut positions = trace.error_positions.clone();
+        positions.sort();
+        assert_eq!(positions, [3, 200]);
+        assert_eq!(trace.error_values.as_ref().unwrap().len(), 2);
+        assert_eq!(trace.residual_syndromes, Some(vec![0; 32]));
+
+        let (block, trace) = correct_message_traced(Code::E16, &input);
+        assert_eq!(block.state, RSState::Corrected(2));
+        assert!(trace.is_none());
+
+        let clean = super::trace(Code::E16, FIXTURE_MSG);
+        assert_eq!(clean.state, RSState::Ok);
+        assert!(clean.iterations.is_empty());
+        assert_eq!(clean.syndromes, vec![0; 32]);
+    }
+
+    #[test]
+    fn test_trace_uncorrectable() {
+        let mut input = *FIXTURE_MSG;
+        for i in 0..20 {
+            input[i * 12] ^= 0x33;
+        }
+        let (block, trace) = correct_message_traced(Code::E8, &input);
+        let trace = trace.unwrap();
+        assert_eq!(trace.state, block.state);
+        assert_eq!(trace.code, Code::E8);
+        assert_eq!(trace.syndromes.len(), 16);
+        assert_eq!(trace.iterations.len(), 16);
+        match block.state {
+            RSState::Uncorrectable(UncorrectableReason::LocatorDegreeMismatch {
+                degree,
+                roots,
+            }) => {
+                assert_eq!(trace.locator.len() - 1, degree);
+                assert_eq!(trace.error_positions.len(), roots);
+                assert_eq!(trace.error_values, None);
+            }
+            ref state => panic!("unexpected state {state:?}"),
+        }
+
+        let (block, trace) = correct_message_traced(Code::E8, &input[1..]);
+        assert!(matches!(block.state, RSState::Uncorrectable(_)));
+        assert!(trace.is_none());
+    }
+
+    #[test]
+    fn test_syndromes() {
+        let mut input = *FIXTURE_MSG;
+        input[30] ^= 0x04;
+        let (block, synd) = correct_message_with_syndromes(Code::E16, &input);
+        assert_eq!(block.state, RSState::Corrected(1));
+        let synd = synd.unwrap();
+        assert_eq!(synd, trace(Code::E16, &input).syndromes);
+        assert!(synd.iter().all(|s| *s != 0));
+        assert_eq!(syndromes(Code::E8, FIXTURE_MSG).len(), 16);
+        assert!(correct_message_with_syndromes(Code::E16, &input[1..])
+            .1
+            .is_none());
+
+        let codeblock = crate::interleave::interleave(&[FIXTURE_MSG, &input]);
+        let (block, synd) = correct_codeblock_with_syndromes(Code::E16, &codeblock, 2);
+        assert_eq!(block.states, vec![RSState::Ok, RSState::Corrected(1)]);
+        assert_eq!(synd, vec![vec![0; 32], syndromes(Code::E16, &input)]);
+        let (block, synd) = correct_codeblock_with_syndromes(Code::E16, &codeblock[1..], 2);
+        assert!(block.message.is_none());
+        assert!(synd.is_empty());
+        assert!(correct_codeblock_with_syndromes(Code::E16, &codeblock, 0)
+            .1
+            .is_empty());
+    }
+}
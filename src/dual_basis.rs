@@ -1,57 +1,161 @@
-// LUTs for converting between dual-basis and convential representation.
-// These values are from CCSDS 131.0-B3 Appendix E.
-const DUAL_TO_CONV: &[u8] = &[
-    0x00, 0xcc, 0xac, 0x60, 0x79, 0xb5, 0xd5, 0x19, 0xf0, 0x3c, 0x5c, 0x90, 0x89, 0x45, 0x25, 0xe9,
-    0xfd, 0x31, 0x51, 0x9d, 0x84, 0x48, 0x28, 0xe4, 0x0d, 0xc1, 0xa1, 0x6d, 0x74, 0xb8, 0xd8, 0x14,
-    0x2e, 0xe2, 0x82, 0x4e, 0x57, 0x9b, 0xfb, 0x37, 0xde, 0x12, 0x72, 0xbe, 0xa7, 0x6b, 0x0b, 0xc7,
-    0xd3, 0x1f, 0x7f, 0xb3, 0xaa, 0x66, 0x06, 0xca, 0x23, 0xef, 0x8f, 0x43, 0x5a, 0x96, 0xf6, 0x3a,
-    0x42, 0x8e, 0xee, 0x22, 0x3b, 0xf7, 0x97, 0x5b, 0xb2, 0x7e, 0x1e, 0xd2, 0xcb, 0x07, 0x67, 0xab,
-    0xbf, 0x73, 0x13, 0xdf, 0xc6, 0x0a, 0x6a, 0xa6, 0x4f, 0x83, 0xe3, 0x2f, 0x36, 0xfa, 0x9a, 0x56,
-    0x6c, 0xa0, 0xc0, 0x0c, 0x15, 0xd9, 0xb9, 0x75, 0x9c, 0x50, 0x30, 0xfc, 0xe5, 0x29, 0x49, 0x85,
-    0x91, 0x5d, 0x3d, 0xf1, 0xe8, 0x24, 0x44, 0x88, 0x61, 0xad, 0xcd, 0x01, 0x18, 0xd4, 0xb4, 0x78,
-    0xc5, 0x09, 0x69, 0xa5, 0xbc, 0x70, 0x10, 0xdc, 0x35, 0xf9, 0x99, 0x55, 0x4c, 0x80, 0xe0, 0x2c,
-    0x38, 0xf4, 0x94, 0x58, 0x41, 0x8d, 0xed, 0x21, 0xc8, 0x04, 0x64, 0xa8, 0xb1, 0x7d, 0x1d, 0xd1,
-    0xeb, 0x27, 0x47, 0x8b, 0x92, 0x5e, 0x3e, 0xf2, 0x1b, 0xd7, 0xb7, 0x7b, 0x62, 0xae, 0xce, 0x02,
-    0x16, 0xda, 0xba, 0x76, 0x6f, 0xa3, 0xc3, 0x0f, 0xe6, 0x2a, 0x4a, 0x86, 0x9f, 0x53, 0x33, 0xff,
-    0x87, 0x4b, 0x2b, 0xe7, 0xfe, 0x32, 0x52, 0x9e, 0x77, 0xbb, 0xdb, 0x17, 0x0e, 0xc2, 0xa2, 0x6e,
-    0x7a, 0xb6, 0xd6, 0x1a, 0x03, 0xcf, 0xaf, 0x63, 0x8a, 0x46, 0x26, 0xea, 0xf3, 0x3f, 0x5f, 0x93,
-    0xa9, 0x65, 0x05, 0xc9, 0xd0, 0x1c, 0x7c, 0xb0, 0x59, 0x95, 0xf5, 0x39, 0x20, 0xec, 0x8c, 0x40,
-    0x54, 0x98, 0xf8, 0x34, 0x2d, 0xe1, 0x81, 0x4d, 0xa4, 0x68, 0x08, 0xc4, 0xdd, 0x11, 0x71, 0xbd,
+// Conversion between dual-basis and conventional representation. Both are linear
+// maps, given in CCSDS 131.0-B3 Appendix E as 8x8 bit matrices, from which the LUTs
+// are generated.
+
+/// Conventional to dual-basis transformation matrix, T_al. Row `i` is the dual-basis
+/// representation of conventional bit `z_i`, where `z_0` is the most significant bit,
+/// i.e., the dual-basis byte is the row vector of conventional bits times T_al.
+const T_AL: [u8; 8] = [
+    0b1000_1101,
+    0b1110_1111,
+    0b1110_1100,
+    0b1000_0110,
+    0b1111_1010,
+    0b1001_1001,
+    0b1010_1111,
+    0b0111_1011,
 ];
 
-const CONV_TO_DUAL: &[u8] = &[
-    0x00, 0x7b, 0xaf, 0xd4, 0x99, 0xe2, 0x36, 0x4d, 0xfa, 0x81, 0x55, 0x2e, 0x63, 0x18, 0xcc, 0xb7,
-    0x86, 0xfd, 0x29, 0x52, 0x1f, 0x64, 0xb0, 0xcb, 0x7c, 0x07, 0xd3, 0xa8, 0xe5, 0x9e, 0x4a, 0x31,
-    0xec, 0x97, 0x43, 0x38, 0x75, 0x0e, 0xda, 0xa1, 0x16, 0x6d, 0xb9, 0xc2, 0x8f, 0xf4, 0x20, 0x5b,
-    0x6a, 0x11, 0xc5, 0xbe, 0xf3, 0x88, 0x5c, 0x27, 0x90, 0xeb, 0x3f, 0x44, 0x09, 0x72, 0xa6, 0xdd,
-    0xef, 0x94, 0x40, 0x3b, 0x76, 0x0d, 0xd9, 0xa2, 0x15, 0x6e, 0xba, 0xc1, 0x8c, 0xf7, 0x23, 0x58,
-    0x69, 0x12, 0xc6, 0xbd, 0xf0, 0x8b, 0x5f, 0x24, 0x93, 0xe8, 0x3c, 0x47, 0x0a, 0x71, 0xa5, 0xde,
-    0x03, 0x78, 0xac, 0xd7, 0x9a, 0xe1, 0x35, 0x4e, 0xf9, 0x82, 0x56, 0x2d, 0x60, 0x1b, 0xcf, 0xb4,
-    0x85, 0xfe, 0x2a, 0x51, 0x1c, 0x67, 0xb3, 0xc8, 0x7f, 0x04, 0xd0, 0xab, 0xe6, 0x9d, 0x49, 0x32,
-    0x8d, 0xf6, 0x22, 0x59, 0x14, 0x6f, 0xbb, 0xc0, 0x77, 0x0c, 0xd8, 0xa3, 0xee, 0x95, 0x41, 0x3a,
-    0x0b, 0x70, 0xa4, 0xdf, 0x92, 0xe9, 0x3d, 0x46, 0xf1, 0x8a, 0x5e, 0x25, 0x68, 0x13, 0xc7, 0xbc,
-    0x61, 0x1a, 0xce, 0xb5, 0xf8, 0x83, 0x57, 0x2c, 0x9b, 0xe0, 0x34, 0x4f, 0x02, 0x79, 0xad, 0xd6,
-    0xe7, 0x9c, 0x48, 0x33, 0x7e, 0x05, 0xd1, 0xaa, 0x1d, 0x66, 0xb2, 0xc9, 0x84, 0xff, 0x2b, 0x50,
-    0x62, 0x19, 0xcd, 0xb6, 0xfb, 0x80, 0x54, 0x2f, 0x98, 0xe3, 0x37, 0x4c, 0x01, 0x7a, 0xae, 0xd5,
-    0xe4, 0x9f, 0x4b, 0x30, 0x7d, 0x06, 0xd2, 0xa9, 0x1e, 0x65, 0xb1, 0xca, 0x87, 0xfc, 0x28, 0x53,
-    0x8e, 0xf5, 0x21, 0x5a, 0x17, 0x6c, 0xb8, 0xc3, 0x74, 0x0f, 0xdb, 0xa0, 0xed, 0x96, 0x42, 0x39,
-    0x08, 0x73, 0xa7, 0xdc, 0x91, 0xea, 0x3e, 0x45, 0xf2, 0x89, 0x5d, 0x26, 0x6b, 0x10, 0xc4, 0xbf,
+/// Dual-basis to conventional transformation matrix, the inverse of [T_AL].
+const T_AL_INV: [u8; 8] = [
+    0b1100_0101,
+    0b0100_0010,
+    0b0010_1110,
+    0b1111_1101,
+    0b1111_0000,
+    0b0111_1001,
+    0b1010_1100,
+    0b1100_1100,
 ];
 
+/// LUT of the linear map with the matrix `rows`, as laid out for [T_AL].
+const fn basis_table(rows: &[u8; 8]) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut x = 0;
+    while x < 256 {
+        let mut i = 0;
+        while i < 8 {
+            if x & (0x80 >> i) != 0 {
+                table[x] ^= rows[i];
+            }
+            i += 1;
+        }
+        x += 1;
+    }
+    table
+}
+
+const DUAL_TO_CONV: [u8; 256] = basis_table(&T_AL_INV);
+const CONV_TO_DUAL: [u8; 256] = basis_table(&T_AL);
+
+// A transcription error in either matrix fails the build, as the maps would no longer
+// be inverses of each other.
+const _: () = {
+    let mut x = 0;
+    while x < 256 {
+        assert!(DUAL_TO_CONV[CONV_TO_DUAL[x] as usize] as usize == x);
+        assert!(CONV_TO_DUAL[DUAL_TO_CONV[x] as usize] as usize == x);
+        x += 1;
+    }
+};
+
+/// Split the linear map `table` into tables for the low and high nibbles, where
+/// table[x] = lo[x & 0xf] ^ hi[x >> 4].
+const fn nibble_tables(table: &[u8]) -> ([u8; 16], [u8; 16]) {
+    let mut lo = [0u8; 16];
+    let mut hi = [0u8; 16];
+    let mut i = 0;
+    while i < 16 {
+        lo[i] = table[i];
+        hi[i] = table[i << 4];
+        i += 1;
+    }
+    (lo, hi)
+}
+
+const DUAL_TO_CONV_NIBBLES: ([u8; 16], [u8; 16]) = nibble_tables(&DUAL_TO_CONV);
+const CONV_TO_DUAL_NIBBLES: ([u8; 16], [u8; 16]) = nibble_tables(&CONV_TO_DUAL);
+
+/// Apply the basis conversion `table`, with nibble tables `nibbles`, in place using a
+/// SIMD backend when available.
+fn convert_in_place(table: &[u8], nibbles: &([u8; 16], [u8; 16]), msg: &mut [u8]) {
+    #[cfg(target_arch = "x86_64")]
+    if crate::simd::has_avx2() {
+        // SAFETY: the required CPU features were detected.
+        return unsafe { crate::simd::avx2::map_linear(&nibbles.0, &nibbles.1, msg) };
+    }
+    #[cfg(target_arch = "aarch64")]
+    if crate::simd::has_neon() {
+        // SAFETY: the required CPU features were detected.
+        return unsafe { crate::simd::neon::map_linear(&nibbles.0, &nibbles.1, msg) };
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let _ = nibbles;
+    for x in msg.iter_mut() {
+        *x = table[*x as usize];
+    }
+}
+
 /// Convert data to conventional format.
+#[cfg(feature = "std")]
 pub fn to_conv(msg: &[u8]) -> Vec<u8> {
-    let mut out = vec![0u8; msg.len()];
-    for (i, x) in msg.iter().enumerate() {
-        out[i as usize] = DUAL_TO_CONV[*x as usize];
-    }
+    let mut out = msg.to_vec();
+    to_conv_in_place(&mut out);
     out
 }
 
 /// Convert data to dual-basis format.
+#[cfg(feature = "std")]
 pub fn to_dual(msg: &[u8]) -> Vec<u8> {
-    let mut out = vec![0u8; msg.len()];
-    for (i, x) in msg.iter().enumerate() {
-        out[i as usize] = CONV_TO_DUAL[*x as usize];
-    }
+    let mut out = msg.to_vec();
+    to_dual_in_place(&mut out);
     out
 }
+
+/// Convert data to conventional format in place.
+pub fn to_conv_in_place(msg: &mut [u8]) {
+    convert_in_place(&DUAL_TO_CONV, &DUAL_TO_CONV_NIBBLES, msg);
+}
+
+/// Convert data to dual-basis format in place.
+pub fn to_dual_in_place(msg: &mut [u8]) {
+    convert_in_place(&CONV_TO_DUAL, &CONV_TO_DUAL_NIBBLES, msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables() {
+        // spot check against values from the previously hand-transcribed tables
+        assert_eq!(DUAL_TO_CONV[..4], [0x00, 0xcc, 0xac, 0x60]);
+        assert_eq!(DUAL_TO_CONV[0x80], 0xc5);
+        assert_eq!(DUAL_TO_CONV[0xff], 0xbd);
+        assert_eq!(CONV_TO_DUAL[..4], [0x00, 0x7b, 0xaf, 0xd4]);
+        assert_eq!(CONV_TO_DUAL[0x80], 0x8d);
+        assert_eq!(CONV_TO_DUAL[0xff], 0xbf);
+    }
+
+    #[test]
+    fn test_nibble_tables() {
+        for (table, (lo, hi)) in [
+            (DUAL_TO_CONV, DUAL_TO_CONV_NIBBLES),
+            (CONV_TO_DUAL, CONV_TO_DUAL_NIBBLES),
+        ] {
+            for x in 0..256 {
+                assert_eq!(table[x], lo[x & 0xf] ^ hi[x >> 4], "x={x}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let msg: Vec<u8> = (0..1000).map(|i| (i * 13) as u8).collect();
+
+        let conv = to_conv(&msg);
+
+        assert_eq!(conv[1], DUAL_TO_CONV[13]);
+        assert_eq!(to_dual(&conv), msg);
+    }
+}
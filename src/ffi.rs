@@ -0,0 +1,269 @@
+//! C ABI for linking the decoder into C/C++ ground software. Requires the `ffi`
+//! feature, which exports the functions below from the `rs2` shared and static
+//! libraries, built with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib,staticlib`. The
+//! corresponding declarations are in `include/rs2.h`.
+//!
+//! All functions return [RS2_SUCCESS], or a negative error code if the arguments are
+//! invalid, e.g., a null pointer, in which case the outputs are not written. Whether
+//! the data could be corrected is reported in the result struct.
+use std::ffi::c_int;
+
+use crate::{Code, RSState, UncorrectableReason, MAX_INTERLEAVE, N};
+
+pub const RS2_SUCCESS: c_int = 0;
+/// A required pointer argument was null.
+pub const RS2_ERR_NULL: c_int = -1;
+/// A length or interleave argument was invalid.
+pub const RS2_ERR_INVALID_ARG: c_int = -2;
+
+/// Disposition of the RS process; see [RSState].
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rs2State {
+    Ok = 0,
+    Corrected = 1,
+    Uncorrectable = 2,
+    NotPerformed = 3,
+}
+
+/// Reason a message could not be corrected; see [UncorrectableReason].
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rs2Reason {
+    None = 0,
+    TooManyErrors = 1,
+    LocatorDegreeMismatch = 2,
+    ZeroErrorMagnitudeDenominator = 3,
+    ResidualSyndrome = 4,
+    InvalidLength = 5,
+    InvalidInterleave = 6,
+    InvalidErasures = 7,
+    InvalidVirtualFill = 8,
+    ErrorInVirtualFill = 9,
+    UndetectableInterleave = 10,
+    ParityMismatch = 11,
+}
+
+/// Result of correcting a single message.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Rs2Result {
+    pub state: Rs2State,
+    /// Number of symbols corrected if the state is corrected, otherwise 0.
+    pub num_corrected: i32,
+    pub reason: Rs2Reason,
+}
+
+/// Result of correcting an interleaved codeblock. Only the first `interleave`
+/// entries of `messages` are set.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Rs2CodeblockResult {
+    pub interleave: u8,
+    pub messages: [Rs2Result; MAX_INTERLEAVE as usize],
+}
+
+impl From<&RSState> for Rs2Result {
+    fn from(state: &RSState) -> Self {
+        let (state, num_corrected, reason) = match state {
+            RSState::Ok => (Rs2State::Ok, 0, Rs2Reason::None),
+            RSState::Corrected(n) => (Rs2State::Corrected, *n, Rs2Reason::None),
+            RSState::Uncorrectable(reason) => {
+                let reason = match reason {
+                    UncorrectableReason::TooManyErrors { .. } => Rs2Reason::TooManyErrors,
+                    UncorrectableReason::LocatorDegreeMismatch { .. } => {
+                        Rs2Reason::LocatorDegreeMismatch
+                    }
+                    UncorrectableReason::ZeroErrorMagnitudeDenominator => {
+                        Rs2Reason::ZeroErrorMagnitudeDenominator
+                    }
+                    UncorrectableReason::ResidualSyndrome => Rs2Reason::ResidualSyndrome,
+                    UncorrectableReason::InvalidLength { .. } => Rs2Reason::InvalidLength,
+                    UncorrectableReason::InvalidInterleave(_) => Rs2Reason::InvalidInterleave,
+                    UncorrectableReason::UndetectableInterleave { .. } => {
+                        Rs2Reason::UndetectableInterleave
+                    }
+                    UncorrectableReason::InvalidErasures => Rs2Reason::InvalidErasures,
+                    UncorrectableReason::InvalidVirtualFill { .. } => Rs2Reason::InvalidVirtualFill,
+                    UncorrectableReason::ErrorInVirtualFill => Rs2Reason::ErrorInVirtualFill,
+                    UncorrectableReason::ParityMismatch => Rs2Reason::ParityMismatch,
+                };
+                (Rs2State::Uncorrectable, 0, reason)
+            }
+            RSState::NotPerformed => (Rs2State::NotPerformed, 0, Rs2Reason::None),
+        };
+        Rs2Result {
+            state,
+            num_corrected,
+            reason,
+        }
+    }
+}
+
+/// Correct a 255 byte RS(255,223) message, writing the corrected message to `output`,
+/// which must be 255 bytes and may be the same as `input`. `output` is only written
+/// if the message is not uncorrectable.
+///
+/// # Safety
+/// `input` must be valid for reads of `len` bytes, `output` must be valid for writes
+/// of 255 bytes, and `result` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rs2_correct_message(
+    input: *const u8,
+    len: usize,
+    output: *mut u8,
+    result: *mut Rs2Result,
+) -> c_int {
+    if input.is_null() || output.is_null() || result.is_null() {
+        return RS2_ERR_NULL;
+    }
+    if len != N as usize {
+        return RS2_ERR_INVALID_ARG;
+    }
+    let mut msg = [0u8; N as usize];
+    msg.copy_from_slice(std::slice::from_raw_parts(input, len));
+    let state = Code::E16.correct_message_in_place(&mut msg);
+    if !matches!(state, RSState::Uncorrectable(_)) {
+        std::ptr::copy_nonoverlapping(msg.as_ptr(), output, msg.len());
+    }
+    result.write(Rs2Result::from(&state));
+    RS2_SUCCESS
+}
+
+/// Correct an RS(255,223) codeblock of `interleave` messages, writing the corrected
+/// codeblock to `output`, which must be `len` bytes and may be the same as `input`.
+/// `output` is only written if none of the messages are uncorrectable.
+///
+/// # Safety
+/// `input` must be valid for reads and `output` for writes of `len` bytes, and
+/// `result` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rs2_correct_codeblock(
+    input: *const u8,
+    len: usize,
+    interleave: u8,
+    output: *mut u8,
+    result: *mut Rs2CodeblockResult,
+) -> c_int {
+    if input.is_null() || output.is_null() || result.is_null() {
+        return RS2_ERR_NULL;
+    }
+    if !(1..=MAX_INTERLEAVE).contains(&interleave) || len != interleave as usize * N as usize {
+        return RS2_ERR_INVALID_ARG;
+    }
+    let block = crate::correct_codeblock(std::slice::from_raw_parts(input, len), interleave);
+    let mut zult = Rs2CodeblockResult {
+        interleave,
+        messages: [Rs2Result::from(&RSState::NotPerformed); MAX_INTERLEAVE as usize],
+    };
+    for (r, state) in zult.messages.iter_mut().zip(block.states.iter()) {
+        *r = Rs2Result::from(state);
+    }
+    if let Some(msg) = block.message {
+        std::ptr::copy_nonoverlapping(msg.as_ptr(), output, msg.len());
+    }
+    result.write(zult);
+    RS2_SUCCESS
+}
+
+/// Encode 223 bytes of data into a 255 byte RS(255,223) message written to `output`.
+///
+/// # Safety
+/// `data` must be valid for reads of `len` bytes and `output` must be valid for writes
+/// of 255 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn rs2_encode_message(data: *const u8, len: usize, output: *mut u8) -> c_int {
+    if data.is_null() || output.is_null() {
+        return RS2_ERR_NULL;
+    }
+    match crate::encode_message(std::slice::from_raw_parts(data, len)) {
+        Ok(msg) => {
+            std::ptr::copy_nonoverlapping(msg.as_ptr(), output, msg.len());
+            RS2_SUCCESS
+        }
+        Err(_) => RS2_ERR_INVALID_ARG,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{interleave, FIXTURE_MSG};
+    use std::mem::MaybeUninit;
+
+    #[test]
+    fn test_ffi_correct_message() {
+        let mut msg = *FIXTURE_MSG;
+        msg[2] ^= 0xaa;
+        let mut result = MaybeUninit::<Rs2Result>::uninit();
+
+        let rc = unsafe {
+            rs2_correct_message(
+                msg.as_ptr(),
+                msg.len(),
+                msg.as_mut_ptr(),
+                result.as_mut_ptr(),
+            )
+        };
+
+        assert_eq!(rc, RS2_SUCCESS);
+        let result = unsafe { result.assume_init() };
+        assert_eq!(result.state, Rs2State::Corrected);
+        assert_eq!(result.num_corrected, 1);
+        assert_eq!(msg, *FIXTURE_MSG);
+
+        let rc = unsafe {
+            rs2_correct_message(msg.as_ptr(), 100, msg.as_mut_ptr(), std::ptr::null_mut())
+        };
+        assert_eq!(rc, RS2_ERR_NULL);
+    }
+
+    #[test]
+    fn test_ffi_correct_codeblock() {
+        let mut uncorrectable = *FIXTURE_MSG;
+        for x in uncorrectable.iter_mut().take(20) {
+            *x ^= 0xff;
+        }
+        let input = interleave(&[FIXTURE_MSG, &uncorrectable]);
+        let mut output = vec![0u8; input.len()];
+        let mut result = MaybeUninit::<Rs2CodeblockResult>::uninit();
+
+        let rc = unsafe {
+            rs2_correct_codeblock(
+                input.as_ptr(),
+                input.len(),
+                2,
+                output.as_mut_ptr(),
+                result.as_mut_ptr(),
+            )
+        };
+
+        assert_eq!(rc, RS2_SUCCESS);
+        let zult = unsafe { result.assume_init() };
+        assert_eq!(zult.interleave, 2);
+        assert_eq!(zult.messages[0].state, Rs2State::Ok);
+        assert_eq!(zult.messages[1].state, Rs2State::Uncorrectable);
+        assert_ne!(zult.messages[1].reason, Rs2Reason::None);
+        assert_eq!(output, vec![0u8; input.len()]);
+
+        let rc = unsafe {
+            rs2_correct_codeblock(
+                input.as_ptr(),
+                input.len(),
+                3,
+                output.as_mut_ptr(),
+                result.as_mut_ptr(),
+            )
+        };
+        assert_eq!(rc, RS2_ERR_INVALID_ARG);
+    }
+
+    #[test]
+    fn test_ffi_encode_message() {
+        let mut output = [0u8; 255];
+        let rc = unsafe { rs2_encode_message(FIXTURE_MSG.as_ptr(), 223, output.as_mut_ptr()) };
+        assert_eq!(rc, RS2_SUCCESS);
+        assert_eq!(output, *FIXTURE_MSG);
+    }
+}
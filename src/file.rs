@@ -0,0 +1,157 @@
+//! Decoding of capture files using memory mapping, designed for multi-gigabyte pass
+//! recordings.
+//!
+//! ```no_run
+//! use rs2::file::{decode_file, DecodeOptions};
+//!
+//! let options = DecodeOptions {
+//!     output: Some("frames.dat".into()),
+//!     strip_parity: true,
+//!     ..Default::default()
+//! };
+//! let summary = decode_file("codeblocks.dat", &options).unwrap();
+//! println!("{summary}");
+//! ```
+use std::fs::File;
+use std::io::{BufWriter, Error, ErrorKind, Result, Write};
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+
+use crate::reader::partial_block;
+use crate::stats::DecodeSummary;
+use crate::{Code, UncorrectableReason, MAX_INTERLEAVE, N};
+
+/// Options for [decode_file].
+#[derive(Debug, PartialEq, Clone)]
+pub struct DecodeOptions {
+    /// Number of interleaved RS messages in each codeblock.
+    pub interleave: u8,
+    /// RS code used to encode the codeblocks.
+    pub code: Code,
+    /// Length of the sync marker preceding each codeblock, which is skipped without
+    /// being checked. Use 0 for back-to-back codeblocks.
+    pub asm_len: usize,
+    /// File to write the corrected codeblocks to. Uncorrectable codeblocks are not
+    /// written. If None, codeblocks are only decoded for the summary.
+    pub output: Option<PathBuf>,
+    /// Whether to strip the check symbols from the written codeblocks, i.e., write
+    /// only the transfer frames.
+    pub strip_parity: bool,
+}
+
+impl Default for DecodeOptions {
+    /// Interleave 4 RS(255,223) codeblocks without ASMs or output.
+    fn default() -> Self {
+        DecodeOptions {
+            interleave: 4,
+            code: Code::E16,
+            asm_len: 0,
+            output: None,
+            strip_parity: false,
+        }
+    }
+}
+
+/// Correct every codeblock in the file at `path`, returning the summary of the
+/// results.
+///
+/// The file is memory mapped so codeblocks are decoded directly from the page cache
+/// rather than copied through a read buffer. The file must not be modified while it
+/// is being decoded.
+///
+/// A file that does not end on a codeblock boundary is an [ErrorKind::UnexpectedEof]
+/// error, after all complete codeblocks have been written.
+pub fn decode_file<P: AsRef<Path>>(path: P, options: &DecodeOptions) -> Result<DecodeSummary> {
+    if options.interleave == 0 || options.interleave > MAX_INTERLEAVE {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            UncorrectableReason::InvalidInterleave(options.interleave).to_string(),
+        ));
+    }
+    let file = File::open(path)?;
+    // SAFETY: the map is only read, and the documented requirement is that the file is
+    // not modified while mapped.
+    let mmap = unsafe { Mmap::map(&file)? };
+    let mut writer = match &options.output {
+        Some(path) => Some(BufWriter::new(File::create(path)?)),
+        None => None,
+    };
+
+    let block_len = options.interleave as usize * N as usize;
+    let frame_len = options.interleave as usize * options.code.data_len();
+    let mut summary = DecodeSummary::new();
+    let mut chunks = mmap.chunks_exact(options.asm_len + block_len);
+    for chunk in chunks.by_ref() {
+        let block = options
+            .code
+            .correct_codeblock(&chunk[options.asm_len..], options.interleave);
+        summary.add_codeblock(&block);
+        if let (Some(writer), Some(data)) = (writer.as_mut(), &block.message) {
+            let data = if options.strip_parity {
+                &data[..frame_len]
+            } else {
+                &data[..]
+            };
+            writer.write_all(data)?;
+        }
+    }
+    if let Some(writer) = writer.as_mut() {
+        writer.flush()?;
+    }
+
+    let remainder = chunks.remainder().len();
+    if remainder > 0 {
+        return Err(partial_block(remainder, options.asm_len + block_len));
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{interleave, FIXTURE_MSG};
+
+    fn tempdir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rs2-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_decode_file() {
+        let dir = tempdir();
+        let input = dir.join("input.dat");
+        let output = dir.join("output.dat");
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[17] ^= 0x01;
+        let mut uncorrectable = *FIXTURE_MSG;
+        uncorrectable[..40].fill(0);
+        let mut data = vec![0xaa; 4];
+        data.extend(interleave(&[FIXTURE_MSG, &corrupt]));
+        data.extend([0xaa; 4]);
+        data.extend(interleave(&[&uncorrectable, FIXTURE_MSG]));
+        std::fs::write(&input, &data).unwrap();
+        let options = DecodeOptions {
+            interleave: 2,
+            asm_len: 4,
+            output: Some(output.clone()),
+            strip_parity: true,
+            ..Default::default()
+        };
+
+        let summary = decode_file(&input, &options).unwrap();
+
+        assert_eq!(summary.ok, 2);
+        assert_eq!(summary.corrected, 1);
+        assert_eq!(summary.uncorrectable, 1);
+        let written = std::fs::read(&output).unwrap();
+        assert_eq!(written, interleave(&[FIXTURE_MSG, FIXTURE_MSG])[..446]);
+
+        std::fs::write(&input, &data[..600]).unwrap();
+        let err = decode_file(&input, &options).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,204 @@
+//! Reading of capture files that are still being written, e.g., during a live pass.
+//!
+//! A [FollowReader] is a [Read] that waits for more data at the end of the file
+//! rather than reporting the end of the stream, so a [crate::pipeline::Pipeline]
+//! decodes a recording concurrently with it being written.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use rs2::follow::FollowReader;
+//! use rs2::pipeline::{Config, Pipeline};
+//!
+//! let reader = FollowReader::open("pass.cadu")
+//!     .unwrap()
+//!     .idle_timeout(Some(Duration::from_secs(30)));
+//! for frame in Pipeline::new(reader, Config::default()).frames() {
+//!     println!("{:?}", frame.unwrap().states);
+//! }
+//! ```
+//!
+//! If the file is truncated, e.g., the recorder restarted, reading restarts from the
+//! beginning of the file. Truncation is detected when the file is shorter than what
+//! has already been read, so a file rewritten past that length between checks is not
+//! detected. If the path is replaced by a new file, e.g., by log style rotation, the
+//! rest of the old file is read and then the new file is read from its start.
+//! Rotation is only detected on Unix.
+use std::fs::File;
+use std::io::{ErrorKind, Read, Result, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default time to wait between checks for more data.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reads a file as it grows; see the [module documentation](self).
+pub struct FollowReader {
+    path: PathBuf,
+    file: File,
+    pos: u64,
+    poll_interval: Duration,
+    idle_timeout: Option<Duration>,
+}
+
+impl FollowReader {
+    /// Open the file at `path`, reading from its start.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Ok(FollowReader {
+            file: File::open(&path)?,
+            path,
+            pos: 0,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            idle_timeout: None,
+        })
+    }
+
+    /// Set the time to wait between checks for more data. Defaults to
+    /// [DEFAULT_POLL_INTERVAL].
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set how long the file may go without growing before the end of the stream is
+    /// reported, e.g., once the pass is over. Defaults to None, i.e., wait forever.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Offset in the current file of the next byte to read.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Check whether the file was truncated or replaced, reopening or rewinding it if
+    /// so. Returns true if there may be more data to read.
+    fn check(&mut self) -> Result<bool> {
+        if self.rotated()? {
+            self.file = File::open(&self.path)?;
+            self.pos = 0;
+            return Ok(true);
+        }
+        let len = self.file.metadata()?.len();
+        if len < self.pos {
+            self.file.seek(SeekFrom::Start(0))?;
+            self.pos = 0;
+            return Ok(len > 0);
+        }
+        Ok(len > self.pos)
+    }
+
+    /// True if the path now refers to a different file than the one open. A missing
+    /// path, e.g., mid-rotation, is not a rotation until the new file appears.
+    #[cfg(unix)]
+    fn rotated(&self) -> Result<bool> {
+        use std::os::unix::fs::MetadataExt;
+        let current = match std::fs::metadata(&self.path) {
+            Ok(meta) => meta,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        let open = self.file.metadata()?;
+        Ok(current.dev() != open.dev() || current.ino() != open.ino())
+    }
+
+    #[cfg(not(unix))]
+    fn rotated(&self) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl Read for FollowReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let start = Instant::now();
+        loop {
+            let n = self.file.read(buf)?;
+            if n > 0 {
+                self.pos += n as u64;
+                return Ok(n);
+            }
+            if self.check()? {
+                continue;
+            }
+            if let Some(timeout) = self.idle_timeout {
+                if start.elapsed() >= timeout {
+                    return Ok(0);
+                }
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn reader(path: &Path) -> FollowReader {
+        FollowReader::open(path)
+            .unwrap()
+            .poll_interval(Duration::from_millis(5))
+            .idle_timeout(Some(Duration::from_millis(300)))
+    }
+
+    #[test]
+    fn test_follow_growing() {
+        let dir = std::env::temp_dir().join(format!("rs2-follow-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("growing.dat");
+        std::fs::write(&path, b"abc").unwrap();
+        let mut reader = reader(&path);
+
+        let writer_path = path.clone();
+        let writer = thread::spawn(move || {
+            let mut file = File::options().append(true).open(&writer_path).unwrap();
+            for chunk in [&b"def"[..], b"ghi"] {
+                thread::sleep(Duration::from_millis(30));
+                file.write_all(chunk).unwrap();
+            }
+        });
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(data, b"abcdefghi");
+        assert_eq!(reader.position(), 9);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_follow_truncate_and_rotate() {
+        let dir = std::env::temp_dir().join(format!("rs2-rotate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("capture.dat");
+        std::fs::write(&path, b"first").unwrap();
+        let mut reader = reader(&path);
+        let mut buf = [0u8; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+
+        // truncated and rewritten in place
+        std::fs::write(&path, b"two").unwrap();
+        assert_eq!(reader.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], b"two");
+
+        #[cfg(unix)]
+        {
+            // replaced by a new file
+            let tmp = dir.join("next.dat");
+            std::fs::write(&tmp, b"rotated").unwrap();
+            std::fs::rename(&tmp, &path).unwrap();
+            assert_eq!(reader.read(&mut buf).unwrap(), 7);
+            assert_eq!(&buf[..7], b"rotated");
+        }
+
+        // idle timeout ends the stream
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Extraction of transfer frames (VCDUs) from CADUs and corrected codeblocks.
+//!
+//! A CADU is the ASM followed by the codeblock, and the codeblock is the transfer frame
+//! followed by the RS check symbol field. For shortened codes the virtual fill is not
+//! transmitted so the transfer frame is correspondingly shorter.
+//!
+//! ```
+//! use rs2::frame::FrameLayout;
+//!
+//! let layout = FrameLayout::default();
+//! let cadu = vec![0u8; layout.cadu_len()];
+//! assert_eq!(layout.frame(&cadu).unwrap().len(), 892);
+//! ```
+use crate::{sync, Code, Codeblock, RSState, UncorrectableReason};
+
+/// Layout of a CADU.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FrameLayout {
+    /// Length of the attached sync marker.
+    pub asm_len: usize,
+    /// Number of interleaved RS messages in each codeblock.
+    pub interleave: u8,
+    /// RS code used to encode the codeblock.
+    pub code: Code,
+    /// Number of zero symbols at the start of each message that are not transmitted.
+    pub virtual_fill: usize,
+}
+
+impl Default for FrameLayout {
+    /// Standard ASM, interleave 4 and RS(255,223) without virtual fill.
+    fn default() -> Self {
+        FrameLayout {
+            asm_len: sync::ASM.len(),
+            interleave: 4,
+            code: Code::E16,
+            virtual_fill: 0,
+        }
+    }
+}
+
+impl FrameLayout {
+    /// Length of the transfer frame.
+    pub fn frame_len(&self) -> usize {
+        self.interleave as usize * self.code.data_len().saturating_sub(self.virtual_fill)
+    }
+
+    /// Length of the RS check symbol field.
+    pub fn check_len(&self) -> usize {
+        self.interleave as usize * self.code.parity_len()
+    }
+
+    /// Length of the codeblock, i.e., the transfer frame and check symbols.
+    pub fn codeblock_len(&self) -> usize {
+        self.frame_len() + self.check_len()
+    }
+
+    /// Length of the CADU including the ASM.
+    pub fn cadu_len(&self) -> usize {
+        self.asm_len + self.codeblock_len()
+    }
+
+    fn validate(&self) -> Result<(), UncorrectableReason> {
+        if self.interleave == 0 || self.interleave > crate::MAX_INTERLEAVE {
+            return Err(UncorrectableReason::InvalidInterleave(self.interleave));
+        }
+        if self.virtual_fill > self.code.data_len() {
+            return Err(UncorrectableReason::InvalidVirtualFill {
+                got: self.virtual_fill,
+                max: self.code.data_len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Return the transfer frame of a complete CADU, i.e., strip the ASM and check
+    /// symbols. The CADU must already be derandomized and corrected.
+    pub fn frame<'a>(&self, cadu: &'a [u8]) -> Result<&'a [u8], UncorrectableReason> {
+        self.validate()?;
+        if cadu.len() != self.cadu_len() {
+            return Err(UncorrectableReason::InvalidLength {
+                got: cadu.len(),
+                want: self.cadu_len(),
+            });
+        }
+        Ok(&cadu[self.asm_len..self.asm_len + self.frame_len()])
+    }
+
+    /// Return the transfer frame of a corrected codeblock, i.e., strip the check
+    /// symbols. If any of the codeblock messages were uncorrectable the reason for
+    /// the first uncorrectable message is returned.
+    pub fn frame_from_codeblock<'a>(
+        &self,
+        codeblock: &'a Codeblock,
+    ) -> Result<&'a [u8], UncorrectableReason> {
+        self.validate()?;
+        let Some(message) = &codeblock.message else {
+            let reason = codeblock.states.iter().find_map(|s| match s {
+                RSState::Uncorrectable(reason) => Some(reason.clone()),
+                _ => None,
+            });
+            return Err(reason.unwrap_or(UncorrectableReason::ResidualSyndrome));
+        };
+        if message.len() != self.codeblock_len() {
+            return Err(UncorrectableReason::InvalidLength {
+                got: message.len(),
+                want: self.codeblock_len(),
+            });
+        }
+        Ok(&message[..self.frame_len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{interleave, FIXTURE_MSG};
+
+    #[test]
+    fn test_frame() {
+        let layout = FrameLayout {
+            interleave: 2,
+            ..Default::default()
+        };
+        assert_eq!(layout.frame_len(), 446);
+        assert_eq!(layout.check_len(), 64);
+        assert_eq!(layout.cadu_len(), 514);
+
+        let mut cadu = sync::ASM.to_vec();
+        cadu.extend(interleave(&[FIXTURE_MSG, FIXTURE_MSG]));
+
+        let frame = layout.frame(&cadu).unwrap();
+        assert_eq!(frame, &cadu[4..450]);
+        assert_eq!(
+            layout.frame(&cadu[..500]),
+            Err(UncorrectableReason::InvalidLength {
+                got: 500,
+                want: 514
+            })
+        );
+    }
+
+    #[test]
+    fn test_frame_shortened() {
+        let layout = FrameLayout {
+            interleave: 1,
+            virtual_fill: 23,
+            ..Default::default()
+        };
+        assert_eq!(layout.frame_len(), 200);
+        assert_eq!(layout.cadu_len(), 236);
+
+        let layout = FrameLayout {
+            virtual_fill: 224,
+            ..layout
+        };
+        assert!(matches!(
+            layout.frame(&[0u8; 10]),
+            Err(UncorrectableReason::InvalidVirtualFill { .. })
+        ));
+    }
+
+    #[test]
+    fn test_frame_from_codeblock() {
+        let layout = FrameLayout {
+            interleave: 2,
+            ..Default::default()
+        };
+        let input = interleave(&[FIXTURE_MSG, FIXTURE_MSG]);
+
+        let codeblock = crate::correct_codeblock(&input, 2);
+        assert_eq!(
+            layout.frame_from_codeblock(&codeblock).unwrap(),
+            &input[..446]
+        );
+
+        let codeblock = crate::correct_codeblock(&input[..10], 2);
+        assert!(matches!(
+            layout.frame_from_codeblock(&codeblock),
+            Err(UncorrectableReason::InvalidLength { .. })
+        ));
+    }
+}
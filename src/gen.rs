@@ -0,0 +1,265 @@
+//! Generation of conformant CADUs from arbitrary payload bytes, e.g., for producing
+//! bit-true stimulus files for testing downstream systems.
+//!
+//! Generation is the inverse of the [crate::pipeline]: each transfer frame is RS
+//! encoded, interleaved, randomized and prepended with the ASM, using the same
+//! [Config] so generated CADUs decode with a [crate::pipeline::Pipeline] using that
+//! config.
+//!
+//! ```
+//! use rs2::gen;
+//! use rs2::pipeline::{Config, Pipeline};
+//!
+//! let config = Config::default();
+//! let stream = gen::cadus(&config, b"hello world", 0);
+//! assert_eq!(stream.len(), 1024);
+//!
+//! let frames: Vec<_> = Pipeline::new(&stream[..], config).frames().collect();
+//! assert_eq!(&frames[0].as_ref().unwrap().data.as_ref().unwrap()[..11], b"hello world");
+//! ```
+//!
+//! A [CaduWriter] re-encodes the frames decoded by a pipeline, producing corrected
+//! CADUs that are bit-compatible with software expecting the original CADUs.
+use std::io::{self, Write};
+
+use crate::pipeline::{Config, Frame};
+use crate::{crc, interleave, UncorrectableReason};
+
+/// Number of payload bytes carried by each CADU, i.e., the transfer frame length less
+/// the FECF if [Config::fecf] is set.
+pub fn payload_len(config: &Config) -> usize {
+    match config.fecf {
+        true => config.frame_len() - crc::FECF_LEN,
+        false => config.frame_len(),
+    }
+}
+
+/// Encode a single transfer frame of [Config::frame_len] bytes into a codeblock of
+/// [Config::block_len] bytes, without ASM or randomization.
+///
+/// Errors are [UncorrectableReason::InvalidInterleave] and
+/// [UncorrectableReason::InvalidLength].
+pub fn codeblock(config: &Config, frame: &[u8]) -> Result<Vec<u8>, UncorrectableReason> {
+    if config.interleave == 0 || config.interleave > crate::MAX_INTERLEAVE {
+        return Err(UncorrectableReason::InvalidInterleave(config.interleave));
+    }
+    if frame.len() != config.frame_len() {
+        return Err(UncorrectableReason::InvalidLength {
+            got: frame.len(),
+            want: config.frame_len(),
+        });
+    }
+    let msgs = interleave::deinterleave(frame, config.interleave)
+        .map(|data| config.code.encode_message(&data.to_vec()))
+        .collect::<Result<Vec<Vec<u8>>, UncorrectableReason>>()?;
+    let msgs: Vec<&[u8]> = msgs.iter().map(|m| &m[..]).collect();
+    Ok(interleave::interleave(&msgs))
+}
+
+/// Encode a single transfer frame into a complete CADU, i.e., the ASM followed by the
+/// codeblock randomized if [Config::derandomize] is set. If [Config::fecf] is set the
+/// frame must already contain the FECF; see [crc::set_fecf].
+///
+/// Errors are the same as for [codeblock].
+pub fn cadu(config: &Config, frame: &[u8]) -> Result<Vec<u8>, UncorrectableReason> {
+    let mut block = codeblock(config, frame)?;
+    if config.derandomize {
+        config.randomizer.apply(&mut block);
+    }
+    let mut cadu = Vec::with_capacity(config.asm.len() + block.len());
+    cadu.extend_from_slice(&config.asm);
+    cadu.extend(block);
+    Ok(cadu)
+}
+
+/// Generate a stream of back-to-back CADUs carrying `payload`.
+///
+/// The payload is split into chunks of [payload_len] bytes, the last of which is
+/// padded with `fill`, and the FECF is appended to each frame if [Config::fecf] is
+/// set. An empty payload produces an empty stream.
+///
+/// # Panics
+/// If the config interleave is invalid.
+pub fn cadus(config: &Config, payload: &[u8], fill: u8) -> Vec<u8> {
+    let chunk_len = payload_len(config);
+    let mut stream = Vec::new();
+    for chunk in payload.chunks(chunk_len) {
+        let mut frame = vec![fill; config.frame_len()];
+        frame[..chunk.len()].copy_from_slice(chunk);
+        if config.fecf {
+            crc::set_fecf(&mut frame);
+        }
+        stream.extend(cadu(config, &frame).expect("invalid config"));
+    }
+    stream
+}
+
+/// Writes corrected frames as complete CADUs, i.e., with the ASM reattached, the check
+/// symbols re-derived from the corrected data, and randomized if [Config::derandomize]
+/// is set.
+///
+/// Uncorrectable frames are not written since their data is unknown; see
+/// [CaduWriter::dropped].
+pub struct CaduWriter<W> {
+    writer: W,
+    config: Config,
+    dropped: u64,
+}
+
+impl<W: Write> CaduWriter<W> {
+    /// Create a writer for frames decoded using `config`.
+    pub fn new(writer: W, config: Config) -> Self {
+        CaduWriter {
+            writer,
+            config,
+            dropped: 0,
+        }
+    }
+
+    /// Write the CADU for `frame`, returning whether it was written. A degraded frame,
+    /// see [Frame::is_degraded], is not written, as re-encoding it would hide that it
+    /// is damaged.
+    ///
+    /// It is an [io::ErrorKind::InvalidInput] error if the frame is not the length
+    /// produced by the config.
+    pub fn write_frame(&mut self, frame: &Frame) -> io::Result<bool> {
+        let Some(data) = frame.data.as_ref().filter(|_| !frame.is_degraded()) else {
+            self.dropped += 1;
+            return Ok(false);
+        };
+        let cadu = cadu(&self.config, data)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        self.writer.write_all(&cadu)?;
+        Ok(true)
+    }
+
+    /// Number of uncorrectable frames that were not written.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{Frame, Pipeline};
+    use crate::{pn, sync, Code, RSState};
+
+    #[test]
+    fn test_codeblock_roundtrip() {
+        let config = Config {
+            interleave: 3,
+            code: Code::E8,
+            ..Default::default()
+        };
+        let frame: Vec<u8> = (0..config.frame_len()).map(|i| i as u8).collect();
+
+        let block = codeblock(&config, &frame).unwrap();
+
+        assert_eq!(block.len(), config.block_len());
+        assert_eq!(&block[..frame.len()], &frame[..]);
+        let zult = config.code.correct_codeblock(&block, 3);
+        assert_eq!(zult.states, vec![RSState::Ok; 3]);
+    }
+
+    #[test]
+    fn test_cadu_layout() {
+        let config = Config::default();
+        let frame = vec![0x5a; config.frame_len()];
+
+        let cadu = cadu(&config, &frame).unwrap();
+
+        assert_eq!(&cadu[..4], &sync::ASM);
+        let mut block = cadu[4..].to_vec();
+        pn::derandomize(&mut block);
+        assert_eq!(block, codeblock(&config, &frame).unwrap());
+    }
+
+    #[test]
+    fn test_cadu_invalid() {
+        let config = Config::default();
+        assert_eq!(
+            cadu(&config, &[0u8; 10]),
+            Err(UncorrectableReason::InvalidLength { got: 10, want: 892 })
+        );
+        let config = Config {
+            interleave: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            cadu(&config, &[]),
+            Err(UncorrectableReason::InvalidInterleave(0))
+        );
+    }
+
+    #[test]
+    fn test_cadus_pipeline() {
+        let config = Config {
+            interleave: 2,
+            fecf: true,
+            ..Default::default()
+        };
+        let payload: Vec<u8> = (0..1000).map(|i| (i * 7) as u8).collect();
+
+        let stream = cadus(&config, &payload, 0xaa);
+
+        let frames = Pipeline::new(&stream[..], config.clone())
+            .frames()
+            .collect::<std::io::Result<Vec<Frame>>>()
+            .unwrap();
+        assert_eq!(frames.len(), 3);
+        let chunk_len = payload_len(&config);
+        let mut decoded = Vec::new();
+        for frame in &frames {
+            assert_eq!(frame.fecf_ok, Some(true));
+            decoded.extend_from_slice(&frame.data.as_ref().unwrap()[..chunk_len]);
+        }
+        assert_eq!(&decoded[..1000], &payload[..]);
+        assert!(decoded[1000..].iter().all(|&x| x == 0xaa));
+    }
+
+    #[test]
+    fn test_cadu_writer() {
+        let config = Config {
+            interleave: 2,
+            ..Default::default()
+        };
+        let clean = cadus(&config, &[0x11; 1000], 0xff);
+        let mut corrupt = clean.clone();
+        corrupt[10] ^= 0x01;
+        for x in &mut corrupt[514 + 4..][..40] {
+            *x ^= 0xff;
+        }
+
+        // degraded frames are dropped like uncorrectable ones
+        for best_effort in [false, true] {
+            let config = Config {
+                best_effort,
+                ..config.clone()
+            };
+            let mut writer = CaduWriter::new(Vec::new(), config.clone());
+
+            for frame in Pipeline::new(&corrupt[..], config).frames() {
+                writer.write_frame(&frame.unwrap()).unwrap();
+            }
+
+            assert_eq!(writer.dropped(), 1);
+            let written = writer.into_inner();
+            assert_eq!(written.len(), 2 * 514);
+            assert_eq!(&written[..514], &clean[..514]);
+            assert_eq!(&written[514..], &clean[2 * 514..]);
+        }
+    }
+}
@@ -1,58 +1,28 @@
-const LOG: &[i32] = &[
-    0x00, 0x00, 0x74, 0x09, 0xe8, 0x12, 0x7d, 0x38, 0x5d, 0x41, 0x86, 0x85, 0xf1, 0x51, 0xac, 0x1b,
-    0xd1, 0x24, 0xb5, 0x7b, 0xfa, 0x70, 0xf9, 0x5a, 0x66, 0x88, 0xc5, 0x4a, 0x21, 0x8e, 0x8f, 0x8a,
-    0x46, 0x93, 0x98, 0x89, 0x2a, 0x3e, 0xef, 0x97, 0x6f, 0xb6, 0xe4, 0x91, 0x6e, 0x53, 0xce, 0xe1,
-    0xda, 0xbd, 0xfc, 0x2d, 0x3a, 0x84, 0xbe, 0x1c, 0x95, 0x63, 0x03, 0x30, 0x04, 0xe9, 0xfe, 0x79,
-    0xba, 0x82, 0x08, 0xd3, 0x0d, 0x0b, 0xfd, 0xf2, 0x9e, 0x40, 0xb2, 0x6c, 0x64, 0x39, 0x0c, 0xc0,
-    0xe3, 0xa2, 0x2b, 0xc6, 0x59, 0x36, 0x06, 0x60, 0xe2, 0x25, 0xc7, 0x45, 0x43, 0xc2, 0x56, 0x8d,
-    0x4f, 0x20, 0x32, 0x9c, 0x71, 0x92, 0xa1, 0x61, 0xae, 0xa0, 0xf8, 0xa8, 0x33, 0x44, 0x90, 0x47,
-    0x0a, 0xea, 0xd7, 0x83, 0x77, 0xdd, 0xa4, 0x5c, 0x78, 0xb3, 0x5e, 0xbf, 0x73, 0x9a, 0xed, 0xd6,
-    0x2f, 0xdf, 0xf6, 0x6b, 0x7c, 0x54, 0x48, 0xa3, 0x81, 0x18, 0x7f, 0xbc, 0x72, 0xc8, 0x67, 0xf0,
-    0x13, 0x31, 0xb4, 0xf3, 0x27, 0x8c, 0xe0, 0xf5, 0xd8, 0x65, 0xad, 0xdb, 0x80, 0x3d, 0x35, 0xe6,
-    0x58, 0x68, 0x17, 0x29, 0x9f, 0xa5, 0x3b, 0x87, 0xcd, 0x6a, 0xaa, 0x55, 0x7a, 0x01, 0xd4, 0x9b,
-    0x57, 0x50, 0x99, 0x22, 0x3c, 0xd9, 0xb9, 0x4d, 0xb7, 0x5b, 0x37, 0xa9, 0xca, 0xb1, 0x02, 0xf4,
-    0xc3, 0x9d, 0x94, 0x8b, 0xa6, 0xdc, 0x11, 0xcc, 0xe5, 0xfb, 0x07, 0xaf, 0x16, 0x1a, 0xd5, 0x14,
-    0x23, 0xc9, 0x15, 0x1f, 0x6d, 0xde, 0x1d, 0x42, 0xa7, 0x10, 0xb8, 0x49, 0x05, 0x75, 0xbb, 0xee,
-    0x7e, 0x96, 0x5f, 0x0e, 0x4c, 0xc4, 0xf7, 0xcb, 0xeb, 0xc1, 0x52, 0x2e, 0x19, 0x4e, 0xd0, 0xb0,
-    0xec, 0x1e, 0x28, 0xab, 0xd2, 0xcf, 0x34, 0x2c, 0xe7, 0x69, 0x0f, 0x76, 0x62, 0x26, 0x4b, 0x3f,
-];
-
-const EXP: &[i32] = &[
-    0x01, 0xad, 0xbe, 0x3a, 0x3c, 0xdc, 0x56, 0xca, 0x42, 0x03, 0x70, 0x45, 0x4e, 0x44, 0xe3, 0xfa,
-    0xd9, 0xc6, 0x05, 0x90, 0xcf, 0xd2, 0xcc, 0xa2, 0x89, 0xec, 0xcd, 0x0f, 0x37, 0xd6, 0xf1, 0xd3,
-    0x61, 0x1c, 0xb3, 0xd0, 0x11, 0x59, 0xfd, 0x94, 0xf2, 0xa3, 0x24, 0x52, 0xf7, 0x33, 0xeb, 0x80,
-    0x3b, 0x91, 0x62, 0x6c, 0xf6, 0x9e, 0x55, 0xba, 0x07, 0x4d, 0x34, 0xa6, 0xb4, 0x9d, 0x25, 0xff,
-    0x49, 0x09, 0xd7, 0x5c, 0x6d, 0x5b, 0x20, 0x6f, 0x86, 0xdb, 0x1b, 0xfe, 0xe4, 0xb7, 0xed, 0x60,
-    0xb1, 0x0d, 0xea, 0x2d, 0x85, 0xab, 0x5e, 0xb0, 0xa0, 0x54, 0x17, 0xb9, 0x77, 0x08, 0x7a, 0xe2,
-    0x57, 0x67, 0xfc, 0x39, 0x4c, 0x99, 0x18, 0x8e, 0xa1, 0xf9, 0xa9, 0x83, 0x4b, 0xd4, 0x2c, 0x28,
-    0x15, 0x64, 0x8c, 0x7c, 0x02, 0xdd, 0xfb, 0x74, 0x78, 0x3f, 0xac, 0x13, 0x84, 0x06, 0xe0, 0x8a,
-    0x9c, 0x88, 0x41, 0x73, 0x35, 0x0b, 0x0a, 0xa7, 0x19, 0x23, 0x1f, 0xc3, 0x95, 0x5f, 0x1d, 0x1e,
-    0x6e, 0x2b, 0x65, 0x21, 0xc2, 0x38, 0xe1, 0x27, 0x22, 0xb2, 0x7d, 0xaf, 0x63, 0xc1, 0x48, 0xa4,
-    0x69, 0x66, 0x51, 0x87, 0x76, 0xa5, 0xc4, 0xd8, 0x6b, 0xbb, 0xaa, 0xf3, 0x0e, 0x9a, 0x68, 0xcb,
-    0xef, 0xbd, 0x4a, 0x79, 0x92, 0x12, 0x29, 0xb8, 0xda, 0xb6, 0x40, 0xde, 0x8b, 0x31, 0x36, 0x7b,
-    0x4f, 0xe9, 0x5d, 0xc0, 0xe5, 0x1a, 0x53, 0x5a, 0x8d, 0xd1, 0xbc, 0xe7, 0xc7, 0xa8, 0x2e, 0xf5,
-    0xee, 0x10, 0xf4, 0x43, 0xae, 0xce, 0x7f, 0x72, 0x98, 0xb5, 0x30, 0x9b, 0xc5, 0x75, 0xd5, 0x81,
-    0x96, 0x2f, 0x58, 0x50, 0x2a, 0xc8, 0x9f, 0xf8, 0x04, 0x3d, 0x71, 0xe8, 0xf0, 0x7e, 0xdf, 0x26,
-    0x8f, 0x0c, 0x47, 0x93, 0xbf, 0x97, 0x82, 0xe6, 0x6a, 0x16, 0x14, 0xc9, 0x32, 0x46, 0x3e, 0x01,
-    0xad, 0xbe, 0x3a, 0x3c, 0xdc, 0x56, 0xca, 0x42, 0x03, 0x70, 0x45, 0x4e, 0x44, 0xe3, 0xfa, 0xd9,
-    0xc6, 0x05, 0x90, 0xcf, 0xd2, 0xcc, 0xa2, 0x89, 0xec, 0xcd, 0x0f, 0x37, 0xd6, 0xf1, 0xd3, 0x61,
-    0x1c, 0xb3, 0xd0, 0x11, 0x59, 0xfd, 0x94, 0xf2, 0xa3, 0x24, 0x52, 0xf7, 0x33, 0xeb, 0x80, 0x3b,
-    0x91, 0x62, 0x6c, 0xf6, 0x9e, 0x55, 0xba, 0x07, 0x4d, 0x34, 0xa6, 0xb4, 0x9d, 0x25, 0xff, 0x49,
-    0x09, 0xd7, 0x5c, 0x6d, 0x5b, 0x20, 0x6f, 0x86, 0xdb, 0x1b, 0xfe, 0xe4, 0xb7, 0xed, 0x60, 0xb1,
-    0x0d, 0xea, 0x2d, 0x85, 0xab, 0x5e, 0xb0, 0xa0, 0x54, 0x17, 0xb9, 0x77, 0x08, 0x7a, 0xe2, 0x57,
-    0x67, 0xfc, 0x39, 0x4c, 0x99, 0x18, 0x8e, 0xa1, 0xf9, 0xa9, 0x83, 0x4b, 0xd4, 0x2c, 0x28, 0x15,
-    0x64, 0x8c, 0x7c, 0x02, 0xdd, 0xfb, 0x74, 0x78, 0x3f, 0xac, 0x13, 0x84, 0x06, 0xe0, 0x8a, 0x9c,
-    0x88, 0x41, 0x73, 0x35, 0x0b, 0x0a, 0xa7, 0x19, 0x23, 0x1f, 0xc3, 0x95, 0x5f, 0x1d, 0x1e, 0x6e,
-    0x2b, 0x65, 0x21, 0xc2, 0x38, 0xe1, 0x27, 0x22, 0xb2, 0x7d, 0xaf, 0x63, 0xc1, 0x48, 0xa4, 0x69,
-    0x66, 0x51, 0x87, 0x76, 0xa5, 0xc4, 0xd8, 0x6b, 0xbb, 0xaa, 0xf3, 0x0e, 0x9a, 0x68, 0xcb, 0xef,
-    0xbd, 0x4a, 0x79, 0x92, 0x12, 0x29, 0xb8, 0xda, 0xb6, 0x40, 0xde, 0x8b, 0x31, 0x36, 0x7b, 0x4f,
-    0xe9, 0x5d, 0xc0, 0xe5, 0x1a, 0x53, 0x5a, 0x8d, 0xd1, 0xbc, 0xe7, 0xc7, 0xa8, 0x2e, 0xf5, 0xee,
-    0x10, 0xf4, 0x43, 0xae, 0xce, 0x7f, 0x72, 0x98, 0xb5, 0x30, 0x9b, 0xc5, 0x75, 0xd5, 0x81, 0x96,
-    0x2f, 0x58, 0x50, 0x2a, 0xc8, 0x9f, 0xf8, 0x04, 0x3d, 0x71, 0xe8, 0xf0, 0x7e, 0xdf, 0x26, 0x8f,
-    0x0c, 0x47, 0x93, 0xbf, 0x97, 0x82, 0xe6, 0x6a, 0x16, 0x14, 0xc9, 0x32, 0x46, 0x3e, 0x01, 0xad,
-];
-
-fn mult_no_lut(x: i32, y: i32, prim: i32) -> i32 {
+//! GF(2^8) arithmetic.
+//!
+//! The decoder uses the CCSDS field, defined by [PRIM] with primitive element [GEN],
+//! through fixed tables. [Field] provides the same arithmetic for any primitive
+//! polynomial and element, e.g., to cross-check results against ground systems or
+//! historical missions using the conventional x^8 + x^4 + x^3 + x^2 + 1 field.
+//!
+//! ```
+//! use rs2::gf::Field;
+//!
+//! const CONVENTIONAL: Field = Field::new(0x11d, 2);
+//! assert_eq!(CONVENTIONAL.mult(0x80, 2), 0x1d);
+//! assert_eq!(CONVENTIONAL.mult(CONVENTIONAL.inv(0x53), 0x53), 1);
+//! ```
+use crate::{GEN, PRIM};
+
+const N: usize = crate::N as usize;
+
+/// Antilog table, i.e., GEN^i, for i in 0..512 so sums of two logs never need to be
+/// reduced modulo 255.
+const EXP: [u8; 512] = Field::CCSDS.exp;
+/// Log table, base GEN. The log of 0 is undefined and set to 0.
+const LOG: [u8; 256] = Field::CCSDS.log;
+
+const fn mult_no_lut(x: i32, y: i32, prim: i32) -> i32 {
     let mut r = 0;
     let mut x = x;
     let mut y = y;
@@ -60,8 +30,8 @@ fn mult_no_lut(x: i32, y: i32, prim: i32) -> i32 {
         if y & 1 > 0 {
             r ^= x;
         }
-        y = y >> 1;
-        x = x << 1;
+        y >>= 1;
+        x <<= 1;
         if prim > 0 && x & 256 >= 1 {
             x ^= prim;
         }
@@ -69,25 +39,149 @@ fn mult_no_lut(x: i32, y: i32, prim: i32) -> i32 {
     r
 }
 
-#[allow(unused)]
-fn gen_exp_table(n: i32, prim: i32, gen: u8) -> ([i32; 512], [i32; 256]) {
-    let mut exp = [0i32; 512];
-    let mut log = [0i32; 256];
-    let mut x: i32 = 1;
-    for i in 0..n {
-        exp[i as usize] = x;
-        log[x as usize] = i;
-        x = mult_no_lut(x, gen as i32, prim);
+/// Tables for GF(2^8) defined by a primitive polynomial and primitive element.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Field {
+    prim: u16,
+    gen: u8,
+    /// gen^i for i in 0..512, so sums of two logs never need to be reduced modulo 255.
+    exp: [u8; 512],
+    /// Log base gen. The log of 0 is undefined and set to 0.
+    log: [u8; 256],
+}
+
+impl Field {
+    /// The CCSDS field, defined by [PRIM] with primitive element [GEN].
+    pub const CCSDS: Field = Field::new(PRIM as u16, GEN);
+
+    /// Create the field defined by the polynomial `prim`, e.g., 0x187 for
+    /// x^8 + x^7 + x^2 + x + 1, with primitive element `gen`.
+    ///
+    /// # Panics
+    /// If `prim` is not of degree 8 or the powers of `gen` are not all 255 nonzero
+    /// elements, i.e., `prim` is not primitive or `gen` is not a primitive element. In
+    /// a const context this is a compile error.
+    pub const fn new(prim: u16, gen: u8) -> Self {
+        assert!(prim >> 8 == 1, "polynomial is not of degree 8");
+        let mut exp = [0u8; 512];
+        let mut log = [0u8; 256];
+        let mut x: i32 = 1;
+        let mut i = 0;
+        while i < 255 {
+            assert!(i == 0 || x != 1, "not a primitive element");
+            exp[i] = x as u8;
+            exp[i + 255] = x as u8;
+            log[x as usize] = i as u8;
+            x = mult_no_lut(x, gen as i32, prim as i32);
+            i += 1;
+        }
+        assert!(x == 1, "not a primitive element");
+        // exp[510] and exp[511] are only reachable with log(0), which is not valid, but
+        // keep the period for completeness.
+        exp[510] = exp[0];
+        exp[511] = exp[1];
+        Field {
+            prim,
+            gen,
+            exp,
+            log,
+        }
+    }
+
+    /// The primitive polynomial defining the field.
+    pub const fn prim(&self) -> u16 {
+        self.prim
+    }
+
+    /// The primitive element, i.e., the base of [Field::exp] and [Field::log].
+    pub const fn generator(&self) -> u8 {
+        self.gen
+    }
+
+    /// The primitive element to the power of `power`.
+    pub const fn exp(&self, power: i32) -> u8 {
+        self.exp[power.rem_euclid(255) as usize]
+    }
+
+    /// Log of `x` base the primitive element, in 0..255, or None if `x` is 0.
+    pub const fn log(&self, x: u8) -> Option<u8> {
+        if x == 0 {
+            None
+        } else {
+            Some(self.log[x as usize])
+        }
+    }
+
+    pub const fn mult(&self, x: u8, y: u8) -> u8 {
+        if x == 0 || y == 0 {
+            0
+        } else {
+            self.exp[self.log[x as usize] as usize + self.log[y as usize] as usize]
+        }
+    }
+
+    /// # Panics
+    /// If `y` is 0.
+    pub const fn div(&self, x: u8, y: u8) -> u8 {
+        assert!(y != 0, "zero division");
+        if x == 0 {
+            return 0;
+        }
+        self.exp[self.log[x as usize] as usize + 255 - self.log[y as usize] as usize]
+    }
+
+    /// # Panics
+    /// If `x` is 0.
+    pub const fn inv(&self, x: u8) -> u8 {
+        self.div(1, x)
+    }
+
+    /// `x` to the power of `power`. 0 to the power of 0 is 1, and to any other power is
+    /// 0.
+    pub const fn pow(&self, x: u8, power: i32) -> u8 {
+        if x == 0 {
+            return (power == 0) as u8;
+        }
+        let power = power.rem_euclid(255);
+        self.exp[(self.log[x as usize] as i32 * power % 255) as usize]
+    }
+
+    /// Evaluate the polynomial `p`, highest degree first, at `x`.
+    pub fn poly_eval(&self, p: &[u8], x: u8) -> u8 {
+        p.iter().fold(0, |y, c| self.mult(y, x) ^ c)
+    }
+}
+
+/// Full multiplication table, i.e., MULT[x][y] = x * y.
+#[cfg(feature = "mult-table")]
+static MULT: [[u8; 256]; 256] = gen_mult_table();
+
+#[cfg(feature = "mult-table")]
+const fn gen_mult_table() -> [[u8; 256]; 256] {
+    let mut table = [[0u8; 256]; 256];
+    let mut x = 1;
+    while x < 256 {
+        let mut y = 1;
+        while y < 256 {
+            table[x][y] = EXP[LOG[x] as usize + LOG[y] as usize];
+            y += 1;
+        }
+        x += 1;
     }
+    table
+}
 
-    (exp, log)
+#[cfg(feature = "mult-table")]
+pub(super) fn mult(x: u8, y: u8) -> u8 {
+    MULT[x as usize][y as usize]
 }
 
+#[cfg(not(feature = "mult-table"))]
 pub(super) fn mult(x: u8, y: u8) -> u8 {
     if x == 0 || y == 0 {
         0
     } else {
-        EXP[(LOG[x as usize] + LOG[y as usize]) as usize] as u8
+        EXP[LOG[x as usize] as usize + LOG[y as usize] as usize]
     }
 }
 
@@ -98,62 +192,226 @@ pub(super) fn div(x: u8, y: u8) -> u8 {
     if x == 0 {
         return 0;
     }
-    EXP[((LOG[x as usize] + 255 - LOG[y as usize]) % 255) as usize] as u8
+    EXP[(LOG[x as usize] as usize + 255 - LOG[y as usize] as usize) % 255]
 }
 
 pub(super) fn pow(x: u8, power: i32) -> u8 {
-    let mut power = power;
-    if power < 0 {
-        power = (255 - (-power % 255)) % 255;
-    }
-    EXP[((LOG[x as usize] * power) % 255) as usize] as u8
+    // reduced first so the product cannot overflow for any power
+    let power = power.rem_euclid(255);
+    EXP[(LOG[x as usize] as i32 * power % 255) as usize]
 }
 
+/// GEN^power by table lookup, i.e., `pow(GEN, power)`, also usable in const contexts.
+pub(super) const fn exp(power: i32) -> u8 {
+    EXP[power.rem_euclid(255) as usize]
+}
+
+/// Multiplication usable in const contexts, e.g., to precompute tables.
+pub(super) const fn mult_const(x: u8, y: u8) -> u8 {
+    Field::CCSDS.mult(x, y)
+}
+
+/// GEN^i for i in 0..255, i.e., every nonzero element, as evaluated by the Chien
+/// search.
+pub(super) const POWERS: [u8; 255] = {
+    let mut powers = [0u8; 255];
+    let mut i = 0;
+    while i < 255 {
+        powers[i] = EXP[i];
+        i += 1;
+    }
+    powers
+};
+
 pub(super) fn inv(x: u8) -> u8 {
-    EXP[(255 - LOG[x as usize]) as usize] as u8
+    EXP[255 - LOG[x as usize] as usize]
 }
 
-pub(super) fn poly_scale(p: &[u8], x: u8) -> Vec<u8> {
-    let mut r = vec![0u8; p.len()];
-    for i in 0..p.len() {
-        r[i] = mult(p[i], x);
+// FIXME: use ndarray or simd here
+pub(super) fn poly_eval(p: &[u8], x: u8) -> u8 {
+    let mut y = 0;
+    for c in p.iter() {
+        y = mult(y, x) ^ c;
     }
-    r
+    y
 }
 
-pub(super) fn poly_add(p: &[u8], q: &[u8]) -> Vec<u8> {
-    let l = if p.len() > q.len() { p.len() } else { q.len() };
-    let mut r = vec![0u8; l];
-    let rl = r.len();
-    let pl = p.len();
-    let ql = q.len();
+/// Evaluate `p` at each of the points in `xs`, writing the results to `out`. This is
+/// equivalent to calling [poly_eval] for each point, but uses a SIMD backend when
+/// available.
+pub(super) fn poly_eval_many(p: &[u8], xs: &[u8], out: &mut [u8]) {
+    #[cfg(target_arch = "x86_64")]
+    if crate::simd::has_gfni_avx2() {
+        // SAFETY: the required CPU features were detected.
+        return unsafe { crate::simd::x86::poly_eval_many(p, xs, out) };
+    }
+    #[cfg(target_arch = "aarch64")]
+    if crate::simd::has_neon() {
+        // SAFETY: the required CPU features were detected.
+        return unsafe { crate::simd::neon::poly_eval_many(p, xs, out) };
+    }
+    for (x, y) in xs.iter().zip(out.iter_mut()) {
+        *y = poly_eval(p, *x);
+    }
+}
 
-    for i in 0..p.len() {
-        r[i + rl - pl] = p[i];
+/// Whether [poly_eval_many] uses a SIMD backend.
+fn has_simd_eval() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    if crate::simd::has_gfni_avx2() {
+        return true;
     }
-    for i in 0..q.len() {
-        r[i + rl - ql] ^= q[i];
+    #[cfg(target_arch = "aarch64")]
+    if crate::simd::has_neon() {
+        return true;
+    }
+    false
+}
+
+/// Return true if `p` is non-zero at any of the points in `xs`. A SIMD backend
+/// evaluates [crate::PARITY_LEN] points for about the cost of one, so evaluates them a
+/// chunk at a time, otherwise each point is evaluated in turn, stopping at the first
+/// non-zero.
+pub(super) fn poly_eval_any(p: &[u8], xs: &[u8]) -> bool {
+    if has_simd_eval() {
+        let mut out = [0u8; crate::PARITY_LEN];
+        return xs.chunks(crate::PARITY_LEN).any(|xs| {
+            let out = &mut out[..xs.len()];
+            poly_eval_many(p, xs, out);
+            out.iter().any(|y| *y != 0)
+        });
+    }
+    xs.iter().any(|x| poly_eval(p, *x) != 0)
+}
+
+/// Number of points evaluated at a time by [find_roots] with a SIMD backend, between
+/// checks for whether to stop.
+const CHIEN_CHUNK: usize = 32;
+
+/// Chien search for the `i` in 0..255 where `p`, highest degree first, is zero at
+/// GEN^i, calling `root` with each in ascending order until it returns false.
+pub(super) fn find_roots(p: &[u8], mut root: impl FnMut(usize) -> bool) {
+    #[cfg(target_arch = "x86_64")]
+    let simd = crate::simd::has_gfni_avx2();
+    #[cfg(target_arch = "aarch64")]
+    let simd = crate::simd::has_neon();
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let simd = false;
+    if !simd {
+        return find_roots_incremental(p, root);
+    }
+    // evaluating a chunk of points at once is cheaper than stepping point by point
+    let mut evals = [0u8; CHIEN_CHUNK];
+    for (chunk, points) in POWERS.chunks(CHIEN_CHUNK).enumerate() {
+        let evals = &mut evals[..points.len()];
+        poly_eval_many(p, points, evals);
+        for (i, y) in evals.iter().enumerate() {
+            if *y == 0 && !root(chunk * CHIEN_CHUNK + i) {
+                return;
+            }
+        }
     }
-    r
 }
 
-pub(super) fn poly_mult(p: &[u8], q: &[u8]) -> Vec<u8> {
-    let mut r = vec![0u8; p.len() + q.len() - 1];
-    for j in 0..q.len() {
-        for i in 0..p.len() {
-            r[i + j] ^= mult(p[i], q[j]);
+/// Chien search like [find_roots] without SIMD, keeping the log of each term
+/// c_k * GEN^(i * (d - k)) so that stepping to the next point is an addition per
+/// coefficient rather than a multiplication.
+fn find_roots_incremental(p: &[u8], mut root: impl FnMut(usize) -> bool) {
+    // GEN^255 is 1, so terms whose degrees are equal modulo 255 are combined
+    let mut coef = [0u8; N];
+    for (k, c) in p.iter().enumerate() {
+        coef[(p.len() - 1 - k) % N] ^= c;
+    }
+    let mut logs = [0usize; N];
+    let mut steps = [0usize; N];
+    let mut terms = 0;
+    for (step, c) in coef.iter().enumerate() {
+        if *c != 0 {
+            logs[terms] = LOG[*c as usize] as usize;
+            steps[terms] = step;
+            terms += 1;
+        }
+    }
+    let (logs, steps) = (&mut logs[..terms], &steps[..terms]);
+    for i in 0..N {
+        let mut y = 0;
+        for (log, step) in logs.iter_mut().zip(steps) {
+            y ^= EXP[*log];
+            *log += step;
+            if *log >= N {
+                *log -= N;
+            }
+        }
+        if y == 0 && !root(i) {
+            return;
         }
     }
-    r
 }
 
-// FIXME: use ndarray or simd here
-pub(super) fn poly_eval(p: &[u8], x: u8) -> u8 {
-    let mut y = p[0];
-    for i in 1..p.len() {
-        y = mult(y, x) ^ p[i];
+/// Number of polynomials evaluated at once by [poly_eval_lanes].
+#[cfg(feature = "std")]
+pub(super) const LANES: usize = 32;
+
+/// Evaluate [LANES] polynomials, the columns of `rows` with the highest degree first,
+/// at each of the points in `xs`, writing the results for each point to `out`.
+///
+/// Equivalent to calling [poly_eval_many] for each polynomial, but with each SIMD lane
+/// evaluating a different polynomial, e.g., a different codeword, at the same point.
+#[cfg(feature = "std")]
+pub(super) fn poly_eval_lanes(rows: &[[u8; LANES]], xs: &[u8], out: &mut [[u8; LANES]]) {
+    #[cfg(target_arch = "x86_64")]
+    if crate::simd::has_gfni_avx2() {
+        // SAFETY: the required CPU features were detected.
+        return unsafe { crate::simd::x86::poly_eval_lanes(rows, xs, out) };
+    }
+    #[cfg(target_arch = "x86_64")]
+    if crate::simd::has_avx2() {
+        // SAFETY: the required CPU features were detected.
+        return unsafe { crate::simd::avx2::poly_eval_lanes(rows, xs, out) };
+    }
+    #[cfg(target_arch = "aarch64")]
+    if crate::simd::has_neon() {
+        // SAFETY: the required CPU features were detected.
+        return unsafe { crate::simd::neon::poly_eval_lanes(rows, xs, out) };
+    }
+    for (x, y) in xs.iter().zip(out.iter_mut()) {
+        *y = [0u8; LANES];
+        for row in rows {
+            for (y, c) in y.iter_mut().zip(row) {
+                *y = mult(*y, *x) ^ c;
+            }
+        }
+    }
+}
+
+/// Evaluate the `depth` polynomials interleaved in `block`, where coefficient `j` of
+/// polynomial `i` is `block[j * depth + i]`, highest degree first, at each of the up to
+/// [crate::PARITY_LEN] points in `xs`, writing the results for polynomial `i` to
+/// `out[i]`.
+///
+/// Equivalent to calling [poly_eval_many] for each polynomial, e.g., each message of an
+/// interleaved codeblock, without deinterleaving them.
+#[cfg(feature = "std")]
+pub(super) fn poly_eval_interleaved(
+    block: &[u8],
+    depth: usize,
+    xs: &[u8],
+    out: &mut [[u8; crate::PARITY_LEN]],
+) {
+    #[cfg(target_arch = "x86_64")]
+    if crate::simd::has_gfni_avx2() {
+        // SAFETY: the required CPU features were detected.
+        return unsafe { crate::simd::x86::poly_eval_interleaved(block, depth, xs, out) };
+    }
+    let mut p = [0u8; N];
+    for (i, out) in out.iter_mut().enumerate() {
+        let mut len = 0;
+        for (c, x) in p.iter_mut().zip(block[i..].iter().step_by(depth)) {
+            *c = *x;
+            len += 1;
+        }
+        poly_eval_many(&p[..len], xs, &mut out[..xs.len()]);
     }
-    y
 }
 
 /*
@@ -182,21 +440,158 @@ mod test {
 }
 */
 
-pub(super) fn poly_div(dividend: &[u8], divisor: &[u8]) -> (Vec<u8>, Vec<u8>) {
-    let mut out = Vec::with_capacity(dividend.len());
-    out.extend_from_slice(dividend);
-
-    for i in 0..(dividend.len() - divisor.len() - 1) {
-        let coef = out[i];
-        if coef != 0 {
-            for j in 1..divisor.len() {
-                if divisor[j] != 0 {
-                    out[i + j] ^= mult(divisor[j], coef);
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables() {
+        // spot check against values from the previously hand-transcribed tables
+        assert_eq!(EXP[1], GEN);
+        assert_eq!(LOG[GEN as usize], 1);
+        assert_eq!(EXP[0x74], 2);
+
+        for x in 1..=255u8 {
+            assert_eq!(EXP[LOG[x as usize] as usize], x);
+            assert_eq!(EXP[LOG[x as usize] as usize + 255], x);
+            assert_eq!(mult(x, inv(x)), 1);
+            assert_eq!(mult_no_lut(x as i32, 3, PRIM), mult(x, 3) as i32);
+        }
+    }
+
+    #[test]
+    fn test_exp() {
+        for power in -600..600 {
+            assert_eq!(exp(power), pow(GEN, power), "power={power}");
+        }
+        assert_eq!(&POWERS[..], &EXP[..255]);
+        for x in 0..=255u8 {
+            assert_eq!(mult_const(x, 0x5a), mult(x, 0x5a));
+        }
+    }
+
+    fn roots(p: &[u8], incremental: bool, limit: usize) -> Vec<usize> {
+        let mut roots = Vec::new();
+        let root = |i| {
+            roots.push(i);
+            roots.len() < limit
+        };
+        if incremental {
+            find_roots_incremental(p, root);
+        } else {
+            find_roots(p, root);
+        }
+        roots
+    }
+
+    #[test]
+    fn test_find_roots() {
+        // (x - GEN^3)(x - GEN^200) * 7, and polynomials without all of their roots
+        let r1 = pow(GEN, 3);
+        let r2 = pow(GEN, 200);
+        let quadratic = [7, mult(7, r1 ^ r2), mult(7, mult(r1, r2))];
+        let long: Vec<u8> = (0..300).map(|i| (i * 3) as u8).collect();
+        let polys: [&[u8]; 7] = [&quadratic, &[1, 1, 1], &[0, 5], &[0, 0], &[9], &[], &long];
+        for p in polys {
+            let expected: Vec<usize> = (0..N).filter(|i| poly_eval(p, EXP[*i]) == 0).collect();
+            assert_eq!(roots(p, false, N), expected, "p={p:?}");
+            assert_eq!(roots(p, true, N), expected, "p={p:?}");
+        }
+        assert_eq!(roots(&quadratic, false, 1), [3]);
+        assert_eq!(roots(&quadratic, true, 1), [3]);
+    }
+
+    #[test]
+    fn test_mult() {
+        for x in 0..=255u8 {
+            for y in 0..=255u8 {
+                assert_eq!(mult(x, y) as i32, mult_no_lut(x as i32, y as i32, PRIM));
+            }
+        }
+    }
+
+    #[test]
+    fn test_field() {
+        let f = Field::CCSDS;
+        assert_eq!((f.prim(), f.generator()), (PRIM as u16, GEN));
+        for x in 0..=255u8 {
+            assert_eq!(f.mult(x, 0x5a), mult(x, 0x5a));
+            if x != 0 {
+                assert_eq!(f.pow(x, 7), pow(x, 7));
+                assert_eq!(f.pow(x, -3), pow(x, -3));
+                assert_eq!(f.div(0x5a, x), div(0x5a, x));
+                assert_eq!(f.inv(x), inv(x));
+                assert_eq!(f.log(x), Some(LOG[x as usize]));
+            }
+        }
+        assert_eq!(f.log(0), None);
+        assert_eq!((f.pow(0, 0), f.pow(0, 5)), (1, 0));
+        assert_eq!(f.poly_eval(&[1, 2, 3], 0x40), poly_eval(&[1, 2, 3], 0x40));
+
+        // the conventional field, e.g., x^8 = x^4 + x^3 + x^2 + 1
+        let f = Field::new(0x11d, 2);
+        assert_eq!(f.exp(8), 0x1d);
+        assert_eq!(f.exp(-1), f.inv(2));
+        assert_eq!(f.log(0x1d), Some(8));
+        for x in 0..=255u8 {
+            for y in [1, 2, 0x53, 0xff] {
+                assert_eq!(f.mult(x, y) as i32, mult_no_lut(x as i32, y as i32, 0x11d));
+            }
+        }
+        // another primitive element of the same field
+        let f9 = Field::new(0x11d, 9);
+        assert_eq!(f9.mult(0x80, 2), f.mult(0x80, 2));
+        assert_eq!(f9.exp(1), 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a primitive element")]
+    fn test_field_not_primitive() {
+        // 2 has order 51 in the AES field
+        Field::new(0x11b, 2);
+    }
+
+    #[test]
+    fn test_poly_eval_lanes() {
+        let rows: Vec<[u8; LANES]> = (0..100)
+            .map(|i| core::array::from_fn(|lane| (i * 31 + lane * 5) as u8))
+            .collect();
+        let xs = [0, 1, 2, GEN, 0x80, 0xff];
+        let mut zult = [[0u8; LANES]; 6];
+        poly_eval_lanes(&rows, &xs, &mut zult);
+        for lane in 0..LANES {
+            let p: Vec<u8> = rows.iter().map(|row| row[lane]).collect();
+            for (x, z) in xs.iter().zip(zult.iter()) {
+                assert_eq!(z[lane], poly_eval(&p, *x));
+            }
+        }
+    }
+
+    #[test]
+    fn test_poly_eval_any() {
+        // (x - GEN^3)(x - GEN^200) is only zero at its roots
+        let r1 = pow(GEN, 3);
+        let r2 = pow(GEN, 200);
+        let quadratic = [1, r1 ^ r2, mult(r1, r2)];
+        assert!(!poly_eval_any(&quadratic, &[r1, r2, r1]));
+        assert!(poly_eval_any(&quadratic, &[r1, r2, 1]));
+        let xs: Vec<u8> = (0..40).map(|i| if i == 39 { 1 } else { r1 }).collect();
+        assert!(poly_eval_any(&quadratic, &xs));
+        assert!(!poly_eval_any(&quadratic, &xs[..39]));
+        assert!(!poly_eval_any(&quadratic, &[]));
+    }
+
+    #[test]
+    fn test_poly_eval_interleaved() {
+        let block: Vec<u8> = (0..5 * 100).map(|i| (i * 31 + 7) as u8).collect();
+        let xs = [0, 1, 2, GEN, 0x80, 0xff];
+        let mut zult = [[0u8; crate::PARITY_LEN]; 5];
+        poly_eval_interleaved(&block, 5, &xs, &mut zult);
+        for (i, z) in zult.iter().enumerate() {
+            let p: Vec<u8> = block[i..].iter().step_by(5).copied().collect();
+            for (x, z) in xs.iter().zip(z.iter()) {
+                assert_eq!(*z, poly_eval(&p, *x));
             }
         }
     }
-    let mid = dividend.len() - divisor.len() + 1;
-    let (head, tail) = out.split_at(mid);
-    (Vec::from(head), Vec::from(tail))
 }
@@ -0,0 +1,195 @@
+//! Symbol interleaving of RS messages within a codeblock, where symbol `j` of message
+//! `i` is at codeblock offset `j * depth + i`.
+//!
+//! Deinterleaving produces [Strided] views of each message rather than copies, so a
+//! message is only copied if and when it is needed as a contiguous slice.
+//!
+//! ```
+//! use rs2::interleave::{deinterleave, interleave};
+//!
+//! let codeblock = interleave(&[b"abc", b"xyz"]);
+//! assert_eq!(codeblock, b"axbycz");
+//!
+//! let msgs: Vec<Vec<u8>> = deinterleave(&codeblock, 2).map(|m| m.to_vec()).collect();
+//! assert_eq!(msgs, vec![b"abc".to_vec(), b"xyz".to_vec()]);
+//! ```
+
+/// View of a single message within an interleaved codeblock.
+#[derive(Debug, Clone, Copy)]
+pub struct Strided<'a> {
+    buf: &'a [u8],
+    index: usize,
+    depth: usize,
+}
+
+impl<'a> Strided<'a> {
+    /// Number of symbols in the message.
+    pub fn len(&self) -> usize {
+        (self.buf.len() + self.depth - 1 - self.index) / self.depth
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Symbol `j` of the message, if it exists.
+    pub fn get(&self, j: usize) -> Option<u8> {
+        self.buf.get(j * self.depth + self.index).copied()
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = u8> + 'a {
+        let n = self.len();
+        self.buf
+            .get(self.index..)
+            .unwrap_or_default()
+            .iter()
+            .step_by(self.depth)
+            .copied()
+            .take(n)
+    }
+
+    /// Copy the message to the start of `out`, which must be at least
+    /// [Strided::len] symbols.
+    pub fn copy_to(&self, out: &mut [u8]) {
+        for (o, x) in out.iter_mut().zip(self.iter()) {
+            *o = x;
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.iter().collect()
+    }
+}
+
+/// Views of each of the `depth` messages in an interleaved codeblock, in interleave
+/// order. If the codeblock length is not a multiple of `depth` the first messages are
+/// 1 symbol longer than the rest.
+///
+/// # Panics
+/// If `depth` is 0.
+pub fn deinterleave(buf: &[u8], depth: u8) -> impl ExactSizeIterator<Item = Strided<'_>> {
+    assert!(depth > 0, "depth must be greater than 0");
+    (0..depth as usize).map(move |index| Strided {
+        buf,
+        index,
+        depth: depth as usize,
+    })
+}
+
+/// Write `msg` to the positions of message `index` of a codeblock interleaved to
+/// `depth`, i.e., the inverse of [deinterleave]. Nothing is written if `index` is past
+/// the end of `buf`.
+///
+/// # Panics
+/// If `depth` is 0.
+pub fn write_message(buf: &mut [u8], depth: u8, index: usize, msg: &[u8]) {
+    assert!(depth > 0, "depth must be greater than 0");
+    for (o, x) in buf
+        .get_mut(index..)
+        .unwrap_or_default()
+        .iter_mut()
+        .step_by(depth as usize)
+        .zip(msg.iter())
+    {
+        *o = *x;
+    }
+}
+
+/// Interleave messages of equal length into a codeblock.
+///
+/// # Panics
+/// If the messages are not all the same length.
+#[cfg(feature = "std")]
+pub fn interleave(msgs: &[&[u8]]) -> Vec<u8> {
+    let len = msgs.first().map_or(0, |m| m.len());
+    let mut out = vec![0u8; msgs.len() * len];
+    interleave_into(msgs, &mut out);
+    out
+}
+
+/// Interleave messages of equal length into `out`, which must be the total length
+/// of the messages.
+///
+/// # Panics
+/// If the messages are not all the same length or `out` is the wrong length.
+pub fn interleave_into(msgs: &[&[u8]], out: &mut [u8]) {
+    let len = msgs.first().map_or(0, |m| m.len());
+    assert!(
+        msgs.iter().all(|m| m.len() == len),
+        "messages must be the same length"
+    );
+    assert_eq!(out.len(), msgs.len() * len, "invalid output length");
+    for (i, msg) in msgs.iter().enumerate() {
+        write_message(out, msgs.len() as u8, i, msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let msgs: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 255]).collect();
+        let refs: Vec<&[u8]> = msgs.iter().map(|m| &m[..]).collect();
+
+        let codeblock = interleave(&refs);
+        assert_eq!(codeblock[..6], [0, 1, 2, 3, 4, 0]);
+
+        let views: Vec<Strided> = deinterleave(&codeblock, 5).collect();
+        assert_eq!(views.len(), 5);
+        for (view, msg) in views.iter().zip(msgs.iter()) {
+            assert_eq!(view.len(), 255);
+            assert_eq!(&view.to_vec(), msg);
+        }
+        assert_eq!(views[3].get(254), Some(3));
+        assert_eq!(views[3].get(255), None);
+
+        let mut out = vec![0u8; codeblock.len()];
+        for (i, view) in views.iter().enumerate() {
+            write_message(&mut out, 5, i, &view.to_vec());
+        }
+        assert_eq!(out, codeblock);
+    }
+
+    #[test]
+    fn test_uneven() {
+        let views: Vec<Strided> = deinterleave(b"abcdefg", 3).collect();
+        let lens: Vec<usize> = views.iter().map(|v| v.len()).collect();
+        assert_eq!(lens, vec![3, 2, 2]);
+        assert_eq!(views[0].to_vec(), b"adg");
+        let mut out = [0u8; 3];
+        views[1].copy_to(&mut out);
+        assert_eq!(out, [b'b', b'e', 0]);
+    }
+
+    #[test]
+    fn test_shorter_than_depth() {
+        let views: Vec<Strided> = deinterleave(b"a", 3).collect();
+        let lens: Vec<usize> = views.iter().map(|v| v.len()).collect();
+        assert_eq!(lens, vec![1, 0, 0]);
+        assert_eq!(views[0].to_vec(), b"a");
+        assert!(views[2].to_vec().is_empty());
+        let mut out = [7u8; 2];
+        views[2].copy_to(&mut out);
+        assert_eq!(out, [7, 7]);
+
+        let mut buf = [0u8; 1];
+        write_message(&mut buf, 3, 2, b"xyz");
+        write_message(&mut buf, 3, 5, b"xyz");
+        assert_eq!(buf, [0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_write_message_zero_depth() {
+        write_message(&mut [0u8; 4], 0, 0, b"ab");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_interleave_unequal() {
+        interleave(&[b"ab", b"c"]);
+    }
+}
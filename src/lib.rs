@@ -6,8 +6,90 @@
 //!
 //! This has been ported and adopted from the Python code found in the excelent article
 //! [Reed-Solomon Codes for Coders](https://en.wikiversity.org/wiki/Reed%E2%80%93Solomon_codes_for_coders).
+//!
+//! # Without std
+//!
+//! With the default `std` feature disabled the crate is `no_std` and does not
+//! allocate. All working storage of the decoder is in fixed size arrays bounded by
+//! [PARITY_LEN], so the in-place correction, e.g., [Code::correct_message_in_place],
+//! [Code::correct_codeword_in_place], and [shortened::Shortened], along with
+//! [algo], [crc], [dual_basis], [interleave], [syndromes], and [selftest()], are
+//! available on targets without an allocator. Everything returning owned buffers, e.g., [Block], requires `std`.
+#![cfg_attr(not(feature = "std"), no_std)]
+pub mod algo;
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "bytes")]
+pub mod bytes;
+#[cfg(feature = "ccsds-interop")]
+pub mod ccsds;
+#[cfg(feature = "std")]
+pub mod checkpoint;
+#[cfg(feature = "std")]
+pub mod conv;
+pub mod crc;
+#[cfg(feature = "std")]
+pub mod decoder;
+#[cfg(feature = "std")]
+pub mod diagnostics;
 pub mod dual_basis;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "mmap")]
+pub mod file;
+#[cfg(feature = "std")]
+pub mod follow;
+#[cfg(feature = "std")]
+pub mod frame;
+#[cfg(feature = "std")]
+pub mod gen;
 pub mod gf;
+pub mod interleave;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod pipeline;
+#[cfg(feature = "std")]
+pub mod pn;
+#[cfg(feature = "std")]
+pub mod presets;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "std")]
+pub mod quicklook;
+#[cfg(feature = "std")]
+pub mod reader;
+#[cfg(feature = "json")]
+pub mod report;
+pub mod selftest;
+#[cfg(feature = "std")]
+pub mod service;
+#[cfg(feature = "toml")]
+pub mod settings;
+pub mod shortened;
+#[cfg(feature = "std")]
+pub mod sim;
+mod simd;
+#[cfg(feature = "spacepackets")]
+pub mod spacepackets;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod sync;
+pub mod syndromes;
+#[cfg(feature = "std")]
+pub mod threads;
+#[cfg(feature = "std")]
+pub mod udp;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use algo::{
+    calc_syndromes, correct_errata, correct_errors_fixed, find_error_locator,
+    find_error_locator_fixed, find_errors, forney_syndromes,
+};
+#[cfg(feature = "std")]
+use decoder::Basis;
 
 /// Symbols per code word
 pub const N: u8 = 255;
@@ -15,7 +97,6 @@ pub const N: u8 = 255;
 #[allow(unused)]
 pub const J: u8 = 8;
 /// Common irreducible primative polynomial x^8 + x^7 + x^2 + x + 1
-#[allow(unused)]
 pub const PRIM: i32 = 391;
 /// Primative element: alpha 11
 pub const GEN: u8 = 173;
@@ -23,364 +104,2395 @@ pub const GEN: u8 = 173;
 pub const FCR: i32 = 112;
 /// Number of bytes of parity for each message.
 pub const PARITY_LEN: usize = 32;
+/// Maximum supported codeblock interleave depth.
+pub const MAX_INTERLEAVE: u8 = 8;
 
 /// Disposition of the RS process
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RSState {
     /// RS was performed and no errors were found
     Ok,
     /// RS was performed and the provided number of errors were successfully correct.
+    /// This counts both erasures and located errors; see [Block::corrected_erasures].
     Corrected(i32),
     /// RS was performed but the RS codeblock was not correctable, e.g., there were
     /// more errors than could be corrected.
-    Uncorrectable(String),
+    Uncorrectable(UncorrectableReason),
     NotPerformed,
 }
 
-fn correct_errata(input: &[u8], synd: &[u8], errpos: &[i32]) -> Result<Vec<u8>, &'static str> {
-    let mut coef_pos = vec![0i32; errpos.len()];
-    for (i, p) in errpos.iter().enumerate() {
-        coef_pos[i] = input.len() as i32 - 1 - p;
+/// Result of checking a message for errors without correcting it; see
+/// [Code::detect_errors].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Detection {
+    /// The message has no errors.
+    Clean,
+    /// The message has errors, estimated to be the provided number of symbols, and is
+    /// very likely correctable, though that is only certain after correcting it.
+    Correctable(usize),
+    /// The message has more errors than can be corrected, or is not a message.
+    Uncorrectable(UncorrectableReason),
+}
+
+/// Reason a message could not be corrected.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UncorrectableReason {
+    /// The error locator indicates more errors than can be corrected given the number
+    /// of erasures, if any.
+    TooManyErrors { found: usize, max: usize },
+    /// The number of error locator roots does not match its degree, which indicates
+    /// more errors than can be corrected.
+    LocatorDegreeMismatch { degree: usize, roots: usize },
+    /// The error magnitude could not be computed because the errata locator derivative
+    /// evaluated to zero.
+    ZeroErrorMagnitudeDenominator,
+    /// Syndromes of the corrected message are not all zero.
+    ResidualSyndrome,
+    /// Input was not the expected length.
+    InvalidLength { got: usize, want: usize },
+    /// Interleave depth was not in `1..=MAX_INTERLEAVE`.
+    InvalidInterleave(u8),
+    /// The interleave depth could not be inferred from the codeblock length because
+    /// it is not a multiple of 255 up to `MAX_INTERLEAVE * 255`.
+    UndetectableInterleave { len: usize },
+    /// Erasure positions were out of range, duplicated, or more than could be
+    /// corrected.
+    InvalidErasures,
+    /// Virtual fill was longer than the data portion of the message.
+    InvalidVirtualFill { got: usize, max: usize },
+    /// A correction was located in the virtual fill, which is known to be zero,
+    /// indicating a miscorrection.
+    ErrorInVirtualFill,
+    /// Re-encoding the corrected data did not reproduce the corrected check symbols;
+    /// see [decoder::RsDecoderBuilder::verify_parity].
+    ParityMismatch,
+}
+
+impl core::fmt::Display for UncorrectableReason {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooManyErrors { found, max } => write!(
+                f,
+                "too many errors to correct; expected no more than {max}, found {found}"
+            ),
+            Self::LocatorDegreeMismatch { degree, roots } => write!(
+                f,
+                "failed to generate error positions; expected {degree} positions, got {roots}"
+            ),
+            Self::ZeroErrorMagnitudeDenominator => write!(f, "failed to find error magnitude"),
+            Self::ResidualSyndrome => write!(f, "failed to correct all errors"),
+            Self::InvalidLength { got, want } => {
+                write!(f, "invalid input length; expected {want}, got {got}")
+            }
+            Self::InvalidInterleave(got) => write!(
+                f,
+                "invalid interleave; expected 1 to {MAX_INTERLEAVE}, got {got}"
+            ),
+            Self::UndetectableInterleave { len } => write!(
+                f,
+                "cannot detect interleave; expected a multiple of {N} up to {}, got {len}",
+                MAX_INTERLEAVE as usize * N as usize
+            ),
+            Self::InvalidErasures => write!(f, "invalid erasure positions"),
+            Self::InvalidVirtualFill { got, max } => {
+                write!(f, "invalid virtual fill; expected at most {max}, got {got}")
+            }
+            Self::ErrorInVirtualFill => write!(f, "error located in virtual fill"),
+            Self::ParityMismatch => write!(f, "re-encoded check symbols do not match"),
+        }
+    }
+}
+
+impl core::error::Error for UncorrectableReason {}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Block {
+    /// Resuting state of the RS process for all contained RS messages.
+    pub state: RSState,
+    /// The corrected codeword, including the RS check symbols, or None if the state is
+    /// [RSState::Uncorrectable]. For a shortened message the virtual fill is not
+    /// included. Use [Block::data] for just the data symbols.
+    pub message: Option<Vec<u8>>,
+    /// Message positions of the symbols that were corrected, in ascending order. This
+    /// is empty unless the state is [RSState::Corrected].
+    pub error_positions: Vec<usize>,
+    /// Error value of each corrected symbol, i.e., the received symbol XOR the
+    /// corrected symbol, in the same order as [Block::error_positions].
+    pub error_values: Vec<u8>,
+    /// Number of the corrected symbols that were at one of the provided erasure
+    /// positions. The other corrected symbols were errors located by the decoder; see
+    /// [Block::corrected_errors].
+    pub corrected_erasures: usize,
+}
+
+#[cfg(feature = "std")]
+impl Block {
+    /// The corrected data symbols, i.e., [Block::message] without the check symbols
+    /// of `code`, the code the message was corrected with. For a 255 byte message and
+    /// [Code::E16] these are the 223 data bytes.
+    ///
+    /// None if the state is [RSState::Uncorrectable].
+    pub fn data(&self, code: Code) -> Option<&[u8]> {
+        let msg = self.message.as_deref()?;
+        Some(&msg[..msg.len().saturating_sub(code.parity_len())])
     }
 
-    let errloc = find_errata_locator(&coef_pos[..]);
-    let mut rev_synd = synd.to_owned();
-    rev_synd.reverse();
-    let mut erreval = find_error_evaluator(&rev_synd, &errloc, errloc.len() as i32 - 1);
-    erreval.reverse();
+    /// Number of bits corrected, i.e., the total Hamming weight of
+    /// [Block::error_values]. Unlike the symbol count in [RSState::Corrected] this is
+    /// what is needed to estimate the channel bit error rate.
+    pub fn corrected_bits(&self) -> u32 {
+        self.error_values.iter().map(|v| v.count_ones()).sum()
+    }
 
-    let mut x = vec![0u8; coef_pos.len()];
-    for (i, p) in coef_pos.iter().enumerate() {
-        x[i] = gf::pow(GEN as u8, -(N as i32 - p));
+    /// Number of the corrected symbols that were located by the decoder rather than
+    /// given as erasures. Erasure corrections only rely on the check symbols to find
+    /// the value, so a located error is the more likely of the two to be a
+    /// miscorrection.
+    pub fn corrected_errors(&self) -> usize {
+        self.error_positions.len() - self.corrected_erasures
     }
 
-    let mut e = vec![0u8; input.len()];
-    for (i, xi) in x.iter().enumerate() {
-        let xi_inv = gf::inv(*xi);
-        let mut errloc_prime_tmp: Vec<u8> = Vec::new();
-        for j in 0..x.len() {
-            if j != i {
-                errloc_prime_tmp.push(1 ^ gf::mult(xi_inv, x[j]));
-            }
-        }
-        let mut errloc_prime = 1u8;
-        for c in errloc_prime_tmp.iter() {
-            errloc_prime = gf::mult(errloc_prime, *c);
+    /// This block, or an uncorrectable block if more than `max` errors were corrected,
+    /// not counting erasures; see [Block::corrected_errors].
+    ///
+    /// Correcting fewer errors than the code allows lowers the probability of a
+    /// miscorrection on a marginal link, i.e., of a message with more errors than can
+    /// be corrected being "corrected" to a different codeword, at the cost of
+    /// rejecting messages with between `max` and the code's limit of errors. Erasures
+    /// are not counted because their positions are given rather than located, so
+    /// they do not add to that probability in the same way. Every `max_errors` of the
+    /// crate, e.g., [Code::correct_codeblock_with_max_errors], has this meaning.
+    pub fn limit_errors(self, max: usize) -> Block {
+        match limit_errors(self.state.clone(), self.corrected_errors(), max) {
+            RSState::Uncorrectable(reason) => Block::uncorrectable(reason),
+            _ => self,
         }
+    }
 
-        let mut erreval_rev = erreval.to_owned();
-        erreval_rev.reverse();
-        let mut y = gf::poly_eval(&erreval_rev, xi_inv);
-        y = gf::mult(gf::pow(*xi, 1 - FCR), y);
+    fn uncorrectable(reason: UncorrectableReason) -> Self {
+        Block {
+            state: RSState::Uncorrectable(reason),
+            message: None,
+            error_positions: Vec::new(),
+            error_values: Vec::new(),
+            corrected_erasures: 0,
+        }
+    }
+}
 
-        if errloc_prime == 0 {
-            return Err("failed to find error magnitude");
+/// The state of a message in which `found` errors were located and corrected, not
+/// counting erasures, if no more than `max` may be corrected; see [Block::limit_errors].
+fn limit_errors(state: RSState, found: usize, max: usize) -> RSState {
+    match state {
+        RSState::Corrected(_) if found > max => {
+            RSState::Uncorrectable(UncorrectableReason::TooManyErrors { found, max })
         }
+        state => state,
+    }
+}
+
+#[cfg(feature = "std")]
+/// The corrected bytes of a [Block], i.e., [Block::message], which are empty if the
+/// message was uncorrectable.
+impl std::ops::Deref for Block {
+    type Target = [u8];
 
-        e[errpos[i] as usize] = gf::div(y, errloc_prime);
+    fn deref(&self) -> &[u8] {
+        self.message.as_deref().unwrap_or_default()
     }
+}
 
-    let zult = &gf::poly_add(&input, &e);
-    Ok(zult.to_vec())
+#[cfg(feature = "std")]
+impl AsRef<[u8]> for Block {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
 }
 
-fn find_errata_locator(errpos: &[i32]) -> Vec<u8> {
-    let mut errloc = vec![1u8];
-    for p in errpos.iter() {
-        let x = &[gf::pow(GEN as u8, *p), 0];
-        let y = gf::poly_add(&[1u8], x);
-        errloc = gf::poly_mult(&errloc, &y);
+#[cfg(feature = "std")]
+impl<'a> IntoIterator for &'a Block {
+    type Item = &'a u8;
+    type IntoIter = std::slice::Iter<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
     }
-    errloc
 }
 
-fn find_error_evaluator(synd: &[u8], errloc: &[u8], n: i32) -> Vec<u8> {
-    let mut divisor: Vec<u8> = vec![0u8; n as usize + 2];
-    divisor[0] = 1;
-    let (_, rem) = gf::poly_div(&gf::poly_mult(&synd, &errloc), &divisor);
-    rem
+#[cfg(feature = "std")]
+impl IntoIterator for Block {
+    type Item = u8;
+    type IntoIter = std::vec::IntoIter<u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.message.unwrap_or_default().into_iter()
+    }
 }
 
-fn find_errors(errloc: &[u8]) -> Vec<i32> {
-    let num_errs = errloc.len() - 1;
-    let mut errpos: Vec<i32> = Vec::with_capacity(num_errs);
-    let n = N as i32;
-    for i in 0..n {
-        if gf::poly_eval(errloc, gf::pow(GEN, i as i32)) == 0 {
-            errpos.push(N as i32 - 1 - i);
+#[cfg(feature = "std")]
+impl std::fmt::Display for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.state {
+            RSState::Ok => write!(f, "ok"),
+            RSState::Corrected(n) => {
+                write!(f, "corrected {n} symbols at {:?}", self.error_positions)
+            }
+            RSState::Uncorrectable(reason) => write!(f, "uncorrectable: {reason}"),
+            RSState::NotPerformed => write!(f, "not performed"),
         }
     }
-    errpos
 }
 
-fn find_error_locator(synd: &[u8], parity_len: usize) -> Vec<u8> {
-    let mut errloc = vec![1u8];
-    let mut oldloc = vec![1u8];
-    let mut synd_shift = 0;
-    if synd.len() > parity_len {
-        synd_shift = synd.len() - parity_len;
+/// A 255 symbol code word, i.e., an RS message including its check symbols.
+///
+/// The length is validated once when converting from a slice, so the correction
+/// methods taking a `Codeword`, e.g., [correct_codeword], cannot fail with
+/// [UncorrectableReason::InvalidLength].
+///
+/// ```
+/// use rs2::{Codeword, RSState};
+///
+/// let input = vec![0u8; 255];
+/// let codeword = Codeword::try_from(&input[..]).unwrap();
+/// assert_eq!(rs2::correct_codeword(&codeword).state, RSState::Ok);
+/// assert!(Codeword::try_from(&input[1..]).is_err());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Codeword(pub [u8; N as usize]);
+
+impl Codeword {
+    pub fn into_inner(self) -> [u8; N as usize] {
+        self.0
     }
-    for i in 0..parity_len {
-        let k = i as usize + synd_shift;
-        let mut delta = synd[k];
-        for j in 1..errloc.len() {
-            delta ^= gf::mult(errloc[errloc.len() - j - 1], synd[k - j]);
-        }
-        oldloc.push(0);
-        if delta != 0 {
-            if oldloc.len() > errloc.len() {
-                let newloc = gf::poly_scale(&oldloc, delta);
-                oldloc = gf::poly_scale(&errloc, gf::inv(delta));
-                errloc = newloc;
-            }
-            errloc = gf::poly_add(&errloc, &gf::poly_scale(&oldloc, delta));
+}
+
+impl Default for Codeword {
+    fn default() -> Self {
+        Codeword([0u8; N as usize])
+    }
+}
+
+impl TryFrom<&[u8]> for Codeword {
+    type Error = UncorrectableReason;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        match <[u8; N as usize]>::try_from(value) {
+            Ok(msg) => Ok(Codeword(msg)),
+            Err(_) => Err(UncorrectableReason::InvalidLength {
+                got: value.len(),
+                want: N as usize,
+            }),
         }
     }
+}
 
-    while errloc.len() > 0 && errloc[0] == 0 {
-        errloc = errloc[1..].to_vec();
+impl From<[u8; N as usize]> for Codeword {
+    fn from(value: [u8; N as usize]) -> Self {
+        Codeword(value)
     }
+}
 
-    errloc
+impl core::ops::Deref for Codeword {
+    type Target = [u8; N as usize];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
 }
 
-fn forney_syndromes(synd: &[u8], pos: &[i32], nmess: i32) -> Vec<u8> {
-    let mut erase_pos_rev = vec![0i32; pos.len()];
-    for (i, p) in pos.iter().enumerate() {
-        erase_pos_rev[i] = nmess - 1 - p;
+impl core::ops::DerefMut for Codeword {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
     }
-    let mut fsynd: Vec<u8> = Vec::with_capacity(synd.len() - 1);
-    fsynd.extend_from_slice(&synd[1..]);
-    for i in 0..pos.len() {
-        let x = gf::pow(GEN as u8, erase_pos_rev[i]);
-        for j in 0..fsynd.len() - 1 {
-            fsynd[j] = gf::mult(fsynd[j], x) ^ fsynd[j + 1];
-        }
+}
+
+impl AsRef<[u8]> for Codeword {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
     }
-    fsynd
 }
 
-fn calc_syndromes(input: &[u8], parity_len: usize) -> Vec<u8> {
-    let mut synd: Vec<u8> = vec![0u8; parity_len + 1];
-    for i in 0..parity_len {
-        let p = gf::pow(GEN, i as i32 + FCR);
-        synd[i + 1] = gf::poly_eval(&input, p);
+const ROOTS_E16: [u8; 32] = code_roots(Code::E16);
+const ROOTS_E8: [u8; 16] = code_roots(Code::E8);
+const GENERATOR_E16: [u8; 33] = code_generator(&ROOTS_E16);
+const GENERATOR_E8: [u8; 17] = code_generator(&ROOTS_E8);
+
+const fn code_roots<const LEN: usize>(code: Code) -> [u8; LEN] {
+    let mut roots = [0u8; LEN];
+    let mut i = 0;
+    while i < LEN {
+        roots[i] = gf::exp(i as i32 + code.fcr());
+        i += 1;
     }
-    synd
+    roots
 }
 
-pub struct Block {
-    /// Resuting state of the RS process for all contained RS messages.
-    pub state: RSState,
-    /// The checked codeblock without the RS check symbols.
-    pub message: Option<Vec<u8>>,
+/// Product of (x - root) for each of `roots`, highest degree first.
+const fn code_generator<const LEN: usize>(roots: &[u8]) -> [u8; LEN] {
+    let mut g = [0u8; LEN];
+    g[0] = 1;
+    let mut r = 0;
+    while r < roots.len() {
+        let mut k = r + 1;
+        while k > 0 {
+            g[k] ^= gf::mult_const(g[k - 1], roots[r]);
+            k -= 1;
+        }
+        r += 1;
+    }
+    g
 }
 
-/// Correct a Reed-Solomon 255 byte code block, where the last [PARITY_LEN] bytes are
-/// the parity/check bytes. The code block is also assumed to be in dual basis
-/// representation.
-///
-/// The returned [Block::message} will contain the corrected message iff the state is
-/// [RSState::Corrected]. Otherwise it will be None.
+/// Reed-Solomon codes defined by CCSDS 131.0-B-5. Both use 255 symbol code words and
+/// differ in the number of check symbols, `2E`, and therefore the number of symbol
+/// errors, `E`, that can be corrected per message.
 ///
-/// The state will be [RSState::Uncorrectable] if there are more errors than can be
-/// corrected or if an algorithm failure occurs.
-pub fn correct_message(input: &[u8]) -> Block {
-    let input = input.to_vec();
-    if input.len() != N as usize {
-        return Block {
-            state: RSState::Uncorrectable("invalid input".to_owned()),
-            message: None,
-        };
+/// The free functions in this crate, e.g., [correct_message], use [Code::E16]. The same
+/// operations are available for either code as methods on this type.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Code {
+    /// RS(255,223) correcting up to 16 symbol errors per message.
+    #[default]
+    E16,
+    /// RS(255,239) correcting up to 8 symbol errors per message.
+    E8,
+}
+
+impl Code {
+    /// Maximum number of symbol errors that can be corrected per message, i.e., `E`.
+    pub const fn max_errors(&self) -> usize {
+        match self {
+            Code::E16 => 16,
+            Code::E8 => 8,
+        }
     }
-    let out = dual_basis::to_conv(&input).clone();
 
-    let synd = calc_syndromes(&out, PARITY_LEN);
-    let max = synd.iter().max().unwrap();
-    // if there are no non-zero elements there are no errors
-    if *max == 0 {
-        return Block {
-            state: RSState::Ok,
-            message: Some(input),
-        };
+    /// Number of check symbols per message.
+    pub const fn parity_len(&self) -> usize {
+        self.max_errors() * 2
     }
 
-    let fsynd = forney_syndromes(&synd, &[], out.len() as i32);
-    let errloc = find_error_locator(&fsynd[..], PARITY_LEN);
+    /// Number of data symbols per message.
+    pub const fn data_len(&self) -> usize {
+        N as usize - self.parity_len()
+    }
 
-    let num_errs = errloc.len() - 1;
-    if num_errs * 2 > PARITY_LEN {
-        return Block {
-            state: RSState::Uncorrectable(format!(
-                "too many errors to correct; expected no more than {:?}, found {:?}",
-                PARITY_LEN / 2,
-                num_errs
-            ))
-            .to_owned(),
-            message: None,
-        };
+    /// First consecutive root in g(x): 128-E
+    pub const fn fcr(&self) -> i32 {
+        128 - self.max_errors() as i32
     }
 
-    let mut errloc_rev = errloc.clone();
-    errloc_rev.reverse();
-    let errpos = find_errors(&errloc_rev[..]);
-    if errpos.len() != num_errs {
-        return Block {
-            state: RSState::Uncorrectable(
-                format!(
-                    "failed to generate error positions; expected {} postions, got {}",
-                    num_errs,
-                    errpos.len()
-                )
-                .to_owned(),
-            ),
-            message: None,
+    /// Roots of the generator polynomial, GEN^(fcr + i) for i in 0..parity_len, i.e.,
+    /// the points at which the syndromes are evaluated.
+    pub(crate) const fn roots(&self) -> &'static [u8] {
+        match self {
+            Code::E16 => &ROOTS_E16,
+            Code::E8 => &ROOTS_E8,
+        }
+    }
+
+    /// Generator polynomial, g(x), in conventional representation, highest degree
+    /// first.
+    const fn generator(&self) -> &'static [u8] {
+        match self {
+            Code::E16 => &GENERATOR_E16,
+            Code::E8 => &GENERATOR_E8,
+        }
+    }
+
+    /// Encode a message in place using this code. See [encode_message_in_place].
+    pub fn encode_message_in_place(&self, msg: &mut [u8; N as usize]) {
+        let data_len = self.data_len();
+        let parity_len = self.parity_len();
+        let g = self.generator();
+
+        let mut data = [0u8; N as usize];
+        data[..data_len].copy_from_slice(&msg[..data_len]);
+        dual_basis::to_conv_in_place(&mut data[..data_len]);
+
+        // remainder of m(x) * x^2E / g(x), highest degree first
+        let mut parity = [0u8; PARITY_LEN];
+        let parity = &mut parity[..parity_len];
+        for x in data[..data_len].iter() {
+            let feedback = x ^ parity[0];
+            parity.copy_within(1.., 0);
+            parity[parity_len - 1] = 0;
+            if feedback != 0 {
+                for (p, c) in parity.iter_mut().zip(g[1..].iter()) {
+                    *p ^= gf::mult(feedback, *c);
+                }
+            }
+        }
+
+        dual_basis::to_dual_in_place(parity);
+        msg[data_len..].copy_from_slice(parity);
+    }
+
+    /// Re-encode the data portion of `msg` and return whether the result matches its
+    /// check symbols, i.e., whether `msg` is a valid codeword as computed by the
+    /// encoder rather than the decoder.
+    pub fn verify_parity(&self, msg: &[u8; N as usize]) -> bool {
+        let mut encoded = *msg;
+        self.encode_message_in_place(&mut encoded);
+        encoded == *msg
+    }
+
+    /// Encode a message using this code. See [encode_message].
+    #[cfg(feature = "std")]
+    pub fn encode_message(&self, data: &[u8]) -> Result<Vec<u8>, UncorrectableReason> {
+        if data.len() != self.data_len() {
+            return Err(UncorrectableReason::InvalidLength {
+                got: data.len(),
+                want: self.data_len(),
+            });
+        }
+        let mut msg = [0u8; N as usize];
+        msg[..data.len()].copy_from_slice(data);
+        self.encode_message_in_place(&mut msg);
+        Ok(msg.to_vec())
+    }
+
+    /// Correct a message using this code. See [correct_message].
+    #[cfg(feature = "std")]
+    pub fn correct_message(&self, input: &[u8]) -> Block {
+        self.correct_message_with_erasures(input, &[])
+    }
+
+    /// Correct a message with erasures using this code. See
+    /// [correct_message_with_erasures].
+    #[cfg(feature = "std")]
+    pub fn correct_message_with_erasures(&self, input: &[u8], erasures: &[usize]) -> Block {
+        self.correct_message_in_basis(input, erasures, Basis::Dual, Basis::Dual)
+    }
+
+    /// Correct a message with erasures like [correct_message_with_erasures], where
+    /// `input` is in the `input_basis` representation and the returned
+    /// [Block::message] is in the `output_basis` representation, e.g., to get the full
+    /// corrected codeword in conventional basis for another tool.
+    ///
+    /// The correction itself is done in conventional basis, so the message is only
+    /// converted to or from dual basis where requested. [Block::error_values] are in
+    /// the input representation, as they describe the received symbols.
+    #[cfg(feature = "std")]
+    pub fn correct_message_in_basis(
+        &self,
+        input: &[u8],
+        erasures: &[usize],
+        input_basis: Basis,
+        output_basis: Basis,
+    ) -> Block {
+        match Codeword::try_from(input) {
+            Ok(codeword) => self.correct_array(&codeword, erasures, input_basis, output_basis),
+            Err(reason) => Block::uncorrectable(reason),
+        }
+    }
+
+    /// Correct a code word using this code. See [correct_codeword].
+    #[cfg(feature = "std")]
+    pub fn correct_codeword(&self, codeword: &Codeword) -> Block {
+        self.correct_array(codeword, &[], Basis::Dual, Basis::Dual)
+    }
+
+    /// Correct a code word with erasures using this code. See
+    /// [correct_message_with_erasures].
+    #[cfg(feature = "std")]
+    pub fn correct_codeword_with_erasures(&self, codeword: &Codeword, erasures: &[usize]) -> Block {
+        self.correct_array(codeword, erasures, Basis::Dual, Basis::Dual)
+    }
+
+    /// Correct a code word in place using this code. See [correct_message_in_place].
+    pub fn correct_codeword_in_place(&self, codeword: &mut Codeword) -> RSState {
+        self.correct_in_place(codeword, &[])
+    }
+
+    /// [Code::correct_message_in_basis] for an input already known to be 255 bytes.
+    #[cfg(feature = "std")]
+    fn correct_array(
+        &self,
+        input: &[u8; N as usize],
+        erasures: &[usize],
+        input_basis: Basis,
+        output_basis: Basis,
+    ) -> Block {
+        if !valid_erasures(erasures, self.parity_len()) {
+            return Block::uncorrectable(UncorrectableReason::InvalidErasures);
+        }
+        let mut msg = *input;
+        if input_basis == Basis::Dual {
+            dual_basis::to_conv_in_place(&mut msg);
+        }
+        let received = msg;
+        let state = self.correct_conventional(&mut msg, erasures);
+        if let RSState::Uncorrectable(reason) = state {
+            return Block::uncorrectable(reason);
+        }
+        let (error_positions, mut error_values): (Vec<usize>, Vec<u8>) = received
+            .iter()
+            .zip(msg.iter())
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, (a, b))| (i, a ^ b))
+            .unzip();
+        // the basis conversion is linear, so errors convert like symbols
+        if input_basis == Basis::Dual {
+            dual_basis::to_dual_in_place(&mut error_values);
+        }
+        if output_basis == Basis::Dual {
+            dual_basis::to_dual_in_place(&mut msg);
+        }
+        let corrected_erasures = error_positions
+            .iter()
+            .filter(|p| erasures.contains(p))
+            .count();
+        Block {
+            state,
+            message: Some(msg.to_vec()),
+            error_positions,
+            error_values,
+            corrected_erasures,
+        }
+    }
+
+    /// Correct a message in place using this code. See [correct_message_in_place].
+    pub fn correct_message_in_place(&self, msg: &mut [u8; N as usize]) -> RSState {
+        self.correct_in_place(msg, &[])
+    }
+
+    /// Correct a message in place like [Code::correct_message_in_place], but treat a
+    /// message with more than `max_errors` errors as uncorrectable; see
+    /// [Block::limit_errors]. There are no erasures, so every corrected symbol counts.
+    pub fn correct_message_in_place_with_max_errors(
+        &self,
+        msg: &mut [u8; N as usize],
+        max_errors: usize,
+    ) -> RSState {
+        let mut out = *msg;
+        let state = match self.correct_in_place(&mut out, &[]) {
+            RSState::Corrected(found) => {
+                limit_errors(RSState::Corrected(found), found as usize, max_errors)
+            }
+            state => state,
         };
+        if let RSState::Corrected(_) = state {
+            *msg = out;
+        }
+        state
     }
 
-    let out = match correct_errata(&out, &synd, &errpos) {
-        Err(err) => {
-            return Block {
-                state: RSState::Uncorrectable(err.to_owned()),
-                message: None,
+    /// Correct a message with erasures in place, without allocating, like
+    /// [correct_message_with_erasures]. `msg` is only modified if it is corrected.
+    pub fn correct_message_in_place_with_erasures(
+        &self,
+        msg: &mut [u8; N as usize],
+        erasures: &[usize],
+    ) -> RSState {
+        if !valid_erasures(erasures, self.parity_len()) {
+            return RSState::Uncorrectable(UncorrectableReason::InvalidErasures);
+        }
+        self.correct_in_place(msg, erasures)
+    }
+
+    /// Correct a message using this code, writing it to `out`. See [correct_message_to].
+    pub fn correct_message_to(&self, input: &[u8], out: &mut [u8]) -> RSState {
+        let got = out.len();
+        let Some(msg) = out.first_chunk_mut::<{ N as usize }>() else {
+            return RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                got,
+                want: N as usize,
+            });
+        };
+        match Codeword::try_from(input) {
+            Ok(codeword) => {
+                *msg = codeword.into_inner();
+                self.correct_in_place(msg, &[])
             }
+            Err(reason) => RSState::Uncorrectable(reason),
         }
-        Ok(block) => block,
-    };
+    }
 
-    let synd = calc_syndromes(&out, PARITY_LEN);
-    if *synd.iter().max().unwrap() > 0 {
-        return Block {
-            state: RSState::Uncorrectable("failed to correct all errors".to_owned()),
-            message: None,
+    /// Correct the message at `offset` of a larger buffer in place like
+    /// [Code::correct_message_in_place], e.g., following a header, without the caller
+    /// slicing it out. The rest of `buf` is not used. If there are fewer than 255 bytes
+    /// from `offset` the state is [UncorrectableReason::InvalidLength].
+    pub fn correct_message_in_place_at(&self, buf: &mut [u8], offset: usize) -> RSState {
+        let end = buf.len().min(offset.saturating_add(N as usize));
+        let msg = buf.get_mut(offset..end).unwrap_or_default();
+        let got = msg.len();
+        match <&mut [u8; N as usize]>::try_from(msg) {
+            Ok(msg) => self.correct_in_place(msg, &[]),
+            Err(_) => RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                got,
+                want: N as usize,
+            }),
+        }
+    }
+
+    /// Correct a message in place with a fixed amount of work using this code. See
+    /// [correct_message_in_place_deterministic].
+    pub fn correct_message_in_place_deterministic(&self, msg: &mut [u8; N as usize]) -> RSState {
+        let parity_len = self.parity_len();
+        let mut out = *msg;
+        dual_basis::to_conv_in_place(&mut out);
+
+        // every step is done regardless of the results of the previous ones, which are
+        // only checked at the end
+        let synd = calc_syndromes(&out, parity_len, self.fcr());
+        let errloc = find_error_locator_fixed(&synd, parity_len);
+        let degree = errloc.iter().rposition(|c| *c != 0).unwrap_or(0);
+        let roots = correct_errors_fixed(&mut out, &synd, &errloc, self.fcr());
+        let residual = calc_syndromes(&out, parity_len, self.fcr());
+        dual_basis::to_dual_in_place(&mut out);
+        let num_corrected = msg.iter().zip(out.iter()).filter(|(a, b)| a != b).count();
+
+        let state = if synd.iter().all(|x| *x == 0) {
+            RSState::Ok
+        } else if degree * 2 > parity_len {
+            RSState::Uncorrectable(UncorrectableReason::TooManyErrors {
+                found: degree,
+                max: parity_len / 2,
+            })
+        } else {
+            match roots {
+                Err(err) => RSState::Uncorrectable(err),
+                Ok(roots) if roots != degree => {
+                    RSState::Uncorrectable(UncorrectableReason::LocatorDegreeMismatch {
+                        degree,
+                        roots,
+                    })
+                }
+                Ok(_) if residual.iter().any(|x| *x != 0) => {
+                    RSState::Uncorrectable(UncorrectableReason::ResidualSyndrome)
+                }
+                Ok(_) => RSState::Corrected(num_corrected as i32),
+            }
         };
+        if let RSState::Corrected(_) = state {
+            *msg = out;
+        }
+        state
     }
 
-    Block {
-        state: RSState::Corrected(errloc.len() as i32 - 1),
-        message: Some(dual_basis::to_dual(&out)),
+    /// Correct a buffer of back-to-back messages using this code. See [correct_buffer].
+    #[cfg(feature = "std")]
+    pub fn correct_buffer(&self, input: &[u8]) -> Vec<Block> {
+        input
+            .chunks(N as usize)
+            .map(|msg| self.correct_message(msg))
+            .collect()
     }
-}
 
-/// Return true if the input code block contains 1 or more errors.
-pub fn has_errors(msg: &[u8]) -> bool {
-    let msg = dual_basis::to_conv(msg);
-    let mut x = 0;
-    for i in calc_syndromes(&msg[..], PARITY_LEN) {
-        if i > x {
-            x = i;
+    /// Correct a buffer of back-to-back messages in place using this code. See
+    /// [correct_buffer_in_place].
+    #[cfg(feature = "std")]
+    pub fn correct_buffer_in_place(&self, buf: &mut [u8]) -> Vec<RSState> {
+        let mut states = Vec::with_capacity(buf.len().div_ceil(N as usize));
+        let mut groups = buf.chunks_exact_mut(N as usize * gf::LANES);
+        for group in groups.by_ref() {
+            self.correct_lanes_in_place(group, &mut states);
+        }
+        let mut chunks = groups.into_remainder().chunks_exact_mut(N as usize);
+        states.extend(chunks.by_ref().map(|msg| {
+            let msg: &mut [u8; N as usize] = msg.try_into().expect("chunk is N bytes");
+            self.correct_in_place(msg, &[])
+        }));
+        let remainder = chunks.into_remainder();
+        if !remainder.is_empty() {
+            states.push(RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                got: remainder.len(),
+                want: N as usize,
+            }));
         }
+        states
     }
-    x != 0
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Correct [gf::LANES] back-to-back dual basis messages in place, adding their
+    /// states to `states`.
+    ///
+    /// The syndromes of all of the messages are computed at once, with each SIMD lane
+    /// handling a different message, and only those with errors go on to be corrected
+    /// individually. Most messages of a typical pass have no errors, so this is most of
+    /// the work.
+    #[cfg(feature = "std")]
+    fn correct_lanes_in_place(&self, group: &mut [u8], states: &mut Vec<RSState>) {
+        let parity_len = self.parity_len();
+        // transpose so that row k holds symbol k of each message
+        let mut rows = [[0u8; gf::LANES]; N as usize];
+        for (lane, msg) in group.chunks_exact(N as usize).enumerate() {
+            for (row, x) in rows.iter_mut().zip(msg) {
+                row[lane] = *x;
+            }
+        }
+        dual_basis::to_conv_in_place(rows.as_flattened_mut());
+        let mut synd = [[0u8; gf::LANES]; PARITY_LEN];
+        gf::poly_eval_lanes(&rows, self.roots(), &mut synd[..parity_len]);
 
-    // RS message, no pn
-    const FIXTURE_MSG: &[u8; 255] = &[
-        0x67, 0xc4, 0x6b, 0xa7, 0x3e, 0xbe, 0x4c, 0x33, 0x6c, 0xb2, 0x23, 0x3a, 0x74, 0x06, 0x2b,
-        0x18, 0xab, 0xb8, 0x09, 0xe6, 0x7d, 0xaf, 0x5d, 0xe5, 0xdf, 0x76, 0x25, 0x3f, 0xb9, 0x14,
-        0xee, 0xec, 0xd1, 0xa3, 0x39, 0x5f, 0x38, 0x68, 0xf0, 0x26, 0xa6, 0x8a, 0xcb, 0x09, 0xaf,
-        0x4e, 0xf8, 0x93, 0xf7, 0x45, 0x4b, 0x0d, 0xa9, 0xb8, 0x74, 0x0e, 0xf3, 0xc7, 0xed, 0x6e,
-        0xa3, 0x0f, 0xf6, 0x79, 0x94, 0x16, 0xe2, 0x7f, 0xad, 0x91, 0x91, 0x04, 0xac, 0xa4, 0xae,
-        0xb4, 0x51, 0x76, 0x2f, 0x62, 0x03, 0x5e, 0xa1, 0xe5, 0x5c, 0x45, 0xf8, 0x1f, 0x7a, 0x7b,
-        0xe8, 0x35, 0xd8, 0xcc, 0x51, 0x0e, 0xae, 0x3a, 0x2a, 0x64, 0x1d, 0x03, 0x10, 0xcd, 0x18,
-        0xe6, 0x7f, 0xef, 0xba, 0xd9, 0xe8, 0x98, 0x47, 0x82, 0x9c, 0xa1, 0x58, 0x47, 0x25, 0xdf,
-        0x41, 0xd2, 0x01, 0x62, 0x3c, 0x24, 0x88, 0x90, 0xe9, 0xd7, 0x38, 0x1b, 0xa0, 0xa2, 0xb4,
-        0x23, 0xea, 0x7e, 0x58, 0x0d, 0xf4, 0x61, 0x24, 0x14, 0xb0, 0x41, 0x90, 0x0c, 0xb7, 0xbb,
-        0x5c, 0x59, 0x1b, 0xc6, 0x69, 0x24, 0x0f, 0xb6, 0x0e, 0x14, 0xa1, 0xb1, 0x8e, 0x48, 0x0f,
-        0x17, 0x1d, 0xfb, 0x0f, 0x38, 0x42, 0xe3, 0x24, 0x58, 0xab, 0x82, 0xa8, 0xfd, 0xdf, 0xac,
-        0x68, 0x93, 0x3d, 0x0d, 0x8f, 0x50, 0x52, 0x44, 0x6c, 0xba, 0xd3, 0x51, 0x99, 0x9c, 0x3e,
-        0xad, 0xd5, 0xa8, 0xd7, 0x9d, 0xc7, 0x7f, 0x9f, 0xc9, 0x2a, 0xac, 0xe5, 0xc2, 0xcd, 0x9a,
-        0x9b, 0xfa, 0x2d, 0x72, 0xab, 0x6b, 0xa4, 0x6b, 0x8b, 0x7d, 0xfa, 0x6c, 0x83, 0x63, 0x77,
-        0x9f, 0x4e, 0x9a, 0x20, 0x35, 0xd2, 0x91, 0xce, 0xf4, 0x21, 0x1a, 0x97, 0x3c, 0x1a, 0x15,
-        0x9d, 0xfc, 0x98, 0xba, 0x72, 0x1b, 0x9a, 0xa2, 0xe9, 0xc9, 0x46, 0x68, 0xce, 0xad, 0x27,
-    ];
+        for (lane, msg) in group.chunks_exact_mut(N as usize).enumerate() {
+            if synd[..parity_len].iter().all(|s| s[lane] == 0) {
+                states.push(RSState::Ok);
+            } else {
+                let msg: &mut [u8; N as usize] = msg.try_into().expect("chunk is N bytes");
+                states.push(self.correct_in_place(msg, &[]));
+            }
+        }
+    }
 
-    #[test]
-    fn test_calc_syndromes() {
-        const EXPECTED: &[u8] = &[
-            0x00, 0xb7, 0xd5, 0x62, 0x7b, 0xf5, 0xa0, 0x52, 0x91, 0xc1, 0xd2, 0x97, 0xd0, 0x40,
-            0x68, 0x59, 0x0d, 0xcb, 0xc0, 0x84, 0x84, 0x68, 0xa6, 0xd9, 0x79, 0xf9, 0xad, 0x4c,
-            0x81, 0x9f, 0x14, 0x2f, 0x78,
-        ];
+    /// Correct a dual basis message in place, where `erasures` have already been
+    /// validated. `msg` is left unmodified unless it is corrected.
+    fn correct_in_place(&self, msg: &mut [u8; N as usize], erasures: &[usize]) -> RSState {
+        let mut out = *msg;
+        dual_basis::to_conv_in_place(&mut out);
+        let state = self.correct_conventional(&mut out, erasures);
+        if let RSState::Corrected(_) = state {
+            dual_basis::to_dual_in_place(&mut out);
+            *msg = out;
+        }
+        state
+    }
 
-        let zult = calc_syndromes(FIXTURE_MSG, PARITY_LEN);
+    /// Allocation free core of all message correction, where `msg` is in conventional
+    /// basis and `erasures` have already been validated. `msg` is left unmodified
+    /// unless it is corrected.
+    fn correct_conventional(&self, msg: &mut [u8; N as usize], erasures: &[usize]) -> RSState {
+        let parity_len = self.parity_len();
+        let mut out = *msg;
 
-        for ((i, z), e) in zult.iter().enumerate().zip(EXPECTED.iter()) {
-            assert_eq!(
-                z, e,
-                "not all elements equal: expected {}, got {} at index {}\n{:?}",
-                e, z, i, zult
-            );
+        let synd = calc_syndromes(&out, parity_len, self.fcr());
+        // if there are no non-zero elements there are no errors
+        if synd.iter().all(|x| *x == 0) {
+            return RSState::Ok;
+        }
+
+        let mut errata = [0i32; PARITY_LEN];
+        for (i, p) in erasures.iter().enumerate() {
+            errata[i] = *p as i32;
+        }
+        let num_erasures = erasures.len();
+        let fsynd = forney_syndromes(&synd, &errata[..num_erasures], out.len() as i32);
+        let errloc = find_error_locator(&fsynd, parity_len, num_erasures);
+
+        let num_errs = errloc.len() - 1;
+        if num_errs * 2 + num_erasures > parity_len {
+            return RSState::Uncorrectable(UncorrectableReason::TooManyErrors {
+                found: num_errs,
+                max: (parity_len - num_erasures) / 2,
+            });
+        }
+
+        let found = find_errors(
+            &errloc.reversed(),
+            &mut errata[num_erasures..num_erasures + num_errs],
+        );
+        if found != num_errs {
+            return RSState::Uncorrectable(UncorrectableReason::LocatorDegreeMismatch {
+                degree: num_errs,
+                roots: found,
+            });
+        }
+
+        let errata = &errata[..num_erasures + num_errs];
+        if let Err(err) = correct_errata(&mut out, &synd, errata, self.fcr()) {
+            return RSState::Uncorrectable(err);
         }
+
+        let synd = calc_syndromes(&out, parity_len, self.fcr());
+        if synd.iter().any(|x| *x != 0) {
+            return RSState::Uncorrectable(UncorrectableReason::ResidualSyndrome);
+        }
+
+        // Erased symbols that were already correct have an error magnitude of zero, so
+        // only count symbols that were actually changed.
+        let num_corrected = msg.iter().zip(out.iter()).filter(|(a, b)| a != b).count();
+        *msg = out;
+        RSState::Corrected(num_corrected as i32)
     }
 
-    #[test]
-    fn test_correct_message_noerrors() {
-        let msg = FIXTURE_MSG.clone();
+    /// Correct a shortened message using this code. See [correct_shortened].
+    #[cfg(feature = "std")]
+    pub fn correct_shortened(&self, input: &[u8], virtual_fill: usize) -> Block {
+        self.correct_shortened_in_basis(input, virtual_fill, Basis::Dual, Basis::Dual)
+    }
 
-        assert!(!has_errors(&msg), "expected message not to have errors");
+    /// Correct a shortened message like [Code::correct_shortened] with the input and
+    /// output representations of [Code::correct_message_in_basis].
+    #[cfg(feature = "std")]
+    pub fn correct_shortened_in_basis(
+        &self,
+        input: &[u8],
+        virtual_fill: usize,
+        input_basis: Basis,
+        output_basis: Basis,
+    ) -> Block {
+        if virtual_fill > self.data_len() {
+            return Block::uncorrectable(UncorrectableReason::InvalidVirtualFill {
+                got: virtual_fill,
+                max: self.data_len(),
+            });
+        }
+        if input.len() + virtual_fill != N as usize {
+            return Block::uncorrectable(UncorrectableReason::InvalidLength {
+                got: input.len(),
+                want: N as usize - virtual_fill,
+            });
+        }
+        let mut padded = vec![0u8; N as usize];
+        padded[virtual_fill..].copy_from_slice(input);
 
-        let block = correct_message(&msg);
+        // zero is the same in either basis
+        let block = self.correct_message_in_basis(&padded, &[], input_basis, output_basis);
+        match block.message {
+            Some(msg) if msg[..virtual_fill].iter().any(|x| *x != 0) => {
+                Block::uncorrectable(UncorrectableReason::ErrorInVirtualFill)
+            }
+            Some(msg) => Block {
+                state: block.state,
+                message: Some(msg[virtual_fill..].to_vec()),
+                error_positions: block
+                    .error_positions
+                    .iter()
+                    .map(|p| p - virtual_fill)
+                    .collect(),
+                error_values: block.error_values,
+                corrected_erasures: block.corrected_erasures,
+            },
+            None => block,
+        }
+    }
 
-        assert_eq!(block.message.unwrap().len(), 255);
-        assert_eq!(
-            block.state,
-            RSState::Ok,
-            "correcting a message with no errors should be Ok"
+    /// Correct an interleaved codeblock using this code. See [correct_codeblock].
+    #[cfg(feature = "std")]
+    pub fn correct_codeblock(&self, input: &[u8], interleave: u8) -> Codeblock {
+        self.correct_codeblock_with_max_errors(input, interleave, usize::MAX)
+    }
+
+    /// Correct an interleaved codeblock like [Code::correct_codeblock], but treat each
+    /// message with more than `max_errors` errors as uncorrectable; see
+    /// [Block::limit_errors].
+    #[cfg(feature = "std")]
+    pub fn correct_codeblock_with_max_errors(
+        &self,
+        input: &[u8],
+        interleave: u8,
+        max_errors: usize,
+    ) -> Codeblock {
+        let mut message = input.to_vec();
+        let mut block =
+            self.correct_codeblock_in_place_with_max_errors(&mut message, interleave, max_errors);
+        let uncorrectable = block
+            .states
+            .iter()
+            .any(|s| matches!(s, RSState::Uncorrectable(_)));
+        if !uncorrectable {
+            block.message = Some(message);
+        }
+        block
+    }
+
+    /// Correct an interleaved codeblock like [Code::correct_codeblock], but return the
+    /// codeblock even if some messages are uncorrectable, with the correctable messages
+    /// corrected and the others as received; see [Codeblock::is_degraded]. There is
+    /// only no codeblock if `interleave` or the input length are invalid.
+    #[cfg(feature = "std")]
+    pub fn correct_codeblock_best_effort(&self, input: &[u8], interleave: u8) -> Codeblock {
+        let mut message = input.to_vec();
+        let mut block = self.correct_codeblock_in_place(&mut message, interleave);
+        let invalid = matches!(
+            block.states[..],
+            [RSState::Uncorrectable(
+                UncorrectableReason::InvalidInterleave(_)
+                    | UncorrectableReason::InvalidLength { .. }
+            )]
         );
+        if !invalid {
+            block.message = Some(message);
+        }
+        block
     }
 
-    #[test]
-    fn test_correct_message_introduced_errors() {
-        let mut msg = FIXTURE_MSG.clone();
+    /// Correct an interleaved codeblock in place like [Code::correct_codeblock], without
+    /// copying it. The returned [Codeblock] has no message, as the corrected codeblock
+    /// is `buf`. Each correctable message is corrected, but uncorrectable messages are
+    /// left unmodified, so `buf` is only the corrected codeblock if no message is
+    /// uncorrectable.
+    #[cfg(feature = "std")]
+    pub fn correct_codeblock_in_place(&self, buf: &mut [u8], interleave: u8) -> Codeblock {
+        self.correct_codeblock_in_place_with_max_errors(buf, interleave, usize::MAX)
+    }
 
-        // corrupt the message
-        msg[0] = 0;
-        msg[2] = 2;
-        msg[4] = 2;
-        msg[6] = 2;
+    /// Correct an interleaved codeblock in place like [Code::correct_codeblock_in_place],
+    /// but treat each message with more than `max_errors` errors as uncorrectable; see
+    /// [Block::limit_errors]. There are no erasures, so every corrected symbol counts.
+    #[cfg(feature = "std")]
+    pub fn correct_codeblock_in_place_with_max_errors(
+        &self,
+        buf: &mut [u8],
+        interleave: u8,
+        max_errors: usize,
+    ) -> Codeblock {
+        let depth = interleave as usize;
+        let reason = if !(1..=MAX_INTERLEAVE).contains(&interleave) {
+            Some(UncorrectableReason::InvalidInterleave(interleave))
+        } else if buf.len() != depth * N as usize {
+            Some(UncorrectableReason::InvalidLength {
+                got: buf.len(),
+                want: depth * N as usize,
+            })
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Codeblock {
+                states: vec![RSState::Uncorrectable(reason)],
+                message: None,
+                error_positions: Vec::new(),
+                corrected_bits: 0,
+            };
+        }
 
-        assert!(has_errors(&msg), "expected message to have errors");
+        // The syndromes of all of the messages are computed together from the
+        // interleaved codeblock, and only messages with errors are read out, with a
+        // stride, to be corrected. Only the corrected symbols are written back.
+        let parity_len = self.parity_len();
+        let mut conv = [0u8; MAX_INTERLEAVE as usize * N as usize];
+        let conv = &mut conv[..buf.len()];
+        conv.copy_from_slice(buf);
+        dual_basis::to_conv_in_place(conv);
+        let mut synd = [[0u8; PARITY_LEN]; MAX_INTERLEAVE as usize];
+        gf::poly_eval_interleaved(conv, depth, self.roots(), &mut synd[..depth]);
 
-        let block = correct_message(&msg);
-        assert_eq!(block.message.unwrap().len(), 255);
-        assert_eq!(block.state, RSState::Corrected(4));
+        let mut states = Vec::with_capacity(depth);
+        let mut error_positions = Vec::new();
+        let mut corrected_bits = 0;
+        let mut received = [0u8; N as usize];
+        for (i, synd) in synd[..depth].iter().enumerate() {
+            if synd[..parity_len].iter().all(|s| *s == 0) {
+                states.push(RSState::Ok);
+                continue;
+            }
+            for (m, x) in received.iter_mut().zip(conv[i..].iter().step_by(depth)) {
+                *m = *x;
+            }
+            let mut msg = received;
+            let state = match self.correct_conventional(&mut msg, &[]) {
+                RSState::Corrected(found) => {
+                    limit_errors(RSState::Corrected(found), found as usize, max_errors)
+                }
+                state => state,
+            };
+            if let RSState::Corrected(_) = state {
+                let mut corrected = msg;
+                dual_basis::to_dual_in_place(&mut corrected);
+                for (j, (a, b)) in received.iter().zip(msg.iter()).enumerate() {
+                    if a != b {
+                        let pos = j * depth + i;
+                        corrected_bits += (buf[pos] ^ corrected[j]).count_ones() as u64;
+                        buf[pos] = corrected[j];
+                        error_positions.push(pos);
+                    }
+                }
+            }
+            states.push(state);
+        }
+        error_positions.sort_unstable();
+
+        Codeblock {
+            states,
+            message: None,
+            error_positions,
+            corrected_bits,
+        }
     }
 
-    #[test]
-    fn test_correct_message2() {
-        // block 80 message 0 from overpass_snpp_2017_7min.dat
-        // This block contains errors and is already pn decoded
-        let msg = vec![
-            0x67, 0x4c, 0x00, 0xff, 0xff, 0x80, 0x02, 0xf8, 0x7f, 0x01, 0xf7, 0x4f, 0xb5, 0x65,
-            0x14, 0x29, 0xfd, 0x68, 0x38, 0x9e, 0x6a, 0xca, 0x28, 0x53, 0xfa, 0xd0, 0x71, 0x3d,
-            0xd4, 0x95, 0x50, 0xa6, 0xf4, 0xa0, 0xe2, 0x7b, 0xa9, 0x2a, 0xa1, 0x4c, 0xe9, 0x41,
-            0xc5, 0xf6, 0x52, 0x54, 0x42, 0x99, 0xd2, 0x83, 0x8b, 0xed, 0xa5, 0xa8, 0x84, 0x33,
-            0xa4, 0x06, 0x16, 0xdb, 0x4b, 0x51, 0x08, 0x66, 0x48, 0x0d, 0x2c, 0xb7, 0x97, 0xa2,
-            0x10, 0xcd, 0x90, 0x1a, 0x59, 0x6e, 0x2e, 0x45, 0x21, 0x9b, 0x20, 0x35, 0xb2, 0xdd,
-            0x5d, 0x8a, 0x43, 0x37, 0x40, 0x6b, 0x64, 0xba, 0xbb, 0x15, 0x87, 0x6f, 0x80, 0xd7,
-            0xc9, 0x74, 0x77, 0x2b, 0x0f, 0xde, 0x01, 0xae, 0x92, 0xe8, 0xef, 0x57, 0x1e, 0xbd,
-            0x03, 0x5c, 0x24, 0xd1, 0xdf, 0xaf, 0x3c, 0x7a, 0x07, 0xb8, 0x49, 0xa3, 0xbe, 0x5f,
-            0x78, 0xf5, 0x0e, 0x70, 0x93, 0x46, 0x7d, 0xbf, 0xf1, 0xea, 0x1d, 0xe1, 0x27, 0x8d,
-            0xfb, 0x7e, 0xe3, 0xd5, 0x3b, 0xc2, 0x4e, 0x1b, 0xf7, 0xfc, 0xc6, 0xaa, 0x76, 0x85,
-            0x9d, 0x36, 0xee, 0xf9, 0x8c, 0x55, 0xec, 0x0b, 0x3a, 0x6c, 0xdc, 0xf3, 0x18, 0xab,
-            0xd8, 0x17, 0x75, 0xd9, 0xb9, 0xe7, 0x31, 0x56, 0xb0, 0x2f, 0xeb, 0xb3, 0x73, 0xcf,
-            0x62, 0xac, 0x60, 0x5e, 0xd6, 0x67, 0xe6, 0x9f, 0xc4, 0x58, 0xc0, 0xbc, 0xad, 0xce,
-            0xcc, 0x3e, 0x88, 0xb1, 0x81, 0x79, 0x5b, 0x9c, 0x98, 0x7c, 0x11, 0x63, 0x02, 0xf2,
-            0xb6, 0x39, 0x30, 0xf8, 0x22, 0xc7, 0x04, 0xe4, 0x6d, 0x72, 0x61, 0xf0, 0x44, 0x8f,
-            0x09, 0xc8, 0xda, 0xe5, 0xc3, 0xe0, 0x89, 0x1f, 0x13, 0x91, 0xb4, 0xcb, 0x86, 0xc1,
-            0x12, 0x3f, 0x26, 0x23, 0x69, 0x96, 0x0c, 0x82, 0x25, 0x7f, 0x4d, 0x47, 0xd3, 0x2d,
-            0x19, 0x05, 0x4a,
-        ];
+    /// Correct the codeblock at `offset` of a larger buffer in place like
+    /// [Code::correct_codeblock_in_place], e.g., the codeblock of a CADU following its
+    /// 4 byte ASM, without the caller slicing it out. The error positions are codeblock
+    /// offsets, so are `offset` less than buffer offsets. If there are fewer than
+    /// `interleave * 255` bytes from `offset` the state is
+    /// [UncorrectableReason::InvalidLength].
+    #[cfg(feature = "std")]
+    pub fn correct_codeblock_in_place_at(
+        &self,
+        buf: &mut [u8],
+        offset: usize,
+        interleave: u8,
+    ) -> Codeblock {
+        let block_len = interleave as usize * N as usize;
+        let end = buf.len().min(offset.saturating_add(block_len));
+        let codeblock = buf.get_mut(offset..end).unwrap_or_default();
+        self.correct_codeblock_in_place(codeblock, interleave)
+    }
 
-        assert!(has_errors(&msg), "expected message to have errors");
+    /// Correct codeblocks at `offset`, `offset + stride`, `offset + 2 * stride`, etc.,
+    /// of a buffer in place, returning a [Codeblock] for each in buffer order, e.g., a
+    /// buffer of back-to-back CADUs has an offset of the ASM length and a stride of the
+    /// CADU length. Each codeblock is corrected like [Code::correct_codeblock_in_place_at],
+    /// so the bytes between them are left unmodified and a trailing partial codeblock is
+    /// [UncorrectableReason::InvalidLength].
+    ///
+    /// If `stride` is less than the codeblock length, or is zero, the buffer is not
+    /// modified and the result is a single codeblock with the state
+    /// [UncorrectableReason::InvalidLength] of the stride.
+    #[cfg(feature = "std")]
+    pub fn correct_codeblocks_in_place_strided(
+        &self,
+        buf: &mut [u8],
+        offset: usize,
+        stride: usize,
+        interleave: u8,
+    ) -> Vec<Codeblock> {
+        let min_stride = (interleave as usize * N as usize).max(1);
+        if stride < min_stride {
+            return vec![Codeblock {
+                states: vec![RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                    got: stride,
+                    want: min_stride,
+                })],
+                message: None,
+                error_positions: Vec::new(),
+                corrected_bits: 0,
+            }];
+        }
+        (offset..buf.len())
+            .step_by(stride)
+            .map(|start| self.correct_codeblock_in_place_at(buf, start, interleave))
+            .collect()
+    }
+
+    /// Correct a codeblock, inferring the interleave from its length, using this code.
+    /// See [correct_codeblock_auto].
+    #[cfg(feature = "std")]
+    pub fn correct_codeblock_auto(&self, input: &[u8]) -> Codeblock {
+        match detect_interleave(input.len()) {
+            Some(interleave) => self.correct_codeblock(input, interleave),
+            None => Codeblock {
+                states: vec![RSState::Uncorrectable(
+                    UncorrectableReason::UndetectableInterleave { len: input.len() },
+                )],
+                message: None,
+                error_positions: Vec::new(),
+                corrected_bits: 0,
+            },
+        }
+    }
+
+    /// Check a message for errors using this code. See [detect_errors].
+    pub fn detect_errors(&self, msg: &[u8]) -> Detection {
+        let mut conv = match Codeword::try_from(msg) {
+            Ok(codeword) => codeword.into_inner(),
+            Err(reason) => return Detection::Uncorrectable(reason),
+        };
+        dual_basis::to_conv_in_place(&mut conv);
+        let synd = calc_syndromes(&conv, self.parity_len(), self.fcr());
+        if synd.iter().all(|x| *x == 0) {
+            return Detection::Clean;
+        }
+        let errloc = find_error_locator(&synd, self.parity_len(), 0);
+        let degree = errloc.len() - 1;
+        if degree > self.max_errors() {
+            return Detection::Uncorrectable(UncorrectableReason::TooManyErrors {
+                found: degree,
+                max: self.max_errors(),
+            });
+        }
+        let mut positions = [0i32; PARITY_LEN];
+        let roots = find_errors(&errloc.reversed(), &mut positions[..degree]);
+        if roots != degree {
+            return Detection::Uncorrectable(UncorrectableReason::LocatorDegreeMismatch {
+                degree,
+                roots,
+            });
+        }
+        Detection::Correctable(degree)
+    }
+
+    /// Return true if the input code block contains 1 or more errors using this code.
+    /// See [has_errors].
+    pub fn has_errors(&self, msg: &[u8]) -> bool {
+        let mut conv = [0u8; N as usize];
+        let Some(conv) = conv.get_mut(..msg.len()) else {
+            // longer than a code word, so cannot be one
+            return true;
+        };
+        conv.copy_from_slice(msg);
+        dual_basis::to_conv_in_place(conv);
+        gf::poly_eval_any(conv, self.roots())
+    }
+
+    /// Return the number of non-zero syndromes of the input code block using this code.
+    /// See [nonzero_syndromes].
+    pub fn nonzero_syndromes(&self, msg: &[u8]) -> usize {
+        let mut conv = [0u8; N as usize];
+        let Some(conv) = conv.get_mut(..msg.len()) else {
+            return self.parity_len();
+        };
+        conv.copy_from_slice(msg);
+        dual_basis::to_conv_in_place(conv);
+        let synd = calc_syndromes(conv, self.parity_len(), self.fcr());
+        synd.iter().filter(|x| **x != 0).count()
+    }
+}
+
+/// Encode 223 bytes of data, in dual basis representation, into a 255 byte
+/// Reed-Solomon code block by appending the [PARITY_LEN] check bytes.
+///
+/// The only error is [UncorrectableReason::InvalidLength] if `data` is not 223 bytes.
+#[cfg(feature = "std")]
+pub fn encode_message(data: &[u8]) -> Result<Vec<u8>, UncorrectableReason> {
+    Code::E16.encode_message(data)
+}
+
+/// Encode a code block in place like [encode_message], computing the check bytes from
+/// the first 223 bytes and writing them to the last [PARITY_LEN] bytes.
+pub fn encode_message_in_place(msg: &mut [u8; N as usize]) {
+    Code::E16.encode_message_in_place(msg)
+}
+
+/// Correct a Reed-Solomon 255 byte code block, where the last [PARITY_LEN] bytes are
+/// the parity/check bytes. The code block is also assumed to be in dual basis
+/// representation.
+///
+/// The returned [Block::message] will contain the full 255 byte corrected code block,
+/// including the check bytes, unless the state is [RSState::Uncorrectable], in which
+/// case it will be None. Use [Block::data] for just the 223 data bytes.
+///
+/// The state will be [RSState::Uncorrectable] if there are more errors than can be
+/// corrected or if an algorithm failure occurs.
+#[cfg(feature = "std")]
+pub fn correct_message(input: &[u8]) -> Block {
+    Code::E16.correct_message(input)
+}
+
+/// Correct a Reed-Solomon 255 byte code block in place like
+/// [correct_message_in_place], but doing the same work for every message, e.g., for
+/// hard real-time processing where jitter matters.
+///
+/// The usual decode returns as soon as a message is found to have no errors or too
+/// many, and the work to locate and correct errors grows with their number. Here the
+/// syndromes, error locator, and error magnitudes are always computed in full, with a
+/// fixed number of iterations, and the locator is evaluated at every message position,
+/// so the time taken varies little with the message. This time is about that of the
+/// usual decode's worst case, and the results are the same. Field multiplications
+/// still skip zeros unless the `mult-table` feature is enabled, and timing is not
+/// otherwise guaranteed to be independent of the data at the instruction level, so
+/// this is not intended to protect against timing side channels. Erasures are not
+/// supported.
+pub fn correct_message_in_place_deterministic(msg: &mut [u8; N as usize]) -> RSState {
+    Code::E16.correct_message_in_place_deterministic(msg)
+}
+
+/// Correct a Reed-Solomon 255 byte code block in place like [correct_message], but
+/// without allocating. The code block is only modified if it is corrected, i.e., the
+/// returned state is [RSState::Corrected].
+pub fn correct_message_in_place(msg: &mut [u8; N as usize]) -> RSState {
+    Code::E16.correct_message_in_place(msg)
+}
+
+/// Correct a Reed-Solomon 255 byte code block like [correct_message], but writing the
+/// corrected code block to the first 255 bytes of `out` rather than allocating, e.g.,
+/// into a pooled or pinned buffer. If the code block is uncorrectable `out` holds it as
+/// received, unless `input` is the wrong length, in which case `out` is not modified.
+/// If `out` is shorter than 255 bytes the state is [UncorrectableReason::InvalidLength]
+/// and `out` is not modified.
+pub fn correct_message_to(input: &[u8], out: &mut [u8]) -> RSState {
+    Code::E16.correct_message_to(input, out)
+}
+
+/// Correct a Reed-Solomon 255 byte code block like [correct_message], additionally
+/// treating the symbols at the provided `erasures` positions as known to be unreliable,
+/// e.g., as flagged by a demodulator.
+///
+/// Because the location of an erasure does not have to be found, a message with `e`
+/// errors and `f` erasures is correctable as long as `2e + f <= PARITY_LEN`, allowing
+/// up to [PARITY_LEN] erased symbols to be corrected rather than [PARITY_LEN] / 2 errors.
+///
+/// Erasure positions must be unique and less than 255, otherwise the state will be
+/// [RSState::Uncorrectable].
+#[cfg(feature = "std")]
+pub fn correct_message_with_erasures(input: &[u8], erasures: &[usize]) -> Block {
+    Code::E16.correct_message_with_erasures(input, erasures)
+}
+
+/// Correct a [Codeword] like [correct_message], without the need to check its length.
+#[cfg(feature = "std")]
+pub fn correct_codeword(codeword: &Codeword) -> Block {
+    Code::E16.correct_codeword(codeword)
+}
+
+fn valid_erasures(erasures: &[usize], parity_len: usize) -> bool {
+    if erasures.len() > parity_len {
+        return false;
+    }
+    for (i, p) in erasures.iter().enumerate() {
+        if *p >= N as usize || erasures[..i].contains(p) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Correct a shortened Reed-Solomon message, i.e., one where the first `virtual_fill`
+/// symbols of the 255 byte code block are zero and were not transmitted. The input
+/// must contain the remaining `255 - virtual_fill` bytes, including the [PARITY_LEN]
+/// check bytes, in dual basis representation.
+///
+/// The virtual fill is added before correction and removed from the returned
+/// [Block::message], which will be the same length as the input. Because the virtual
+/// fill is known to be zero, a correction that lands in the virtual fill indicates a
+/// miscorrection and results in [RSState::Uncorrectable].
+#[cfg(feature = "std")]
+pub fn correct_shortened(input: &[u8], virtual_fill: usize) -> Block {
+    Code::E16.correct_shortened(input, virtual_fill)
+}
+
+/// Result of correcting an interleaved codeblock.
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Codeblock {
+    /// Resulting state of the RS process for each interleaved message, in interleave
+    /// order, i.e., the message starting at codeblock byte 0 is first.
+    pub states: Vec<RSState>,
+    /// The reassembled codeblock, including check symbols, in dual basis
+    /// representation. This is None if any of the messages were uncorrectable, except
+    /// from [Code::correct_codeblock_best_effort].
+    pub message: Option<Vec<u8>>,
+    /// Codeblock offsets of the symbols that were corrected, in ascending order,
+    /// including those of messages that were corrected when another message in the
+    /// codeblock was uncorrectable.
+    pub error_positions: Vec<usize>,
+    /// Total number of bits corrected across all messages; see [Block::corrected_bits].
+    pub corrected_bits: u64,
+}
+
+#[cfg(feature = "std")]
+impl Codeblock {
+    /// True if there is a codeblock even though some of its messages were
+    /// uncorrectable, i.e., it is damaged data from
+    /// [Code::correct_codeblock_best_effort].
+    pub fn is_degraded(&self) -> bool {
+        self.message.is_some()
+            && self
+                .states
+                .iter()
+                .any(|s| matches!(s, RSState::Uncorrectable(_)))
+    }
+}
+
+/// Correct an interleaved Reed-Solomon codeblock consisting of `interleave` 255 byte
+/// messages, where symbol `j` of message `i` is at codeblock offset `j * interleave + i`.
+///
+/// Each message is deinterleaved, corrected using [correct_message], and the corrected
+/// messages are reassembled into a codeblock in the original interleaved order.
+///
+/// If `interleave` is not in `1..=MAX_INTERLEAVE` or the input length is not
+/// `interleave * 255` the result will contain a single [RSState::Uncorrectable] state.
+#[cfg(feature = "std")]
+pub fn correct_codeblock(input: &[u8], interleave: u8) -> Codeblock {
+    Code::E16.correct_codeblock(input, interleave)
+}
+
+/// Infer the interleave depth of a codeblock of `len` bytes, i.e., `len / 255` if
+/// `len` is a multiple of 255 and the depth is in `1..=MAX_INTERLEAVE`.
+pub fn detect_interleave(len: usize) -> Option<u8> {
+    let depth = len / N as usize;
+    if len.is_multiple_of(N as usize) && (1..=MAX_INTERLEAVE as usize).contains(&depth) {
+        Some(depth as u8)
+    } else {
+        None
+    }
+}
+
+/// Correct an interleaved codeblock like [correct_codeblock], inferring the interleave
+/// depth from the codeblock length using [detect_interleave].
+///
+/// If the depth cannot be inferred the result will contain a single
+/// [RSState::Uncorrectable] state with [UncorrectableReason::UndetectableInterleave].
+#[cfg(feature = "std")]
+pub fn correct_codeblock_auto(input: &[u8]) -> Codeblock {
+    Code::E16.correct_codeblock_auto(input)
+}
+
+/// Correct a buffer of back-to-back 255 byte messages using [correct_message],
+/// returning a [Block] for each message in buffer order.
+///
+/// If the buffer length is not a multiple of 255 the final [Block] will be
+/// [RSState::Uncorrectable].
+#[cfg(feature = "std")]
+pub fn correct_buffer(input: &[u8]) -> Vec<Block> {
+    Code::E16.correct_buffer(input)
+}
+
+/// Correct a buffer of back-to-back 255 byte messages in place, returning the state of
+/// each message in buffer order. Like [correct_message_in_place], messages are only
+/// modified if they are corrected.
+///
+/// Messages are checked for errors 32 at a time, one per SIMD lane, which is
+/// considerably faster than correcting each message on its own when most have no
+/// errors.
+///
+/// If the buffer length is not a multiple of 255 the trailing partial message is left
+/// unmodified and its state will be [RSState::Uncorrectable].
+#[cfg(feature = "std")]
+pub fn correct_buffer_in_place(buf: &mut [u8]) -> Vec<RSState> {
+    Code::E16.correct_buffer_in_place(buf)
+}
+
+/// Correct a buffer of back-to-back 255 byte messages in parallel using the global
+/// rayon thread pool, returning a [Block] for each message in buffer order.
+///
+/// If the buffer length is not a multiple of 255 the final [Block] will be
+/// [RSState::Uncorrectable].
+#[cfg(feature = "parallel")]
+pub fn correct_messages_par(input: &[u8]) -> Vec<Block> {
+    use rayon::prelude::*;
+
+    input.par_chunks(N as usize).map(correct_message).collect()
+}
+
+/// Correct a buffer of back-to-back 255 byte messages in parallel like
+/// [correct_messages_par], but using `pool` rather than the global pool, e.g., one
+/// built with [threads::ThreadConfig::build_pool].
+#[cfg(feature = "parallel")]
+pub fn correct_messages_par_in(pool: &rayon::ThreadPool, input: &[u8]) -> Vec<Block> {
+    pool.install(|| correct_messages_par(input))
+}
+
+/// Lazily correct the back-to-back 255 byte messages read from `reader`, e.g., a
+/// [std::fs::File] or `&[u8]`. See [reader::CodeblockIter] for interleaved
+/// codeblocks and skipping sync markers.
+#[cfg(feature = "std")]
+pub fn codeblocks<R: std::io::Read>(reader: R) -> reader::CodeblockIter<R> {
+    reader::CodeblockIter::new(reader)
+}
+
+/// Check a 255 byte message for errors without correcting it, estimating the number of
+/// symbol errors and whether they can be corrected, e.g., to screen link quality.
+///
+/// Only the syndromes, the error locator, and its roots are computed, skipping the
+/// error magnitudes and the correction itself, so this is cheaper than
+/// [correct_message] for messages with errors. The estimate is the degree of the
+/// locator, which is the number of symbol errors for any correctable message. A
+/// message with more errors than can be corrected is usually detected as such, but
+/// may instead be estimated as correctable to some other codeword, just as
+/// [correct_message] would miscorrect it.
+pub fn detect_errors(msg: &[u8]) -> Detection {
+    Code::E16.detect_errors(msg)
+}
+
+/// Return true if the input code block contains 1 or more errors. A message longer
+/// than 255 symbols is never a valid code word, so always has errors.
+///
+/// This returns as soon as a non-zero syndrome is found, so is cheaper than
+/// [detect_errors] or [nonzero_syndromes] for a message with errors.
+pub fn has_errors(msg: &[u8]) -> bool {
+    Code::E16.has_errors(msg)
+}
+
+/// Return the number of non-zero syndromes of the input code block, as a cheap hint
+/// of how damaged it is, which is 0 if it has no errors. A message longer than 255
+/// symbols always has all [PARITY_LEN] non-zero.
+///
+/// Each syndrome of a message with errors is zero with a probability of only about
+/// 1/256 regardless of the number of errors, so a count well below [PARITY_LEN] is
+/// unusual and suggests a particular error pattern rather than few errors. Use
+/// [detect_errors] for an estimate of the number of errors.
+pub fn nonzero_syndromes(msg: &[u8]) -> usize {
+    Code::E16.nonzero_syndromes(msg)
+}
+
+/// Check the field tables and arithmetic by encoding and correcting known vectors,
+/// e.g., as a power-on sanity check. See [selftest::run].
+pub fn selftest() -> Result<(), selftest::SelfTestError> {
+    selftest::run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RS message, no pn
+    pub(crate) const FIXTURE_MSG: &[u8; 255] = &crate::selftest::VECTOR;
+
+    #[test]
+    fn test_calc_syndromes() {
+        const EXPECTED: &[u8] = &[
+            0x00, 0xb7, 0xd5, 0x62, 0x7b, 0xf5, 0xa0, 0x52, 0x91, 0xc1, 0xd2, 0x97, 0xd0, 0x40,
+            0x68, 0x59, 0x0d, 0xcb, 0xc0, 0x84, 0x84, 0x68, 0xa6, 0xd9, 0x79, 0xf9, 0xad, 0x4c,
+            0x81, 0x9f, 0x14, 0x2f, 0x78,
+        ];
+
+        let zult = calc_syndromes(FIXTURE_MSG, PARITY_LEN, FCR);
+
+        for ((i, z), e) in zult.iter().enumerate().zip(EXPECTED.iter()) {
+            assert_eq!(
+                z, e,
+                "not all elements equal: expected {}, got {} at index {}\n{:?}",
+                e, z, i, zult
+            );
+        }
+    }
+
+    #[test]
+    fn test_correct_message_noerrors() {
+        let msg = *FIXTURE_MSG;
+
+        assert!(!has_errors(&msg), "expected message not to have errors");
+
+        let block = correct_message(&msg);
+
+        assert_eq!(block.message.unwrap().len(), 255);
+        assert_eq!(
+            block.state,
+            RSState::Ok,
+            "correcting a message with no errors should be Ok"
+        );
+    }
+
+    #[test]
+    fn test_correct_message_introduced_errors() {
+        let mut msg = *FIXTURE_MSG;
+
+        // corrupt the message
+        msg[0] = 0;
+        msg[2] = 2;
+        msg[4] = 2;
+        msg[6] = 2;
+
+        assert!(has_errors(&msg), "expected message to have errors");
+
+        let block = correct_message(&msg);
+        assert_eq!(block.message.unwrap().len(), 255);
+        assert_eq!(block.state, RSState::Corrected(4));
+    }
+
+    pub(crate) use crate::interleave::interleave;
+
+    // Codeword g(x) * x^shift in dual basis representation.
+    pub(crate) fn shifted_generator(code: Code, shift: usize) -> Vec<u8> {
+        let g = code.generator();
+        let mut msg = vec![0u8; N as usize];
+        let start = N as usize - g.len() - shift;
+        msg[start..start + g.len()].copy_from_slice(g);
+        dual_basis::to_dual(&msg)
+    }
+
+    #[test]
+    fn test_code_tables() {
+        for code in [Code::E16, Code::E8] {
+            assert_eq!(code.roots().len(), code.parity_len());
+            assert_eq!(code.generator().len(), code.parity_len() + 1);
+            for (i, r) in code.roots().iter().enumerate() {
+                assert_eq!(*r, gf::pow(GEN, i as i32 + code.fcr()));
+                assert_eq!(gf::poly_eval(code.generator(), *r), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_correct_message_error_positions() {
+        let mut input = *FIXTURE_MSG;
+        input[250] ^= 0x81;
+        input[7] ^= 0x02;
+
+        let block = correct_message(&input);
+
+        assert_eq!(block.state, RSState::Corrected(2));
+        assert_eq!(block.error_positions, vec![7, 250]);
+        assert_eq!(block.error_values, vec![0x02, 0x81]);
+        assert_eq!(block.corrected_bits(), 3);
+
+        let block = correct_message(FIXTURE_MSG);
+        assert!(block.error_positions.is_empty());
+        assert!(block.error_values.is_empty());
+        assert_eq!(block.corrected_erasures, 0);
+    }
+
+    #[test]
+    fn test_block_data() {
+        let mut input = *FIXTURE_MSG;
+        input[3] ^= 0x10;
+        let block = correct_message(&input);
+        assert_eq!(block.message.as_ref().unwrap().len(), 255);
+        assert_eq!(block.data(Code::E16).unwrap(), &FIXTURE_MSG[..223]);
+
+        let msg = shifted_generator(Code::E8, 0);
+        let block = Code::E8.correct_shortened(&msg[100..], 100);
+        assert_eq!(block.data(Code::E8).unwrap(), &msg[100..239]);
+
+        input[..20].fill(0);
+        assert!(correct_message(&input).data(Code::E16).is_none());
+    }
+
+    #[test]
+    fn test_block_traits() {
+        let mut input = *FIXTURE_MSG;
+        input[3] ^= 0x10;
+        input[9] ^= 0x01;
+        let block = correct_message(&input);
+        assert_eq!(&block[..], &FIXTURE_MSG[..]);
+        assert_eq!(block.as_ref().len(), 255);
+        assert_eq!((&block).into_iter().count(), 255);
+        assert_eq!(block.to_string(), "corrected 2 symbols at [3, 9]");
+        assert_eq!(block.into_iter().collect::<Vec<u8>>(), FIXTURE_MSG.to_vec());
+
+        assert_eq!(correct_message(FIXTURE_MSG).to_string(), "ok");
+        let block = correct_message(&input[1..]);
+        assert!(block.is_empty());
+        assert_eq!(block.into_iter().count(), 0);
+        assert_eq!(
+            correct_message(&input[1..]).to_string(),
+            "uncorrectable: invalid input length; expected 255, got 254"
+        );
+    }
+
+    #[test]
+    fn test_correct_shortened() {
+        let msg = shifted_generator(Code::E16, 0);
+        assert!(!has_errors(&msg));
+
+        let expected = msg[200..].to_vec();
+        let mut input = expected.clone();
+        input[0] ^= 0x01;
+        input[30] ^= 0x10;
+        input[54] ^= 0xff;
+
+        let block = correct_shortened(&input, 200);
+
+        assert_eq!(block.state, RSState::Corrected(3));
+        assert_eq!(block.message.unwrap(), expected);
+        assert_eq!(block.error_positions, vec![0, 30, 54]);
+        assert_eq!(block.error_values, vec![0x01, 0x10, 0xff]);
+    }
+
+    #[test]
+    fn test_correct_shortened_error_in_fill() {
+        // Leading coefficient lies at index 122, so removing it leaves a single error
+        // that can only be corrected inside the virtual fill.
+        let msg = shifted_generator(Code::E16, 100);
+        let block = correct_shortened(&msg[123..], 123);
+
+        assert_eq!(
+            block.state,
+            RSState::Uncorrectable(UncorrectableReason::ErrorInVirtualFill)
+        );
+        assert!(block.message.is_none());
+    }
+
+    #[test]
+    fn test_correct_shortened_invalid_input() {
+        let msg = shifted_generator(Code::E16, 0);
+        for (input, fill) in [(&msg[200..], 100), (&msg[230..], 225)] {
+            let block = correct_shortened(input, fill);
+            assert!(matches!(block.state, RSState::Uncorrectable(_)));
+        }
+    }
+
+    #[test]
+    fn test_code_e16_constants() {
+        let code = Code::E16;
+        assert_eq!(code.parity_len(), PARITY_LEN);
+        assert_eq!(code.fcr(), FCR);
+        assert_eq!(code.data_len(), 223);
+    }
+
+    #[test]
+    fn test_correct_message_e8() {
+        let code = Code::E8;
+        let expected = shifted_generator(code, 50);
+        assert!(!code.has_errors(&expected));
+        // not a valid E16 code word
+        assert!(has_errors(&expected));
+
+        let mut msg = expected.clone();
+        for i in 0..code.max_errors() {
+            msg[i * 20] ^= 0x81;
+        }
+
+        let block = code.correct_message(&msg);
+
+        assert_eq!(block.state, RSState::Corrected(8));
+        assert_eq!(block.message.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_correct_message_e8_too_many_errors() {
+        let code = Code::E8;
+        let mut msg = shifted_generator(code, 50);
+        for i in 0..=code.max_errors() {
+            msg[i * 20] ^= 0x81;
+        }
+
+        let block = code.correct_message(&msg);
+
+        assert!(matches!(
+            block.state,
+            RSState::Uncorrectable(
+                UncorrectableReason::TooManyErrors { .. }
+                    | UncorrectableReason::LocatorDegreeMismatch { .. }
+            )
+        ));
+    }
+
+    #[test]
+    fn test_correct_codeblock() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[10] = 0;
+        corrupt[200] = 0;
+        let input = interleave(&[FIXTURE_MSG, &corrupt, FIXTURE_MSG, FIXTURE_MSG]);
+
+        let block = correct_codeblock(&input, 4);
+
+        assert_eq!(
+            block.states,
+            vec![RSState::Ok, RSState::Corrected(2), RSState::Ok, RSState::Ok]
+        );
+        assert_eq!(block.error_positions, vec![41, 801]);
+        assert_eq!(
+            block.corrected_bits,
+            (FIXTURE_MSG[10].count_ones() + FIXTURE_MSG[200].count_ones()) as u64
+        );
+        assert_eq!(
+            block.message.unwrap(),
+            interleave(&[FIXTURE_MSG, FIXTURE_MSG, FIXTURE_MSG, FIXTURE_MSG])
+        );
+    }
+
+    #[test]
+    fn test_detect_errors() {
+        assert_eq!(detect_errors(FIXTURE_MSG), Detection::Clean);
+        assert_eq!(
+            detect_errors(&FIXTURE_MSG[1..]),
+            Detection::Uncorrectable(UncorrectableReason::InvalidLength {
+                got: 254,
+                want: 255
+            })
+        );
+
+        let mut msg = *FIXTURE_MSG;
+        for i in 0..16 {
+            msg[i * 15] ^= 0x5a;
+            assert_eq!(detect_errors(&msg), Detection::Correctable(i + 1));
+        }
+        msg[250] ^= 0x5a;
+        assert!(matches!(detect_errors(&msg), Detection::Uncorrectable(_)));
+        assert!(matches!(
+            correct_message(&msg).state,
+            RSState::Uncorrectable(_)
+        ));
+
+        for code in [Code::E16, Code::E8] {
+            let mut msg = shifted_generator(code, 0);
+            msg[3] ^= 0x01;
+            assert_eq!(code.detect_errors(&msg), Detection::Correctable(1));
+        }
+    }
+
+    #[test]
+    fn test_nonzero_syndromes() {
+        assert_eq!(nonzero_syndromes(FIXTURE_MSG), 0);
+        assert_eq!(nonzero_syndromes(&[0u8; 300]), 32);
+        assert!(has_errors(&[0u8; 300]));
+
+        // a single error makes every syndrome non-zero
+        let mut msg = *FIXTURE_MSG;
+        msg[100] ^= 0x01;
+        assert_eq!(nonzero_syndromes(&msg), 32);
+        for code in [Code::E16, Code::E8] {
+            let mut msg = shifted_generator(code, 0);
+            assert_eq!(code.nonzero_syndromes(&msg), 0);
+            assert!(!code.has_errors(&msg));
+            msg[254] ^= 0x80;
+            assert_eq!(code.nonzero_syndromes(&msg), code.parity_len());
+            assert!(code.has_errors(&msg));
+        }
+    }
+
+    #[test]
+    fn test_max_errors() {
+        let mut corrupt = *FIXTURE_MSG;
+        for i in [10, 20, 30] {
+            corrupt[i] ^= 0x01;
+        }
+        let too_many =
+            RSState::Uncorrectable(UncorrectableReason::TooManyErrors { found: 3, max: 2 });
+
+        let block = correct_message(&corrupt).limit_errors(2);
+        assert_eq!(block.state, too_many);
+        assert_eq!(block.message, None);
+        // erasures are not counted, so the same limit accepts the message once enough of
+        // its errors are given as erasures, even though as many symbols are corrected
+        let block = correct_message_with_erasures(&corrupt, &[10]).limit_errors(2);
+        assert_eq!(block.state, RSState::Corrected(3));
+        assert_eq!(block.message.as_deref(), Some(&FIXTURE_MSG[..]));
+        let block = correct_message_with_erasures(&corrupt, &[10, 20]).limit_errors(0);
+        assert_eq!(
+            block.state,
+            RSState::Uncorrectable(UncorrectableReason::TooManyErrors { found: 1, max: 0 })
+        );
+        // an erased symbol without an error is not a corrected erasure either
+        let block = correct_message_with_erasures(&corrupt, &[40]).limit_errors(2);
+        assert_eq!(block.state, too_many);
+
+        let mut msg = corrupt;
+        assert_eq!(
+            Code::E16.correct_message_in_place_with_max_errors(&mut msg, 2),
+            too_many
+        );
+        assert_eq!(msg, corrupt);
+        assert_eq!(
+            Code::E16.correct_message_in_place_with_max_errors(&mut msg, 3),
+            RSState::Corrected(3)
+        );
+        assert_eq!(msg, *FIXTURE_MSG);
+
+        let input = interleave(&[FIXTURE_MSG, &corrupt]);
+        let block = Code::E16.correct_codeblock_with_max_errors(&input, 2, 2);
+        assert_eq!(block.states, vec![RSState::Ok, too_many]);
+        assert_eq!(block.message, None);
+        assert!(block.error_positions.is_empty());
+    }
+
+    #[test]
+    fn test_correct_codeblock_uncorrectable() {
+        let mut corrupt = *FIXTURE_MSG;
+        for x in corrupt.iter_mut().take(20) {
+            *x ^= 0xff;
+        }
+        let input = interleave(&[FIXTURE_MSG, &corrupt]);
+
+        let block = correct_codeblock(&input, 2);
+
+        assert_eq!(block.states[0], RSState::Ok);
+        assert!(matches!(block.states[1], RSState::Uncorrectable(_)));
+        assert!(block.message.is_none());
+    }
+
+    #[test]
+    fn test_correct_codeblock_invalid_input() {
+        let input = interleave(&[FIXTURE_MSG, FIXTURE_MSG]);
+
+        let cases = [
+            (&input[..], 0, UncorrectableReason::InvalidInterleave(0)),
+            (&input[..], 9, UncorrectableReason::InvalidInterleave(9)),
+            (
+                &input[..100],
+                2,
+                UncorrectableReason::InvalidLength {
+                    got: 100,
+                    want: 510,
+                },
+            ),
+        ];
+        for (input, interleave, reason) in cases {
+            let block = correct_codeblock(input, interleave);
+            assert_eq!(block.states, vec![RSState::Uncorrectable(reason)]);
+            assert!(block.message.is_none());
+            let block = Code::E16.correct_codeblock_best_effort(input, interleave);
+            assert!(block.message.is_none());
+            assert!(!block.is_degraded());
+        }
+    }
+
+    #[test]
+    fn test_correct_codeblock_best_effort() {
+        let mut corrupt = *FIXTURE_MSG;
+        for x in corrupt.iter_mut().take(20) {
+            *x ^= 0xff;
+        }
+        let mut correctable = *FIXTURE_MSG;
+        correctable[100] ^= 0x01;
+        let input = interleave(&[&correctable, &corrupt]);
+
+        let block = Code::E16.correct_codeblock_best_effort(&input, 2);
+
+        assert_eq!(block.states[0], RSState::Corrected(1));
+        assert!(matches!(block.states[1], RSState::Uncorrectable(_)));
+        assert!(block.is_degraded());
+        assert_eq!(
+            block.message.unwrap(),
+            interleave(&[FIXTURE_MSG, &corrupt]),
+            "corrected message fixed and uncorrectable message as received"
+        );
+
+        let block = Code::E16.correct_codeblock_best_effort(&interleave(&[&correctable]), 1);
+        assert!(!block.is_degraded());
+        assert_eq!(block.message.unwrap(), FIXTURE_MSG.to_vec());
+        assert!(!correct_codeblock(&input, 2).is_degraded());
+    }
+
+    #[test]
+    fn test_correct_codeblocks_in_place_strided() {
+        let asm = [0x1a, 0xcf, 0xfc, 0x1d];
+        let codeblock = interleave(&[FIXTURE_MSG, FIXTURE_MSG]);
+        let cadu = [&asm[..], &codeblock].concat();
+        let mut buf = [&cadu[..], &cadu, &cadu[..100]].concat();
+        buf[4 + 7] ^= 0x01;
+        buf[cadu.len() + 4 + 300] ^= 0xff;
+
+        let block = Code::E16.correct_codeblock_in_place_at(&mut buf.clone(), 4, 2);
+        assert_eq!(block.states, vec![RSState::Ok, RSState::Corrected(1)]);
+        assert_eq!(block.error_positions, vec![7]);
+
+        let blocks = Code::E16.correct_codeblocks_in_place_strided(&mut buf, 4, cadu.len(), 2);
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].error_positions, vec![7]);
+        assert_eq!(blocks[1].error_positions, vec![300]);
+        assert_eq!(
+            blocks[2].states,
+            vec![RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                got: 96,
+                want: 510
+            })]
+        );
+        assert_eq!(&buf[..2 * cadu.len()], &[&cadu[..], &cadu].concat()[..]);
+
+        let block = Code::E16.correct_codeblock_in_place_at(&mut buf, 4, 0);
+        assert_eq!(
+            block.states,
+            vec![RSState::Uncorrectable(
+                UncorrectableReason::InvalidInterleave(0)
+            )]
+        );
+
+        buf[4 + 7] ^= 0x01;
+        let original = buf.clone();
+        for (stride, interleave, want) in [(509, 2, 510), (0, 2, 510), (0, 0, 1)] {
+            let blocks =
+                Code::E16.correct_codeblocks_in_place_strided(&mut buf, 4, stride, interleave);
+            assert_eq!(
+                blocks[0].states,
+                vec![RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                    got: stride,
+                    want
+                })]
+            );
+            assert_eq!(blocks.len(), 1);
+        }
+        assert_eq!(buf, original);
+    }
+
+    #[test]
+    fn test_correct_codeblock_auto() {
+        assert_eq!(detect_interleave(255), Some(1));
+        assert_eq!(detect_interleave(2040), Some(8));
+        assert_eq!(detect_interleave(0), None);
+        assert_eq!(detect_interleave(2295), None);
+        assert_eq!(detect_interleave(1024), None);
+
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[0] ^= 0x01;
+        let input = interleave(&[FIXTURE_MSG, FIXTURE_MSG, &corrupt]);
+        let block = correct_codeblock_auto(&input);
+        assert_eq!(
+            block.states,
+            vec![RSState::Ok, RSState::Ok, RSState::Corrected(1)]
+        );
+
+        let block = correct_codeblock_auto(&input[..700]);
+        assert_eq!(
+            block.states,
+            vec![RSState::Uncorrectable(
+                UncorrectableReason::UndetectableInterleave { len: 700 }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_correct_message_with_erasures() {
+        let mut msg = *FIXTURE_MSG;
+        let erasures: Vec<usize> = (0..20).map(|i| i * 12).collect();
+        for p in erasures.iter() {
+            msg[*p] ^= 0x5a;
+        }
+        // more errors than can be corrected without erasure information
+        assert!(matches!(
+            correct_message(&msg).state,
+            RSState::Uncorrectable(_)
+        ));
+
+        let block = correct_message_with_erasures(&msg, &erasures);
+
+        assert_eq!(block.state, RSState::Corrected(20));
+        assert_eq!(block.message.unwrap(), FIXTURE_MSG.to_vec());
+
+        assert_eq!(
+            Code::E16.correct_message_in_place_with_erasures(&mut msg, &erasures),
+            RSState::Corrected(20)
+        );
+        assert_eq!(&msg, FIXTURE_MSG);
+    }
+
+    #[test]
+    fn test_correct_message_with_erasures_and_errors() {
+        let mut msg = *FIXTURE_MSG;
+        // 10 erasures, of which 2 are not actually in error
+        let erasures: Vec<usize> = (0..10).map(|i| 100 + i).collect();
+        for p in erasures.iter().skip(2) {
+            msg[*p] ^= 0xff;
+        }
+        // 11 errors; 2 * 11 + 10 == PARITY_LEN
+        for i in 0..11 {
+            msg[i * 3] ^= 0x01;
+        }
+
+        let block = correct_message_with_erasures(&msg, &erasures);
+
+        assert_eq!(block.state, RSState::Corrected(19));
+        assert_eq!(block.corrected_erasures, 8);
+        assert_eq!(block.corrected_errors(), 11);
+        assert_eq!(block.message.unwrap(), FIXTURE_MSG.to_vec());
+    }
+
+    #[test]
+    fn test_correct_message_in_basis() {
+        let conv = dual_basis::to_conv(FIXTURE_MSG);
+        let mut input = *FIXTURE_MSG;
+        input[5] ^= 0x01;
+        let block =
+            Code::E16.correct_message_in_basis(&input, &[], Basis::Dual, Basis::Conventional);
+        assert_eq!(block.state, RSState::Corrected(1));
+        assert_eq!(block.message.unwrap(), conv);
+        assert_eq!(block.error_values, vec![0x01]);
+
+        let mut input = conv.clone();
+        input[7] ^= 0x80;
+        for (output, expected) in [
+            (Basis::Conventional, conv.clone()),
+            (Basis::Dual, FIXTURE_MSG.to_vec()),
+        ] {
+            let block =
+                Code::E16.correct_message_in_basis(&input, &[7], Basis::Conventional, output);
+            assert_eq!(block.state, RSState::Corrected(1));
+            assert_eq!(block.message.unwrap(), expected);
+            assert_eq!(block.error_values, vec![0x80]);
+            assert_eq!(block.corrected_erasures, 1);
+        }
+
+        // an error free message is still converted
+        let block =
+            Code::E16.correct_message_in_basis(FIXTURE_MSG, &[], Basis::Dual, Basis::Conventional);
+        assert_eq!(block.state, RSState::Ok);
+        assert_eq!(block.message.unwrap(), conv);
+    }
+
+    #[test]
+    fn test_codeword() {
+        let mut codeword = Codeword::try_from(&FIXTURE_MSG[..]).unwrap();
+        codeword[10] ^= 0x42;
+        let block = correct_codeword(&codeword);
+        assert_eq!(block.state, RSState::Corrected(1));
+        assert_eq!(block.message.unwrap(), FIXTURE_MSG.to_vec());
+
+        let block = Code::E16.correct_codeword_with_erasures(&codeword, &[10]);
+        assert_eq!(block.corrected_erasures, 1);
+
+        assert_eq!(
+            Code::E16.correct_codeword_in_place(&mut codeword),
+            RSState::Corrected(1)
+        );
+        assert_eq!(&codeword.into_inner(), FIXTURE_MSG);
+
+        assert_eq!(
+            Codeword::try_from(&FIXTURE_MSG[..254]),
+            Err(UncorrectableReason::InvalidLength {
+                got: 254,
+                want: 255
+            })
+        );
+    }
+
+    #[test]
+    fn test_correct_message_with_erasures_invalid() {
+        let msg = *FIXTURE_MSG;
+        for erasures in [vec![255], vec![1, 2, 1], (0..33).collect()] {
+            let block = correct_message_with_erasures(&msg, &erasures);
+            assert_eq!(
+                block.state,
+                RSState::Uncorrectable(UncorrectableReason::InvalidErasures)
+            );
+        }
+    }
+
+    #[test]
+    fn test_correct_message_in_place() {
+        let mut msg = *FIXTURE_MSG;
+        assert_eq!(correct_message_in_place(&mut msg), RSState::Ok);
+        assert_eq!(&msg, FIXTURE_MSG);
+
+        msg[3] ^= 0x0f;
+        msg[250] ^= 0xf0;
+        assert_eq!(correct_message_in_place(&mut msg), RSState::Corrected(2));
+        assert_eq!(&msg, FIXTURE_MSG);
+    }
+
+    #[test]
+    fn test_correct_message_in_place_uncorrectable() {
+        let mut msg = *FIXTURE_MSG;
+        for x in msg.iter_mut().take(17) {
+            *x ^= 0x01;
+        }
+        let expected = msg;
+
+        let state = correct_message_in_place(&mut msg);
+
+        assert!(matches!(state, RSState::Uncorrectable(_)));
+        assert_eq!(
+            msg, expected,
+            "uncorrectable message should not be modified"
+        );
+    }
+
+    #[test]
+    fn test_correct_message_to() {
+        let mut input = *FIXTURE_MSG;
+        input[20] ^= 0x80;
+        let mut out = [0u8; 300];
+        assert_eq!(correct_message_to(&input, &mut out), RSState::Corrected(1));
+        assert_eq!(&out[..255], FIXTURE_MSG);
+        assert_eq!(&out[255..], &[0; 45]);
+
+        for x in input.iter_mut().take(17) {
+            *x ^= 0x01;
+        }
+        assert!(matches!(
+            correct_message_to(&input, &mut out),
+            RSState::Uncorrectable(_)
+        ));
+        assert_eq!(&out[..255], &input);
+
+        let mut out = [0u8; 255];
+        assert_eq!(
+            Code::E8.correct_message_to(&input[1..], &mut out),
+            RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                got: 254,
+                want: 255
+            })
+        );
+        assert_eq!(out, [0; 255]);
+
+        let mut out = [0u8; 254];
+        assert_eq!(
+            correct_message_to(FIXTURE_MSG, &mut out),
+            RSState::Uncorrectable(UncorrectableReason::InvalidLength {
+                got: 254,
+                want: 255
+            })
+        );
+        assert_eq!(out, [0; 254]);
+    }
+
+    #[test]
+    fn test_correct_message_in_place_at() {
+        let mut buf = [&[0xaa; 4][..], FIXTURE_MSG, &[0xbb; 3]].concat();
+        buf[4 + 10] ^= 0x55;
+        assert_eq!(
+            Code::E16.correct_message_in_place_at(&mut buf, 4),
+            RSState::Corrected(1)
+        );
+        assert_eq!(&buf[4..259], FIXTURE_MSG);
+        assert_eq!(&buf[..4], &[0xaa; 4]);
+        assert_eq!(&buf[259..], &[0xbb; 3]);
+
+        for offset in [8, 1000] {
+            assert!(matches!(
+                Code::E16.correct_message_in_place_at(&mut buf, offset),
+                RSState::Uncorrectable(UncorrectableReason::InvalidLength { want: 255, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_encode_message() {
+        assert_eq!(
+            encode_message(&FIXTURE_MSG[..223]).unwrap(),
+            FIXTURE_MSG.to_vec()
+        );
+        assert_eq!(
+            encode_message(&FIXTURE_MSG[..200]),
+            Err(UncorrectableReason::InvalidLength {
+                got: 200,
+                want: 223
+            })
+        );
+
+        let mut msg = [0u8; 255];
+        msg[..239].copy_from_slice(&FIXTURE_MSG[..239]);
+        Code::E8.encode_message_in_place(&mut msg);
+        assert!(!Code::E8.has_errors(&msg));
+        msg[0] ^= 0xff;
+        assert_eq!(
+            Code::E8.correct_message_in_place(&mut msg),
+            RSState::Corrected(1)
+        );
+        assert_eq!(msg[..239], FIXTURE_MSG[..239]);
+    }
+
+    #[test]
+    fn test_correct_buffer() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[17] ^= 0x80;
+        corrupt[18] ^= 0x80;
+        let mut input = FIXTURE_MSG.to_vec();
+        input.extend(corrupt);
+        input.extend(&FIXTURE_MSG[..5]);
+
+        let blocks = correct_buffer(&input);
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].state, RSState::Ok);
+        assert_eq!(blocks[1].state, RSState::Corrected(2));
+        assert_eq!(blocks[1].message.as_ref().unwrap(), &FIXTURE_MSG.to_vec());
+        assert!(matches!(blocks[2].state, RSState::Uncorrectable(_)));
+
+        let states = correct_buffer_in_place(&mut input);
+
+        assert_eq!(
+            states,
+            blocks.into_iter().map(|b| b.state).collect::<Vec<_>>()
+        );
+        assert_eq!(input[..255], FIXTURE_MSG[..]);
+        assert_eq!(input[255..510], FIXTURE_MSG[..]);
+        assert_eq!(input[510..], FIXTURE_MSG[..5]);
+    }
+
+    #[test]
+    fn test_correct_buffer_in_place_lanes() {
+        let mut rng = 0x1a2e5;
+        for code in [Code::E16, Code::E8] {
+            // enough messages for 2 full groups of lanes and a partial one
+            let mut input = Vec::new();
+            for i in 0..70 {
+                let mut msg = shifted_generator(code, i);
+                let errors = xorshift(&mut rng) as usize % (code.max_errors() + 3);
+                for _ in 0..errors {
+                    msg[xorshift(&mut rng) as usize % 255] ^= xorshift(&mut rng) as u8 | 1;
+                }
+                input.extend(msg);
+            }
+            let expected: Vec<u8> = input
+                .chunks(N as usize)
+                .flat_map(|msg| code.correct_message(msg).message.unwrap_or(msg.to_vec()))
+                .collect();
+            let blocks = code.correct_buffer(&input);
+
+            let states = code.correct_buffer_in_place(&mut input);
+
+            assert_eq!(
+                states,
+                blocks.into_iter().map(|b| b.state).collect::<Vec<_>>()
+            );
+            assert_eq!(input, expected);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_correct_messages_par() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[1] ^= 0xff;
+        let mut input = Vec::new();
+        for i in 0..20 {
+            input.extend_from_slice(if i % 2 == 0 { FIXTURE_MSG } else { &corrupt });
+        }
+        input.extend_from_slice(&FIXTURE_MSG[..10]);
+
+        let blocks = correct_messages_par(&input);
+        let pool = threads::ThreadConfig::with_threads(2).build_pool().unwrap();
+        let pooled = correct_messages_par_in(&pool, &input);
+        assert!(pooled.iter().zip(&blocks).all(|(a, b)| a.state == b.state));
+
+        assert_eq!(blocks.len(), 21);
+        for (i, block) in blocks[..20].iter().enumerate() {
+            let expected = if i % 2 == 0 {
+                RSState::Ok
+            } else {
+                RSState::Corrected(1)
+            };
+            assert_eq!(block.state, expected);
+            assert_eq!(block.message.as_ref().unwrap(), &FIXTURE_MSG.to_vec());
+        }
+        assert_eq!(
+            blocks[20].state,
+            RSState::Uncorrectable(UncorrectableReason::InvalidLength { got: 10, want: 255 })
+        );
+    }
+
+    #[test]
+    fn test_correct_message2() {
+        // block 80 message 0 from overpass_snpp_2017_7min.dat
+        // This block contains errors and is already pn decoded
+        let msg = vec![
+            0x67, 0x4c, 0x00, 0xff, 0xff, 0x80, 0x02, 0xf8, 0x7f, 0x01, 0xf7, 0x4f, 0xb5, 0x65,
+            0x14, 0x29, 0xfd, 0x68, 0x38, 0x9e, 0x6a, 0xca, 0x28, 0x53, 0xfa, 0xd0, 0x71, 0x3d,
+            0xd4, 0x95, 0x50, 0xa6, 0xf4, 0xa0, 0xe2, 0x7b, 0xa9, 0x2a, 0xa1, 0x4c, 0xe9, 0x41,
+            0xc5, 0xf6, 0x52, 0x54, 0x42, 0x99, 0xd2, 0x83, 0x8b, 0xed, 0xa5, 0xa8, 0x84, 0x33,
+            0xa4, 0x06, 0x16, 0xdb, 0x4b, 0x51, 0x08, 0x66, 0x48, 0x0d, 0x2c, 0xb7, 0x97, 0xa2,
+            0x10, 0xcd, 0x90, 0x1a, 0x59, 0x6e, 0x2e, 0x45, 0x21, 0x9b, 0x20, 0x35, 0xb2, 0xdd,
+            0x5d, 0x8a, 0x43, 0x37, 0x40, 0x6b, 0x64, 0xba, 0xbb, 0x15, 0x87, 0x6f, 0x80, 0xd7,
+            0xc9, 0x74, 0x77, 0x2b, 0x0f, 0xde, 0x01, 0xae, 0x92, 0xe8, 0xef, 0x57, 0x1e, 0xbd,
+            0x03, 0x5c, 0x24, 0xd1, 0xdf, 0xaf, 0x3c, 0x7a, 0x07, 0xb8, 0x49, 0xa3, 0xbe, 0x5f,
+            0x78, 0xf5, 0x0e, 0x70, 0x93, 0x46, 0x7d, 0xbf, 0xf1, 0xea, 0x1d, 0xe1, 0x27, 0x8d,
+            0xfb, 0x7e, 0xe3, 0xd5, 0x3b, 0xc2, 0x4e, 0x1b, 0xf7, 0xfc, 0xc6, 0xaa, 0x76, 0x85,
+            0x9d, 0x36, 0xee, 0xf9, 0x8c, 0x55, 0xec, 0x0b, 0x3a, 0x6c, 0xdc, 0xf3, 0x18, 0xab,
+            0xd8, 0x17, 0x75, 0xd9, 0xb9, 0xe7, 0x31, 0x56, 0xb0, 0x2f, 0xeb, 0xb3, 0x73, 0xcf,
+            0x62, 0xac, 0x60, 0x5e, 0xd6, 0x67, 0xe6, 0x9f, 0xc4, 0x58, 0xc0, 0xbc, 0xad, 0xce,
+            0xcc, 0x3e, 0x88, 0xb1, 0x81, 0x79, 0x5b, 0x9c, 0x98, 0x7c, 0x11, 0x63, 0x02, 0xf2,
+            0xb6, 0x39, 0x30, 0xf8, 0x22, 0xc7, 0x04, 0xe4, 0x6d, 0x72, 0x61, 0xf0, 0x44, 0x8f,
+            0x09, 0xc8, 0xda, 0xe5, 0xc3, 0xe0, 0x89, 0x1f, 0x13, 0x91, 0xb4, 0xcb, 0x86, 0xc1,
+            0x12, 0x3f, 0x26, 0x23, 0x69, 0x96, 0x0c, 0x82, 0x25, 0x7f, 0x4d, 0x47, 0xd3, 0x2d,
+            0x19, 0x05, 0x4a,
+        ];
+
+        assert!(has_errors(&msg), "expected message to have errors");
 
         let block = correct_message(&msg);
         assert_eq!(block.message.unwrap().len(), 255);
         assert_eq!(block.state, RSState::Corrected(11));
     }
+
+    /// Xorshift generator for the deterministic fuzz tests.
+    pub(crate) fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn test_correct_deterministic() {
+        let mut rng = 0x1234_5678_9abc_def1;
+        for code in [Code::E16, Code::E8] {
+            let expected: [u8; 255] = shifted_generator(code, 30).try_into().unwrap();
+            let mut msg = expected;
+            assert_eq!(
+                code.correct_message_in_place_deterministic(&mut msg),
+                RSState::Ok
+            );
+
+            // up to more errors than can be corrected, in which case both may fail or
+            // miscorrect to the same codeword
+            for num_errors in 0..=code.parity_len() / 2 + 3 {
+                let mut msg = expected;
+                for _ in 0..num_errors {
+                    msg[xorshift(&mut rng) as usize % 255] ^= 1 + xorshift(&mut rng) as u8 % 255;
+                }
+                let mut want = msg;
+                let want_state = code.correct_message_in_place(&mut want);
+                let state = code.correct_message_in_place_deterministic(&mut msg);
+                assert_eq!(msg, want, "{code:?} with {num_errors} errors");
+                match want_state {
+                    RSState::Uncorrectable(_) => {
+                        assert!(matches!(state, RSState::Uncorrectable(_)))
+                    }
+                    _ => assert_eq!(state, want_state),
+                }
+            }
+        }
+
+        let mut msg = *FIXTURE_MSG;
+        msg[0] ^= 0xff;
+        msg[254] ^= 0x01;
+        assert_eq!(
+            correct_message_in_place_deterministic(&mut msg),
+            RSState::Corrected(2)
+        );
+        assert_eq!(msg, *FIXTURE_MSG);
+    }
+
+    #[test]
+    fn test_malformed_input_does_not_panic() {
+        let mut rng = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2000 {
+            let code = if xorshift(&mut rng).is_multiple_of(2) {
+                Code::E16
+            } else {
+                Code::E8
+            };
+            // codewords with up to twice the correctable errors, or random bytes of
+            // any length
+            let mut msg = shifted_generator(code, xorshift(&mut rng) as usize % 200);
+            if xorshift(&mut rng).is_multiple_of(4) {
+                msg.resize(xorshift(&mut rng) as usize % 600, 0);
+                msg.iter_mut().for_each(|x| *x = xorshift(&mut rng) as u8);
+            } else {
+                for _ in 0..xorshift(&mut rng) % (2 * code.parity_len() as u64 + 1) {
+                    let i = xorshift(&mut rng) as usize % msg.len();
+                    msg[i] ^= xorshift(&mut rng) as u8;
+                }
+            }
+            let erasures: Vec<usize> = (0..xorshift(&mut rng) % 40)
+                .map(|_| xorshift(&mut rng) as usize % 300)
+                .collect();
+
+            code.correct_message(&msg);
+            code.correct_message_with_erasures(&msg, &erasures);
+            code.correct_shortened(&msg, xorshift(&mut rng) as usize % 300);
+            code.correct_codeblock(&msg, xorshift(&mut rng) as u8 % 10);
+            code.correct_codeblock_auto(&msg);
+            code.correct_buffer(&msg);
+            code.has_errors(&msg);
+            code.correct_message_to(&msg, &mut vec![0; xorshift(&mut rng) as usize % 300]);
+            code.correct_codeblocks_in_place_strided(
+                &mut msg.clone(),
+                xorshift(&mut rng) as usize % 300,
+                xorshift(&mut rng) as usize % 600,
+                xorshift(&mut rng) as u8 % 3,
+            );
+            decoder::Decoder::new(code).decode_with_erasures(&msg, &erasures);
+            let rs = decoder::RsDecoder::builder()
+                .code(code)
+                .interleave(xorshift(&mut rng) as u8 % 10)
+                .virtual_fill(xorshift(&mut rng) as usize % 300)
+                .verify_parity(true)
+                .build();
+            if let Ok(rs) = rs {
+                rs.decode(&msg);
+            }
+            if let Ok(mut array) = <[u8; N as usize]>::try_from(&msg[..]) {
+                code.correct_message_in_place_deterministic(&mut array.clone());
+                code.correct_message_in_place_with_erasures(&mut array, &erasures);
+            }
+
+            // the algorithm building blocks are public, so also call them directly
+            // with arbitrary arguments
+            let parity_len = xorshift(&mut rng) as usize % 40;
+            let fcr = xorshift(&mut rng) as i32;
+            let synd = algo::calc_syndromes(&msg, parity_len, fcr);
+            let positions: Vec<i32> = erasures.iter().map(|p| *p as i32 - 20).collect();
+            let fsynd = algo::forney_syndromes(&synd, &positions, msg.len() as i32);
+            let errloc = algo::find_error_locator(&fsynd, parity_len, positions.len());
+            let mut errpos = [0i32; 40];
+            algo::find_errors(&errloc, &mut errpos);
+            let _ = algo::correct_errata(&mut msg, &synd, &positions, fcr);
+            let _ = algo::correct_errata(&mut msg, &synd, &errpos, fcr);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[3] ^= 0x11;
+        let block = correct_message(&corrupt);
+
+        let json = serde_json::to_string(&block).unwrap();
+        let got: Block = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(got.state, RSState::Corrected(1));
+        assert_eq!(got.message, block.message);
+        assert_eq!(got.error_positions, vec![3]);
+        assert_eq!(got.error_values, vec![0x11]);
+
+        let state =
+            RSState::Uncorrectable(UncorrectableReason::TooManyErrors { found: 17, max: 16 });
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(
+            json,
+            r#"{"Uncorrectable":{"TooManyErrors":{"found":17,"max":16}}}"#
+        );
+        assert_eq!(serde_json::from_str::<RSState>(&json).unwrap(), state);
+    }
 }
@@ -0,0 +1,225 @@
+//! Prometheus metrics for long-running decode services.
+//!
+//! [Metrics] are registered with a [prometheus::Registry] and updated from the
+//! [Event]s of a [crate::pipeline::Pipeline]. Throughput is exported as byte and
+//! message counters, from which Prometheus computes rates, e.g.,
+//! `rate(rs2_input_bytes_total[1m])`.
+//!
+//! ```
+//! use rs2::metrics::Metrics;
+//! use rs2::pipeline::{Config, Pipeline};
+//!
+//! let config = Config::default();
+//! let registry = prometheus::Registry::new();
+//! let metrics = Metrics::register(&registry, &config).unwrap();
+//!
+//! let stream = rs2::gen::cadus(&config, b"hello world", 0);
+//! for event in Pipeline::new(&stream[..], config) {
+//!     metrics.observe(&event.unwrap());
+//! }
+//!
+//! let text = rs2::metrics::encode(&registry).unwrap();
+//! assert!(text.contains("rs2_messages_total{state=\"ok\"} 4"));
+//! ```
+use prometheus::{
+    Encoder, IntCounter, IntCounterVec, IntGauge, Opts, Registry, Result, TextEncoder,
+};
+
+use crate::pipeline::{Config, Event};
+use crate::RSState;
+
+/// Counters and gauges describing the output of a pipeline.
+///
+/// Cloning produces a handle to the same underlying metrics, so a clone may be moved
+/// to the thread doing the decoding.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    messages: IntCounterVec,
+    corrected_symbols: IntCounter,
+    corrected_bits: IntCounter,
+    frames: IntCounter,
+    idle_frames: IntCounter,
+    fecf_failures: IntCounter,
+    input_bytes: IntCounter,
+    skipped_bytes: IntCounter,
+    locked: IntGauge,
+    cadu_len: u64,
+}
+
+impl Metrics {
+    /// Create the metrics for a pipeline using `config` without registering them,
+    /// e.g., to register them with a registry using a prefix or constant labels.
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Metrics {
+            messages: IntCounterVec::new(
+                Opts::new(
+                    "rs2_messages_total",
+                    "RS messages decoded, by result (ok, corrected, uncorrectable, not_performed)",
+                ),
+                &["state"],
+            )?,
+            corrected_symbols: IntCounter::new(
+                "rs2_corrected_symbols_total",
+                "Symbols corrected across all corrected RS messages",
+            )?,
+            corrected_bits: IntCounter::new(
+                "rs2_corrected_bits_total",
+                "Bits corrected across all corrected RS messages",
+            )?,
+            frames: IntCounter::new("rs2_frames_total", "CADUs found and decoded")?,
+            idle_frames: IntCounter::new(
+                "rs2_idle_frames_total",
+                "CADUs recognized as idle and not decoded, also counted as frames",
+            )?,
+            fecf_failures: IntCounter::new(
+                "rs2_fecf_failures_total",
+                "Decoded frames that failed FECF verification",
+            )?,
+            input_bytes: IntCounter::new(
+                "rs2_input_bytes_total",
+                "Input stream bytes consumed, including skipped bytes",
+            )?,
+            skipped_bytes: IntCounter::new(
+                "rs2_skipped_bytes_total",
+                "Input stream bytes discarded while searching for sync",
+            )?,
+            locked: IntGauge::new(
+                "rs2_sync_locked",
+                "1 if the synchronizer is locked, otherwise 0",
+            )?,
+            cadu_len: (config.asm.len() + config.block_len()) as u64,
+        })
+    }
+
+    /// Create the metrics for a pipeline using `config` and register them with
+    /// `registry`.
+    pub fn register(registry: &Registry, config: &Config) -> Result<Self> {
+        let metrics = Self::new(config)?;
+        registry.register(Box::new(metrics.messages.clone()))?;
+        registry.register(Box::new(metrics.corrected_symbols.clone()))?;
+        registry.register(Box::new(metrics.corrected_bits.clone()))?;
+        registry.register(Box::new(metrics.frames.clone()))?;
+        registry.register(Box::new(metrics.idle_frames.clone()))?;
+        registry.register(Box::new(metrics.fecf_failures.clone()))?;
+        registry.register(Box::new(metrics.input_bytes.clone()))?;
+        registry.register(Box::new(metrics.skipped_bytes.clone()))?;
+        registry.register(Box::new(metrics.locked.clone()))?;
+        Ok(metrics)
+    }
+
+    /// Record the result of decoding a message.
+    pub fn observe_state(&self, state: &RSState) {
+        let label = match state {
+            RSState::Ok => "ok",
+            RSState::Corrected(n) => {
+                self.corrected_symbols.inc_by(*n as u64);
+                "corrected"
+            }
+            RSState::Uncorrectable(_) => "uncorrectable",
+            RSState::NotPerformed => "not_performed",
+        };
+        self.messages.with_label_values(&[label]).inc();
+    }
+
+    /// Record a pipeline event.
+    ///
+    /// Frames are counted as the bytes of a full CADU even if the codeblock was
+    /// uncorrectable, since the whole CADU was consumed from the input.
+    pub fn observe(&self, event: &Event) {
+        match event {
+            Event::Frame(frame) => {
+                self.frames.inc();
+                self.input_bytes.inc_by(self.cadu_len);
+                self.corrected_bits.inc_by(frame.corrected_bits);
+                if frame.fecf_ok == Some(false) {
+                    self.fecf_failures.inc();
+                }
+                if frame.idle {
+                    self.idle_frames.inc();
+                    return;
+                }
+                for state in &frame.states {
+                    self.observe_state(state);
+                }
+            }
+            Event::Skipped(range) => {
+                let len = range.end - range.start;
+                self.skipped_bytes.inc_by(len);
+                self.input_bytes.inc_by(len);
+            }
+            Event::Locked(_) => self.locked.set(1),
+            Event::Unlocked(_) => self.locked.set(0),
+        }
+    }
+}
+
+/// Encode all metrics in `registry` using the Prometheus text exposition format, e.g.,
+/// for the body of a `/metrics` response.
+pub fn encode(registry: &Registry) -> Result<String> {
+    let mut buf = Vec::new();
+    TextEncoder::new().encode(&registry.gather(), &mut buf)?;
+    Ok(String::from_utf8(buf).expect("text encoding is utf-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::Frame;
+    use crate::UncorrectableReason;
+
+    #[test]
+    fn test_observe() {
+        let registry = Registry::new();
+        let config = Config::default();
+        let metrics = Metrics::register(&registry, &config).unwrap();
+        let frame = Frame {
+            offset: 0,
+            data: None,
+            states: vec![
+                RSState::Ok,
+                RSState::Corrected(3),
+                RSState::Uncorrectable(UncorrectableReason::ResidualSyndrome),
+            ],
+            error_positions: vec![4, 9, 13],
+            corrected_bits: 7,
+            fecf_ok: None,
+            inverted: false,
+            idle: false,
+        };
+        let idle = Frame {
+            data: Some(vec![]),
+            states: vec![RSState::NotPerformed],
+            error_positions: vec![],
+            corrected_bits: 0,
+            idle: true,
+            ..frame.clone()
+        };
+
+        metrics.observe(&Event::Skipped(0..10));
+        metrics.observe(&Event::Locked(10));
+        metrics.observe(&Event::Frame(frame));
+        metrics.observe(&Event::Frame(idle));
+
+        let text = encode(&registry).unwrap();
+        for line in [
+            "rs2_messages_total{state=\"ok\"} 1",
+            "rs2_messages_total{state=\"corrected\"} 1",
+            "rs2_messages_total{state=\"uncorrectable\"} 1",
+            "rs2_corrected_symbols_total 3",
+            "rs2_corrected_bits_total 7",
+            "rs2_frames_total 2",
+            "rs2_idle_frames_total 1",
+            "rs2_fecf_failures_total 0",
+            "rs2_input_bytes_total 2058",
+            "rs2_skipped_bytes_total 10",
+            "rs2_sync_locked 1",
+        ] {
+            assert!(text.lines().any(|l| l == line), "{line} not in:\n{text}");
+        }
+
+        metrics.observe(&Event::Unlocked(2000));
+        assert!(encode(&registry).unwrap().contains("rs2_sync_locked 0"));
+        // registering the same metrics twice is an error
+        assert!(Metrics::register(&registry, &config).is_err());
+    }
+}
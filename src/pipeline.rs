@@ -0,0 +1,887 @@
+//! End-to-end CADU decoding composing ASM synchronization, derandomization,
+//! deinterleaving, and RS correction to produce transfer frames.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use rs2::pipeline::{Config, Pipeline};
+//!
+//! let file = File::open("overpass.dat").unwrap();
+//! for frame in Pipeline::new(file, Config::default()).frames() {
+//!     let frame = frame.unwrap();
+//!     println!("{}: {:?}", frame.offset, frame.states);
+//! }
+//! ```
+use std::io::{Read, Result};
+use std::ops::Range;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::JoinHandle;
+
+use crate::diagnostics::{self, Trace};
+use crate::presets::Preset;
+use crate::sync::{self, Synchronizer};
+use crate::threads::ThreadConfig;
+use crate::{crc, pn, Code, RSState, N};
+
+/// Pipeline configuration.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Config {
+    /// Attached sync marker preceding each CADU. This must not be empty, otherwise
+    /// creating a pipeline panics.
+    pub asm: Vec<u8>,
+    /// Maximum number of bit errors in a matching ASM; see [Synchronizer::threshold].
+    pub asm_threshold: u32,
+    /// Number of interleaved RS messages in each codeblock, 1 to [crate::MAX_INTERLEAVE].
+    pub interleave: u8,
+    /// Whether CADUs are randomized and must be derandomized before correction.
+    pub derandomize: bool,
+    /// Pseudo-random sequence used if [Config::derandomize] is true.
+    pub randomizer: pn::Sequence,
+    /// RS code used to encode the codeblocks.
+    pub code: Code,
+    /// Whether transfer frames end with a FECF that should be verified after
+    /// correction; see [crate::crc].
+    pub fecf: bool,
+    /// Use the synchronizer lock state machine, tolerating missing ASMs. If None, each
+    /// CADU must be confirmed by the following ASM.
+    pub lock: Option<sync::LockConfig>,
+    /// Maximum number of errors corrected in each RS message, with messages needing
+    /// more being uncorrectable; see [crate::Block::limit_errors]. If None, as many as
+    /// the code can correct.
+    pub max_errors: Option<usize>,
+    /// Recognize idle codeblocks and skip their decode. If None, every codeblock is
+    /// decoded.
+    pub idle: Option<IdleFilter>,
+    /// Output the transfer frame of codeblocks with uncorrectable messages, with the
+    /// correctable messages corrected and the others as received, rather than no
+    /// data; see [Frame::is_degraded].
+    pub best_effort: bool,
+}
+
+impl Default for Config {
+    /// Standard ASM, randomized CADUs, interleave 4 and RS(255,223) without FECF.
+    fn default() -> Self {
+        Config {
+            asm: sync::ASM.to_vec(),
+            asm_threshold: 0,
+            interleave: 4,
+            derandomize: true,
+            randomizer: pn::Sequence::Pn255,
+            code: Code::E16,
+            fecf: false,
+            lock: None,
+            max_errors: None,
+            idle: None,
+            best_effort: false,
+        }
+    }
+}
+
+impl Config {
+    /// Length of the codeblock following the ASM.
+    pub fn block_len(&self) -> usize {
+        self.interleave as usize * N as usize
+    }
+
+    /// Length of the transfer frame, i.e., the codeblock without the check symbols.
+    pub fn frame_len(&self) -> usize {
+        self.interleave as usize * self.code.data_len()
+    }
+}
+
+/// Recognizes idle codeblocks, e.g., the fill sent when there is no data, whose
+/// decode is skipped; see [Config::idle].
+///
+/// Idle codeblocks dominate many passes, and recognizing them is much cheaper than
+/// decoding them. They are produced as frames with [Frame::idle] set.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IdleFilter {
+    /// Every byte of the derandomized codeblock is the provided fill byte.
+    Constant(u8),
+    /// The derandomized codeblock is exactly the provided bytes, e.g., a fixed idle
+    /// frame and its check symbols.
+    Pattern(Vec<u8>),
+    /// The transfer frame primary header has the provided virtual channel id, e.g., 63
+    /// for the CCSDS only idle data channel. Only the header is checked, so the frame
+    /// may have errors elsewhere, and an error in the header of another frame may
+    /// cause it to be treated as idle.
+    Vcid(u8),
+}
+
+impl IdleFilter {
+    /// Return true if the derandomized `codeblock` is idle.
+    pub fn matches(&self, codeblock: &[u8]) -> bool {
+        match self {
+            IdleFilter::Constant(fill) => codeblock.iter().all(|x| x == fill),
+            IdleFilter::Pattern(pattern) => codeblock == &pattern[..],
+            IdleFilter::Vcid(vcid) => codeblock.get(1).is_some_and(|x| x & 0x3f == *vcid),
+        }
+    }
+}
+
+/// A transfer frame produced by the [Pipeline].
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frame {
+    /// Byte offset of the start of the CADU ASM in the input stream.
+    pub offset: u64,
+    /// The corrected transfer frame without check symbols. This is None if any of the
+    /// codeblock messages were uncorrectable, unless [Config::best_effort] is true.
+    pub data: Option<Vec<u8>>,
+    /// Resulting state of the RS process for each interleaved message.
+    pub states: Vec<RSState>,
+    /// Codeblock offsets of the symbols that were corrected, in ascending order; see
+    /// [crate::Codeblock::error_positions].
+    pub error_positions: Vec<usize>,
+    /// Total number of bits corrected; see [crate::Codeblock::corrected_bits].
+    pub corrected_bits: u64,
+    /// Result of verifying the FECF. This is None if [Config::fecf] is false or there
+    /// is no data.
+    pub fecf_ok: Option<bool>,
+    /// True if the CADU was found with an inverted polarity and was inverted before
+    /// decoding.
+    pub inverted: bool,
+    /// True if the codeblock was recognized as idle by [Config::idle] and so was not
+    /// decoded, in which case the state of each message is [RSState::NotPerformed]
+    /// and the data is the uncorrected transfer frame.
+    pub idle: bool,
+}
+
+impl Frame {
+    /// True if there is data even though some of the codeblock messages were
+    /// uncorrectable, i.e., it is damaged data output because of
+    /// [Config::best_effort].
+    pub fn is_degraded(&self) -> bool {
+        self.data.is_some()
+            && self
+                .states
+                .iter()
+                .any(|s| matches!(s, RSState::Uncorrectable(_)))
+    }
+}
+
+/// Output of the [Pipeline].
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    /// A CADU was found and decoded.
+    Frame(Frame),
+    /// Input stream byte range that did not belong to any complete CADU and was
+    /// discarded.
+    Skipped(Range<u64>),
+    /// Synchronizer lock was acquired at the provided stream offset.
+    Locked(u64),
+    /// Synchronizer lock was lost at the provided stream offset.
+    Unlocked(u64),
+}
+
+impl Event {
+    /// Decode a sync event, calling `hook` with the frame of a CADU.
+    pub(crate) fn from_sync(
+        config: &Config,
+        event: sync::Event,
+        hook: Option<&mut dyn FrameHook>,
+    ) -> Self {
+        match event {
+            sync::Event::Cadu(cadu) => Event::Frame(decode_with_hook(config, cadu, hook)),
+            sync::Event::Skipped(range) => Event::Skipped(range),
+            sync::Event::Locked(offset) => Event::Locked(offset),
+            sync::Event::Unlocked(offset) => Event::Unlocked(offset),
+        }
+    }
+}
+
+/// Callback invoked with each frame as it is decoded, e.g., for custom logging,
+/// alerting, or routing without wrapping the iterator of events; see
+/// [Pipeline::with_hook].
+///
+/// Any `FnMut(&Frame, &[Trace])` is a hook, without traces.
+pub trait FrameHook {
+    /// Called with each decoded frame, in stream order, before it is produced as an
+    /// [Event::Frame]. `traces` has a [Trace] of each uncorrectable message, in
+    /// interleave order, if [FrameHook::traces] is true, and is otherwise empty.
+    fn on_frame(&mut self, frame: &Frame, traces: &[Trace]);
+
+    /// Whether to trace the uncorrectable messages of each frame for
+    /// [FrameHook::on_frame], which decodes each of them again. False by default.
+    fn traces(&self) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(&Frame, &[Trace])> FrameHook for F {
+    fn on_frame(&mut self, frame: &Frame, traces: &[Trace]) {
+        self(frame, traces)
+    }
+}
+
+/// Decodes a raw CADU byte stream into transfer frames.
+pub struct Pipeline<R> {
+    sync: Synchronizer<R>,
+    config: Config,
+    hook: Option<Box<dyn FrameHook>>,
+}
+
+impl<R: Read> Pipeline<R> {
+    /// # Panics
+    /// If [Config::asm] is empty.
+    pub fn new(reader: R, config: Config) -> Self {
+        Pipeline {
+            sync: synchronizer(reader, &config),
+            config,
+            hook: None,
+        }
+    }
+
+    /// Call `hook` with each frame as it is decoded, replacing any previous hook.
+    pub fn with_hook<H: FrameHook + 'static>(mut self, hook: H) -> Self {
+        self.hook = Some(Box::new(hook));
+        self
+    }
+
+    /// Create a pipeline using the configuration of a mission preset.
+    pub fn for_preset(reader: R, preset: Preset) -> Self {
+        Self::new(reader, preset.config())
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Iterate over only the decoded frames, ignoring all other events.
+    pub fn frames(self) -> impl Iterator<Item = Result<Frame>> {
+        self.filter_map(|event| match event {
+            Ok(Event::Frame(frame)) => Some(Ok(frame)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+    }
+}
+
+pub(crate) fn synchronizer<R: Read>(reader: R, config: &Config) -> Synchronizer<R> {
+    let sync = match config.lock {
+        Some(lock) => Synchronizer::with_lock(reader, &config.asm, config.block_len(), lock),
+        None => Synchronizer::with_asm(reader, &config.asm, config.block_len()),
+    };
+    sync.threshold(config.asm_threshold)
+}
+
+/// Derandomize and correct a CADU produced by synchronization.
+pub(crate) fn decode(config: &Config, cadu: sync::Cadu) -> Frame {
+    decode_with_hook(config, cadu, None)
+}
+
+/// [decode], calling `hook` with the frame.
+fn decode_with_hook(config: &Config, cadu: sync::Cadu, hook: Option<&mut dyn FrameHook>) -> Frame {
+    let mut data = cadu.data;
+    let (mut frame, output) = decode_in_place(config, cadu.offset, cadu.inverted, &mut data);
+    // uncorrectable messages are left as received, so are traced from the corrected
+    // codeblock
+    let traces = match &hook {
+        Some(hook) if hook.traces() && !frame.idle => {
+            diagnostics::trace_uncorrectable(config.code, &data, &frame.states)
+        }
+        _ => Vec::new(),
+    };
+    if output {
+        data.truncate(config.frame_len());
+        frame.data = Some(data);
+    }
+    if let Some(hook) = hook {
+        hook.on_frame(&frame, &traces);
+    }
+    frame
+}
+
+/// Derandomize and correct the codeblock of a CADU in place, returning its [Frame]
+/// without the data and whether the data is output, i.e., the first
+/// [Config::frame_len] bytes of `data` are the transfer frame, which is degraded if
+/// any message is uncorrectable and [Config::best_effort] is true.
+pub(crate) fn decode_in_place(
+    config: &Config,
+    offset: u64,
+    inverted: bool,
+    data: &mut [u8],
+) -> (Frame, bool) {
+    if config.derandomize {
+        config.randomizer.apply(data);
+    }
+    if config.idle.as_ref().is_some_and(|idle| idle.matches(data)) {
+        let frame = Frame {
+            offset,
+            data: None,
+            states: vec![RSState::NotPerformed; config.interleave as usize],
+            error_positions: Vec::new(),
+            corrected_bits: 0,
+            fecf_ok: None,
+            inverted,
+            idle: true,
+        };
+        return (frame, true);
+    }
+    let block = config.code.correct_codeblock_in_place_with_max_errors(
+        data,
+        config.interleave,
+        config.max_errors.unwrap_or(usize::MAX),
+    );
+    let output = config.best_effort
+        || !block
+            .states
+            .iter()
+            .any(|s| matches!(s, RSState::Uncorrectable(_)));
+    let fecf_ok = (output && config.fecf).then(|| crc::verify_fecf(&data[..config.frame_len()]));
+    let frame = Frame {
+        offset,
+        data: None,
+        states: block.states,
+        error_positions: block.error_positions,
+        corrected_bits: block.corrected_bits,
+        fecf_ok,
+        inverted,
+        idle: false,
+    };
+    (frame, output)
+}
+
+impl<R: Read> Iterator for Pipeline<R> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.sync.next()?;
+        let hook = self
+            .hook
+            .as_deref_mut()
+            .map(|hook| hook as &mut dyn FrameHook);
+        Some(event.map(|event| Event::from_sync(&self.config, event, hook)))
+    }
+}
+
+/// Maximum number of items queued between the stages of a [ThreadedPipeline].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct QueueDepths {
+    /// Sync events waiting to be derandomized.
+    pub sync: usize,
+    /// Derandomized events waiting to be corrected.
+    pub derandomize: usize,
+    /// Decoded events waiting to be consumed.
+    pub output: usize,
+}
+
+impl Default for QueueDepths {
+    fn default() -> Self {
+        QueueDepths {
+            sync: 64,
+            derandomize: 64,
+            output: 64,
+        }
+    }
+}
+
+/// A [Pipeline] where synchronization, derandomization, and RS correction each run
+/// on their own thread, connected by bounded channels.
+///
+/// This overlaps I/O with decoding for live streams. Each stage blocks once its
+/// output queue is full, so a slow consumer applies backpressure all the way to the
+/// reader. Events are produced in the same order as [Pipeline].
+///
+/// Dropping the pipeline stops the stages once they next try to send, which for the
+/// sync stage is after its current read returns.
+pub struct ThreadedPipeline {
+    rx: Receiver<Result<Event>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl ThreadedPipeline {
+    /// # Panics
+    /// If [Config::asm] is empty.
+    pub fn new<R: Read + Send + 'static>(reader: R, config: Config, depths: QueueDepths) -> Self {
+        Self::spawn(reader, config, depths, &ThreadConfig::default(), None)
+    }
+
+    /// Create a pipeline like [ThreadedPipeline::new] with its stages pinned as
+    /// configured by `threads`.
+    pub fn with_threads<R: Read + Send + 'static>(
+        reader: R,
+        config: Config,
+        depths: QueueDepths,
+        threads: &ThreadConfig,
+    ) -> Self {
+        Self::spawn(reader, config, depths, threads, None)
+    }
+
+    /// Create a pipeline like [ThreadedPipeline::new] that calls `hook` with each frame
+    /// as it is decoded, on the RS correction thread; see [Pipeline::with_hook].
+    pub fn with_hook<R, H>(reader: R, config: Config, depths: QueueDepths, hook: H) -> Self
+    where
+        R: Read + Send + 'static,
+        H: FrameHook + Send + 'static,
+    {
+        Self::spawn(
+            reader,
+            config,
+            depths,
+            &ThreadConfig::default(),
+            Some(Box::new(hook)),
+        )
+    }
+
+    fn spawn<R: Read + Send + 'static>(
+        reader: R,
+        config: Config,
+        depths: QueueDepths,
+        threads: &ThreadConfig,
+        mut hook: Option<Box<dyn FrameHook + Send>>,
+    ) -> Self {
+        let (sync_tx, sync_rx) = sync_channel(depths.sync);
+        let (derand_tx, derand_rx) = sync_channel(depths.derandomize);
+        let (out_tx, rx) = sync_channel(depths.output);
+
+        let sync = synchronizer(reader, &config);
+        let randomizer = config.derandomize.then_some(config.randomizer);
+        let config = Config {
+            derandomize: false,
+            ..config
+        };
+        let handles = vec![
+            threads.spawn(0, move || forward(sync, &sync_tx)),
+            threads.spawn(1, move || {
+                let events = sync_rx.into_iter().map(|event: Result<sync::Event>| {
+                    let mut event = event?;
+                    if let (sync::Event::Cadu(cadu), Some(randomizer)) = (&mut event, randomizer) {
+                        randomizer.apply(&mut cadu.data);
+                    }
+                    Ok(event)
+                });
+                forward(events, &derand_tx)
+            }),
+            threads.spawn(2, move || {
+                let events = derand_rx.into_iter().map(|event: Result<sync::Event>| {
+                    let hook = hook.as_deref_mut().map(|hook| hook as &mut dyn FrameHook);
+                    Ok(Event::from_sync(&config, event?, hook))
+                });
+                forward(events, &out_tx)
+            }),
+        ];
+        ThreadedPipeline { rx, handles }
+    }
+}
+
+/// Send each item to `tx` until the items are exhausted or the receiver is dropped.
+fn forward<T>(items: impl Iterator<Item = T>, tx: &SyncSender<T>) {
+    for item in items {
+        if tx.send(item).is_err() {
+            return;
+        }
+    }
+}
+
+impl Iterator for ThreadedPipeline {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.rx.recv() {
+            Ok(event) => Some(event),
+            Err(_) => {
+                // all stages have finished, so surface any panic rather than
+                // silently ending the stream
+                for handle in self.handles.drain(..) {
+                    if let Err(panic) = handle.join() {
+                        std::panic::resume_unwind(panic);
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{interleave, xorshift, FIXTURE_MSG};
+
+    fn cadu(msgs: &[&[u8]]) -> Vec<u8> {
+        let mut block = interleave(msgs);
+        pn::randomize(&mut block);
+        let mut cadu = sync::ASM.to_vec();
+        cadu.extend(block);
+        cadu
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[0] ^= 0xff;
+        corrupt[100] ^= 0xff;
+        let mut uncorrectable = *FIXTURE_MSG;
+        for x in uncorrectable.iter_mut().take(40) {
+            *x ^= 0xff;
+        }
+
+        let mut input = vec![0u8; 10];
+        input.extend(cadu(&[FIXTURE_MSG, FIXTURE_MSG]));
+        input.extend(cadu(&[FIXTURE_MSG, &corrupt]));
+        input.extend(cadu(&[&uncorrectable, FIXTURE_MSG]));
+        let config = Config {
+            interleave: 2,
+            ..Default::default()
+        };
+
+        let events = Pipeline::new(&input[..], config)
+            .collect::<Result<Vec<Event>>>()
+            .unwrap();
+
+        assert_eq!(events.len(), 4, "{events:?}");
+        assert_eq!(events[0], Event::Skipped(0..10));
+        let expected = interleave(&[FIXTURE_MSG, FIXTURE_MSG])[..446].to_vec();
+        let Event::Frame(frame) = &events[1] else {
+            panic!("expected frame");
+        };
+        assert_eq!(frame.offset, 10);
+        assert_eq!(frame.states, vec![RSState::Ok, RSState::Ok]);
+        assert_eq!(frame.data.as_ref().unwrap(), &expected);
+
+        let Event::Frame(frame) = &events[2] else {
+            panic!("expected frame");
+        };
+        assert_eq!(frame.offset, 524);
+        assert_eq!(frame.states, vec![RSState::Ok, RSState::Corrected(2)]);
+        assert_eq!(frame.data.as_ref().unwrap(), &expected);
+
+        let Event::Frame(frame) = &events[3] else {
+            panic!("expected frame");
+        };
+        assert!(matches!(frame.states[0], RSState::Uncorrectable(_)));
+        assert!(frame.data.is_none());
+        assert!(!frame.is_degraded());
+    }
+
+    #[test]
+    fn test_pipeline_frames() {
+        let mut input = cadu(&[FIXTURE_MSG]);
+        input.extend([0u8; 100]);
+        input.extend(cadu(&[FIXTURE_MSG]));
+        let config = Config {
+            interleave: 1,
+            ..Default::default()
+        };
+
+        let frames = Pipeline::new(&input[..], config)
+            .frames()
+            .collect::<Result<Vec<Frame>>>()
+            .unwrap();
+
+        // The first CADU is not followed by an ASM so it cannot be confirmed
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].data.as_ref().unwrap(), &FIXTURE_MSG[..223]);
+        assert_eq!(frames[0].fecf_ok, None);
+        assert!(!frames[0].inverted);
+    }
+
+    #[test]
+    fn test_pipeline_best_effort() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[3] ^= 0x01;
+        let mut uncorrectable = *FIXTURE_MSG;
+        for x in uncorrectable.iter_mut().take(40) {
+            *x ^= 0xff;
+        }
+        let input = cadu(&[&uncorrectable, &corrupt]);
+        let config = Config {
+            interleave: 2,
+            best_effort: true,
+            ..Default::default()
+        };
+
+        let frames = Pipeline::new(&input[..], config)
+            .frames()
+            .collect::<Result<Vec<Frame>>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].is_degraded());
+        assert_eq!(
+            frames[0].data.as_ref().unwrap(),
+            &interleave(&[&uncorrectable, FIXTURE_MSG])[..446]
+        );
+    }
+
+    #[test]
+    fn test_pipeline_hook() {
+        use std::sync::{Arc, Mutex};
+
+        // records the frame offsets and the locator degree of each trace
+        type Record = (u64, Vec<usize>);
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<Record>>>);
+
+        impl FrameHook for Recorder {
+            fn on_frame(&mut self, frame: &Frame, traces: &[Trace]) {
+                let degrees = traces.iter().map(|t| t.locator.len() - 1).collect();
+                self.0.lock().unwrap().push((frame.offset, degrees));
+            }
+
+            fn traces(&self) -> bool {
+                true
+            }
+        }
+
+        let mut uncorrectable = *FIXTURE_MSG;
+        for i in 0..17 {
+            uncorrectable[i * 15] ^= 0x5a;
+        }
+        let mut input = cadu(&[FIXTURE_MSG, FIXTURE_MSG]);
+        input.extend(cadu(&[FIXTURE_MSG, &uncorrectable]));
+        let config = Config {
+            interleave: 2,
+            ..Default::default()
+        };
+        let expected = vec![(0, vec![]), (514, vec![17])];
+
+        let recorder = Recorder::default();
+        let frames = Pipeline::new(&input[..], config.clone())
+            .with_hook(recorder.clone())
+            .frames()
+            .collect::<Result<Vec<Frame>>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(*recorder.0.lock().unwrap(), expected);
+
+        let recorder = Recorder::default();
+        let depths = QueueDepths::default();
+        let events = ThreadedPipeline::with_hook(
+            std::io::Cursor::new(input.clone()),
+            config.clone(),
+            depths,
+            recorder.clone(),
+        )
+        .count();
+        assert_eq!(events, 2);
+        assert_eq!(*recorder.0.lock().unwrap(), expected);
+
+        // a closure is a hook without traces
+        let calls = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = calls.clone();
+        let frames = Pipeline::new(&input[..], config)
+            .with_hook(move |_: &Frame, traces: &[Trace]| {
+                assert!(traces.is_empty());
+                counter.set(counter.get() + 1);
+            })
+            .frames()
+            .count();
+        assert_eq!(frames, 2);
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_pipeline_inverted() {
+        let mut input = cadu(&[FIXTURE_MSG]);
+        input.extend(cadu(&[FIXTURE_MSG]));
+        input.iter_mut().for_each(|x| *x = !*x);
+        let config = Config {
+            interleave: 1,
+            ..Default::default()
+        };
+
+        let frames = Pipeline::new(&input[..], config)
+            .frames()
+            .collect::<Result<Vec<Frame>>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert!(frames.iter().all(|f| f.inverted));
+        assert_eq!(frames[0].states, vec![RSState::Ok]);
+    }
+
+    #[test]
+    fn test_pipeline_malformed_stream() {
+        let mut rng = 0x9e37_79b9_7f4a_7c15;
+        for _ in 0..50 {
+            // random bytes with ASMs and corrupted CADUs at random offsets
+            let mut input = vec![0u8; xorshift(&mut rng) as usize % 8000];
+            input.iter_mut().for_each(|x| *x = xorshift(&mut rng) as u8);
+            for _ in 0..xorshift(&mut rng) % 10 {
+                let mut block = cadu(&[FIXTURE_MSG]);
+                for _ in 0..xorshift(&mut rng) % 40 {
+                    let i = xorshift(&mut rng) as usize % block.len();
+                    block[i] ^= xorshift(&mut rng) as u8;
+                }
+                let at = xorshift(&mut rng) as usize % (input.len() + 1);
+                input.splice(at..at, block);
+            }
+            let config = Config {
+                interleave: 1 + xorshift(&mut rng) as u8 % 8,
+                code: if xorshift(&mut rng).is_multiple_of(2) {
+                    Code::E16
+                } else {
+                    Code::E8
+                },
+                fecf: xorshift(&mut rng).is_multiple_of(2),
+                lock: xorshift(&mut rng)
+                    .is_multiple_of(2)
+                    .then(sync::LockConfig::default),
+                ..Default::default()
+            };
+
+            for event in Pipeline::new(&input[..], config) {
+                event.unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_pipeline_idle() {
+        let mut idle = [0u8; 223];
+        idle[..2].copy_from_slice(&[0x40, 0x7f]);
+        idle[6..].fill(0x55);
+        let mut idle = crate::encode_message(&idle).unwrap();
+        // errors outside the header go unnoticed
+        idle[100] ^= 0xff;
+        let mut input = cadu(&[FIXTURE_MSG]);
+        input.extend(cadu(&[&idle]));
+        input.extend(cadu(&[&[0u8; 255]]));
+        let config = Config {
+            interleave: 1,
+            idle: Some(IdleFilter::Vcid(63)),
+            ..Default::default()
+        };
+
+        let frames = Pipeline::new(&input[..], config.clone())
+            .frames()
+            .collect::<Result<Vec<Frame>>>()
+            .unwrap();
+
+        assert_eq!(
+            frames.iter().map(|f| f.idle).collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+        assert_eq!(frames[0].states, vec![RSState::Ok]);
+        assert_eq!(frames[1].states, vec![RSState::NotPerformed]);
+        assert_eq!(frames[1].data.as_deref(), Some(&idle[..223]));
+
+        assert!(IdleFilter::Constant(0).matches(&[0u8; 255]));
+        assert!(!IdleFilter::Constant(0).matches(&idle));
+        assert!(IdleFilter::Pattern(idle.clone()).matches(&idle));
+        assert!(!IdleFilter::Pattern(idle.clone()).matches(&idle[1..]));
+        assert!(!IdleFilter::Vcid(63).matches(&[]));
+    }
+
+    #[test]
+    fn test_pipeline_long_randomizer() {
+        let mut input = sync::ASM.to_vec();
+        let mut block = FIXTURE_MSG.to_vec();
+        pn::Sequence::Pn131071.apply(&mut block);
+        input.extend(block);
+        let config = Config {
+            interleave: 1,
+            randomizer: pn::Sequence::Pn131071,
+            ..Default::default()
+        };
+
+        let frames = Pipeline::new(&input[..], config)
+            .frames()
+            .collect::<Result<Vec<Frame>>>()
+            .unwrap();
+
+        assert_eq!(frames[0].states, vec![RSState::Ok]);
+    }
+
+    #[test]
+    fn test_pipeline_fecf() {
+        let mut data = [0x33u8; 223];
+        crc::set_fecf(&mut data);
+        let good = crate::encode_message(&data).unwrap();
+        // a valid codeword whose frame has a bad FECF, as from a miscorrection
+        let bad = FIXTURE_MSG;
+        let mut input = cadu(&[&good]);
+        input.extend(cadu(&[bad]));
+        let config = Config {
+            interleave: 1,
+            fecf: true,
+            ..Default::default()
+        };
+
+        let frames = Pipeline::new(&input[..], config)
+            .frames()
+            .collect::<Result<Vec<Frame>>>()
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].fecf_ok, Some(true));
+        assert_eq!(frames[1].fecf_ok, Some(false));
+    }
+
+    #[test]
+    fn test_pipeline_lock() {
+        let mut input = cadu(&[FIXTURE_MSG]);
+        input.extend(cadu(&[FIXTURE_MSG]));
+        // missing ASM is flywheeled through when locked
+        let mut missing = cadu(&[FIXTURE_MSG]);
+        missing[..4].fill(0);
+        input.extend(missing);
+        let config = Config {
+            interleave: 1,
+            lock: Some(sync::LockConfig {
+                check: 1,
+                flywheel: 1,
+            }),
+            ..Default::default()
+        };
+
+        let events = Pipeline::new(&input[..], config)
+            .collect::<Result<Vec<Event>>>()
+            .unwrap();
+
+        assert_eq!(events.len(), 4, "{events:?}");
+        assert_eq!(events[0], Event::Locked(0));
+        for event in &events[1..] {
+            let Event::Frame(frame) = event else {
+                panic!("expected frame");
+            };
+            assert_eq!(frame.states, vec![RSState::Ok]);
+        }
+    }
+
+    #[test]
+    fn test_threaded_pipeline() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[9] ^= 0xff;
+        let mut input = vec![0u8; 7];
+        for _ in 0..10 {
+            input.extend(cadu(&[FIXTURE_MSG, &corrupt]));
+        }
+        let config = Config {
+            interleave: 2,
+            ..Default::default()
+        };
+        let expected = Pipeline::new(&input[..], config.clone())
+            .collect::<Result<Vec<Event>>>()
+            .unwrap();
+        let depths = QueueDepths {
+            sync: 1,
+            derandomize: 2,
+            output: 1,
+        };
+
+        let events =
+            ThreadedPipeline::new(std::io::Cursor::new(input.clone()), config.clone(), depths)
+                .collect::<Result<Vec<Event>>>()
+                .unwrap();
+
+        assert_eq!(events.len(), 11);
+        assert_eq!(events, expected);
+
+        let threads = ThreadConfig::default();
+        let pinned = ThreadedPipeline::with_threads(
+            std::io::Cursor::new(input.clone()),
+            config.clone(),
+            depths,
+            &threads,
+        );
+        assert_eq!(pinned.collect::<Result<Vec<Event>>>().unwrap(), expected);
+
+        // stopping early does not block
+        let mut pipeline = ThreadedPipeline::new(std::io::Cursor::new(input), config, depths);
+        assert_eq!(pipeline.next().unwrap().unwrap(), Event::Skipped(0..7));
+        drop(pipeline);
+    }
+}
@@ -0,0 +1,195 @@
+//! CCSDS pseudo-randomizer as documented in section 10 of
+//! [131.0-B-5](https://public.ccsds.org/Pubs/131x0b5.pdf).
+//!
+//! The randomizer XORs each CADU, excluding the ASM, with a pseudo-random sequence
+//! with the generator initialized to all ones at the start of each CADU. Because the
+//! operation is an XOR, randomizing and derandomizing are the same operation.
+//!
+//! Two sequences are defined, see [Sequence]. The free functions in this module, e.g.,
+//! [derandomize], use the 255 bit sequence.
+use std::sync::OnceLock;
+
+/// Length, in bytes, of the 255 bit pseudo-random sequence before it repeats.
+pub const SEQUENCE_LEN: usize = 255;
+
+/// Length, in bytes, of the 131071 bit pseudo-random sequence before it repeats on a
+/// byte boundary, i.e., 8 periods of the bit sequence.
+pub const LONG_SEQUENCE_LEN: usize = 131071;
+
+/// Pseudo-random sequence used to randomize CADUs.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Sequence {
+    /// The 255 bit sequence generated by `h(x) = x^8 + x^7 + x^5 + x^3 + 1`, used by
+    /// most missions.
+    #[default]
+    Pn255,
+    /// The 131071 bit sequence generated by `h(x) = x^17 + x^14 + 1`.
+    Pn131071,
+}
+
+impl Sequence {
+    /// The sequence bytes, which repeat after the returned length.
+    pub fn bytes(&self) -> &'static [u8] {
+        match self {
+            Sequence::Pn255 => &SEQUENCE,
+            Sequence::Pn131071 => LONG_SEQUENCE.get_or_init(gen_long_sequence),
+        }
+    }
+
+    /// Randomize, or derandomize, a single CADU, excluding the ASM, in place.
+    pub fn apply(&self, buf: &mut [u8]) {
+        Randomizer::with_sequence(*self).apply(buf);
+    }
+}
+
+const fn gen_sequence() -> [u8; SEQUENCE_LEN] {
+    let mut seq = [0u8; SEQUENCE_LEN];
+    let mut state: u8 = 0xff;
+    let mut i = 0;
+    while i < SEQUENCE_LEN {
+        let mut byte = 0u8;
+        let mut bit = 0;
+        while bit < 8 {
+            byte = (byte << 1) | (state >> 7);
+            let feedback = ((state >> 7) ^ (state >> 4) ^ (state >> 2) ^ state) & 1;
+            state = (state << 1) | feedback;
+            bit += 1;
+        }
+        seq[i] = byte;
+        i += 1;
+    }
+    seq
+}
+
+static SEQUENCE: [u8; SEQUENCE_LEN] = gen_sequence();
+
+// Generated on first use rather than at compile time due to its length.
+static LONG_SEQUENCE: OnceLock<Vec<u8>> = OnceLock::new();
+
+fn gen_long_sequence() -> Vec<u8> {
+    let mut seq = vec![0u8; LONG_SEQUENCE_LEN];
+    let mut state: u32 = 0x1ffff;
+    for byte in seq.iter_mut() {
+        for _ in 0..8 {
+            *byte = (*byte << 1) | (state >> 16) as u8;
+            let feedback = ((state >> 16) ^ (state >> 2)) & 1;
+            state = ((state << 1) | feedback) & 0x1ffff;
+        }
+    }
+    seq
+}
+
+/// Stateful randomizer that tracks its position in the pseudo-random sequence so a
+/// single CADU may be processed across multiple calls, e.g., as data arrives.
+///
+/// Call [Randomizer::reset] at the start of each CADU.
+#[derive(Debug, Default, Clone)]
+pub struct Randomizer {
+    sequence: Sequence,
+    offset: usize,
+}
+
+impl Randomizer {
+    /// Create a randomizer using the 255 bit sequence.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_sequence(sequence: Sequence) -> Self {
+        Randomizer {
+            sequence,
+            offset: 0,
+        }
+    }
+
+    /// XOR `buf` in place with the pseudo-random sequence, continuing from the
+    /// current sequence position.
+    pub fn apply(&mut self, buf: &mut [u8]) {
+        let seq = self.sequence.bytes();
+        for x in buf.iter_mut() {
+            *x ^= seq[self.offset];
+            self.offset = (self.offset + 1) % seq.len();
+        }
+    }
+
+    /// Reset to the start of the sequence.
+    pub fn reset(&mut self) {
+        self.offset = 0;
+    }
+}
+
+/// Randomize a single CADU, excluding the ASM, in place.
+pub fn randomize(buf: &mut [u8]) {
+    Randomizer::new().apply(buf);
+}
+
+/// Derandomize a single CADU, excluding the ASM, in place.
+pub fn derandomize(buf: &mut [u8]) {
+    Randomizer::new().apply(buf);
+}
+
+/// The 255 bit pseudo-random sequence.
+pub fn sequence() -> &'static [u8; SEQUENCE_LEN] {
+    &SEQUENCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence() {
+        // First bytes of the sequence from 131.0-B-5
+        let expected = [0xff, 0x48, 0x0e, 0xc0, 0x9a, 0x0d, 0x70, 0xbc];
+        assert_eq!(sequence()[..expected.len()], expected);
+    }
+
+    #[test]
+    fn test_long_sequence() {
+        let seq = Sequence::Pn131071.bytes();
+        assert_eq!(seq.len(), LONG_SEQUENCE_LEN);
+        assert_eq!(seq[..4], [0xff, 0xff, 0x8e, 0x38]);
+        // maximal length: the bit sequence only repeats after 2^17 - 1 bits
+        let bits = |i: usize| (seq[i / 8] >> (7 - i % 8)) & 1;
+        let period = (1 << 17) - 1;
+        assert!((0..64).all(|i| bits(i) == bits(i + period)));
+        assert!((1..period).all(|p| (0..17).any(|i| bits(i) != bits(i + p))));
+
+        let mut buf = vec![0u8; 20];
+        let mut pn = Randomizer::with_sequence(Sequence::Pn131071);
+        pn.apply(&mut buf[..7]);
+        pn.apply(&mut buf[7..]);
+        assert_eq!(buf, seq[..20]);
+        Sequence::Pn131071.apply(&mut buf);
+        assert_eq!(buf, vec![0u8; 20]);
+    }
+
+    #[test]
+    fn test_derandomize() {
+        let mut buf = vec![0u8; 1020];
+        derandomize(&mut buf);
+        assert_eq!(buf[..SEQUENCE_LEN], SEQUENCE);
+        assert_eq!(buf[SEQUENCE_LEN..2 * SEQUENCE_LEN], SEQUENCE);
+
+        randomize(&mut buf);
+        assert_eq!(buf, vec![0u8; 1020]);
+    }
+
+    #[test]
+    fn test_randomizer_chunks() {
+        let mut expected = vec![0u8; 600];
+        derandomize(&mut expected);
+
+        let mut buf = vec![0u8; 600];
+        let mut pn = Randomizer::new();
+        for chunk in buf.chunks_mut(7) {
+            pn.apply(chunk);
+        }
+        assert_eq!(buf, expected);
+
+        pn.reset();
+        let mut buf = vec![0u8; 10];
+        pn.apply(&mut buf);
+        assert_eq!(buf, expected[..10]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Named pipeline configurations for the downlinks of common missions.
+//!
+//! ```
+//! use rs2::pipeline::Pipeline;
+//! use rs2::presets::Preset;
+//!
+//! let stream = rs2::gen::cadus(&Preset::Snpp.config(), b"hello world", 0);
+//! let mut frames = Pipeline::for_preset(&stream[..], Preset::Snpp).frames();
+//! assert_eq!(&frames.next().unwrap().unwrap().data.unwrap()[..11], b"hello world");
+//! ```
+//!
+//! For a working decode in one call, each preset has a module with a `decode` function
+//! that writes the frames of a CADU stream, e.g., [snpp::decode].
+//!
+//! ```
+//! let stream = rs2::gen::cadus(&rs2::presets::Preset::Aqua.config(), b"hello world", 0);
+//! let mut frames = Vec::new();
+//! let summary = rs2::presets::aqua::decode(&stream[..], &mut frames).unwrap();
+//! assert_eq!(summary.ok, 4);
+//! assert_eq!(&frames[..11], b"hello world");
+//! ```
+//!
+//! Presets describe the CADU layout as seen after demodulation and any convolutional
+//! decoding, which are outside the scope of this crate.
+use std::fmt;
+use std::io::{Read, Result, Write};
+
+use crate::pipeline::{Config, Pipeline};
+use crate::stats::DecodeSummary;
+use crate::{pn, sync, Code};
+
+macro_rules! preset_module {
+    ($module:ident, $preset:ident) => {
+        #[doc = concat!("One-call decoding with [Preset::", stringify!($preset), "].")]
+        pub mod $module {
+            use std::io::{Read, Result, Write};
+
+            use super::Preset;
+            use crate::stats::DecodeSummary;
+
+            #[doc = concat!("Decode with [Preset::", stringify!($preset), "]; see [Preset::decode].")]
+            pub fn decode<R: Read, W: Write>(reader: R, writer: W) -> Result<DecodeSummary> {
+                Preset::$preset.decode(reader, writer)
+            }
+        }
+    };
+}
+
+preset_module!(snpp, Snpp);
+preset_module!(aqua, Aqua);
+preset_module!(terra, Terra);
+preset_module!(metop, Metop);
+
+/// A mission whose direct broadcast downlink has a known CADU layout.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Preset {
+    /// Suomi NPP and JPSS (NOAA-20, NOAA-21) HRD: 1024 byte CADUs, interleave 4
+    /// RS(255,223), randomized.
+    Snpp,
+    /// EOS Aqua direct broadcast: 1024 byte CADUs, interleave 4 RS(255,223),
+    /// randomized.
+    Aqua,
+    /// EOS Terra direct broadcast: 1024 byte CADUs, interleave 4 RS(255,223),
+    /// randomized.
+    Terra,
+    /// MetOp AHRPT: 1024 byte CADUs, interleave 4 RS(255,223), randomized.
+    Metop,
+}
+
+impl Preset {
+    /// All presets, e.g., for listing them in a user interface.
+    pub const ALL: [Preset; 4] = [Preset::Snpp, Preset::Aqua, Preset::Terra, Preset::Metop];
+
+    /// Lowercase name of the preset as accepted by [Preset::from_name].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Snpp => "snpp",
+            Preset::Aqua => "aqua",
+            Preset::Terra => "terra",
+            Preset::Metop => "metop",
+        }
+    }
+
+    /// Look up a preset by name, ignoring case. Mission names sharing a preset, such
+    /// as `jpss` or `noaa20` for [Preset::Snpp], are also accepted.
+    pub fn from_name(name: &str) -> Option<Preset> {
+        let preset = match name.to_ascii_lowercase().as_str() {
+            "snpp" | "npp" | "jpss" | "noaa20" | "noaa21" | "jpss1" | "jpss2" => Preset::Snpp,
+            "aqua" => Preset::Aqua,
+            "terra" => Preset::Terra,
+            "metop" | "metop-b" | "metop-c" => Preset::Metop,
+            _ => return None,
+        };
+        Some(preset)
+    }
+
+    /// Pipeline configuration for the preset.
+    pub fn config(&self) -> Config {
+        match self {
+            Preset::Snpp | Preset::Aqua | Preset::Terra | Preset::Metop => Config {
+                asm: sync::ASM.to_vec(),
+                asm_threshold: 0,
+                interleave: 4,
+                derandomize: true,
+                randomizer: pn::Sequence::Pn255,
+                code: Code::E16,
+                fecf: false,
+                lock: None,
+                max_errors: None,
+                idle: None,
+                best_effort: false,
+            },
+        }
+    }
+
+    /// Decode the CADUs of `reader` with the preset and write each frame with data,
+    /// i.e., without uncorrectable messages, to `writer`, returning the results of the
+    /// messages of all frames.
+    pub fn decode<R: Read, W: Write>(&self, reader: R, mut writer: W) -> Result<DecodeSummary> {
+        let mut summary = DecodeSummary::new();
+        for frame in Pipeline::for_preset(reader, *self).frames() {
+            let frame = frame?;
+            summary.add_frame(&frame);
+            if let Some(data) = &frame.data {
+                writer.write_all(data)?;
+            }
+        }
+        writer.flush()?;
+        Ok(summary)
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for preset in Preset::ALL {
+            assert_eq!(Preset::from_name(preset.name()), Some(preset));
+            assert_eq!(preset.to_string(), preset.name());
+            let config = preset.config();
+            assert_eq!(config.asm.len() + config.block_len(), 1024);
+        }
+        assert_eq!(Preset::from_name("NOAA20"), Some(Preset::Snpp));
+        assert_eq!(Preset::from_name("goes"), None);
+    }
+
+    #[test]
+    fn test_decode() {
+        let config = Preset::Snpp.config();
+        let frame_len = config.frame_len();
+        let data: Vec<u8> = (0..3 * frame_len).map(|i| i as u8).collect();
+        let mut stream = crate::gen::cadus(&config, &data, 0);
+        // a corrected error in the first CADU, and an uncorrectable message in the second
+        stream[10] ^= 0x01;
+        for i in 0..20 {
+            stream[1024 + 4 + i * 4] ^= 0xff;
+        }
+
+        let check = |summary: DecodeSummary, output: Vec<u8>| {
+            assert_eq!(
+                (summary.ok, summary.corrected, summary.uncorrectable),
+                (10, 1, 1)
+            );
+            assert_eq!(output.len(), 2 * frame_len);
+            assert_eq!(&output[..frame_len], &data[..frame_len]);
+            assert_eq!(&output[frame_len..], &data[2 * frame_len..]);
+        };
+        let mut output = Vec::new();
+        check(snpp::decode(&stream[..], &mut output).unwrap(), output);
+        let mut output = Vec::new();
+        check(aqua::decode(&stream[..], &mut output).unwrap(), output);
+        let mut output = Vec::new();
+        check(terra::decode(&stream[..], &mut output).unwrap(), output);
+        let mut output = Vec::new();
+        check(metop::decode(&stream[..], &mut output).unwrap(), output);
+    }
+}
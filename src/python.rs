@@ -0,0 +1,263 @@
+//! Python bindings using PyO3. Requires the `python` feature.
+//!
+//! The extension module is built with [maturin](https://www.maturin.rs), e.g.,
+//! `maturin develop`, using the configuration in `pyproject.toml`, which builds the
+//! library as a cdylib with `cargo rustc --crate-type cdylib`. Inputs may be any
+//! object supporting the buffer protocol, e.g., `bytes`, `bytearray` or a numpy
+//! `uint8` array.
+//!
+//! ```python
+//! import rs2
+//!
+//! block = rs2.correct_message(data)
+//! print(block.state, block.num_corrected, block.error_positions)
+//!
+//! for frame in rs2.decode_cadus(open("overpass.dat", "rb").read(), interleave=4):
+//!     print(frame.offset, frame.states)
+//! ```
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::pipeline::{Config, Frame, Pipeline};
+use crate::{Block, Code, RSState};
+
+fn parse_code(code: &str) -> PyResult<Code> {
+    match code {
+        "e16" => Ok(Code::E16),
+        "e8" => Ok(Code::E8),
+        _ => Err(PyValueError::new_err(format!(
+            "invalid code; expected 'e16' or 'e8', got '{code}'"
+        ))),
+    }
+}
+
+fn state_name(state: &RSState) -> &'static str {
+    match state {
+        RSState::Ok => "ok",
+        RSState::Corrected(_) => "corrected",
+        RSState::Uncorrectable(_) => "uncorrectable",
+        RSState::NotPerformed => "not_performed",
+    }
+}
+
+fn num_corrected(state: &RSState) -> i32 {
+    match state {
+        RSState::Corrected(n) => *n,
+        _ => 0,
+    }
+}
+
+/// Result of correcting a single message.
+#[pyclass(name = "Block", module = "rs2", frozen)]
+struct PyBlock {
+    block: Block,
+}
+
+#[pymethods]
+impl PyBlock {
+    /// One of "ok", "corrected", "uncorrectable" or "not_performed".
+    #[getter]
+    fn state(&self) -> &'static str {
+        state_name(&self.block.state)
+    }
+
+    #[getter]
+    fn num_corrected(&self) -> i32 {
+        num_corrected(&self.block.state)
+    }
+
+    /// Reason the message was uncorrectable, if it was.
+    #[getter]
+    fn reason(&self) -> Option<String> {
+        match &self.block.state {
+            RSState::Uncorrectable(reason) => Some(reason.to_string()),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn message<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyBytes>> {
+        self.block.message.as_ref().map(|m| PyBytes::new(py, m))
+    }
+
+    #[getter]
+    fn error_positions(&self) -> Vec<usize> {
+        self.block.error_positions.clone()
+    }
+
+    #[getter]
+    fn error_values(&self) -> Vec<u8> {
+        self.block.error_values.clone()
+    }
+
+    /// Number of bits corrected.
+    #[getter]
+    fn corrected_bits(&self) -> u32 {
+        self.block.corrected_bits()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Block(state={:?})", self.block.state)
+    }
+}
+
+/// A transfer frame decoded from a CADU.
+#[pyclass(name = "Frame", module = "rs2", frozen)]
+struct PyFrame {
+    frame: Frame,
+}
+
+#[pymethods]
+impl PyFrame {
+    /// Byte offset of the CADU ASM in the input.
+    #[getter]
+    fn offset(&self) -> u64 {
+        self.frame.offset
+    }
+
+    /// Corrected frame without check symbols, or None if uncorrectable.
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyBytes>> {
+        self.frame.data.as_ref().map(|d| PyBytes::new(py, d))
+    }
+
+    /// State of each interleaved message.
+    #[getter]
+    fn states(&self) -> Vec<&'static str> {
+        self.frame.states.iter().map(state_name).collect()
+    }
+
+    /// Number of symbols corrected in each interleaved message.
+    #[getter]
+    fn num_corrected(&self) -> Vec<i32> {
+        self.frame.states.iter().map(num_corrected).collect()
+    }
+
+    /// Result of verifying the FECF, or None if not checked.
+    #[getter]
+    fn fecf_ok(&self) -> Option<bool> {
+        self.frame.fecf_ok
+    }
+
+    /// Whether the CADU was found with inverted polarity.
+    #[getter]
+    fn inverted(&self) -> bool {
+        self.frame.inverted
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Frame(offset={}, states={:?})",
+            self.frame.offset, self.frame.states
+        )
+    }
+}
+
+/// Correct a 255 byte dual basis RS message.
+#[pyfunction]
+#[pyo3(signature = (input, code = "e16"))]
+fn correct_message(py: Python<'_>, input: PyBuffer<u8>, code: &str) -> PyResult<PyBlock> {
+    let code = parse_code(code)?;
+    let input = input.to_vec(py)?;
+    let block = py.allow_threads(|| code.correct_message(&input));
+    Ok(PyBlock { block })
+}
+
+/// Encode dual basis data, 223 bytes for "e16" or 239 bytes for "e8", into a 255
+/// byte RS message.
+#[pyfunction]
+#[pyo3(signature = (data, code = "e16"))]
+fn encode_message<'py>(
+    py: Python<'py>,
+    data: PyBuffer<u8>,
+    code: &str,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let code = parse_code(code)?;
+    let msg = code
+        .encode_message(&data.to_vec(py)?)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(PyBytes::new(py, &msg))
+}
+
+/// Synchronize, derandomize and correct a buffer of CADUs using the standard ASM,
+/// returning the decoded frames. If `fecf` is true the FECF of each frame is
+/// verified.
+#[pyfunction]
+#[pyo3(signature = (data, interleave = 4, derandomize = true, code = "e16", fecf = false))]
+fn decode_cadus(
+    py: Python<'_>,
+    data: PyBuffer<u8>,
+    interleave: u8,
+    derandomize: bool,
+    code: &str,
+    fecf: bool,
+) -> PyResult<Vec<PyFrame>> {
+    if !(1..=crate::MAX_INTERLEAVE).contains(&interleave) {
+        return Err(PyValueError::new_err(
+            crate::UncorrectableReason::InvalidInterleave(interleave).to_string(),
+        ));
+    }
+    let config = Config {
+        interleave,
+        derandomize,
+        code: parse_code(code)?,
+        fecf,
+        ..Default::default()
+    };
+    let data = data.to_vec(py)?;
+    let frames = py.allow_threads(|| {
+        Pipeline::new(&data[..], config)
+            .frames()
+            .collect::<std::io::Result<Vec<Frame>>>()
+    })?;
+    Ok(frames.into_iter().map(|frame| PyFrame { frame }).collect())
+}
+
+#[pymodule]
+fn rs2(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBlock>()?;
+    m.add_class::<PyFrame>()?;
+    m.add_function(wrap_pyfunction!(correct_message, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_message, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_cadus, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::FIXTURE_MSG;
+
+    fn buffer<'py>(py: Python<'py>, data: &[u8]) -> PyBuffer<u8> {
+        PyBuffer::get(&PyBytes::new(py, data)).unwrap()
+    }
+
+    #[test]
+    fn test_python_bindings() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let msg = encode_message(py, buffer(py, &FIXTURE_MSG[..223]), "e16").unwrap();
+            assert_eq!(msg.as_bytes(), FIXTURE_MSG);
+
+            let mut corrupt = *FIXTURE_MSG;
+            corrupt[1] ^= 0x10;
+            let block = correct_message(py, buffer(py, &corrupt), "e16").unwrap();
+            assert_eq!(block.state(), "corrected");
+            assert_eq!(block.error_positions(), vec![1]);
+            assert_eq!(block.message(py).unwrap().as_bytes(), FIXTURE_MSG);
+
+            let mut cadu = crate::sync::ASM.to_vec();
+            let mut data = FIXTURE_MSG.to_vec();
+            crate::pn::randomize(&mut data);
+            cadu.extend(data);
+            let frames = decode_cadus(py, buffer(py, &cadu), 1, true, "e16", false).unwrap();
+            assert_eq!(frames.len(), 1);
+            assert_eq!(frames[0].states(), vec!["ok"]);
+
+            assert!(correct_message(py, buffer(py, &corrupt), "e32").is_err());
+            assert!(decode_cadus(py, buffer(py, &cadu), 0, true, "e16", false).is_err());
+        });
+    }
+}
@@ -0,0 +1,302 @@
+//! Fast quality estimates of large CADU files that fully decode only a sample of the
+//! codeblocks.
+//!
+//! Before committing to a full reprocess of a huge archive, a [Quicklook] decodes only
+//! every Nth CADU and, unless [Unsampled::Skip], checks the others for errors with
+//! [Code::has_errors], which returns at the first non-zero syndrome. Every CADU must
+//! still be found by synchronization, but that is much cheaper than correcting it.
+//!
+//! ```
+//! use rs2::pipeline::Config;
+//! use rs2::quicklook::quicklook;
+//!
+//! let config = Config::default();
+//! let stream = rs2::gen::cadus(&config, &[7u8; 8920], 0);
+//! let summary = quicklook(&stream[..], config, 4).unwrap();
+//! assert_eq!(summary.cadus, 10);
+//! assert_eq!(summary.decoded_frames, 3);
+//! assert_eq!(summary.checked, 7);
+//! assert_eq!(summary.error_ratio(), 0.0);
+//! ```
+//!
+//! [Code::has_errors]: crate::Code::has_errors
+use std::fmt;
+use std::io::{Read, Result};
+
+use crate::interleave;
+use crate::pipeline::{self, Config};
+use crate::stats::DecodeSummary;
+use crate::sync;
+use crate::{RSState, N};
+
+/// What is done with the CADUs that are not in the sample.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Unsampled {
+    /// Check each message for errors without correcting it.
+    #[default]
+    Check,
+    /// Only count the CADU.
+    Skip,
+}
+
+/// Counts of a [Quicklook] of a stream of CADUs.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuicklookSummary {
+    /// Number of CADUs found.
+    pub cadus: u64,
+    /// Number of sampled CADUs that were fully decoded.
+    pub decoded_frames: u64,
+    /// Number of decoded CADUs with any message corrected or uncorrectable.
+    pub decoded_with_errors: u64,
+    /// Number of decoded CADUs with any message uncorrectable.
+    pub uncorrectable_frames: u64,
+    /// Results of the messages of the decoded CADUs.
+    pub summary: DecodeSummary,
+    /// Number of CADUs outside the sample whose messages were checked for errors.
+    pub checked: u64,
+    /// Number of checked CADUs with errors in any message.
+    pub checked_with_errors: u64,
+    /// Number of CADUs recognized as idle, sampled or not, which are not otherwise
+    /// counted; see [Config::idle].
+    pub idle: u64,
+    /// Number of CADUs outside the sample that were not checked.
+    pub skipped: u64,
+    /// Number of bytes skipped by synchronization between CADUs.
+    pub skipped_bytes: u64,
+}
+
+impl QuicklookSummary {
+    /// Estimated fraction of frames with any message uncorrectable, from the decoded
+    /// CADUs, or 0 if none were decoded.
+    pub fn uncorrectable_ratio(&self) -> f64 {
+        ratio(self.uncorrectable_frames, self.decoded_frames)
+    }
+
+    /// Estimated fraction of frames with errors, corrected or not, from the decoded
+    /// and checked CADUs, or 0 if there were none.
+    pub fn error_ratio(&self) -> f64 {
+        ratio(
+            self.decoded_with_errors + self.checked_with_errors,
+            self.decoded_frames + self.checked,
+        )
+    }
+}
+
+fn ratio(count: u64, total: u64) -> f64 {
+    match total {
+        0 => 0.0,
+        n => count as f64 / n as f64,
+    }
+}
+
+impl fmt::Display for QuicklookSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cadus={} decoded_frames={} decoded_with_errors={} uncorrectable_frames={} checked={} checked_with_errors={} idle={} skipped={} skipped_bytes={} error_ratio={:.4} uncorrectable_ratio={:.4}",
+            self.cadus,
+            self.decoded_frames,
+            self.decoded_with_errors,
+            self.uncorrectable_frames,
+            self.checked,
+            self.checked_with_errors,
+            self.idle,
+            self.skipped,
+            self.skipped_bytes,
+            self.error_ratio(),
+            self.uncorrectable_ratio(),
+        )
+    }
+}
+
+/// Accumulates a [QuicklookSummary] from the events of a [sync::Synchronizer],
+/// fully decoding only the first of every `every` CADUs.
+#[derive(Debug, Clone)]
+pub struct Quicklook {
+    config: Config,
+    every: u64,
+    unsampled: Unsampled,
+    summary: QuicklookSummary,
+}
+
+impl Quicklook {
+    /// Decode every `every` CADUs with `config`, where 1 decodes them all.
+    ///
+    /// # Panics
+    /// If `every` is 0.
+    pub fn new(config: Config, every: u64) -> Self {
+        assert!(every > 0, "sampling interval must be greater than 0");
+        Quicklook {
+            config,
+            every,
+            unsampled: Unsampled::default(),
+            summary: QuicklookSummary::default(),
+        }
+    }
+
+    /// Set what is done with the CADUs outside the sample, [Unsampled::Check] by
+    /// default.
+    pub fn unsampled(mut self, unsampled: Unsampled) -> Self {
+        self.unsampled = unsampled;
+        self
+    }
+
+    /// Record a synchronization event.
+    pub fn add(&mut self, event: sync::Event) {
+        match event {
+            sync::Event::Cadu(cadu) => self.add_cadu(cadu),
+            sync::Event::Skipped(range) => self.summary.skipped_bytes += range.end - range.start,
+            sync::Event::Locked(_) | sync::Event::Unlocked(_) => {}
+        }
+    }
+
+    fn add_cadu(&mut self, mut cadu: sync::Cadu) {
+        let summary = &mut self.summary;
+        let sampled = summary.cadus.is_multiple_of(self.every);
+        summary.cadus += 1;
+        if sampled {
+            let frame = pipeline::decode(&self.config, cadu);
+            if frame.idle {
+                summary.idle += 1;
+                return;
+            }
+            summary.decoded_frames += 1;
+            summary.summary.add_frame(&frame);
+            if frame.states.iter().any(|s| *s != RSState::Ok) {
+                summary.decoded_with_errors += 1;
+            }
+            if frame
+                .states
+                .iter()
+                .any(|s| matches!(s, RSState::Uncorrectable(_)))
+            {
+                summary.uncorrectable_frames += 1;
+            }
+            return;
+        }
+        if self.unsampled == Unsampled::Skip {
+            summary.skipped += 1;
+            return;
+        }
+        let config = &self.config;
+        if config.derandomize {
+            config.randomizer.apply(&mut cadu.data);
+        }
+        if config
+            .idle
+            .as_ref()
+            .is_some_and(|idle| idle.matches(&cadu.data))
+        {
+            summary.idle += 1;
+            return;
+        }
+        summary.checked += 1;
+        let mut msg = [0u8; N as usize];
+        for strided in interleave::deinterleave(&cadu.data, config.interleave) {
+            strided.copy_to(&mut msg);
+            if config.code.has_errors(&msg) {
+                summary.checked_with_errors += 1;
+                break;
+            }
+        }
+    }
+
+    /// The counts so far.
+    pub fn summary(&self) -> &QuicklookSummary {
+        &self.summary
+    }
+
+    pub fn into_summary(self) -> QuicklookSummary {
+        self.summary
+    }
+}
+
+/// Synchronize the CADUs of `reader` with `config` and take a [Quicklook], decoding
+/// every `every` CADUs and checking the others.
+///
+/// # Panics
+/// If `every` is 0.
+pub fn quicklook<R: Read>(reader: R, config: Config, every: u64) -> Result<QuicklookSummary> {
+    let sync = pipeline::synchronizer(reader, &config);
+    let mut quicklook = Quicklook::new(config, every);
+    for event in sync {
+        quicklook.add(event?);
+    }
+    Ok(quicklook.into_summary())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen;
+    use crate::pipeline::IdleFilter;
+    use crate::sync::Synchronizer;
+
+    #[test]
+    fn test_quicklook() {
+        let config = Config {
+            interleave: 1,
+            ..Config::default()
+        };
+        let cadu_len = config.asm.len() + config.block_len();
+        let mut stream = gen::cadus(&config, &[1u8; 223 * 8], 0);
+        // a corrected error in CADU 0, decoded, and in CADU 1, checked
+        stream[10] ^= 0x01;
+        stream[cadu_len + 10] ^= 0x01;
+        // CADU 2 is uncorrectable, and CADU 3 has too many errors, but is only checked
+        for i in 0..20 {
+            stream[2 * cadu_len + 4 + i * 10] ^= 0xff;
+            stream[3 * cadu_len + 4 + i * 10] ^= 0xff;
+        }
+
+        let summary = quicklook(&stream[..], config.clone(), 2).unwrap();
+        assert_eq!(
+            summary,
+            QuicklookSummary {
+                cadus: 8,
+                decoded_frames: 4,
+                decoded_with_errors: 2,
+                uncorrectable_frames: 1,
+                summary: DecodeSummary {
+                    ok: 2,
+                    corrected: 1,
+                    uncorrectable: 1,
+                    corrected_symbols: 1,
+                    corrected_bits: 1,
+                    bytes: 4 * 255,
+                    ..Default::default()
+                },
+                checked: 4,
+                checked_with_errors: 2,
+                ..Default::default()
+            }
+        );
+        assert_eq!(summary.uncorrectable_ratio(), 0.25);
+        assert_eq!(summary.error_ratio(), 0.5);
+        assert!(summary.to_string().starts_with("cadus=8 decoded_frames=4 "));
+
+        let mut quicklook = Quicklook::new(config.clone(), 3).unsampled(Unsampled::Skip);
+        for event in Synchronizer::new(&stream[..], config.block_len()) {
+            quicklook.add(event.unwrap());
+        }
+        let summary = quicklook.summary();
+        assert_eq!(
+            (summary.decoded_frames, summary.checked, summary.skipped),
+            (3, 0, 5)
+        );
+        // CADU 3 is now decoded
+        assert_eq!(summary.uncorrectable_frames, 1);
+        assert_eq!(summary.decoded_with_errors, 2);
+
+        // idle CADUs are counted whether sampled or not
+        let idle = gen::cadus(&config, &[0x7f; 223 * 3], 0);
+        let config = Config {
+            idle: Some(IdleFilter::Vcid(63)),
+            ..config
+        };
+        let summary = super::quicklook(&idle[..], config, 2).unwrap();
+        assert_eq!((summary.cadus, summary.idle), (3, 3));
+        assert_eq!(summary.error_ratio(), 0.0);
+    }
+}
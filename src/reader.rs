@@ -0,0 +1,301 @@
+//! Adapters for correcting codeblocks read from any [Read] source, e.g., files or
+//! sockets.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use rs2::reader::RsReader;
+//!
+//! let file = File::open("codeblocks.dat").unwrap();
+//! for block in RsReader::new(file, 4) {
+//!     println!("{:?}", block.unwrap().states);
+//! }
+//! ```
+//!
+//! [CodeblockIter] instead yields a [Block] for each individual message:
+//!
+//! ```no_run
+//! use std::fs::File;
+//!
+//! let file = File::open("codewords.dat").unwrap();
+//! for block in rs2::codeblocks(file) {
+//!     println!("{:?}", block.unwrap().state);
+//! }
+//! ```
+use std::io::{Error, ErrorKind, Read, Result};
+
+use crate::{Block, Code, Codeblock, N};
+
+/// Read until `buf` is full or the stream ends, returning the number of bytes read
+/// which will only be less than the buffer length at the end of the stream.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(total)
+}
+
+pub(crate) fn partial_block(got: usize, want: usize) -> Error {
+    Error::new(
+        ErrorKind::UnexpectedEof,
+        format!("partial codeblock; expected {want} bytes, got {got}"),
+    )
+}
+
+/// Reads a stream of back-to-back codeblocks, without ASMs or randomization, and
+/// yields the corrected [Codeblock]s.
+///
+/// Short reads from the underlying reader are handled transparently. If the stream
+/// ends part way through a codeblock an [ErrorKind::UnexpectedEof] error is produced
+/// for the partial codeblock.
+pub struct RsReader<R> {
+    reader: R,
+    interleave: u8,
+    code: Code,
+    max_errors: usize,
+    done: bool,
+}
+
+impl<R: Read> RsReader<R> {
+    /// Create a reader for codeblocks of `interleave` messages using RS(255,223).
+    pub fn new(reader: R, interleave: u8) -> Self {
+        Self::with_code(reader, interleave, Code::E16)
+    }
+
+    pub fn with_code(reader: R, interleave: u8, code: Code) -> Self {
+        RsReader {
+            reader,
+            interleave,
+            code,
+            max_errors: usize::MAX,
+            done: false,
+        }
+    }
+
+    /// Maximum number of errors corrected in each message; see
+    /// [Code::correct_codeblock_with_max_errors]. Defaults to as many as the code can
+    /// correct.
+    pub fn max_errors(mut self, max: usize) -> Self {
+        self.max_errors = max;
+        self
+    }
+}
+
+impl<R: Read> Iterator for RsReader<R> {
+    type Item = Result<Codeblock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut buf = vec![0u8; self.interleave as usize * N as usize];
+        match read_full(&mut self.reader, &mut buf) {
+            Ok(n) if n == buf.len() => Some(Ok(self.code.correct_codeblock_with_max_errors(
+                &buf,
+                self.interleave,
+                self.max_errors,
+            ))),
+            Ok(0) => {
+                self.done = true;
+                None
+            }
+            Ok(n) => {
+                self.done = true;
+                Some(Err(partial_block(n, buf.len())))
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Lazily chunks a byte stream into codeblocks and yields a corrected [Block] for
+/// each message, in message order within each codeblock.
+///
+/// By default each codeblock is a single 255 byte message with nothing between
+/// codeblocks. Use [CodeblockIter::interleave] and [CodeblockIter::skip_asm] for
+/// interleaved codeblocks or CADUs. Sync markers are skipped without being checked,
+/// see [crate::sync] for streams that require synchronization.
+pub struct CodeblockIter<R> {
+    reader: R,
+    interleave: u8,
+    asm_len: usize,
+    code: Code,
+    blocks: std::vec::IntoIter<Block>,
+    done: bool,
+}
+
+impl<R: Read> CodeblockIter<R> {
+    pub fn new(reader: R) -> Self {
+        CodeblockIter {
+            reader,
+            interleave: 1,
+            asm_len: 0,
+            code: Code::E16,
+            blocks: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    /// Set the number of interleaved messages in each codeblock.
+    pub fn interleave(mut self, interleave: u8) -> Self {
+        self.interleave = interleave;
+        self
+    }
+
+    /// Skip `asm_len` bytes of sync marker preceding each codeblock.
+    pub fn skip_asm(mut self, asm_len: usize) -> Self {
+        self.asm_len = asm_len;
+        self
+    }
+
+    /// Set the RS code used to encode the codeblocks.
+    pub fn code(mut self, code: Code) -> Self {
+        self.code = code;
+        self
+    }
+
+    fn read_codeblock(&mut self) -> Option<Result<Vec<Block>>> {
+        let depth = self.interleave as usize;
+        let mut buf = vec![0u8; self.asm_len + depth * N as usize];
+        let n = match read_full(&mut self.reader, &mut buf) {
+            Ok(0) => return None,
+            Ok(n) => n,
+            Err(err) => return Some(Err(err)),
+        };
+        if n != buf.len() {
+            return Some(Err(partial_block(n, buf.len())));
+        }
+
+        let block = &buf[self.asm_len..];
+        let blocks = crate::interleave::deinterleave(block, self.interleave)
+            .map(|msg| self.code.correct_message(&msg.to_vec()))
+            .collect();
+        Some(Ok(blocks))
+    }
+}
+
+impl<R: Read> Iterator for CodeblockIter<R> {
+    type Item = Result<Block>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(block) = self.blocks.next() {
+            return Some(Ok(block));
+        }
+        if self.done {
+            return None;
+        }
+        if self.interleave == 0 {
+            self.done = true;
+            return Some(Err(Error::new(
+                ErrorKind::InvalidInput,
+                crate::UncorrectableReason::InvalidInterleave(0).to_string(),
+            )));
+        }
+        match self.read_codeblock() {
+            Some(Ok(blocks)) => {
+                self.blocks = blocks.into_iter();
+                self.blocks.next().map(Ok)
+            }
+            Some(Err(err)) => {
+                self.done = true;
+                Some(Err(err))
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{interleave, FIXTURE_MSG};
+    use crate::RSState;
+
+    // Reader returning at most 7 bytes per read
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+            let n = buf.len().min(7).min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_rsreader() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[5] = 0;
+        let mut input = interleave(&[FIXTURE_MSG, FIXTURE_MSG]);
+        input.extend(interleave(&[&corrupt, FIXTURE_MSG]));
+
+        let blocks = RsReader::new(Trickle(&input), 2)
+            .collect::<Result<Vec<Codeblock>>>()
+            .unwrap();
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].states, vec![RSState::Ok, RSState::Ok]);
+        assert_eq!(blocks[1].states, vec![RSState::Corrected(1), RSState::Ok]);
+        assert_eq!(blocks[1].message.as_ref().unwrap(), &input[..510]);
+    }
+
+    #[test]
+    fn test_rsreader_partial() {
+        let mut input = FIXTURE_MSG.to_vec();
+        input.extend(&FIXTURE_MSG[..100]);
+
+        let mut reader = RsReader::new(&input[..], 1);
+
+        assert_eq!(reader.next().unwrap().unwrap().states, vec![RSState::Ok]);
+        let err = reader.next().unwrap().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_codeblock_iter() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[9] ^= 0x0f;
+        let mut input = FIXTURE_MSG.to_vec();
+        input.extend(corrupt);
+
+        let blocks = crate::codeblocks(Trickle(&input))
+            .collect::<Result<Vec<Block>>>()
+            .unwrap();
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].state, RSState::Ok);
+        assert_eq!(blocks[1].state, RSState::Corrected(1));
+        assert_eq!(blocks[1].message.as_ref().unwrap(), &FIXTURE_MSG.to_vec());
+    }
+
+    #[test]
+    fn test_codeblock_iter_interleaved_asm() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[200] ^= 0xff;
+        let mut input = crate::sync::ASM.to_vec();
+        input.extend(interleave(&[FIXTURE_MSG, &corrupt]));
+        input.extend(crate::sync::ASM);
+        input.extend(&interleave(&[FIXTURE_MSG, FIXTURE_MSG])[..300]);
+
+        let mut blocks = CodeblockIter::new(&input[..]).interleave(2).skip_asm(4);
+
+        let states: Vec<RSState> = blocks.by_ref().take(2).map(|b| b.unwrap().state).collect();
+        assert_eq!(states, vec![RSState::Ok, RSState::Corrected(1)]);
+        let err = blocks.next().unwrap().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert!(blocks.next().is_none());
+    }
+}
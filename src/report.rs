@@ -0,0 +1,200 @@
+//! JSON-lines reports of per-codeblock decode results for QA tooling.
+//!
+//! Each line is a [BlockRecord] for one codeblock, e.g.,
+//!
+//! ```text
+//! {"offset":1024,"state":"corrected","corrected_symbols":3,"corrected_bits":4,"positions":[41,801,802],"longest_burst":2,"states":["ok","corrected","corrected","ok"],"reason":null,"fecf_ok":null}
+//! ```
+//!
+//! ```
+//! use rs2::pipeline::{Config, Pipeline};
+//! use rs2::report::JsonLinesReport;
+//!
+//! let stream = rs2::gen::cadus(&Config::default(), b"hello world", 0);
+//! let mut report = JsonLinesReport::new(Vec::new());
+//! for event in Pipeline::new(&stream[..], Config::default()) {
+//!     report.write_event(&event.unwrap()).unwrap();
+//! }
+//! let lines = String::from_utf8(report.into_inner()).unwrap();
+//! assert!(lines.starts_with(r#"{"offset":0,"state":"ok","#));
+//! ```
+use std::io::{Result, Write};
+
+use serde::Serialize;
+
+use crate::pipeline::{Event, Frame};
+use crate::stats::BurstStats;
+use crate::{Codeblock, RSState};
+
+/// Summary of the decode of a single codeblock.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+pub struct BlockRecord {
+    /// Byte offset of the codeblock, or its CADU, in the input stream.
+    pub offset: u64,
+    /// Overall state: `uncorrectable` if any message was uncorrectable, otherwise
+    /// `corrected` if any message was corrected, otherwise `ok` or `not_performed`.
+    pub state: &'static str,
+    /// Total number of symbols corrected across all messages.
+    pub corrected_symbols: u64,
+    /// Total number of bits corrected across all messages.
+    pub corrected_bits: u64,
+    /// Codeblock offsets of the corrected symbols.
+    pub positions: Vec<usize>,
+    /// Longest run of consecutive corrected symbols; see [BurstStats].
+    pub longest_burst: usize,
+    /// State of each interleaved message.
+    pub states: Vec<&'static str>,
+    /// Reason the first uncorrectable message could not be corrected.
+    pub reason: Option<String>,
+    /// Result of verifying the FECF, if performed.
+    pub fecf_ok: Option<bool>,
+}
+
+fn state_name(state: &RSState) -> &'static str {
+    match state {
+        RSState::Ok => "ok",
+        RSState::Corrected(_) => "corrected",
+        RSState::Uncorrectable(_) => "uncorrectable",
+        RSState::NotPerformed => "not_performed",
+    }
+}
+
+impl BlockRecord {
+    /// Create the record for a codeblock at `offset` with the provided message states,
+    /// corrected symbol positions, and number of corrected bits.
+    pub fn new(offset: u64, states: &[RSState], positions: &[usize], corrected_bits: u64) -> Self {
+        let state = ["uncorrectable", "corrected", "not_performed"]
+            .into_iter()
+            .find(|name| states.iter().any(|s| state_name(s) == *name))
+            .unwrap_or("ok");
+        let corrected_symbols = states
+            .iter()
+            .map(|s| match s {
+                RSState::Corrected(n) => *n as u64,
+                _ => 0,
+            })
+            .sum();
+        let reason = states.iter().find_map(|s| match s {
+            RSState::Uncorrectable(reason) => Some(reason.to_string()),
+            _ => None,
+        });
+        BlockRecord {
+            offset,
+            state,
+            corrected_symbols,
+            corrected_bits,
+            positions: positions.to_vec(),
+            longest_burst: BurstStats::from_positions(positions).longest_run,
+            states: states.iter().map(state_name).collect(),
+            reason,
+            fecf_ok: None,
+        }
+    }
+
+    /// Create the record for a codeblock decoded at `offset`, e.g., by a
+    /// [crate::reader::RsReader].
+    pub fn from_codeblock(offset: u64, codeblock: &Codeblock) -> Self {
+        Self::new(
+            offset,
+            &codeblock.states,
+            &codeblock.error_positions,
+            codeblock.corrected_bits,
+        )
+    }
+}
+
+impl From<&Frame> for BlockRecord {
+    fn from(frame: &Frame) -> Self {
+        BlockRecord {
+            fecf_ok: frame.fecf_ok,
+            ..Self::new(
+                frame.offset,
+                &frame.states,
+                &frame.error_positions,
+                frame.corrected_bits,
+            )
+        }
+    }
+}
+
+/// Writes one JSON object per line for each codeblock.
+pub struct JsonLinesReport<W> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesReport<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesReport { writer }
+    }
+
+    /// Write a single record.
+    pub fn write(&mut self, record: &BlockRecord) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Write the record for a frame event. Other events are ignored.
+    pub fn write_event(&mut self, event: &Event) -> Result<()> {
+        match event {
+            Event::Frame(frame) => self.write(&frame.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UncorrectableReason;
+
+    #[test]
+    fn test_report() {
+        let frame = Frame {
+            offset: 1024,
+            data: Some(vec![]),
+            states: vec![RSState::Ok, RSState::Corrected(2), RSState::Corrected(1)],
+            error_positions: vec![5, 6, 42],
+            corrected_bits: 6,
+            fecf_ok: Some(true),
+            inverted: false,
+            idle: false,
+        };
+        let block = Codeblock {
+            states: vec![
+                RSState::Corrected(1),
+                RSState::Uncorrectable(UncorrectableReason::ResidualSyndrome),
+            ],
+            message: None,
+            error_positions: vec![6],
+            corrected_bits: 1,
+        };
+        let mut report = JsonLinesReport::new(Vec::new());
+
+        report.write_event(&Event::Skipped(0..1024)).unwrap();
+        report.write_event(&Event::Frame(frame)).unwrap();
+        report
+            .write(&BlockRecord::from_codeblock(2048, &block))
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(report.into_inner()).unwrap(),
+            concat!(
+                r#"{"offset":1024,"state":"corrected","corrected_symbols":3,"corrected_bits":6,"positions":[5,6,42],"longest_burst":2,"#,
+                r#""states":["ok","corrected","corrected"],"reason":null,"fecf_ok":true}"#,
+                "\n",
+                r#"{"offset":2048,"state":"uncorrectable","corrected_symbols":1,"corrected_bits":1,"positions":[6],"longest_burst":1,"#,
+                r#""states":["corrected","uncorrectable"],"reason":"failed to correct all errors","fecf_ok":null}"#,
+                "\n",
+            )
+        );
+    }
+}
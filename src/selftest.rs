@@ -0,0 +1,159 @@
+//! Power-on self-test of the field tables and decoder against known vectors.
+//!
+//! Operational deployments can call [run], or [crate::selftest()], before processing
+//! data to check that the tables and arithmetic are intact, e.g., after loading onto
+//! a flight or ground processor. It does not allocate, so it is available without
+//! `std`.
+//!
+//! ```
+//! rs2::selftest().expect("RS self-test failed");
+//! ```
+use crate::{dual_basis, gf, Code, RSState, N};
+
+/// A CCSDS RS(255,223) message in dual basis, without pseudo-randomization.
+pub(crate) const VECTOR: [u8; N as usize] = [
+    0x67, 0xc4, 0x6b, 0xa7, 0x3e, 0xbe, 0x4c, 0x33, 0x6c, 0xb2, 0x23, 0x3a, 0x74, 0x06, 0x2b, 0x18,
+    0xab, 0xb8, 0x09, 0xe6, 0x7d, 0xaf, 0x5d, 0xe5, 0xdf, 0x76, 0x25, 0x3f, 0xb9, 0x14, 0xee, 0xec,
+    0xd1, 0xa3, 0x39, 0x5f, 0x38, 0x68, 0xf0, 0x26, 0xa6, 0x8a, 0xcb, 0x09, 0xaf, 0x4e, 0xf8, 0x93,
+    0xf7, 0x45, 0x4b, 0x0d, 0xa9, 0xb8, 0x74, 0x0e, 0xf3, 0xc7, 0xed, 0x6e, 0xa3, 0x0f, 0xf6, 0x79,
+    0x94, 0x16, 0xe2, 0x7f, 0xad, 0x91, 0x91, 0x04, 0xac, 0xa4, 0xae, 0xb4, 0x51, 0x76, 0x2f, 0x62,
+    0x03, 0x5e, 0xa1, 0xe5, 0x5c, 0x45, 0xf8, 0x1f, 0x7a, 0x7b, 0xe8, 0x35, 0xd8, 0xcc, 0x51, 0x0e,
+    0xae, 0x3a, 0x2a, 0x64, 0x1d, 0x03, 0x10, 0xcd, 0x18, 0xe6, 0x7f, 0xef, 0xba, 0xd9, 0xe8, 0x98,
+    0x47, 0x82, 0x9c, 0xa1, 0x58, 0x47, 0x25, 0xdf, 0x41, 0xd2, 0x01, 0x62, 0x3c, 0x24, 0x88, 0x90,
+    0xe9, 0xd7, 0x38, 0x1b, 0xa0, 0xa2, 0xb4, 0x23, 0xea, 0x7e, 0x58, 0x0d, 0xf4, 0x61, 0x24, 0x14,
+    0xb0, 0x41, 0x90, 0x0c, 0xb7, 0xbb, 0x5c, 0x59, 0x1b, 0xc6, 0x69, 0x24, 0x0f, 0xb6, 0x0e, 0x14,
+    0xa1, 0xb1, 0x8e, 0x48, 0x0f, 0x17, 0x1d, 0xfb, 0x0f, 0x38, 0x42, 0xe3, 0x24, 0x58, 0xab, 0x82,
+    0xa8, 0xfd, 0xdf, 0xac, 0x68, 0x93, 0x3d, 0x0d, 0x8f, 0x50, 0x52, 0x44, 0x6c, 0xba, 0xd3, 0x51,
+    0x99, 0x9c, 0x3e, 0xad, 0xd5, 0xa8, 0xd7, 0x9d, 0xc7, 0x7f, 0x9f, 0xc9, 0x2a, 0xac, 0xe5, 0xc2,
+    0xcd, 0x9a, 0x9b, 0xfa, 0x2d, 0x72, 0xab, 0x6b, 0xa4, 0x6b, 0x8b, 0x7d, 0xfa, 0x6c, 0x83, 0x63,
+    0x77, 0x9f, 0x4e, 0x9a, 0x20, 0x35, 0xd2, 0x91, 0xce, 0xf4, 0x21, 0x1a, 0x97, 0x3c, 0x1a, 0x15,
+    0x9d, 0xfc, 0x98, 0xba, 0x72, 0x1b, 0x9a, 0xa2, 0xe9, 0xc9, 0x46, 0x68, 0xce, 0xad, 0x27,
+];
+
+/// The first check that failed.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SelfTestError {
+    /// `x` times its inverse is not 1, or the power and antilog tables disagree at `x`.
+    Field { x: u8 },
+    /// `x` does not round trip through the conventional and dual basis tables.
+    DualBasis { x: u8 },
+    /// Encoding the data of the known vector gave the wrong check symbol at `index`.
+    Encode { code: Code, index: usize },
+    /// Correcting the known vector with the maximum number of errors did not correct
+    /// them all, with the resulting state.
+    Correct { code: Code, state: RSState },
+    /// Correcting the known vector reported success but symbol `index` is wrong.
+    Miscorrected { code: Code, index: usize },
+}
+
+impl core::fmt::Display for SelfTestError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            SelfTestError::Field { x } => write!(f, "field arithmetic failed for {x:#04x}"),
+            SelfTestError::DualBasis { x } => {
+                write!(f, "dual basis conversion failed for {x:#04x}")
+            }
+            SelfTestError::Encode { code, index } => {
+                write!(f, "{code:?} encoding wrong at symbol {index}")
+            }
+            SelfTestError::Correct { code, state } => {
+                write!(f, "{code:?} correction failed with state {state:?}")
+            }
+            SelfTestError::Miscorrected { code, index } => {
+                write!(f, "{code:?} correction wrong at symbol {index}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SelfTestError {}
+
+fn check_field() -> Result<(), SelfTestError> {
+    for x in 1..=255u8 {
+        let ok = gf::mult(x, gf::inv(x)) == 1
+            && gf::div(x, x) == 1
+            && gf::pow(gf::exp(1), x as i32) == gf::exp(x as i32);
+        if !ok {
+            return Err(SelfTestError::Field { x });
+        }
+    }
+    for x in 0..=255u8 {
+        let mut y = [x];
+        dual_basis::to_conv_in_place(&mut y);
+        dual_basis::to_dual_in_place(&mut y);
+        if y[0] != x {
+            return Err(SelfTestError::DualBasis { x });
+        }
+    }
+    Ok(())
+}
+
+/// Encode the data of `vector`, compare the check symbols, then add the maximum
+/// number of errors and correct them.
+fn check_code(code: Code, vector: &[u8; N as usize]) -> Result<(), SelfTestError> {
+    let mut msg = *vector;
+    msg[code.data_len()..].fill(0);
+    code.encode_message_in_place(&mut msg);
+    if let Some(index) = (0..N as usize).find(|i| msg[*i] != vector[*i]) {
+        return Err(SelfTestError::Encode { code, index });
+    }
+
+    // spread across the data and check symbols
+    let step = N as usize / code.max_errors();
+    for i in 0..code.max_errors() {
+        msg[i * step + 3] ^= (i as u8).wrapping_mul(37) | 1;
+    }
+    let state = code.correct_message_in_place(&mut msg);
+    if state != RSState::Corrected(code.max_errors() as i32) {
+        return Err(SelfTestError::Correct { code, state });
+    }
+    if let Some(index) = (0..N as usize).find(|i| msg[*i] != vector[*i]) {
+        return Err(SelfTestError::Miscorrected { code, index });
+    }
+    Ok(())
+}
+
+/// Check the field and dual basis tables, and encode and correct the known vectors
+/// for both codes.
+pub fn run() -> Result<(), SelfTestError> {
+    check_field()?;
+    check_code(Code::E16, &VECTOR)?;
+    // there is no known RS(255,239) vector, so its check symbols are computed here and
+    // only the correction is checked against them
+    let mut e8 = VECTOR;
+    e8[Code::E8.data_len()..].fill(0);
+    Code::E8.encode_message_in_place(&mut e8);
+    check_code(Code::E8, &e8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selftest() {
+        assert_eq!(run(), Ok(()));
+        assert_eq!(crate::selftest(), Ok(()));
+    }
+
+    #[test]
+    fn test_check_code() {
+        let mut vector = VECTOR;
+        vector[254] ^= 1;
+        assert_eq!(
+            check_code(Code::E16, &vector),
+            Err(SelfTestError::Encode {
+                code: Code::E16,
+                index: 254
+            })
+        );
+        assert_eq!(
+            SelfTestError::Encode {
+                code: Code::E16,
+                index: 254
+            }
+            .to_string(),
+            "E16 encoding wrong at symbol 254"
+        );
+    }
+}
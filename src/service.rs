@@ -0,0 +1,206 @@
+//! Long-running decode service accepting CADU byte streams over TCP.
+//!
+//! Each accepted connection is run through a [Pipeline] until the peer disconnects,
+//! and the decoded frames are forwarded to a [FrameSink], after which the next
+//! connection is accepted. Connections are handled one at a time, as is typical for a
+//! single downlink feed.
+//!
+//! ```no_run
+//! use rs2::pipeline::Config;
+//! use rs2::service::{Service, WriteSink};
+//!
+//! let service = Service::bind("0.0.0.0:5000", Config::default()).unwrap();
+//! let mut sink = WriteSink::new(std::io::stdout());
+//! service.run(&mut sink).unwrap();
+//! ```
+use std::io::{Result, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::pipeline::{Config, Event, Frame, Pipeline};
+use crate::stats::DecodeSummary;
+
+/// Destination for the frames decoded by a [Service].
+///
+/// Any `FnMut(&Frame) -> Result<()>` is a sink.
+pub trait FrameSink {
+    fn send(&mut self, frame: &Frame) -> Result<()>;
+
+    /// Called when a connection ends so buffered frames are delivered promptly.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&Frame) -> Result<()>> FrameSink for F {
+    fn send(&mut self, frame: &Frame) -> Result<()> {
+        self(frame)
+    }
+}
+
+/// Writes the data of each correctable frame to a writer, e.g., a file or a
+/// connection to a downstream processor. Uncorrectable frames are dropped.
+pub struct WriteSink<W> {
+    writer: W,
+}
+
+impl<W: Write> WriteSink<W> {
+    pub fn new(writer: W) -> Self {
+        WriteSink { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> FrameSink for WriteSink<W> {
+    fn send(&mut self, frame: &Frame) -> Result<()> {
+        match &frame.data {
+            Some(data) => self.writer.write_all(data),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Results for a single connection.
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    /// Number of CADUs decoded.
+    pub frames: u64,
+    /// Number of input bytes that did not belong to a CADU.
+    pub skipped_bytes: u64,
+    /// Results for each RS message.
+    pub summary: DecodeSummary,
+    /// Error reading from the connection, e.g., a reset by the peer, which ended the
+    /// connection. None if the peer closed the connection.
+    pub error: Option<std::io::Error>,
+}
+
+/// Accepts TCP connections carrying CADU byte streams and decodes them.
+pub struct Service {
+    listener: TcpListener,
+    config: Config,
+}
+
+impl Service {
+    pub fn new(listener: TcpListener, config: Config) -> Self {
+        Service { listener, config }
+    }
+
+    /// Create a service listening on `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A, config: Config) -> Result<Self> {
+        Ok(Self::new(TcpListener::bind(addr)?, config))
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Wait for the next connection.
+    pub fn accept(&self) -> Result<(TcpStream, SocketAddr)> {
+        self.listener.accept()
+    }
+
+    /// Decode a connection until the peer closes it, sending each frame to `sink`.
+    ///
+    /// Errors reading from the connection end it and are reported in the
+    /// [ConnectionStats], so only errors from the sink are returned.
+    pub fn handle<S: FrameSink + ?Sized>(
+        &self,
+        stream: TcpStream,
+        sink: &mut S,
+    ) -> Result<ConnectionStats> {
+        let mut stats = ConnectionStats::default();
+        for event in Pipeline::new(stream, self.config.clone()) {
+            match event {
+                Ok(Event::Frame(frame)) => {
+                    stats.frames += 1;
+                    stats.summary.add_frame(&frame);
+                    sink.send(&frame)?;
+                }
+                Ok(Event::Skipped(range)) => stats.skipped_bytes += range.end - range.start,
+                Ok(Event::Locked(_) | Event::Unlocked(_)) => {}
+                Err(err) => {
+                    stats.error = Some(err);
+                    break;
+                }
+            }
+        }
+        sink.flush()?;
+        Ok(stats)
+    }
+
+    /// Accept and decode connections, one at a time, until accepting a connection or
+    /// the sink fails.
+    pub fn run<S: FrameSink + ?Sized>(&self, sink: &mut S) -> Result<()> {
+        loop {
+            let (stream, _) = self.accept()?;
+            self.handle(stream, sink)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_service() {
+        let config = Config {
+            interleave: 1,
+            ..Default::default()
+        };
+        let service = Service::bind("127.0.0.1:0", config.clone()).unwrap();
+        let addr = service.local_addr().unwrap();
+        let payload: Vec<u8> = (0..=255).collect();
+        let mut stream = vec![0x55u8; 5];
+        stream.extend(gen::cadus(&config, &payload, 0));
+        let client = std::thread::spawn(move || {
+            for _ in 0..2 {
+                let mut conn = TcpStream::connect(addr).unwrap();
+                // split writes across CADU boundaries
+                for chunk in stream.chunks(100) {
+                    conn.write_all(chunk).unwrap();
+                }
+            }
+        });
+
+        let mut sink = WriteSink::new(Vec::new());
+        for _ in 0..2 {
+            let (conn, _) = service.accept().unwrap();
+            let stats = service.handle(conn, &mut sink).unwrap();
+            assert_eq!(stats.frames, 2);
+            assert_eq!(stats.skipped_bytes, 5);
+            assert_eq!(stats.summary.ok, 2);
+            assert!(stats.error.is_none());
+        }
+        client.join().unwrap();
+
+        let output = sink.into_inner();
+        assert_eq!(output.len(), 4 * 223);
+        assert_eq!(&output[..256], &payload[..]);
+        assert_eq!(&output[446..702], &payload[..]);
+
+        // sink errors are returned
+        let mut failing = |_: &Frame| Err(std::io::Error::other("sink closed"));
+        std::thread::spawn(move || {
+            TcpStream::connect(addr)
+                .unwrap()
+                .write_all(&gen::cadus(&config, b"x", 0))
+                .unwrap();
+        });
+        let (conn, _) = service.accept().unwrap();
+        let err = service.handle(conn, &mut failing).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Other);
+    }
+}
@@ -0,0 +1,350 @@
+//! Pipeline and decoder settings loaded from TOML, so deployments can be
+//! reconfigured without recompiling.
+//!
+//! All keys are optional. Unset keys take their values from the `preset`, if any, or
+//! otherwise from [Config::default].
+//!
+//! ```toml
+//! preset = "snpp"            # see rs2::presets
+//! interleave = 4
+//! code = "e16"               # or "e8"
+//! frame_len = 892            # checked against interleave and code
+//! asm = "1acffc1d"
+//! asm_threshold = 0
+//! derandomize = true
+//! randomizer = "pn255"       # or "pn131071"
+//! fecf = false
+//! max_errors = 10            # default is as many as the code can correct
+//! skip_idle = false          # skip decoding virtual channel 63 idle frames
+//! best_effort = false        # output frames with uncorrectable messages as received
+//! basis = "dual"             # output basis, or "conventional"
+//!
+//! [lock]                     # omit to disable lock tracking
+//! check = 2
+//! flywheel = 3
+//!
+//! [output]
+//! path = "frames.dat"
+//! strip_parity = true
+//! ```
+//!
+//! ```
+//! use rs2::settings::Settings;
+//!
+//! let settings = Settings::from_toml("interleave = 2\n[output]\nstrip_parity = true").unwrap();
+//! assert_eq!(settings.pipeline.interleave, 2);
+//! assert!(settings.output.strip_parity);
+//! ```
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::decoder::{Basis, RsDecoder};
+use crate::pipeline::{Config, IdleFilter};
+use crate::presets::Preset;
+use crate::sync::LockConfig;
+use crate::{pn, Code, MAX_INTERLEAVE};
+
+/// Error loading [Settings].
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not valid TOML or has keys of the wrong type or unknown keys.
+    Parse(toml::de::Error),
+    /// A value is not valid, e.g., an unknown preset or an interleave out of range.
+    Invalid(String),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read settings: {err}"),
+            Self::Parse(err) => write!(f, "failed to parse settings: {err}"),
+            Self::Invalid(msg) => write!(f, "invalid settings: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Invalid(_) => None,
+        }
+    }
+}
+
+/// Where and how decoded data is written.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct OutputSettings {
+    /// File to write decoded data to.
+    pub path: Option<PathBuf>,
+    /// Whether the check symbols are removed from the output.
+    pub strip_parity: bool,
+}
+
+/// Settings loaded from TOML.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Settings {
+    pub pipeline: Config,
+    /// Representation of the output symbols.
+    pub basis: Basis,
+    pub output: OutputSettings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLock {
+    check: Option<usize>,
+    flywheel: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOutput {
+    path: Option<PathBuf>,
+    strip_parity: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSettings {
+    preset: Option<String>,
+    interleave: Option<u8>,
+    code: Option<String>,
+    frame_len: Option<usize>,
+    asm: Option<String>,
+    asm_threshold: Option<u32>,
+    derandomize: Option<bool>,
+    randomizer: Option<String>,
+    fecf: Option<bool>,
+    max_errors: Option<usize>,
+    skip_idle: Option<bool>,
+    best_effort: Option<bool>,
+    basis: Option<String>,
+    lock: Option<RawLock>,
+    #[serde(default)]
+    output: RawOutput,
+}
+
+fn invalid(msg: String) -> SettingsError {
+    SettingsError::Invalid(msg)
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    // from_str_radix also accepts a sign
+    if s.is_empty() || !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+impl Settings {
+    /// Parse settings from a TOML document.
+    pub fn from_toml(s: &str) -> Result<Settings, SettingsError> {
+        let raw: RawSettings = toml::from_str(s).map_err(SettingsError::Parse)?;
+
+        let mut config = match raw.preset {
+            Some(name) => Preset::from_name(&name)
+                .ok_or_else(|| invalid(format!("unknown preset {name:?}")))?
+                .config(),
+            None => Config::default(),
+        };
+        if let Some(interleave) = raw.interleave {
+            if !(1..=MAX_INTERLEAVE).contains(&interleave) {
+                return Err(invalid(format!(
+                    "interleave must be 1 to {MAX_INTERLEAVE}, got {interleave}"
+                )));
+            }
+            config.interleave = interleave;
+        }
+        if let Some(code) = raw.code {
+            config.code = match code.to_ascii_lowercase().as_str() {
+                "e16" => Code::E16,
+                "e8" => Code::E8,
+                _ => return Err(invalid(format!("unknown code {code:?}"))),
+            };
+        }
+        if let Some(asm) = raw.asm {
+            config.asm = parse_hex(&asm).ok_or_else(|| invalid(format!("invalid asm {asm:?}")))?;
+        }
+        if let Some(threshold) = raw.asm_threshold {
+            config.asm_threshold = threshold;
+        }
+        if let Some(derandomize) = raw.derandomize {
+            config.derandomize = derandomize;
+        }
+        if let Some(randomizer) = raw.randomizer {
+            config.randomizer = match randomizer.to_ascii_lowercase().as_str() {
+                "pn255" => pn::Sequence::Pn255,
+                "pn131071" => pn::Sequence::Pn131071,
+                _ => return Err(invalid(format!("unknown randomizer {randomizer:?}"))),
+            };
+        }
+        if let Some(fecf) = raw.fecf {
+            config.fecf = fecf;
+        }
+        if raw.max_errors.is_some() {
+            config.max_errors = raw.max_errors;
+        }
+        if let Some(skip_idle) = raw.skip_idle {
+            config.idle = skip_idle.then_some(IdleFilter::Vcid(63));
+        }
+        if let Some(best_effort) = raw.best_effort {
+            config.best_effort = best_effort;
+        }
+        if let Some(lock) = raw.lock {
+            let default = LockConfig::default();
+            config.lock = Some(LockConfig {
+                check: lock.check.unwrap_or(default.check),
+                flywheel: lock.flywheel.unwrap_or(default.flywheel),
+            });
+        }
+        if let Some(frame_len) = raw.frame_len {
+            if frame_len != config.frame_len() {
+                return Err(invalid(format!(
+                    "frame_len {frame_len} does not match interleave {} and code, which give {}",
+                    config.interleave,
+                    config.frame_len()
+                )));
+            }
+        }
+        let basis = match raw.basis.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("dual") => Basis::Dual,
+            Some("conventional") => Basis::Conventional,
+            Some(basis) => return Err(invalid(format!("unknown basis {basis:?}"))),
+        };
+
+        Ok(Settings {
+            pipeline: config,
+            basis,
+            output: OutputSettings {
+                path: raw.output.path,
+                strip_parity: raw.output.strip_parity.unwrap_or(false),
+            },
+        })
+    }
+
+    /// Read and parse settings from the TOML file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Settings, SettingsError> {
+        let s = std::fs::read_to_string(path).map_err(SettingsError::Io)?;
+        Self::from_toml(&s)
+    }
+
+    /// Decoder for the codeblocks described by these settings.
+    pub fn decoder(&self) -> RsDecoder {
+        let mut builder = RsDecoder::builder()
+            .code(self.pipeline.code)
+            .interleave(self.pipeline.interleave)
+            .output_basis(self.basis)
+            .strip_parity(self.output.strip_parity);
+        if let Some(max) = self.pipeline.max_errors {
+            builder = builder.max_errors(max);
+        }
+        builder
+            .build()
+            .expect("interleave is validated when parsing")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let settings = Settings::from_toml(
+            r#"
+            preset = "metop"
+            interleave = 2
+            code = "E8"
+            frame_len = 478
+            asm = "0x1acffc1d"
+            asm_threshold = 3
+            randomizer = "pn131071"
+            fecf = true
+            max_errors = 10
+            skip_idle = true
+            best_effort = true
+            basis = "conventional"
+
+            [lock]
+            flywheel = 5
+
+            [output]
+            path = "out.dat"
+            strip_parity = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings,
+            Settings {
+                pipeline: Config {
+                    interleave: 2,
+                    code: Code::E8,
+                    asm_threshold: 3,
+                    randomizer: pn::Sequence::Pn131071,
+                    fecf: true,
+                    lock: Some(LockConfig {
+                        check: 2,
+                        flywheel: 5
+                    }),
+                    max_errors: Some(10),
+                    idle: Some(IdleFilter::Vcid(63)),
+                    best_effort: true,
+                    ..Preset::Metop.config()
+                },
+                basis: Basis::Conventional,
+                output: OutputSettings {
+                    path: Some("out.dat".into()),
+                    strip_parity: true,
+                },
+            }
+        );
+        let block = settings.decoder().decode(&[0u8; 510]);
+        assert_eq!(block.message.unwrap().len(), 478);
+
+        let settings = Settings::from_toml("").unwrap();
+        assert_eq!(settings.pipeline, Config::default());
+        assert_eq!(settings.basis, Basis::Dual);
+        assert_eq!(settings.output, OutputSettings::default());
+    }
+
+    #[test]
+    fn test_from_toml_invalid() {
+        for (doc, msg) in [
+            ("interleave = 9", "interleave must be 1 to 8, got 9"),
+            ("preset = \"goes\"", "unknown preset \"goes\""),
+            ("asm = \"1acffc1\"", "invalid asm \"1acffc1\""),
+            ("asm = \"+1+2\"", "invalid asm \"+1+2\""),
+            (
+                "frame_len = 1000",
+                "frame_len 1000 does not match interleave 4 and code, which give 892",
+            ),
+            ("basis = \"other\"", "unknown basis \"other\""),
+        ] {
+            match Settings::from_toml(doc) {
+                Err(SettingsError::Invalid(got)) => assert_eq!(got, msg),
+                other => panic!("{doc}: {other:?}"),
+            }
+        }
+        assert!(matches!(
+            Settings::from_toml("interleaved = 4"),
+            Err(SettingsError::Parse(_))
+        ));
+        assert!(matches!(
+            Settings::load("/nonexistent/rs2.toml"),
+            Err(SettingsError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+//! Shortened codes with a message length fixed at compile time.
+//!
+//! Missions using shortened codeblocks, where the first symbols of each message are a
+//! virtual fill of zeros that is not transmitted, usually have a single fixed message
+//! length. [Shortened] takes that length as a const parameter so buffers are arrays of
+//! the transmitted length and the virtual fill is added and removed on the stack.
+//!
+//! ```
+//! use rs2::shortened::Shortened;
+//! use rs2::{Code, RSState};
+//!
+//! // 200 transmitted symbols, i.e., 55 symbols of virtual fill
+//! const RS: Shortened<200> = Shortened::new(Code::E16);
+//!
+//! let mut msg = [0u8; 200];
+//! msg[..168].copy_from_slice(&[0x42; 168]);
+//! RS.encode_in_place(&mut msg);
+//! msg[7] ^= 0xff;
+//! assert_eq!(RS.correct_in_place(&mut msg), RSState::Corrected(1));
+//! assert_eq!(msg[7], 0x42);
+//! ```
+use crate::{Code, RSState, UncorrectableReason, N};
+
+/// A [Code] shortened to messages of `LEN` transmitted symbols, including the check
+/// symbols.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Shortened<const LEN: usize> {
+    code: Code,
+}
+
+impl<const LEN: usize> Shortened<LEN> {
+    /// Number of leading zero symbols that are not transmitted.
+    pub const VIRTUAL_FILL: usize = N as usize - LEN;
+
+    /// Create the shortened code.
+    ///
+    /// # Panics
+    /// If `LEN` is more than 255 or not more than the number of check symbols of
+    /// `code`. In a const context, as in the module example, this is a compile error.
+    pub const fn new(code: Code) -> Self {
+        assert!(
+            LEN <= N as usize,
+            "shortened message longer than 255 symbols"
+        );
+        assert!(
+            LEN > code.parity_len(),
+            "shortened message has no data symbols"
+        );
+        Shortened { code }
+    }
+
+    pub const fn code(&self) -> Code {
+        self.code
+    }
+
+    /// Number of data symbols in each transmitted message.
+    pub const fn data_len(&self) -> usize {
+        LEN - self.code.parity_len()
+    }
+
+    fn padded(msg: &[u8; LEN]) -> [u8; N as usize] {
+        let mut full = [0u8; N as usize];
+        full[Self::VIRTUAL_FILL..].copy_from_slice(msg);
+        full
+    }
+
+    /// Compute the check symbols for the first [Shortened::data_len] symbols of `msg`
+    /// and write them to the rest of `msg`.
+    pub fn encode_in_place(&self, msg: &mut [u8; LEN]) {
+        let mut full = Self::padded(msg);
+        self.code.encode_message_in_place(&mut full);
+        msg.copy_from_slice(&full[Self::VIRTUAL_FILL..]);
+    }
+
+    /// Correct a message in place. Like [crate::Code::correct_message_in_place], `msg`
+    /// is only modified if it is corrected.
+    ///
+    /// A correction located in the virtual fill results in
+    /// [UncorrectableReason::ErrorInVirtualFill]; see [crate::correct_shortened].
+    pub fn correct_in_place(&self, msg: &mut [u8; LEN]) -> RSState {
+        let mut full = Self::padded(msg);
+        let state = self.code.correct_message_in_place(&mut full);
+        if let RSState::Corrected(_) = state {
+            if full[..Self::VIRTUAL_FILL].iter().any(|x| *x != 0) {
+                return RSState::Uncorrectable(UncorrectableReason::ErrorInVirtualFill);
+            }
+            msg.copy_from_slice(&full[Self::VIRTUAL_FILL..]);
+        }
+        state
+    }
+
+    /// Return true if `msg` contains 1 or more errors.
+    pub fn has_errors(&self, msg: &[u8; LEN]) -> bool {
+        self.code.has_errors(&Self::padded(msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::shifted_generator;
+
+    #[test]
+    fn test_shortened() {
+        const RS: Shortened<100> = Shortened::new(Code::E8);
+        assert_eq!(Shortened::<100>::VIRTUAL_FILL, 155);
+        assert_eq!(RS.data_len(), 84);
+
+        let expected: [u8; 100] = shifted_generator(Code::E8, 0)[155..].try_into().unwrap();
+        let mut msg = expected;
+        msg[84..].fill(0);
+        RS.encode_in_place(&mut msg);
+        assert_eq!(msg, expected);
+        assert!(!RS.has_errors(&msg));
+
+        for i in [0, 50, 99] {
+            msg[i] ^= 0x11;
+        }
+        assert!(RS.has_errors(&msg));
+        assert_eq!(RS.correct_in_place(&mut msg), RSState::Corrected(3));
+        assert_eq!(msg, expected);
+    }
+
+    #[test]
+    fn test_shortened_error_in_fill() {
+        // see crate::tests::test_correct_shortened_error_in_fill
+        let rs = Shortened::<132>::new(Code::E16);
+        let mut msg: [u8; 132] = shifted_generator(Code::E16, 100)[123..].try_into().unwrap();
+        let before = msg;
+        assert_eq!(
+            rs.correct_in_place(&mut msg),
+            RSState::Uncorrectable(UncorrectableReason::ErrorInVirtualFill)
+        );
+        assert_eq!(msg, before);
+    }
+
+    #[test]
+    #[should_panic(expected = "no data symbols")]
+    fn test_shortened_too_short() {
+        Shortened::<32>::new(Code::E16);
+    }
+}
@@ -0,0 +1,364 @@
+//! Reproducible error injection for characterizing decoder behavior.
+//!
+//! An [Injector] corrupts codeblocks, CADUs or any other buffer using a seeded
+//! pseudo-random generator, so the same seed always produces the same errors.
+//!
+//! ```
+//! use rs2::sim::Injector;
+//!
+//! let mut msg = rs2::encode_message(&[0u8; 223]).unwrap();
+//! let mut injector = Injector::new(42);
+//! injector.symbol_errors(&mut msg, 16);
+//! assert_eq!(rs2::correct_message(&msg).state, rs2::RSState::Corrected(16));
+//! ```
+//!
+//! A [Simulation] sweeps the bit error probability of a binary symmetric channel and
+//! reports the frame error rate of the decoder at each point:
+//!
+//! ```
+//! use rs2::sim::Simulation;
+//! use rs2::Code;
+//!
+//! let points = Simulation::new(Code::E16, 4).frames(20).sweep(&[1e-3, 1e-2]);
+//! assert_eq!(points[0].frames, 20);
+//! assert!(points[0].fer() <= points[1].fer());
+//! ```
+use crate::pipeline::Config;
+use crate::{gen, Code};
+
+/// Seedable pseudo-random number generator (splitmix64).
+///
+/// Not suitable for anything but simulation.
+#[derive(Debug, Clone)]
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..n`, where `n` must not be zero.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        // The bias for the small ranges used here is negligible.
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Uniform value in `[0, 1)`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform non-zero byte, i.e., an error value that always changes a symbol.
+    pub(crate) fn nonzero_u8(&mut self) -> u8 {
+        1 + self.below(255) as u8
+    }
+
+    pub(crate) fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(8) {
+            let x = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&x[..chunk.len()]);
+        }
+    }
+}
+
+/// Corrupts buffers with reproducible random errors.
+#[derive(Debug, Clone)]
+pub struct Injector {
+    rng: Rng,
+}
+
+impl Injector {
+    /// Create an injector where the same `seed` always produces the same errors.
+    pub fn new(seed: u64) -> Self {
+        Injector {
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Fill `buf` with random bytes, e.g., for generating payloads.
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        self.rng.fill(buf);
+    }
+
+    /// Corrupt `n` distinct random symbols, i.e., bytes, of `buf` with random non-zero
+    /// error values, returning the sorted error positions. At most `buf.len()` symbols
+    /// are corrupted.
+    pub fn symbol_errors(&mut self, buf: &mut [u8], n: usize) -> Vec<usize> {
+        let n = n.min(buf.len());
+        // partial Fisher-Yates shuffle of the positions
+        let mut positions: Vec<usize> = (0..buf.len()).collect();
+        for i in 0..n {
+            let j = i + self.rng.below(buf.len() - i);
+            positions.swap(i, j);
+        }
+        let mut positions = positions[..n].to_vec();
+        positions.sort_unstable();
+        for &pos in &positions {
+            buf[pos] ^= self.rng.nonzero_u8();
+        }
+        positions
+    }
+
+    /// Corrupt every byte of a burst of `len` consecutive bytes at a random position,
+    /// returning the start of the burst. The burst is truncated to the buffer length.
+    ///
+    /// A burst of `len` bytes in a codeblock with interleave `I` affects at most
+    /// `len.div_ceil(I)` symbols of each message.
+    pub fn burst(&mut self, buf: &mut [u8], len: usize) -> usize {
+        let len = len.min(buf.len());
+        let start = self.rng.below(buf.len() - len + 1);
+        for x in &mut buf[start..start + len] {
+            *x ^= self.rng.nonzero_u8();
+        }
+        start
+    }
+
+    /// Flip each bit of `buf` independently with probability `p`, as for a binary
+    /// symmetric channel, returning the number of bits flipped.
+    pub fn bit_flips(&mut self, buf: &mut [u8], p: f64) -> usize {
+        let nbits = buf.len() * 8;
+        if p <= 0.0 || nbits == 0 {
+            return 0;
+        }
+        if p >= 1.0 {
+            buf.iter_mut().for_each(|x| *x = !*x);
+            return nbits;
+        }
+        // Skip ahead by geometrically distributed gaps rather than drawing for every
+        // bit, which matters for the low error rates of interest.
+        let ln_q = (1.0 - p).ln();
+        let mut flipped = 0;
+        let mut bit = 0usize;
+        loop {
+            let u = 1.0 - self.rng.next_f64();
+            let gap = (u.ln() / ln_q).floor();
+            if gap >= (nbits - bit) as f64 {
+                break;
+            }
+            bit += gap as usize;
+            buf[bit / 8] ^= 0x80 >> (bit % 8);
+            flipped += 1;
+            bit += 1;
+            if bit >= nbits {
+                break;
+            }
+        }
+        flipped
+    }
+}
+
+/// Result of simulating a single channel error probability.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    /// Channel bit error probability.
+    pub p: f64,
+    /// Number of codeblocks decoded.
+    pub frames: u64,
+    /// Number of bits flipped by the channel.
+    pub bit_errors: u64,
+    /// Codeblocks with an uncorrectable message, i.e., detected failures.
+    pub detected: u64,
+    /// Codeblocks where all messages were reported correctable but the decoded data
+    /// did not match what was sent, i.e., miscorrections.
+    pub undetected: u64,
+}
+
+impl Point {
+    fn new(p: f64) -> Self {
+        Point {
+            p,
+            frames: 0,
+            bit_errors: 0,
+            detected: 0,
+            undetected: 0,
+        }
+    }
+
+    /// Frame error rate, i.e., the fraction of codeblocks not decoded correctly
+    /// whether or not the failure was detected.
+    pub fn fer(&self) -> f64 {
+        self.rate(self.detected + self.undetected)
+    }
+
+    /// Fraction of codeblocks that were miscorrected.
+    pub fn undetected_rate(&self) -> f64 {
+        self.rate(self.undetected)
+    }
+
+    fn rate(&self, count: u64) -> f64 {
+        match self.frames {
+            0 => 0.0,
+            n => count as f64 / n as f64,
+        }
+    }
+}
+
+/// Monte Carlo simulation of codeblocks sent over a binary symmetric channel.
+///
+/// Each frame is a random payload encoded into a codeblock, corrupted with
+/// independent bit flips using [Injector::bit_flips], and then decoded. Randomization
+/// and sync are not simulated since they do not affect the decoder.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    code: Code,
+    interleave: u8,
+    frames: u64,
+    seed: u64,
+}
+
+impl Simulation {
+    /// Create a simulation of 1000 frames per point for codeblocks of `interleave`
+    /// messages using `code`.
+    pub fn new(code: Code, interleave: u8) -> Self {
+        Simulation {
+            code,
+            interleave,
+            frames: 1000,
+            seed: 0,
+        }
+    }
+
+    /// Set the number of frames simulated for each point.
+    pub fn frames(mut self, frames: u64) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// Set the seed used for the payloads and channel errors.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Simulate a single channel bit error probability.
+    ///
+    /// # Panics
+    /// If the interleave is invalid.
+    pub fn run(&self, p: f64) -> Point {
+        let config = Config {
+            interleave: self.interleave,
+            code: self.code,
+            ..Default::default()
+        };
+        let mut injector = Injector::new(self.seed ^ p.to_bits());
+        let mut frame = vec![0u8; config.frame_len()];
+        let mut point = Point::new(p);
+        for _ in 0..self.frames {
+            injector.fill(&mut frame);
+            let sent = gen::codeblock(&config, &frame).expect("invalid interleave");
+            let mut received = sent.clone();
+            point.bit_errors += injector.bit_flips(&mut received, p) as u64;
+
+            let block = self.code.correct_codeblock(&received, self.interleave);
+            point.frames += 1;
+            match block.message {
+                None => point.detected += 1,
+                Some(msg) if msg != sent => point.undetected += 1,
+                Some(_) => {}
+            }
+        }
+        point
+    }
+
+    /// Simulate each of the channel bit error probabilities in `ps`.
+    pub fn sweep(&self, ps: &[f64]) -> Vec<Point> {
+        ps.iter().map(|&p| self.run(p)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::FIXTURE_MSG;
+    use crate::{correct_message, RSState};
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = vec![0u8; 1020];
+        let mut b = vec![0u8; 1020];
+
+        Injector::new(7).symbol_errors(&mut a, 20);
+        Injector::new(7).symbol_errors(&mut b, 20);
+
+        assert_eq!(a, b);
+        let mut c = vec![0u8; 1020];
+        Injector::new(8).symbol_errors(&mut c, 20);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_symbol_errors() {
+        let mut injector = Injector::new(1);
+        for n in [1, 8, 16, 17] {
+            let mut msg = *FIXTURE_MSG;
+
+            let positions = injector.symbol_errors(&mut msg, n);
+
+            assert_eq!(positions.len(), n);
+            let diffs: Vec<usize> = (0..255).filter(|&i| msg[i] != FIXTURE_MSG[i]).collect();
+            assert_eq!(diffs, positions);
+            let block = correct_message(&msg);
+            if n <= 16 {
+                assert_eq!(block.state, RSState::Corrected(n as i32), "n={n}");
+            } else {
+                assert_ne!(block.message.as_deref(), Some(&FIXTURE_MSG[..]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_burst() {
+        let mut buf = vec![0u8; 100];
+
+        let start = Injector::new(3).burst(&mut buf, 10);
+
+        assert!(start <= 90);
+        for (i, x) in buf.iter().enumerate() {
+            assert_eq!(*x != 0, (start..start + 10).contains(&i));
+        }
+        assert_eq!(Injector::new(3).burst(&mut buf, 200), 0);
+    }
+
+    #[test]
+    fn test_bit_flips() {
+        let mut injector = Injector::new(5);
+        let mut buf = vec![0u8; 10_000];
+
+        let flipped = injector.bit_flips(&mut buf, 0.01);
+
+        let ones: u32 = buf.iter().map(|x| x.count_ones()).sum();
+        assert_eq!(ones as usize, flipped);
+        // 800 expected, with a standard deviation of about 28
+        assert!((650..950).contains(&flipped), "{flipped}");
+        assert_eq!(injector.bit_flips(&mut buf, 0.0), 0);
+        assert_eq!(injector.bit_flips(&mut [0u8; 3], 1.0), 24);
+    }
+
+    #[test]
+    fn test_simulation() {
+        let sim = Simulation::new(Code::E16, 2).frames(50).seed(9);
+
+        let points = sim.sweep(&[0.0, 0.002, 0.05]);
+
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].frames, 50);
+        assert_eq!(points[0].bit_errors, 0);
+        assert_eq!(points[0].fer(), 0.0);
+        // ~8 expected bit errors per codeblock is well within the correction power
+        assert_eq!(points[1].fer(), 0.0);
+        assert!(points[1].bit_errors > 0);
+        // ~200 expected bit errors per codeblock is well beyond it
+        assert_eq!(points[2].fer(), 1.0);
+        assert_eq!(points[2].detected + points[2].undetected, 50);
+        assert_eq!(sim.run(0.05), points[2]);
+    }
+}
@@ -0,0 +1,121 @@
+//! x86_64 AVX2 backend for byte-wise GF(2)-linear maps, such as the dual basis
+//! conversion and multiplication by a constant.
+//!
+//! A linear map of bytes is the XOR of its values for the low and high nibbles, so a
+//! 256 entry table lookup is replaced by two 16 entry VPSHUFB lookups, 32 bytes at a
+//! time.
+use core::arch::x86_64::*;
+
+#[cfg(feature = "std")]
+use crate::gf;
+
+const LANES: usize = 32;
+
+#[target_feature(enable = "avx2")]
+fn lookup(lo: __m256i, hi: __m256i, x: __m256i) -> __m256i {
+    let mask = _mm256_set1_epi8(0x0f);
+    let lo_idx = _mm256_and_si256(x, mask);
+    let hi_idx = _mm256_and_si256(_mm256_srli_epi16::<4>(x), mask);
+    _mm256_xor_si256(
+        _mm256_shuffle_epi8(lo, lo_idx),
+        _mm256_shuffle_epi8(hi, hi_idx),
+    )
+}
+
+/// Apply the linear map with nibble tables `lo` and `hi`, i.e., x -> lo[x & 0xf] ^
+/// hi[x >> 4], to each byte of `buf`.
+///
+/// # Safety
+/// The CPU must support AVX2; see [super::has_avx2].
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn map_linear(lo: &[u8; 16], hi: &[u8; 16], buf: &mut [u8]) {
+    let mut chunks = buf.chunks_exact_mut(LANES);
+    // SAFETY: all loads and stores are unaligned and in bounds.
+    unsafe {
+        let lo_table = _mm256_broadcastsi128_si256(_mm_loadu_si128(lo.as_ptr() as *const __m128i));
+        let hi_table = _mm256_broadcastsi128_si256(_mm_loadu_si128(hi.as_ptr() as *const __m128i));
+        for chunk in chunks.by_ref() {
+            let x = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+            let y = lookup(lo_table, hi_table, x);
+            _mm256_storeu_si256(chunk.as_mut_ptr() as *mut __m256i, y);
+        }
+    }
+    for x in chunks.into_remainder() {
+        *x = lo[(*x & 0xf) as usize] ^ hi[(*x >> 4) as usize];
+    }
+}
+
+/// Evaluate [LANES] polynomials at each of the points in `xs`, writing the results to
+/// `out`. The polynomials are the columns of `rows`, highest degree first.
+///
+/// Multiplication by a point is linear, so each step of Horner's method is a pair of
+/// nibble lookups for all of the polynomials at once.
+///
+/// # Safety
+/// The CPU must support AVX2; see [super::has_avx2].
+#[cfg(feature = "std")]
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn poly_eval_lanes(rows: &[[u8; LANES]], xs: &[u8], out: &mut [[u8; LANES]]) {
+    assert_eq!(xs.len(), out.len());
+    for (x, out) in xs.iter().zip(out.iter_mut()) {
+        let lo: [u8; 16] = core::array::from_fn(|i| gf::mult(i as u8, *x));
+        let hi: [u8; 16] = core::array::from_fn(|i| gf::mult((i << 4) as u8, *x));
+        // SAFETY: all loads and stores are unaligned and of arrays of LANES bytes.
+        unsafe {
+            let lo = _mm256_broadcastsi128_si256(_mm_loadu_si128(lo.as_ptr() as *const __m128i));
+            let hi = _mm256_broadcastsi128_si256(_mm_loadu_si128(hi.as_ptr() as *const __m128i));
+            let mut acc = _mm256_setzero_si256();
+            for row in rows {
+                let c = _mm256_loadu_si256(row.as_ptr() as *const __m256i);
+                acc = _mm256_xor_si256(lookup(lo, hi, acc), c);
+            }
+            _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, acc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_linear() {
+        // multiplication by a constant is linear over GF(2)
+        let lo: [u8; 16] = std::array::from_fn(|i| crate::gf::mult(i as u8, 0x35));
+        let hi: [u8; 16] = std::array::from_fn(|i| crate::gf::mult((i << 4) as u8, 0x35));
+        let mut buf: Vec<u8> = (0..=255).chain(0..40).map(|i| i as u8).collect();
+        let expected: Vec<u8> = buf.iter().map(|x| crate::gf::mult(*x, 0x35)).collect();
+
+        if !super::super::has_avx2() {
+            return;
+        }
+
+        // SAFETY: AVX2 support was checked above.
+        unsafe { map_linear(&lo, &hi, &mut buf) };
+
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_poly_eval_lanes() {
+        let rows: Vec<[u8; LANES]> = (0..255)
+            .map(|i| std::array::from_fn(|lane| (i * 7 + lane * 13 + 3) as u8))
+            .collect();
+        let xs: Vec<u8> = (0..=255).map(|i| i as u8).collect();
+        let mut zult = vec![[0u8; LANES]; xs.len()];
+
+        if !super::super::has_avx2() {
+            return;
+        }
+
+        // SAFETY: AVX2 support was checked above.
+        unsafe { poly_eval_lanes(&rows, &xs, &mut zult) };
+
+        for lane in 0..LANES {
+            let p: Vec<u8> = rows.iter().map(|row| row[lane]).collect();
+            for (x, z) in xs.iter().zip(zult.iter()) {
+                assert_eq!(z[lane], crate::gf::poly_eval(&p, *x), "lane={lane} x={x}");
+            }
+        }
+    }
+}
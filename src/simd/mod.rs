@@ -0,0 +1,49 @@
+//! SIMD kernels for the hot loops in GF(2^8) arithmetic and byte-wise table lookups.
+//!
+//! Backends are selected at runtime based on the features of the CPU, falling back
+//! to the scalar implementations in [crate::gf] and [crate::dual_basis], so a single
+//! binary runs on any CPU of the target architecture. Detection is cached by std and
+//! compiles away entirely when the features are enabled at compile time, e.g., with
+//! `RUSTFLAGS="-C target-cpu=native"`.
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod avx2;
+#[cfg(target_arch = "aarch64")]
+pub(crate) mod neon;
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod x86;
+
+/// Whether the GFNI/AVX2 backend in [x86] can be used.
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+pub(crate) fn has_gfni_avx2() -> bool {
+    std::arch::is_x86_feature_detected!("gfni") && std::arch::is_x86_feature_detected!("avx2")
+}
+
+/// Whether the AVX2 backend in [avx2] can be used.
+#[cfg(all(target_arch = "x86_64", feature = "std"))]
+pub(crate) fn has_avx2() -> bool {
+    std::arch::is_x86_feature_detected!("avx2")
+}
+
+/// Whether the NEON backend in [neon] can be used.
+#[cfg(all(target_arch = "aarch64", feature = "std"))]
+pub(crate) fn has_neon() -> bool {
+    std::arch::is_aarch64_feature_detected!("neon")
+}
+
+// Without std there is no runtime detection, so only the features enabled at compile
+// time are used.
+
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+pub(crate) fn has_gfni_avx2() -> bool {
+    cfg!(all(target_feature = "gfni", target_feature = "avx2"))
+}
+
+#[cfg(all(target_arch = "x86_64", not(feature = "std")))]
+pub(crate) fn has_avx2() -> bool {
+    cfg!(target_feature = "avx2")
+}
+
+#[cfg(all(target_arch = "aarch64", not(feature = "std")))]
+pub(crate) fn has_neon() -> bool {
+    cfg!(target_feature = "neon")
+}
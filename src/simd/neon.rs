@@ -0,0 +1,224 @@
+//! aarch64 NEON backend.
+//!
+//! NEON has no GF(2^8) multiply, so products are computed with the 8x8 bit
+//! carry-less PMULL and the high byte of the 15 bit product is reduced modulo
+//! [crate::PRIM] using a pair of nibble table lookups, as the reduction is linear over
+//! GF(2). The same nibble lookups implement byte-wise linear maps such as the dual
+//! basis conversion.
+use core::arch::aarch64::*;
+
+use crate::PRIM;
+
+const LANES: usize = 16;
+
+/// Reduce the carry-less product `x` modulo `prim`.
+const fn reduce(x: u32, prim: u32) -> u8 {
+    let mut x = x;
+    let mut bit = 15;
+    while bit >= 8 {
+        if x & (1 << bit) > 0 {
+            x ^= prim << (bit - 8);
+        }
+        bit -= 1;
+    }
+    x as u8
+}
+
+/// Reduction of the high byte of a product split into tables for its low and high
+/// nibbles, i.e., (h * x^8) mod PRIM = LO[h & 0xf] ^ HI[h >> 4].
+const fn reduction_tables() -> ([u8; 16], [u8; 16]) {
+    let mut lo = [0u8; 16];
+    let mut hi = [0u8; 16];
+    let mut i = 0;
+    while i < 16 {
+        lo[i] = reduce((i as u32) << 8, PRIM as u32);
+        hi[i] = reduce((i as u32) << 12, PRIM as u32);
+        i += 1;
+    }
+    (lo, hi)
+}
+
+const REDUCE: ([u8; 16], [u8; 16]) = reduction_tables();
+
+#[target_feature(enable = "neon")]
+fn lookup(lo: uint8x16_t, hi: uint8x16_t, x: uint8x16_t) -> uint8x16_t {
+    let lo_idx = vandq_u8(x, vdupq_n_u8(0x0f));
+    let hi_idx = vshrq_n_u8::<4>(x);
+    veorq_u8(vqtbl1q_u8(lo, lo_idx), vqtbl1q_u8(hi, hi_idx))
+}
+
+/// Multiply each lane of `a` and `b` in GF(2^8).
+#[target_feature(enable = "neon")]
+fn mult(a: uint8x16_t, b: uint8x16_t, reduce_lo: uint8x16_t, reduce_hi: uint8x16_t) -> uint8x16_t {
+    let a = vreinterpretq_p8_u8(a);
+    let b = vreinterpretq_p8_u8(b);
+    let prod_lo = vreinterpretq_u8_p16(vmull_p8(vget_low_p8(a), vget_low_p8(b)));
+    let prod_hi = vreinterpretq_u8_p16(vmull_high_p8(a, b));
+    // low and high bytes of each 16 bit product, in lane order
+    let lo = vuzp1q_u8(prod_lo, prod_hi);
+    let hi = vuzp2q_u8(prod_lo, prod_hi);
+    veorq_u8(lo, lookup(reduce_lo, reduce_hi, hi))
+}
+
+/// Evaluate the polynomial `p`, highest degree first, at each of the points in `xs`,
+/// writing the results to `out`.
+///
+/// # Safety
+/// The CPU must support NEON; see [super::has_neon].
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn poly_eval_many(p: &[u8], xs: &[u8], out: &mut [u8]) {
+    assert_eq!(xs.len(), out.len());
+    // SAFETY: all loads and stores are from arrays of LANES bytes.
+    unsafe {
+        let reduce_lo = vld1q_u8(REDUCE.0.as_ptr());
+        let reduce_hi = vld1q_u8(REDUCE.1.as_ptr());
+        for (xs, out) in xs.chunks(LANES).zip(out.chunks_mut(LANES)) {
+            let mut points = [0u8; LANES];
+            points[..xs.len()].copy_from_slice(xs);
+            let x = vld1q_u8(points.as_ptr());
+            // Horner's method for each lane
+            let mut acc = vdupq_n_u8(0);
+            for c in p.iter() {
+                acc = veorq_u8(mult(acc, x, reduce_lo, reduce_hi), vdupq_n_u8(*c));
+            }
+            let mut zult = [0u8; LANES];
+            vst1q_u8(zult.as_mut_ptr(), acc);
+            out.copy_from_slice(&zult[..out.len()]);
+        }
+    }
+}
+
+/// Evaluate 2 * [LANES] polynomials at each of the points in `xs`, writing the results
+/// to `out`. The polynomials are the columns of `rows`, highest degree first.
+///
+/// Multiplication by a point is linear, so each step of Horner's method is a pair of
+/// nibble lookups rather than a full multiply.
+///
+/// # Safety
+/// The CPU must support NEON; see [super::has_neon].
+#[cfg(feature = "std")]
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn poly_eval_lanes(
+    rows: &[[u8; 2 * LANES]],
+    xs: &[u8],
+    out: &mut [[u8; 2 * LANES]],
+) {
+    assert_eq!(xs.len(), out.len());
+    for (x, out) in xs.iter().zip(out.iter_mut()) {
+        let lo: [u8; 16] = core::array::from_fn(|i| crate::gf::mult(i as u8, *x));
+        let hi: [u8; 16] = core::array::from_fn(|i| crate::gf::mult((i << 4) as u8, *x));
+        // SAFETY: all loads and stores are in bounds of arrays of 2 * LANES bytes.
+        unsafe {
+            let lo = vld1q_u8(lo.as_ptr());
+            let hi = vld1q_u8(hi.as_ptr());
+            let mut acc0 = vdupq_n_u8(0);
+            let mut acc1 = vdupq_n_u8(0);
+            for row in rows {
+                acc0 = veorq_u8(lookup(lo, hi, acc0), vld1q_u8(row.as_ptr()));
+                acc1 = veorq_u8(lookup(lo, hi, acc1), vld1q_u8(row[LANES..].as_ptr()));
+            }
+            vst1q_u8(out.as_mut_ptr(), acc0);
+            vst1q_u8(out[LANES..].as_mut_ptr(), acc1);
+        }
+    }
+}
+
+/// Apply the linear map with nibble tables `lo` and `hi`, i.e., x -> lo[x & 0xf] ^
+/// hi[x >> 4], to each byte of `buf`.
+///
+/// # Safety
+/// The CPU must support NEON; see [super::has_neon].
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn map_linear(lo: &[u8; 16], hi: &[u8; 16], buf: &mut [u8]) {
+    let mut chunks = buf.chunks_exact_mut(LANES);
+    // SAFETY: all loads and stores are in bounds.
+    unsafe {
+        let lo_table = vld1q_u8(lo.as_ptr());
+        let hi_table = vld1q_u8(hi.as_ptr());
+        for chunk in chunks.by_ref() {
+            let x = vld1q_u8(chunk.as_ptr());
+            vst1q_u8(chunk.as_mut_ptr(), lookup(lo_table, hi_table, x));
+        }
+    }
+    for x in chunks.into_remainder() {
+        *x = lo[(*x & 0xf) as usize] ^ hi[(*x >> 4) as usize];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gf;
+
+    #[test]
+    fn test_reduction_tables() {
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                let mut prod = 0u32;
+                for i in 0..8 {
+                    if b & (1 << i) > 0 {
+                        prod ^= (a as u32) << i;
+                    }
+                }
+                let h = (prod >> 8) as usize;
+                let zult = prod as u8 ^ REDUCE.0[h & 0xf] ^ REDUCE.1[h >> 4];
+                assert_eq!(zult, gf::mult(a, b), "a={a} b={b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_poly_eval_many() {
+        let p: Vec<u8> = (0..255).map(|i| (i * 7 + 3) as u8).collect();
+        let xs: Vec<u8> = (0..=255).map(|i| i as u8).collect();
+        let mut zult = vec![0u8; xs.len()];
+        if !super::super::has_neon() {
+            return;
+        }
+
+        // SAFETY: NEON support was checked above.
+        unsafe { poly_eval_many(&p, &xs, &mut zult) };
+
+        for (x, z) in xs.iter().zip(zult.iter()) {
+            assert_eq!(*z, gf::poly_eval(&p, *x), "x={x}");
+        }
+    }
+
+    #[test]
+    fn test_map_linear() {
+        let lo: [u8; 16] = std::array::from_fn(|i| gf::mult(i as u8, 0x35));
+        let hi: [u8; 16] = std::array::from_fn(|i| gf::mult((i << 4) as u8, 0x35));
+        let mut buf: Vec<u8> = (0..=255).chain(0..40).map(|i| i as u8).collect();
+        let expected: Vec<u8> = buf.iter().map(|x| gf::mult(*x, 0x35)).collect();
+        if !super::super::has_neon() {
+            return;
+        }
+
+        // SAFETY: NEON support was checked above.
+        unsafe { map_linear(&lo, &hi, &mut buf) };
+
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_poly_eval_lanes() {
+        let rows: Vec<[u8; 2 * LANES]> = (0..255)
+            .map(|i| std::array::from_fn(|lane| (i * 7 + lane * 13 + 3) as u8))
+            .collect();
+        let xs: Vec<u8> = (0..=255).map(|i| i as u8).collect();
+        let mut zult = vec![[0u8; 2 * LANES]; xs.len()];
+        if !super::super::has_neon() {
+            return;
+        }
+
+        // SAFETY: NEON support was checked above.
+        unsafe { poly_eval_lanes(&rows, &xs, &mut zult) };
+
+        for lane in 0..2 * LANES {
+            let p: Vec<u8> = rows.iter().map(|row| row[lane]).collect();
+            for (x, z) in xs.iter().zip(zult.iter()) {
+                assert_eq!(z[lane], gf::poly_eval(&p, *x), "lane={lane} x={x}");
+            }
+        }
+    }
+}
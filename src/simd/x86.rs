@@ -0,0 +1,316 @@
+//! x86_64 GFNI/AVX2 backend.
+//!
+//! GF2P8MULB multiplies in the AES field defined by x^8 + x^4 + x^3 + x + 1 rather than
+//! the CCSDS field defined by [crate::PRIM]. Both are representations of GF(2^8), so
+//! elements are mapped into the AES field using GF2P8AFFINEQB with the matrix of a
+//! field isomorphism, multiplied there, and mapped back.
+use core::arch::x86_64::*;
+
+const AES_PRIM: u16 = 0x11b;
+const LANES: usize = 32;
+
+const fn field_mult(x: u8, y: u8, prim: u16) -> u8 {
+    let mut r: u16 = 0;
+    let mut x = x as u16;
+    let mut y = y;
+    while y > 0 {
+        if y & 1 > 0 {
+            r ^= x;
+        }
+        y >>= 1;
+        x <<= 1;
+        if x & 0x100 > 0 {
+            x ^= prim;
+        }
+    }
+    r as u8
+}
+
+/// Root in the AES field of the CCSDS primitive polynomial x^8 + x^7 + x^2 + x + 1.
+const fn aes_root() -> u8 {
+    let mut b: u16 = 2;
+    while b < 256 {
+        let b1 = b as u8;
+        let b2 = field_mult(b1, b1, AES_PRIM);
+        let b4 = field_mult(b2, b2, AES_PRIM);
+        let b7 = field_mult(field_mult(b4, b2, AES_PRIM), b1, AES_PRIM);
+        let b8 = field_mult(b4, b4, AES_PRIM);
+        if b8 ^ b7 ^ b2 ^ b1 ^ 1 == 0 {
+            return b1;
+        }
+        b += 1;
+    }
+    panic!("no root found");
+}
+
+/// Image of each CCSDS field element in the AES field, mapping x^i to beta^i.
+const fn to_aes_table() -> [u8; 256] {
+    let beta = aes_root();
+    let mut basis = [0u8; 8];
+    let mut i = 0;
+    let mut p = 1u8;
+    while i < 8 {
+        basis[i] = p;
+        p = field_mult(p, beta, AES_PRIM);
+        i += 1;
+    }
+    let mut table = [0u8; 256];
+    let mut a = 0;
+    while a < 256 {
+        let mut y = 0u8;
+        let mut j = 0;
+        while j < 8 {
+            if (a >> j) & 1 > 0 {
+                y ^= basis[j];
+            }
+            j += 1;
+        }
+        table[a] = y;
+        a += 1;
+    }
+    table
+}
+
+/// Encode the linear map `table` as a GF2P8AFFINEQB matrix, where byte `7 - i` of the
+/// matrix selects the input bits that contribute to output bit `i`.
+const fn affine_matrix(table: &[u8; 256]) -> i64 {
+    let mut matrix: u64 = 0;
+    let mut i = 0;
+    while i < 8 {
+        let mut row = 0u64;
+        let mut j = 0;
+        while j < 8 {
+            row |= (((table[1 << j] >> i) & 1) as u64) << j;
+            j += 1;
+        }
+        matrix |= row << (8 * (7 - i));
+        i += 1;
+    }
+    matrix as i64
+}
+
+const fn invert(table: &[u8; 256]) -> [u8; 256] {
+    let mut inv = [0u8; 256];
+    let mut a = 0;
+    while a < 256 {
+        inv[table[a] as usize] = a as u8;
+        a += 1;
+    }
+    inv
+}
+
+const TO_AES: [u8; 256] = to_aes_table();
+const TO_AES_MATRIX: i64 = affine_matrix(&TO_AES);
+const FROM_AES_MATRIX: i64 = affine_matrix(&invert(&TO_AES));
+
+/// Evaluate the polynomial `p`, highest degree first, at each of the points in `xs`,
+/// writing the results to `out`.
+///
+/// # Safety
+/// The CPU must support GFNI and AVX2; see [super::has_gfni_avx2].
+#[target_feature(enable = "gfni,avx2")]
+pub(crate) unsafe fn poly_eval_many(p: &[u8], xs: &[u8], out: &mut [u8]) {
+    assert_eq!(xs.len(), out.len());
+    for (xs, out) in xs.chunks(LANES).zip(out.chunks_mut(LANES)) {
+        let mut points = [0u8; LANES];
+        points[..xs.len()].copy_from_slice(xs);
+        let zult = poly_eval_points(p, &points);
+        out.copy_from_slice(&zult[..out.len()]);
+    }
+}
+
+#[target_feature(enable = "gfni,avx2")]
+fn poly_eval_points(p: &[u8], points: &[u8; LANES]) -> [u8; LANES] {
+    let mut zult = [0u8; LANES];
+    // SAFETY: all loads and stores are unaligned and in bounds.
+    unsafe {
+        let to_aes = _mm256_set1_epi64x(TO_AES_MATRIX);
+        let from_aes = _mm256_set1_epi64x(FROM_AES_MATRIX);
+        let x = _mm256_gf2p8affine_epi64_epi8::<0>(
+            _mm256_loadu_si256(points.as_ptr() as *const __m256i),
+            to_aes,
+        );
+        // Horner's method for each lane, 32 points at a time
+        let mut acc = _mm256_setzero_si256();
+        for c in p.iter() {
+            let c = _mm256_set1_epi8(TO_AES[*c as usize] as i8);
+            acc = _mm256_xor_si256(_mm256_gf2p8mul_epi8(acc, x), c);
+        }
+        let acc = _mm256_gf2p8affine_epi64_epi8::<0>(acc, from_aes);
+        _mm256_storeu_si256(zult.as_mut_ptr() as *mut __m256i, acc);
+    }
+    zult
+}
+
+/// Evaluate [LANES] polynomials at each of the points in `xs`, writing the results to
+/// `out`. The polynomials are the columns of `rows`, highest degree first.
+///
+/// # Safety
+/// The CPU must support GFNI and AVX2; see [super::has_gfni_avx2].
+#[cfg(feature = "std")]
+#[target_feature(enable = "gfni,avx2")]
+pub(crate) unsafe fn poly_eval_lanes(rows: &[[u8; LANES]], xs: &[u8], out: &mut [[u8; LANES]]) {
+    assert_eq!(xs.len(), out.len());
+    // SAFETY: all loads and stores are unaligned and of arrays of LANES bytes.
+    unsafe {
+        let to_aes = _mm256_set1_epi64x(TO_AES_MATRIX);
+        let from_aes = _mm256_set1_epi64x(FROM_AES_MATRIX);
+        for (x, out) in xs.iter().zip(out.iter_mut()) {
+            let x = _mm256_set1_epi8(TO_AES[*x as usize] as i8);
+            let mut acc = _mm256_setzero_si256();
+            for row in rows {
+                let c = _mm256_gf2p8affine_epi64_epi8::<0>(
+                    _mm256_loadu_si256(row.as_ptr() as *const __m256i),
+                    to_aes,
+                );
+                acc = _mm256_xor_si256(_mm256_gf2p8mul_epi8(acc, x), c);
+            }
+            let acc = _mm256_gf2p8affine_epi64_epi8::<0>(acc, from_aes);
+            _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, acc);
+        }
+    }
+}
+
+/// Evaluate the `depth` polynomials interleaved in `block`, where coefficient `j` of
+/// polynomial `i` is `block[j * depth + i]`, highest degree first, at each of the up to
+/// [LANES] points in `xs`, writing the results for polynomial `i` to `out[i]`.
+///
+/// The accumulators of the polynomials are independent, so evaluating them together
+/// is bound by throughput rather than by the latency of each multiplication as in
+/// [poly_eval_many].
+///
+/// # Safety
+/// The CPU must support GFNI and AVX2; see [super::has_gfni_avx2].
+#[cfg(feature = "std")]
+#[target_feature(enable = "gfni,avx2")]
+pub(crate) unsafe fn poly_eval_interleaved(
+    block: &[u8],
+    depth: usize,
+    xs: &[u8],
+    out: &mut [[u8; LANES]],
+) {
+    match depth {
+        1 => eval_interleaved::<1>(block, xs, out),
+        2 => eval_interleaved::<2>(block, xs, out),
+        3 => eval_interleaved::<3>(block, xs, out),
+        4 => eval_interleaved::<4>(block, xs, out),
+        5 => eval_interleaved::<5>(block, xs, out),
+        6 => eval_interleaved::<6>(block, xs, out),
+        7 => eval_interleaved::<7>(block, xs, out),
+        8 => eval_interleaved::<8>(block, xs, out),
+        _ => panic!("unsupported depth {depth}"),
+    }
+}
+
+/// [poly_eval_interleaved] for a depth known at compile time, so the accumulators are
+/// kept in registers.
+#[cfg(feature = "std")]
+#[target_feature(enable = "gfni,avx2")]
+fn eval_interleaved<const D: usize>(block: &[u8], xs: &[u8], out: &mut [[u8; LANES]]) {
+    assert!(xs.len() <= LANES);
+    assert_eq!(out.len(), D);
+    let mut points = [0u8; LANES];
+    points[..xs.len()].copy_from_slice(xs);
+    // SAFETY: all loads and stores are unaligned and of arrays of LANES bytes.
+    unsafe {
+        let to_aes = _mm256_set1_epi64x(TO_AES_MATRIX);
+        let from_aes = _mm256_set1_epi64x(FROM_AES_MATRIX);
+        let x = _mm256_gf2p8affine_epi64_epi8::<0>(
+            _mm256_loadu_si256(points.as_ptr() as *const __m256i),
+            to_aes,
+        );
+        let mut acc = [_mm256_setzero_si256(); D];
+        for row in block.chunks_exact(D) {
+            for (acc, c) in acc.iter_mut().zip(row) {
+                let c = _mm256_set1_epi8(TO_AES[*c as usize] as i8);
+                *acc = _mm256_xor_si256(_mm256_gf2p8mul_epi8(*acc, x), c);
+            }
+        }
+        for (acc, out) in acc.iter().zip(out.iter_mut()) {
+            let acc = _mm256_gf2p8affine_epi64_epi8::<0>(*acc, from_aes);
+            _mm256_storeu_si256(out.as_mut_ptr() as *mut __m256i, acc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gf;
+
+    #[test]
+    fn test_to_aes_is_isomorphism() {
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                let expected = TO_AES[gf::mult(a, b) as usize];
+                let zult = field_mult(TO_AES[a as usize], TO_AES[b as usize], AES_PRIM);
+                assert_eq!(zult, expected, "a={a} b={b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_poly_eval_many() {
+        let p: Vec<u8> = (0..255).map(|i| (i * 7 + 3) as u8).collect();
+        let xs: Vec<u8> = (0..=255).map(|i| i as u8).collect();
+        let mut zult = vec![0u8; xs.len()];
+
+        if !super::super::has_gfni_avx2() {
+            return;
+        }
+
+        // SAFETY: GFNI and AVX2 support was checked above.
+        unsafe { poly_eval_many(&p, &xs, &mut zult) };
+
+        for (x, z) in xs.iter().zip(zult.iter()) {
+            assert_eq!(*z, gf::poly_eval(&p, *x), "x={x}");
+        }
+    }
+
+    #[test]
+    fn test_poly_eval_lanes() {
+        let rows: Vec<[u8; LANES]> = (0..255)
+            .map(|i| std::array::from_fn(|lane| (i * 7 + lane * 13 + 3) as u8))
+            .collect();
+        let xs: Vec<u8> = (0..=255).map(|i| i as u8).collect();
+        let mut zult = vec![[0u8; LANES]; xs.len()];
+
+        if !super::super::has_gfni_avx2() {
+            return;
+        }
+
+        // SAFETY: GFNI and AVX2 support was checked above.
+        unsafe { poly_eval_lanes(&rows, &xs, &mut zult) };
+
+        for lane in 0..LANES {
+            let p: Vec<u8> = rows.iter().map(|row| row[lane]).collect();
+            for (x, z) in xs.iter().zip(zult.iter()) {
+                assert_eq!(z[lane], gf::poly_eval(&p, *x), "lane={lane} x={x}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_poly_eval_interleaved() {
+        let block: Vec<u8> = (0..8 * 255).map(|i| (i * 7 + 3) as u8).collect();
+        let xs: Vec<u8> = (0..LANES).map(|i| (i * 11 + 1) as u8).collect();
+
+        if !super::super::has_gfni_avx2() {
+            return;
+        }
+
+        for depth in 1..=8 {
+            let block = &block[..depth * 255];
+            let mut zult = vec![[0u8; LANES]; depth];
+            // SAFETY: GFNI and AVX2 support was checked above.
+            unsafe { poly_eval_interleaved(block, depth, &xs[..depth * 3], &mut zult) };
+
+            for (i, z) in zult.iter().enumerate() {
+                let p: Vec<u8> = block[i..].iter().step_by(depth).copied().collect();
+                for (x, z) in xs[..depth * 3].iter().zip(z.iter()) {
+                    assert_eq!(*z, gf::poly_eval(&p, *x), "depth={depth} i={i} x={x}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! Extraction of space packets from corrected transfer frames, using the
+//! [spacepackets] crate's packet header. Requires the `spacepackets` feature.
+//!
+//! The data field of each AOS transfer frame is an M_PDU, a 2 byte header giving the
+//! offset of the first packet header in the packet zone that follows it. Packets may
+//! span frames, so a [PacketExtractor] reassembles the packets of each virtual channel
+//! from its frames in order, and [packets] is the whole flow from a CADU stream to
+//! packets.
+//!
+//! ```
+//! use rs2::pipeline::Config;
+//! use rs2::spacepackets::packets;
+//!
+//! let config = Config::default();
+//! // a frame on VCID 1 with one packet of APID 100 and 4 bytes of data, then fill
+//! let mut frame = vec![0x40, 0x01, 0, 0, 0, 0, 0, 0];
+//! frame.extend([0x08, 0x64, 0xc0, 0x00, 0x00, 0x03, 1, 2, 3, 4]);
+//! frame.resize(config.frame_len(), 0xff);
+//! let stream = rs2::gen::cadus(&config, &frame, 0);
+//!
+//! let packets: Vec<_> = packets(&stream[..], config).collect();
+//! let packet = packets[0].as_ref().unwrap();
+//! assert_eq!(packet.vcid, 1);
+//! assert_eq!(packet.header.apid().value(), 100);
+//! assert_eq!(&packet.data[6..], &[1, 2, 3, 4]);
+//! ```
+use std::collections::HashMap;
+use std::io::{Read, Result};
+
+use ::spacepackets::SpHeader;
+
+use crate::crc;
+use crate::pipeline::{Config, Frame, Pipeline};
+
+/// Length of the transfer frame primary header.
+pub const PRIMARY_HEADER_LEN: usize = 6;
+/// Length of the M_PDU header.
+pub const MPDU_HEADER_LEN: usize = 2;
+/// First header pointer of an M_PDU without a packet header, i.e., whose packet zone
+/// only continues the previous packet.
+pub const NO_HEADER: u16 = 0x7ff;
+/// First header pointer of an M_PDU of idle data.
+pub const IDLE: u16 = 0x7fe;
+/// Virtual channel of fill transfer frames.
+pub const FILL_VCID: u8 = 63;
+
+/// Layout of the transfer frames carrying M_PDUs.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MpduLayout {
+    /// Length of the transfer frame before the M_PDU, i.e., the primary header and any
+    /// frame header error control or insert zone, at least [PRIMARY_HEADER_LEN].
+    pub header_len: usize,
+    /// Length of the transfer frame after the M_PDU, e.g., the FECF.
+    pub trailer_len: usize,
+}
+
+impl Default for MpduLayout {
+    /// Only the primary header and no trailer.
+    fn default() -> Self {
+        MpduLayout {
+            header_len: PRIMARY_HEADER_LEN,
+            trailer_len: 0,
+        }
+    }
+}
+
+impl From<&Config> for MpduLayout {
+    /// Only the primary header, followed by the FECF if [Config::fecf] is set.
+    fn from(config: &Config) -> Self {
+        MpduLayout {
+            header_len: PRIMARY_HEADER_LEN,
+            trailer_len: if config.fecf { crc::FECF_LEN } else { 0 },
+        }
+    }
+}
+
+impl MpduLayout {
+    /// Return the M_PDU of a transfer frame, or None if the frame is too short.
+    pub fn mpdu<'a>(&self, frame: &'a [u8]) -> Option<Mpdu<'a>> {
+        let start = self.header_len.max(PRIMARY_HEADER_LEN);
+        let end = frame.len().checked_sub(self.trailer_len)?;
+        if start + MPDU_HEADER_LEN > end {
+            return None;
+        }
+        Some(Mpdu {
+            vcid: frame[1] & 0x3f,
+            counter: u32::from_be_bytes([0, frame[2], frame[3], frame[4]]),
+            first_header: u16::from_be_bytes([frame[start], frame[start + 1]]) & 0x7ff,
+            zone: &frame[start + MPDU_HEADER_LEN..end],
+        })
+    }
+}
+
+/// The M_PDU of a transfer frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Mpdu<'a> {
+    /// Virtual channel of the transfer frame.
+    pub vcid: u8,
+    /// Virtual channel frame count of the transfer frame.
+    pub counter: u32,
+    /// Offset of the first packet header in the packet zone, or [NO_HEADER] or [IDLE].
+    pub first_header: u16,
+    /// The packet zone.
+    pub zone: &'a [u8],
+}
+
+/// A space packet reassembled from the M_PDUs of a virtual channel.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Packet {
+    /// Virtual channel of the transfer frames the packet was in.
+    pub vcid: u8,
+    /// The packet primary header.
+    pub header: SpHeader,
+    /// The whole packet, including the primary header.
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct Channel {
+    counter: Option<u32>,
+    partial: Option<Vec<u8>>,
+}
+
+/// Reassembles the space packets of each virtual channel from its transfer frames.
+///
+/// A packet is dropped if any frame it spans is missing, as detected by a gap in the
+/// virtual channel frame count, or if the first header pointer of the frame following
+/// it does not match its length.
+#[derive(Debug, Default)]
+pub struct PacketExtractor {
+    layout: MpduLayout,
+    channels: HashMap<u8, Channel>,
+}
+
+impl PacketExtractor {
+    pub fn new(layout: MpduLayout) -> Self {
+        PacketExtractor {
+            layout,
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Add the next transfer frame, returning the packets completed by it. Fill frames,
+    /// idle M_PDUs and frames too short for the layout have no packets.
+    pub fn push(&mut self, frame: &[u8]) -> Vec<Packet> {
+        let mut packets = Vec::new();
+        let Some(mpdu) = self.layout.mpdu(frame) else {
+            return packets;
+        };
+        if mpdu.vcid == FILL_VCID || mpdu.first_header == IDLE {
+            return packets;
+        }
+        let channel = self.channels.entry(mpdu.vcid).or_default();
+        if channel
+            .counter
+            .is_some_and(|c| (c + 1) & 0xff_ffff != mpdu.counter)
+        {
+            channel.partial = None;
+        }
+        channel.counter = Some(mpdu.counter);
+
+        if mpdu.first_header == NO_HEADER {
+            if let Some(partial) = channel.partial.as_mut() {
+                partial.extend_from_slice(mpdu.zone);
+                if let (Some(packet), _) = split_packet(mpdu.vcid, partial) {
+                    packets.push(packet);
+                    channel.partial = None;
+                }
+            }
+            return packets;
+        }
+
+        let first = mpdu.first_header as usize;
+        if first > mpdu.zone.len() {
+            channel.partial = None;
+            return packets;
+        }
+        if let Some(mut partial) = channel.partial.take() {
+            partial.extend_from_slice(&mpdu.zone[..first]);
+            if let (Some(packet), []) = split_packet(mpdu.vcid, &partial) {
+                packets.push(packet);
+            }
+        }
+        let mut rest = &mpdu.zone[first..];
+        while let (Some(packet), tail) = split_packet(mpdu.vcid, rest) {
+            packets.push(packet);
+            rest = tail;
+        }
+        channel.partial = (!rest.is_empty()).then(|| rest.to_vec());
+        packets
+    }
+
+    /// Add the next frame of a [Pipeline] like [PacketExtractor::push]. Frames without
+    /// data, degraded frames, frames with a bad FECF and idle frames are not used, so
+    /// packets spanning them are dropped.
+    pub fn push_frame(&mut self, frame: &Frame) -> Vec<Packet> {
+        match &frame.data {
+            Some(data) if !frame.is_degraded() && frame.fecf_ok != Some(false) && !frame.idle => {
+                self.push(data)
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Split the packet at the start of `buf` from the rest, if it is complete.
+fn split_packet(vcid: u8, buf: &[u8]) -> (Option<Packet>, &[u8]) {
+    let Ok((header, _)) = SpHeader::from_be_bytes(buf) else {
+        return (None, buf);
+    };
+    match buf.split_at_checked(header.packet_len()) {
+        Some((data, rest)) => {
+            let packet = Packet {
+                vcid,
+                header,
+                data: data.to_vec(),
+            };
+            (Some(packet), rest)
+        }
+        None => (None, buf),
+    }
+}
+
+/// Decode the CADUs of `reader` with a [Pipeline] and extract the space packets of
+/// the frames with a [PacketExtractor], in the order they are completed.
+pub fn packets<R: Read>(reader: R, config: Config) -> impl Iterator<Item = Result<Packet>> {
+    let mut extractor = PacketExtractor::new(MpduLayout::from(&config));
+    Pipeline::new(reader, config)
+        .frames()
+        .flat_map(move |frame| match frame {
+            Ok(frame) => extractor.push_frame(&frame).into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(err)],
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen;
+
+    fn packet(apid: u16, len: usize) -> Vec<u8> {
+        let mut packet = vec![(apid >> 8) as u8 & 0x07, apid as u8, 0xc0, 0x00];
+        packet.extend(((len - 1) as u16).to_be_bytes());
+        packet.extend((0..len).map(|i| i as u8));
+        packet
+    }
+
+    fn frame(vcid: u8, counter: u32, first_header: u16, zone: &[u8], len: usize) -> Vec<u8> {
+        let mut frame = vec![0x40, vcid];
+        frame.extend(&counter.to_be_bytes()[1..]);
+        frame.push(0);
+        frame.extend(first_header.to_be_bytes());
+        frame.extend(zone);
+        frame.resize(len, 0xff);
+        frame
+    }
+
+    #[test]
+    fn test_extractor() {
+        // a packet spanning 3 frames, followed by one in the last frame
+        let a = packet(1, 40);
+        let b = packet(2, 10);
+        let mut extractor = PacketExtractor::default();
+        assert!(extractor.push(&frame(5, 7, 0, &a[..20], 28)).is_empty());
+        assert!(extractor
+            .push(&frame(5, 8, NO_HEADER, &a[20..40], 28))
+            .is_empty());
+        // a frame on another channel in between
+        assert!(extractor.push(&frame(6, 0, IDLE, &[], 28)).is_empty());
+        let zone = [&a[40..], &b[..]].concat();
+        let packets = extractor.push(&frame(5, 9, (a.len() - 40) as u16, &zone, 8 + zone.len()));
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].data, a);
+        assert_eq!(packets[0].vcid, 5);
+        assert_eq!(packets[0].header.apid().value(), 1);
+        assert_eq!(packets[1].data, b);
+
+        // a gap in the frame count drops the partial packet
+        assert!(extractor.push(&frame(5, 10, 0, &a[..20], 28)).is_empty());
+        let zone = [&a[40..], &b[..]].concat();
+        let packets = extractor.push(&frame(5, 12, (a.len() - 40) as u16, &zone, 8 + zone.len()));
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].data, b);
+
+        // as does a first header pointer that does not match
+        assert!(extractor.push(&frame(5, 13, 0, &a[..20], 28)).is_empty());
+        let packets = extractor.push(&frame(5, 14, 2, &[&[0, 0][..], &b].concat(), 28));
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].data, b);
+
+        assert!(extractor.push(&[0x40, 5, 0, 0]).is_empty());
+    }
+
+    #[test]
+    fn test_packets() {
+        let config = Config {
+            interleave: 1,
+            fecf: true,
+            ..Config::default()
+        };
+        let layout = MpduLayout::from(&config);
+        assert_eq!(layout.trailer_len, 2);
+        let len = gen::payload_len(&config);
+        let a = packet(3, 300);
+        let frames = [
+            frame(1, 0, 0, &a[..len - 8], len),
+            frame(1, 1, NO_HEADER, &a[len - 8..], len),
+        ];
+        let mut stream = gen::cadus(&config, &frames.concat(), 0);
+        let found: Vec<Packet> = packets(&stream[..], config.clone())
+            .map(|p| p.unwrap())
+            .collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].data, a);
+
+        // an uncorrectable frame drops the packet
+        for x in stream[10..30].iter_mut() {
+            *x ^= 0xff;
+        }
+        assert_eq!(packets(&stream[..], config).count(), 0);
+    }
+}
@@ -0,0 +1,533 @@
+//! Statistics accumulated over the RS decoding of a pass.
+//!
+//! ```
+//! use rs2::stats::DecodeSummary;
+//!
+//! let mut summary = DecodeSummary::default();
+//! let block = rs2::correct_message(&[0u8; 255]);
+//! summary.add(&block.state, 255);
+//! println!("{summary}");
+//! ```
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::pipeline::Frame;
+use crate::{Codeblock, RSState, N};
+
+/// Counts of the RS decoding results for a number of messages.
+///
+/// Summaries from multiple threads, files, etc. may be combined using
+/// [DecodeSummary::merge].
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodeSummary {
+    /// Messages without errors.
+    pub ok: u64,
+    /// Messages with errors that were corrected.
+    pub corrected: u64,
+    /// Messages with errors that could not be corrected.
+    pub uncorrectable: u64,
+    /// Messages for which RS was not performed.
+    pub not_performed: u64,
+    /// Total number of symbols corrected across all corrected messages.
+    pub corrected_symbols: u64,
+    /// Total number of bits corrected across all corrected messages. This is only
+    /// counted by [DecodeSummary::add_codeblock] and [DecodeSummary::add_frame], since
+    /// an [RSState] does not include it.
+    pub corrected_bits: u64,
+    /// Number of frames recognized as idle and not decoded, whose messages are not
+    /// otherwise counted; see [crate::pipeline::Config::idle]. This is only counted
+    /// by [DecodeSummary::add_frame].
+    pub idle_frames: u64,
+    /// Total number of message bytes processed.
+    pub bytes: u64,
+}
+
+impl DecodeSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the result for a single message of `len` bytes.
+    pub fn add(&mut self, state: &RSState, len: usize) {
+        match state {
+            RSState::Ok => self.ok += 1,
+            RSState::Corrected(n) => {
+                self.corrected += 1;
+                self.corrected_symbols += *n as u64;
+            }
+            RSState::Uncorrectable(_) => self.uncorrectable += 1,
+            RSState::NotPerformed => self.not_performed += 1,
+        }
+        self.bytes += len as u64;
+    }
+
+    /// Record the results for each message in a codeblock. Each message is counted as
+    /// 255 bytes.
+    pub fn add_codeblock(&mut self, codeblock: &Codeblock) {
+        for state in &codeblock.states {
+            self.add(state, N as usize);
+        }
+        self.corrected_bits += codeblock.corrected_bits;
+    }
+
+    /// Record the results for each message of a frame produced by a
+    /// [crate::pipeline::Pipeline]. Each message is counted as 255 bytes.
+    pub fn add_frame(&mut self, frame: &Frame) {
+        if frame.idle {
+            self.idle_frames += 1;
+            return;
+        }
+        for state in &frame.states {
+            self.add(state, N as usize);
+        }
+        self.corrected_bits += frame.corrected_bits;
+    }
+
+    /// Add the counts from `other` to this summary.
+    pub fn merge(&mut self, other: &DecodeSummary) {
+        self.ok += other.ok;
+        self.corrected += other.corrected;
+        self.uncorrectable += other.uncorrectable;
+        self.not_performed += other.not_performed;
+        self.corrected_symbols += other.corrected_symbols;
+        self.corrected_bits += other.corrected_bits;
+        self.idle_frames += other.idle_frames;
+        self.bytes += other.bytes;
+    }
+
+    /// Total number of messages recorded.
+    pub fn total(&self) -> u64 {
+        self.ok + self.corrected + self.uncorrectable + self.not_performed
+    }
+}
+
+impl fmt::Display for DecodeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "messages={} ok={} corrected={} uncorrectable={} not_performed={} corrected_symbols={} corrected_bits={} idle_frames={} bytes={}",
+            self.total(),
+            self.ok,
+            self.corrected,
+            self.uncorrectable,
+            self.not_performed,
+            self.corrected_symbols,
+            self.corrected_bits,
+            self.idle_frames,
+            self.bytes
+        )
+    }
+}
+
+/// Number of messages by the number of symbols corrected, for messages that were
+/// [RSState::Ok] or [RSState::Corrected].
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorHistogram {
+    counts: Vec<u64>,
+}
+
+impl ErrorHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the result for a single message.
+    pub fn add(&mut self, state: &RSState) {
+        let n = match state {
+            RSState::Ok => 0,
+            RSState::Corrected(n) => *n as usize,
+            RSState::Uncorrectable(_) | RSState::NotPerformed => return,
+        };
+        if self.counts.len() <= n {
+            self.counts.resize(n + 1, 0);
+        }
+        self.counts[n] += 1;
+    }
+
+    /// Add the counts from `other` to this histogram.
+    pub fn merge(&mut self, other: &ErrorHistogram) {
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (a, b) in self.counts.iter_mut().zip(&other.counts) {
+            *a += b;
+        }
+    }
+
+    /// Message counts indexed by the number of symbols corrected, up to the largest
+    /// number recorded.
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+}
+
+impl fmt::Display for ErrorHistogram {
+    /// Space separated `symbols:messages` pairs, omitting empty bins.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        for (n, count) in self.counts.iter().enumerate().filter(|(_, c)| **c > 0) {
+            write!(f, "{sep}{n}:{count}")?;
+            sep = " ";
+        }
+        Ok(())
+    }
+}
+
+/// Runs and spacing of the corrected symbols of codeblocks, to tell bursts of errors
+/// from random noise.
+///
+/// Errors from random channel noise are spread evenly, while a channel burst is a run
+/// of consecutive codeblock symbols. An interleaver misconfiguration instead shows as
+/// spacings at multiples of the real interleave depth.
+///
+/// ```
+/// use rs2::stats::BurstStats;
+///
+/// let bursts = BurstStats::from_positions(&[10, 11, 12, 20, 24]);
+/// assert_eq!(bursts.longest_run, 3);
+/// assert_eq!(bursts.to_string(), "symbols=5 runs=3 longest_run=3 spacings=1:2 4:1 8:1");
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BurstStats {
+    /// Number of corrected symbols.
+    pub symbols: u64,
+    /// Number of runs of consecutive corrected symbols, where an isolated symbol is a
+    /// run of 1.
+    pub runs: u64,
+    /// Length of the longest run.
+    pub longest_run: usize,
+    spacings: BTreeMap<usize, u64>,
+}
+
+impl BurstStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The statistics of a single codeblock with corrected symbols at `positions`.
+    pub fn from_positions(positions: &[usize]) -> Self {
+        let mut stats = Self::default();
+        stats.add(positions);
+        stats
+    }
+
+    /// Record the positions of the corrected symbols of a codeblock, e.g.,
+    /// [Codeblock::error_positions], which are sorted first if they are not in
+    /// ascending order. Runs do not continue across codeblocks.
+    pub fn add(&mut self, positions: &[usize]) {
+        if !positions.is_sorted() {
+            let mut sorted = positions.to_vec();
+            sorted.sort_unstable();
+            return self.add(&sorted);
+        }
+        let mut run = 0;
+        for (i, pos) in positions.iter().enumerate() {
+            let spacing = i.checked_sub(1).map(|prev| pos - positions[prev]);
+            if let Some(spacing) = spacing {
+                *self.spacings.entry(spacing).or_default() += 1;
+            }
+            if spacing == Some(1) {
+                run += 1;
+            } else {
+                self.runs += 1;
+                run = 1;
+            }
+            self.longest_run = self.longest_run.max(run);
+        }
+        self.symbols += positions.len() as u64;
+    }
+
+    /// Record the corrected symbols of a codeblock.
+    pub fn add_codeblock(&mut self, codeblock: &Codeblock) {
+        self.add(&codeblock.error_positions);
+    }
+
+    /// Record the corrected symbols of a frame produced by a
+    /// [crate::pipeline::Pipeline].
+    pub fn add_frame(&mut self, frame: &Frame) {
+        self.add(&frame.error_positions);
+    }
+
+    /// Add the counts from `other` to these statistics.
+    pub fn merge(&mut self, other: &BurstStats) {
+        self.symbols += other.symbols;
+        self.runs += other.runs;
+        self.longest_run = self.longest_run.max(other.longest_run);
+        for (spacing, count) in &other.spacings {
+            *self.spacings.entry(*spacing).or_default() += count;
+        }
+    }
+
+    /// Number of pairs of successive corrected symbols in the same codeblock by the
+    /// number of symbols from one to the next, e.g., 1 for adjacent symbols.
+    pub fn spacings(&self) -> &BTreeMap<usize, u64> {
+        &self.spacings
+    }
+}
+
+impl fmt::Display for BurstStats {
+    /// Counts followed by space separated `spacing:pairs` pairs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "symbols={} runs={} longest_run={} spacings=",
+            self.symbols, self.runs, self.longest_run
+        )?;
+        let mut sep = "";
+        for (spacing, count) in &self.spacings {
+            write!(f, "{sep}{spacing}:{count}")?;
+            sep = " ";
+        }
+        Ok(())
+    }
+}
+
+/// Counts for messages at one position within their codeblocks; see [SliceStats].
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SliceCounts {
+    /// Total number of symbols corrected.
+    pub corrected_symbols: u64,
+    /// Number of messages that could not be corrected.
+    pub uncorrectable: u64,
+}
+
+/// Counts by interleave slice, i.e., the index of the message within each codeblock,
+/// from 0 to the interleave less one.
+///
+/// Channel errors are spread evenly across the slices by interleaving, so errors
+/// concentrated in some slices point to an upstream problem, e.g., a framing bug or
+/// the wrong interleave depth.
+///
+/// ```
+/// use rs2::stats::SliceStats;
+/// use rs2::RSState;
+///
+/// let mut slices = SliceStats::new();
+/// slices.add(&[RSState::Corrected(1), RSState::Corrected(9)]);
+/// slices.add(&[RSState::Ok, RSState::Corrected(7)]);
+/// assert_eq!(slices.to_string(), "corrected_symbols=1,16 uncorrectable=0,0");
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SliceStats {
+    slices: Vec<SliceCounts>,
+}
+
+impl SliceStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the state of each message of a codeblock, in interleave order as in
+    /// [Codeblock::states].
+    pub fn add(&mut self, states: &[RSState]) {
+        if self.slices.len() < states.len() {
+            self.slices.resize(states.len(), SliceCounts::default());
+        }
+        for (slice, state) in self.slices.iter_mut().zip(states) {
+            match state {
+                RSState::Corrected(n) => slice.corrected_symbols += *n as u64,
+                RSState::Uncorrectable(_) => slice.uncorrectable += 1,
+                RSState::Ok | RSState::NotPerformed => {}
+            }
+        }
+    }
+
+    /// Record the results of a codeblock.
+    pub fn add_codeblock(&mut self, codeblock: &Codeblock) {
+        self.add(&codeblock.states);
+    }
+
+    /// Record the results of a frame produced by a [crate::pipeline::Pipeline].
+    pub fn add_frame(&mut self, frame: &Frame) {
+        self.add(&frame.states);
+    }
+
+    /// Add the counts from `other` to these statistics.
+    pub fn merge(&mut self, other: &SliceStats) {
+        if self.slices.len() < other.slices.len() {
+            self.slices
+                .resize(other.slices.len(), SliceCounts::default());
+        }
+        for (a, b) in self.slices.iter_mut().zip(&other.slices) {
+            a.corrected_symbols += b.corrected_symbols;
+            a.uncorrectable += b.uncorrectable;
+        }
+    }
+
+    /// Counts indexed by slice, up to the largest interleave recorded.
+    pub fn slices(&self) -> &[SliceCounts] {
+        &self.slices
+    }
+}
+
+impl fmt::Display for SliceStats {
+    /// Comma separated counts for each slice.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |count: fn(&SliceCounts) -> u64| {
+            self.slices
+                .iter()
+                .map(|s| count(s).to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        write!(
+            f,
+            "corrected_symbols={} uncorrectable={}",
+            join(|s| s.corrected_symbols),
+            join(|s| s.uncorrectable)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UncorrectableReason;
+
+    #[test]
+    fn test_summary() {
+        let mut a = DecodeSummary::new();
+        a.add(&RSState::Ok, 255);
+        a.add(&RSState::Corrected(3), 255);
+        let mut b = DecodeSummary::new();
+        b.add_codeblock(&Codeblock {
+            states: vec![
+                RSState::Corrected(2),
+                RSState::Uncorrectable(UncorrectableReason::ResidualSyndrome),
+            ],
+            message: None,
+            error_positions: vec![1, 3],
+            corrected_bits: 9,
+        });
+        b.add(&RSState::NotPerformed, 100);
+        b.add_frame(&Frame {
+            offset: 0,
+            data: Some(vec![0u8; 223]),
+            states: vec![RSState::NotPerformed],
+            error_positions: vec![],
+            corrected_bits: 0,
+            fecf_ok: None,
+            inverted: false,
+            idle: true,
+        });
+
+        a.merge(&b);
+
+        assert_eq!(
+            a,
+            DecodeSummary {
+                ok: 1,
+                corrected: 2,
+                uncorrectable: 1,
+                not_performed: 1,
+                corrected_symbols: 5,
+                corrected_bits: 9,
+                idle_frames: 1,
+                bytes: 1120,
+            }
+        );
+        assert_eq!(a.total(), 5);
+        assert_eq!(
+            a.to_string(),
+            "messages=5 ok=1 corrected=2 uncorrectable=1 not_performed=1 corrected_symbols=5 corrected_bits=9 idle_frames=1 bytes=1120"
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut a = ErrorHistogram::new();
+        a.add(&RSState::Ok);
+        a.add(&RSState::Corrected(3));
+        a.add(&RSState::NotPerformed);
+        let mut b = ErrorHistogram::new();
+        b.add(&RSState::Corrected(5));
+        b.add(&RSState::Corrected(3));
+
+        a.merge(&b);
+
+        assert_eq!(a.counts(), &[1, 0, 0, 2, 0, 1]);
+        assert_eq!(a.to_string(), "0:1 3:2 5:1");
+        assert_eq!(ErrorHistogram::new().to_string(), "");
+    }
+
+    #[test]
+    fn test_bursts() {
+        let mut a = BurstStats::from_positions(&[0, 1, 2, 3, 100, 200, 201]);
+        assert_eq!((a.symbols, a.runs, a.longest_run), (7, 3, 4), "{a}");
+        assert_eq!(a.spacings(), &BTreeMap::from([(1, 4), (97, 1), (100, 1)]));
+        // unsorted positions are the same as sorted
+        assert_eq!(
+            BurstStats::from_positions(&[201, 3, 100, 0, 2, 200, 1]),
+            BurstStats::from_positions(&[0, 1, 2, 3, 100, 200, 201])
+        );
+
+        let mut b = BurstStats::new();
+        b.add(&[]);
+        b.add(&[5]);
+        b.add_codeblock(&Codeblock {
+            states: vec![RSState::Corrected(2)],
+            message: None,
+            error_positions: vec![6, 10],
+            corrected_bits: 2,
+        });
+        assert_eq!((b.symbols, b.runs, b.longest_run), (3, 3, 1));
+
+        a.merge(&b);
+        assert_eq!(
+            a.to_string(),
+            "symbols=10 runs=6 longest_run=4 spacings=1:4 4:1 97:1 100:1"
+        );
+        assert_eq!(
+            BurstStats::new().to_string(),
+            "symbols=0 runs=0 longest_run=0 spacings="
+        );
+    }
+
+    #[test]
+    fn test_slices() {
+        let mut a = SliceStats::new();
+        a.add(&[RSState::Corrected(2), RSState::Ok]);
+        a.add_codeblock(&Codeblock {
+            states: vec![
+                RSState::Ok,
+                RSState::Uncorrectable(UncorrectableReason::ResidualSyndrome),
+                RSState::Corrected(4),
+            ],
+            message: None,
+            error_positions: vec![],
+            corrected_bits: 0,
+        });
+        let mut b = SliceStats::new();
+        b.add(&[RSState::NotPerformed, RSState::Corrected(1)]);
+
+        b.merge(&a);
+
+        assert_eq!(
+            b.slices(),
+            &[
+                SliceCounts {
+                    corrected_symbols: 2,
+                    uncorrectable: 0
+                },
+                SliceCounts {
+                    corrected_symbols: 1,
+                    uncorrectable: 1
+                },
+                SliceCounts {
+                    corrected_symbols: 4,
+                    uncorrectable: 0
+                },
+            ]
+        );
+        assert_eq!(b.to_string(), "corrected_symbols=2,1,4 uncorrectable=0,1,0");
+        assert_eq!(
+            SliceStats::new().to_string(),
+            "corrected_symbols= uncorrectable="
+        );
+    }
+}
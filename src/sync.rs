@@ -0,0 +1,774 @@
+//! Attached Sync Marker (ASM) frame synchronization for extracting fixed length
+//! CADUs from a raw byte stream.
+//!
+//! A CADU is only emitted once it is confirmed to be complete, i.e., it is followed by
+//! another ASM or the stream ends exactly at the end of the CADU. Anything else, such
+//! as partial CADUs, test patterns or recorder artifacts between CADUs, is skipped and
+//! reported as an [Event::Skipped] byte range so messy recordings can be decoded
+//! without passing corrupt CADUs on to the decoder.
+//!
+//! Alternatively, [Synchronizer::with_lock] uses the classic SEARCH/CHECK/LOCK/FLYWHEEL
+//! state machine, see [LockConfig], which continues to extract CADUs when ASMs are
+//! corrupted by bit errors.
+//!
+//! The ASM complement is also searched for, so inverted streams, e.g., due to a BPSK
+//! phase ambiguity, are detected and their CADUs inverted back; see [Cadu::inverted].
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use rs2::sync::{Event, Synchronizer};
+//!
+//! let file = File::open("overpass.dat").unwrap();
+//! for event in Synchronizer::new(file, 1020) {
+//!     match event.unwrap() {
+//!         Event::Cadu(mut cadu) => {
+//!             rs2::pn::derandomize(&mut cadu.data);
+//!             let block = rs2::correct_codeblock(&cadu.data, 4);
+//!             println!("{}: {:?}", cadu.offset, block.states);
+//!         }
+//!         Event::Skipped(range) => println!("skipped {range:?}"),
+//!         event => println!("{event:?}"),
+//!     }
+//! }
+//! ```
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Result};
+use std::ops::Range;
+use std::task::Poll;
+
+/// The standard CCSDS attached sync marker.
+pub const ASM: [u8; 4] = [0x1a, 0xcf, 0xfc, 0x1d];
+
+const READ_SIZE: usize = 8192;
+
+/// A CADU extracted by the [Synchronizer].
+#[derive(Debug, PartialEq, Clone)]
+pub struct Cadu {
+    /// Byte offset of the start of the ASM in the input stream. If the stream is not
+    /// byte aligned this is the offset of the byte containing the first bit of the ASM.
+    pub offset: u64,
+    /// CADU contents following the ASM, e.g., the possibly randomized codeblock.
+    pub data: Vec<u8>,
+    /// True if the ASM was not found and the CADU was extracted because the
+    /// synchronizer was in [LockState::Flywheel].
+    pub flywheel: bool,
+    /// True if the ASM was found complemented, i.e., the stream polarity is inverted,
+    /// in which case `data` has already been inverted back.
+    pub inverted: bool,
+}
+
+/// Output of the [Synchronizer].
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    /// A complete CADU was found.
+    Cadu(Cadu),
+    /// Input stream byte range that did not belong to any complete CADU and was
+    /// discarded.
+    Skipped(Range<u64>),
+    /// Lock was acquired at the provided stream offset, i.e., the offset of the first
+    /// CADU of the locked sequence. Only produced when using [LockConfig].
+    Locked(u64),
+    /// Lock was lost at the provided stream offset, i.e., where the expected ASM was
+    /// missing. Only produced when using [LockConfig].
+    Unlocked(u64),
+}
+
+/// State of the synchronizer lock state machine.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LockState {
+    /// Scanning for an ASM at any offset.
+    Search,
+    /// An ASM was found and the following ASMs are being checked before declaring lock.
+    Check,
+    /// ASMs are found where expected.
+    Lock,
+    /// Locked, but the most recent expected ASMs were missing.
+    Flywheel,
+}
+
+/// Configuration of the SEARCH/CHECK/LOCK/FLYWHEEL lock state machine.
+///
+/// Once an ASM is found, lock is declared after `check` further ASMs are found at the
+/// expected CADU boundaries. Once locked, CADUs are extracted at the expected
+/// boundaries even if the ASM is missing, for up to `flywheel` consecutive missing
+/// ASMs, after which lock is lost and the search resumes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LockConfig {
+    pub check: usize,
+    pub flywheel: usize,
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        LockConfig {
+            check: 2,
+            flywheel: 3,
+        }
+    }
+}
+
+/// Scans a byte stream for the ASM and extracts fixed length CADUs.
+pub struct Synchronizer<R> {
+    reader: R,
+    scanner: Scanner,
+}
+
+impl<R: Read> Synchronizer<R> {
+    /// Create a synchronizer for CADUs using the standard [ASM] where `block_len` is
+    /// the length of the CADU excluding the ASM, e.g., 1020 for an interleave of 4.
+    pub fn new(reader: R, block_len: usize) -> Self {
+        Self::with_asm(reader, &ASM, block_len)
+    }
+
+    /// Create a synchronizer using a non-standard sync marker.
+    ///
+    /// # Panics
+    /// If `asm` is empty.
+    pub fn with_asm(reader: R, asm: &[u8], block_len: usize) -> Self {
+        Synchronizer {
+            reader,
+            scanner: Scanner::new(asm, block_len, None),
+        }
+    }
+
+    /// Create a synchronizer using the lock state machine rather than requiring each
+    /// CADU to be followed by an ASM. This tolerates missing ASMs, e.g., due to bit
+    /// errors, at the cost of possibly extracting CADUs at stale boundaries until the
+    /// flywheel expires.
+    ///
+    /// # Panics
+    /// If `asm` is empty.
+    pub fn with_lock(reader: R, asm: &[u8], block_len: usize, lock: LockConfig) -> Self {
+        Synchronizer {
+            reader,
+            scanner: Scanner::new(asm, block_len, Some(lock)),
+        }
+    }
+
+    /// Accept ASMs with up to `threshold` bit errors. The default of 0 requires an
+    /// exact match.
+    ///
+    /// The ASM is correlated at every bit offset, so streams that are not byte aligned
+    /// or that slip bits are also handled, whereas extracted CADUs are always byte
+    /// aligned relative to their ASM.
+    pub fn threshold(mut self, threshold: u32) -> Self {
+        self.scanner = self.scanner.with_threshold(threshold);
+        self
+    }
+
+    /// Current lock state. Without a [LockConfig] this is always [LockState::Search].
+    pub fn state(&self) -> LockState {
+        self.scanner.state
+    }
+}
+
+impl<R: Read> Iterator for Synchronizer<R> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut tmp = [0u8; READ_SIZE];
+        loop {
+            match self.scanner.next_event() {
+                Step::Event(event) => return Some(Ok(event)),
+                Step::Done => return None,
+                Step::NeedData => match self.reader.read(&mut tmp) {
+                    Ok(n) => self.scanner.push(&tmp[..n]),
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err) => return Some(Err(err)),
+                },
+            }
+        }
+    }
+}
+
+/// Result of [Scanner::next_event].
+pub(crate) enum Step {
+    Event(Event),
+    /// More data must be pushed before the next event can be determined.
+    NeedData,
+    /// The end of the stream was reached and there are no more events.
+    Done,
+}
+
+/// I/O free synchronization state machine shared by the blocking and async
+/// synchronizers. Data is provided using [Scanner::push].
+pub(crate) struct Scanner {
+    asm: Vec<u8>,
+    block_len: usize,
+    buf: Vec<u8>,
+    // stream offset of buf[0]
+    offset: u64,
+    // bit offset within buf[0] of the current alignment
+    shift: u32,
+    // maximum number of bit errors in a matching ASM
+    threshold: u32,
+    // whether the ASM was found complemented, i.e., the stream is inverted
+    inverted: bool,
+    eof: bool,
+    // start of the range of bytes skipped since the last emitted CADU
+    skip_start: Option<u64>,
+    pending: VecDeque<Event>,
+    lock: Option<LockConfig>,
+    state: LockState,
+    // consecutive missing ASMs while locked
+    misses: usize,
+}
+
+impl Scanner {
+    pub(crate) fn new(asm: &[u8], block_len: usize, lock: Option<LockConfig>) -> Self {
+        assert!(!asm.is_empty(), "asm must not be empty");
+        Scanner {
+            asm: asm.to_vec(),
+            block_len,
+            buf: Vec::new(),
+            offset: 0,
+            shift: 0,
+            threshold: 0,
+            inverted: false,
+            eof: false,
+            skip_start: None,
+            pending: VecDeque::new(),
+            lock,
+            state: LockState::Search,
+            misses: 0,
+        }
+    }
+
+    /// Set the maximum number of bit errors allowed in a matching ASM.
+    pub(crate) fn with_threshold(mut self, threshold: u32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Add data read from the stream, where empty data indicates the end of the
+    /// stream.
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            self.eof = true;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    fn cadu_len(&self) -> usize {
+        self.asm.len() + self.block_len
+    }
+
+    /// Number of complete bytes available at the current bit alignment.
+    fn avail(&self) -> usize {
+        match self.shift {
+            0 => self.buf.len(),
+            _ => self.buf.len().saturating_sub(1),
+        }
+    }
+
+    /// Byte `idx` of the buffer at the current bit alignment and polarity.
+    fn byte(&self, idx: usize) -> u8 {
+        let byte = aligned_byte(&self.buf, idx, self.shift);
+        if self.inverted {
+            !byte
+        } else {
+            byte
+        }
+    }
+
+    /// Discard `n` bytes from the start of the buffer.
+    fn skip(&mut self, n: usize) {
+        self.skip_start.get_or_insert(self.offset);
+        self.buf.drain(..n);
+        self.offset += n as u64;
+    }
+
+    /// Discard the remainder of the buffer at the end of the stream. Trailing bits
+    /// that do not make up a complete byte at the current alignment are ignored.
+    fn skip_all(&mut self) {
+        let n = self.buf.len();
+        if self.avail() > 0 {
+            self.skip(n);
+        } else {
+            self.buf.clear();
+            self.offset += n as u64;
+        }
+        self.shift = 0;
+    }
+
+    /// Discard a single bit from the start of the buffer.
+    fn skip_bit(&mut self) {
+        self.skip_start.get_or_insert(self.offset);
+        self.shift += 1;
+        if self.shift == 8 {
+            self.shift = 0;
+            self.skip(1);
+        }
+    }
+
+    fn take_skipped(&mut self) {
+        if let Some(start) = self.skip_start.take() {
+            if start < self.offset {
+                self.pending.push_back(Event::Skipped(start..self.offset));
+            }
+        }
+    }
+
+    /// Number of bit errors between the ASM and the buffer at byte `idx` and
+    /// `shift`, stopping early once the threshold is exceeded.
+    fn distance(&self, idx: usize, shift: u32, inverted: bool) -> u32 {
+        let flip = if inverted { 0xff } else { 0 };
+        let mut errors = 0;
+        for (i, x) in self.asm.iter().enumerate() {
+            errors += (aligned_byte(&self.buf, idx + i, shift) ^ x ^ flip).count_ones();
+            if errors > self.threshold {
+                break;
+            }
+        }
+        errors
+    }
+
+    /// Correlate the ASM, and its complement, at every bit offset starting at the
+    /// current alignment, returning the byte index, bit shift, and polarity of the
+    /// first match.
+    fn find_asm(&self) -> Option<(usize, u32, bool)> {
+        let asm_len = self.asm.len();
+        let mut idx = 0;
+        let mut shift = self.shift;
+        while idx + asm_len + (shift > 0) as usize <= self.buf.len() {
+            for inverted in [false, true] {
+                if self.distance(idx, shift, inverted) <= self.threshold {
+                    return Some((idx, shift, inverted));
+                }
+            }
+            shift += 1;
+            if shift == 8 {
+                shift = 0;
+                idx += 1;
+            }
+        }
+        None
+    }
+
+    /// Skip to the first ASM in the buffer. False if there is none, in which case
+    /// everything but enough bytes to find an ASM split across reads is skipped.
+    fn seek_asm(&mut self) -> bool {
+        match self.find_asm() {
+            Some((idx, shift, inverted)) => {
+                if idx > 0 || shift != self.shift {
+                    self.skip_start.get_or_insert(self.offset);
+                }
+                if idx > 0 {
+                    self.skip(idx);
+                }
+                self.shift = shift;
+                self.inverted = inverted;
+                true
+            }
+            None => {
+                let n = self.buf.len().saturating_sub(self.asm.len());
+                if n > 0 {
+                    self.skip(n);
+                    self.shift = 0;
+                }
+                false
+            }
+        }
+    }
+
+    fn asm_at(&self, pos: usize) -> bool {
+        self.distance(pos, self.shift, self.inverted) <= self.threshold
+    }
+
+    /// Queue the CADU at the start of the buffer, preceded by any skipped bytes.
+    fn emit_cadu(&mut self, flywheel: bool) {
+        self.take_skipped();
+        let cadu_len = self.cadu_len();
+        let cadu = Cadu {
+            offset: self.offset,
+            data: (self.asm.len()..cadu_len).map(|i| self.byte(i)).collect(),
+            flywheel,
+            inverted: self.inverted,
+        };
+        self.buf.drain(..cadu_len);
+        self.offset += cadu_len as u64;
+        self.pending.push_back(Event::Cadu(cadu));
+    }
+
+    /// Queue the next confirmed CADU, skipping any bytes preceding it. Ready(false)
+    /// at the end of the stream.
+    fn next_cadu(&mut self) -> Poll<bool> {
+        let asm_len = self.asm.len();
+        let cadu_len = self.cadu_len();
+        loop {
+            if self.avail() < cadu_len + asm_len && !self.eof {
+                return Poll::Pending;
+            }
+            if !self.seek_asm() {
+                if self.eof {
+                    self.skip_all();
+                    return Poll::Ready(false);
+                }
+                continue;
+            }
+            if self.avail() < cadu_len + asm_len && !self.eof {
+                return Poll::Pending;
+            }
+            if self.avail() < cadu_len {
+                // not enough data left for a complete CADU
+                self.skip_all();
+                return Poll::Ready(false);
+            }
+
+            // A CADU is confirmed by the next ASM or by the stream ending exactly at
+            // the end of the CADU.
+            let confirmed = if self.avail() >= cadu_len + asm_len {
+                self.asm_at(cadu_len)
+            } else {
+                self.eof && self.avail() == cadu_len
+            };
+            if !confirmed {
+                // Not a complete CADU, e.g., a partial CADU or an ASM pattern in
+                // garbage data, so resume searching just past this ASM.
+                self.skip_bit();
+                continue;
+            }
+
+            self.emit_cadu(false);
+            return Poll::Ready(true);
+        }
+    }
+
+    /// Queue the next events of the lock state machine. Ready(false) at the end of
+    /// the stream.
+    fn next_locked(&mut self, lock: LockConfig) -> Poll<bool> {
+        let asm_len = self.asm.len();
+        let cadu_len = self.cadu_len();
+        loop {
+            match self.state {
+                LockState::Search => {
+                    if self.seek_asm() {
+                        self.state = LockState::Check;
+                    } else if self.eof {
+                        self.skip_all();
+                        return Poll::Ready(false);
+                    } else {
+                        return Poll::Pending;
+                    }
+                }
+                LockState::Check => {
+                    let need = lock.check * cadu_len + asm_len;
+                    if self.avail() < need && !self.eof {
+                        return Poll::Pending;
+                    }
+                    // number of consecutive CADU boundaries with an ASM
+                    let mut hits = 1;
+                    while hits <= lock.check && (hits * cadu_len + asm_len) <= self.avail() {
+                        if !self.asm_at(hits * cadu_len) {
+                            break;
+                        }
+                        hits += 1;
+                    }
+                    let confirmed = hits > lock.check
+                        // the stream ended exactly at the end of the last CADU
+                        || (self.eof && self.avail() == hits * cadu_len);
+                    if !confirmed {
+                        self.skip_bit();
+                        self.state = LockState::Search;
+                        continue;
+                    }
+                    self.take_skipped();
+                    self.pending.push_back(Event::Locked(self.offset));
+                    for _ in 0..hits.min(lock.check).max(1) {
+                        if self.avail() >= cadu_len {
+                            self.emit_cadu(false);
+                        }
+                    }
+                    self.state = LockState::Lock;
+                    self.misses = 0;
+                    return Poll::Ready(true);
+                }
+                LockState::Lock | LockState::Flywheel => {
+                    if self.avail() < cadu_len {
+                        if !self.eof {
+                            return Poll::Pending;
+                        }
+                        self.skip_all();
+                        return Poll::Ready(false);
+                    }
+                    if self.asm_at(0) {
+                        self.state = LockState::Lock;
+                        self.misses = 0;
+                        self.emit_cadu(false);
+                        return Poll::Ready(true);
+                    }
+                    self.misses += 1;
+                    if self.misses > lock.flywheel {
+                        self.pending.push_back(Event::Unlocked(self.offset));
+                        self.state = LockState::Search;
+                        self.misses = 0;
+                        return Poll::Ready(true);
+                    }
+                    self.state = LockState::Flywheel;
+                    self.emit_cadu(true);
+                    return Poll::Ready(true);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn next_event(&mut self) -> Step {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Step::Event(event);
+            }
+            let progress = match self.lock {
+                None => self.next_cadu(),
+                Some(lock) => self.next_locked(lock),
+            };
+            match progress {
+                Poll::Pending => return Step::NeedData,
+                Poll::Ready(true) => continue,
+                Poll::Ready(false) => {
+                    self.take_skipped();
+                    if self.pending.is_empty() {
+                        return Step::Done;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Byte `idx` of `buf` after shifting the whole buffer left by `shift` bits.
+fn aligned_byte(buf: &[u8], idx: usize, shift: u32) -> u8 {
+    match shift {
+        0 => buf[idx],
+        _ => (buf[idx] << shift) | (buf[idx + 1] >> (8 - shift)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK_LEN: usize = 20;
+
+    fn cadu(fill: u8) -> Vec<u8> {
+        let mut buf = ASM.to_vec();
+        buf.extend_from_slice(&[fill; BLOCK_LEN]);
+        buf
+    }
+
+    fn events(input: &[u8]) -> Vec<Event> {
+        Synchronizer::new(input, BLOCK_LEN)
+            .collect::<Result<Vec<Event>>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sync_clean() {
+        let mut input = cadu(1);
+        input.extend(cadu(2));
+
+        let zult = events(&input);
+
+        assert_eq!(
+            zult,
+            vec![
+                Event::Cadu(Cadu {
+                    offset: 0,
+                    data: vec![1; BLOCK_LEN],
+                    flywheel: false,
+                    inverted: false,
+                }),
+                Event::Cadu(Cadu {
+                    offset: 24,
+                    data: vec![2; BLOCK_LEN],
+                    flywheel: false,
+                    inverted: false,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sync_garbage() {
+        let mut input = vec![0x55; 7];
+        input.extend(cadu(1));
+        // partial CADU followed by garbage containing a partial ASM
+        input.extend(&cadu(2)[..10]);
+        input.extend([0xaa, 0x1a, 0xcf, 0xaa]);
+        input.extend(cadu(3));
+        input.extend(cadu(4));
+        // trailing partial CADU
+        input.extend(&cadu(5)[..BLOCK_LEN]);
+
+        let zult = events(&input);
+
+        assert_eq!(zult.len(), 6, "{zult:?}");
+        assert_eq!(zult[0], Event::Skipped(0..7));
+        assert!(matches!(&zult[1], Event::Cadu(c) if c.offset == 7 && c.data[0] == 1));
+        assert_eq!(zult[2], Event::Skipped(31..45));
+        assert!(matches!(&zult[3], Event::Cadu(c) if c.offset == 45 && c.data[0] == 3));
+        assert!(matches!(&zult[4], Event::Cadu(c) if c.offset == 69 && c.data[0] == 4));
+        assert_eq!(zult[5], Event::Skipped(93..113));
+    }
+
+    #[test]
+    fn test_sync_no_asm() {
+        let input = vec![0u8; 100];
+        assert_eq!(events(&input), vec![Event::Skipped(0..100)]);
+    }
+
+    fn locked_events(input: &[u8], lock: LockConfig) -> Vec<Event> {
+        Synchronizer::with_lock(input, &ASM, BLOCK_LEN, lock)
+            .collect::<Result<Vec<Event>>>()
+            .unwrap()
+    }
+
+    fn summarize(events: &[Event]) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| match e {
+                Event::Cadu(c) if c.flywheel => format!("F{}", c.offset),
+                Event::Cadu(c) => format!("C{}", c.offset),
+                Event::Skipped(r) => format!("S{}-{}", r.start, r.end),
+                Event::Locked(o) => format!("L{o}"),
+                Event::Unlocked(o) => format!("U{o}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sync_lock_flywheel() {
+        let mut input = vec![0x55; 3];
+        for i in 0..4 {
+            input.extend(cadu(i));
+        }
+        // 2 CADUs with corrupt ASMs are flywheeled through
+        let mut bad = cadu(4);
+        bad[0] = 0;
+        input.extend(&bad);
+        input.extend(&bad);
+        input.extend(cadu(5));
+        // 3 corrupt ASMs exceed the flywheel and unlock
+        for _ in 0..3 {
+            input.extend(&bad);
+        }
+        input.extend(cadu(6));
+        input.extend(cadu(7));
+        input.extend(cadu(8));
+
+        let lock = LockConfig {
+            check: 2,
+            flywheel: 2,
+        };
+        let zult = summarize(&locked_events(&input, lock));
+
+        assert_eq!(
+            zult,
+            vec![
+                "S0-3", "L3", "C3", "C27", "C51", "C75", "F99", "F123", "C147", "F171", "F195",
+                "U219", "S219-243", "L243", "C243", "C267", "C291",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_sync_lock_check_fails() {
+        // an isolated ASM pattern followed by garbage never locks
+        let mut input = vec![0u8; 10];
+        input.extend(cadu(1));
+        input.extend([0u8; 30]);
+        input.extend(cadu(2));
+        input.extend(cadu(3));
+
+        let zult = summarize(&locked_events(
+            &input,
+            LockConfig {
+                check: 1,
+                flywheel: 0,
+            },
+        ));
+
+        assert_eq!(zult, vec!["S0-64", "L64", "C64", "C88"]);
+    }
+
+    /// Shift `data` right by `bits`, i.e., prefix with `bits` zero bits.
+    fn shift_bits(data: &[u8], bits: u32) -> Vec<u8> {
+        let mut out = vec![0u8; data.len() + 1];
+        for (i, x) in data.iter().enumerate() {
+            out[i] |= x >> bits;
+            out[i + 1] |= x.checked_shl(8 - bits).unwrap_or(0);
+        }
+        out
+    }
+
+    #[test]
+    fn test_sync_bit_aligned() {
+        let mut input = vec![0x00; 5];
+        for i in 0..3 {
+            input.extend(cadu(i));
+        }
+        for bits in 1..8 {
+            let shifted = shift_bits(&input, bits);
+
+            let zult = events(&shifted);
+
+            assert_eq!(zult.len(), 4, "bits={bits} {zult:?}");
+            assert_eq!(zult[0], Event::Skipped(0..5));
+            for (i, event) in zult[1..].iter().enumerate() {
+                let Event::Cadu(cadu) = event else {
+                    panic!("expected cadu");
+                };
+                assert_eq!(cadu.offset, 5 + 24 * i as u64);
+                assert_eq!(cadu.data, vec![i as u8; BLOCK_LEN], "bits={bits}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_sync_threshold() {
+        let mut input = cadu(1);
+        let mut bad = cadu(2);
+        bad[0] ^= 0x81;
+        bad[2] ^= 0x10;
+        input.extend(&bad);
+        input.extend(cadu(3));
+
+        let count = |threshold| {
+            Synchronizer::new(&input[..], BLOCK_LEN)
+                .threshold(threshold)
+                .filter(|e| matches!(e, Ok(Event::Cadu(_))))
+                .count()
+        };
+
+        // the corrupt ASM means the first CADU cannot be confirmed either
+        assert_eq!(count(0), 1);
+        assert_eq!(count(2), 1);
+        assert_eq!(count(3), 3);
+    }
+
+    #[test]
+    fn test_sync_inverted() {
+        let mut input = vec![0x00; 3];
+        input.extend(cadu(1));
+        let mut inverted: Vec<u8> = [cadu(2), cadu(3)].concat();
+        inverted.iter_mut().for_each(|x| *x = !*x);
+        input.extend(inverted);
+
+        let zult = events(&input);
+
+        // the first CADU is not confirmed by the following, inverted, ASM
+        assert_eq!(zult.len(), 3, "{zult:?}");
+        assert_eq!(zult[0], Event::Skipped(0..27));
+        for (event, fill) in zult[1..].iter().zip([2, 3]) {
+            let Event::Cadu(cadu) = event else {
+                panic!("expected cadu");
+            };
+            assert!(cadu.inverted);
+            assert_eq!(cadu.data, vec![fill; BLOCK_LEN]);
+        }
+    }
+    #[test]
+    #[should_panic(expected = "asm must not be empty")]
+    fn test_empty_asm() {
+        Synchronizer::with_asm(&[0u8; 40][..], &[], BLOCK_LEN);
+    }
+}
@@ -0,0 +1,196 @@
+//! Incremental syndrome computation for codeblocks received in chunks.
+//!
+//! The syndromes are most of the work of decoding a codeblock without errors, and
+//! all of the work of finding that it has none. A [SyndromeAccumulator] is fed the
+//! codeblock as bytes arrive from the link, so that work overlaps reception and the
+//! result is available as soon as the last byte is received.
+//!
+//! ```
+//! use rs2::syndromes::SyndromeAccumulator;
+//! use rs2::Code;
+//!
+//! let codeblock = [0u8; 2 * 255];
+//! let mut acc = SyndromeAccumulator::new(Code::E16, 2).unwrap();
+//! for chunk in codeblock.chunks(100) {
+//!     acc.update(chunk);
+//! }
+//! assert!(acc.is_complete());
+//! assert!(!acc.has_errors());
+//! ```
+use crate::algo::Poly;
+use crate::{dual_basis, gf, Code, UncorrectableReason, MAX_INTERLEAVE, N, PARITY_LEN};
+
+/// Syndromes of each message of an interleaved codeblock, accumulated as the
+/// codeblock is received.
+///
+/// The codeblock bytes are in dual basis, as transmitted, and derandomized.
+#[derive(Debug, Clone)]
+pub struct SyndromeAccumulator {
+    code: Code,
+    interleave: u8,
+    synd: [[u8; PARITY_LEN]; MAX_INTERLEAVE as usize],
+    len: usize,
+}
+
+impl SyndromeAccumulator {
+    /// Create an accumulator for codeblocks of `interleave` messages of `code`.
+    pub fn new(code: Code, interleave: u8) -> Result<Self, UncorrectableReason> {
+        if !(1..=MAX_INTERLEAVE).contains(&interleave) {
+            return Err(UncorrectableReason::InvalidInterleave(interleave));
+        }
+        Ok(SyndromeAccumulator {
+            code,
+            interleave,
+            synd: [[0u8; PARITY_LEN]; MAX_INTERLEAVE as usize],
+            len: 0,
+        })
+    }
+
+    /// Length of each codeblock.
+    pub fn block_len(&self) -> usize {
+        self.interleave as usize * N as usize
+    }
+
+    /// Number of codeblock bytes received so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// True once the whole codeblock has been received.
+    pub fn is_complete(&self) -> bool {
+        self.len == self.block_len()
+    }
+
+    /// Clear the syndromes for the next codeblock.
+    pub fn reset(&mut self) {
+        self.synd = [[0u8; PARITY_LEN]; MAX_INTERLEAVE as usize];
+        self.len = 0;
+    }
+
+    /// Add the next bytes of the codeblock, returning the number used. Bytes past the
+    /// end of the codeblock are not used, e.g., they start the next CADU, so the
+    /// count is less than `chunk.len()` once the codeblock is complete.
+    pub fn update(&mut self, chunk: &[u8]) -> usize {
+        let chunk = &chunk[..chunk.len().min(self.block_len() - self.len)];
+        let depth = self.interleave as usize;
+        let parity_len = self.code.parity_len();
+        let roots = self.code.roots();
+        // Each message's symbols in the chunk are evaluated like a message of their
+        // own, and the existing syndromes shifted past them, i.e., Horner's method a
+        // chunk at a time.
+        let mut symbols = [0u8; N as usize];
+        let mut evals = [0u8; PARITY_LEN];
+        for first in 0..depth.min(chunk.len()) {
+            let mut n = 0;
+            for x in chunk.iter().skip(first).step_by(depth) {
+                symbols[n] = *x;
+                n += 1;
+            }
+            let symbols = &mut symbols[..n];
+            dual_basis::to_conv_in_place(symbols);
+            gf::poly_eval_many(symbols, roots, &mut evals[..parity_len]);
+            let message = (self.len + first) % depth;
+            for (j, (s, e)) in self.synd[message].iter_mut().zip(evals.iter()).enumerate() {
+                // the root to the power of n, i.e., GEN^((j + fcr) * n)
+                let shift = gf::exp(((j as i32 + self.code.fcr()) * n as i32) % 255);
+                *s = gf::mult(*s, shift) ^ e;
+            }
+        }
+        self.len += chunk.len();
+        chunk.len()
+    }
+
+    /// Syndromes of message `message` of the codeblock, the same as
+    /// [crate::algo::calc_syndromes] of the message in conventional basis, once the
+    /// codeblock is complete.
+    ///
+    /// # Panics
+    /// If `message` is not less than the interleave.
+    pub fn syndromes(&self, message: usize) -> Poly {
+        assert!(message < self.interleave as usize, "message out of range");
+        let mut synd = Poly::zeros(1);
+        for s in &self.synd[message][..self.code.parity_len()] {
+            synd.push(*s);
+        }
+        synd
+    }
+
+    /// Return true if message `message` of the complete codeblock has errors.
+    ///
+    /// # Panics
+    /// If `message` is not less than the interleave.
+    pub fn message_has_errors(&self, message: usize) -> bool {
+        assert!(message < self.interleave as usize, "message out of range");
+        self.synd[message].iter().any(|s| *s != 0)
+    }
+
+    /// Return true if any message of the complete codeblock has errors, in which case
+    /// it must be corrected, e.g., using [Code::correct_codeblock]. If false, the
+    /// codeblock is used as received.
+    pub fn has_errors(&self) -> bool {
+        (0..self.interleave as usize).any(|i| self.message_has_errors(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algo::calc_syndromes;
+    use crate::interleave::{deinterleave, interleave};
+    use crate::tests::{shifted_generator, xorshift, FIXTURE_MSG};
+
+    #[test]
+    fn test_accumulator() {
+        let mut corrupt = *FIXTURE_MSG;
+        corrupt[7] ^= 0x10;
+        let clean = interleave(&[&FIXTURE_MSG[..]; 3]);
+        let dirty = interleave(&[&FIXTURE_MSG[..], &FIXTURE_MSG[..], &corrupt]);
+        let mut rng = 0x5eed;
+
+        let mut acc = SyndromeAccumulator::new(Code::E16, 3).unwrap();
+        for block in [&clean, &dirty] {
+            acc.reset();
+            let mut rest = &block[..];
+            while !rest.is_empty() {
+                let n = xorshift(&mut rng) as usize % 40;
+                assert_eq!(acc.update(&rest[..n.min(rest.len())]), n.min(rest.len()));
+                rest = &rest[n.min(rest.len())..];
+            }
+            assert!(acc.is_complete());
+            for (i, msg) in deinterleave(block, 3).enumerate() {
+                let conv = dual_basis::to_conv(&msg.to_vec());
+                let want = calc_syndromes(&conv, 32, Code::E16.fcr());
+                assert_eq!(&acc.syndromes(i)[..], &want[..]);
+            }
+        }
+        assert!(acc.has_errors());
+        assert!(!acc.message_has_errors(0));
+        assert!(acc.message_has_errors(2));
+
+        // bytes of the next codeblock are not used
+        acc.reset();
+        assert_eq!(acc.update(&[clean.clone(), vec![1, 2, 3]].concat()), 765);
+        assert!(!acc.has_errors());
+        assert_eq!(acc.update(&[1]), 0);
+        assert!(SyndromeAccumulator::new(Code::E16, 0).is_err());
+    }
+
+    #[test]
+    fn test_accumulator_e8() {
+        let mut msg = shifted_generator(Code::E8, 10);
+        let mut acc = SyndromeAccumulator::new(Code::E8, 1).unwrap();
+        acc.update(&msg[..100]);
+        acc.update(&msg[100..]);
+        assert!(!acc.has_errors());
+
+        msg[254] ^= 1;
+        acc.reset();
+        acc.update(&msg);
+        assert!(acc.has_errors());
+        assert_eq!(acc.syndromes(0).len(), 17);
+    }
+}
@@ -0,0 +1,113 @@
+//! Control of the threads used by the parallel decode and pipeline runtimes, for
+//! deployments co-resident with other real-time software.
+//!
+//! By default `correct_messages_par`, with the `parallel` feature, runs on the global
+//! rayon pool, with a thread for each CPU, and the [crate::pipeline::ThreadedPipeline]
+//! stages run wherever the OS schedules them. A [ThreadConfig] limits the number of workers of a
+//! dedicated pool and, with the `affinity` feature, pins each thread to a CPU core.
+//!
+//! ```
+//! # #[cfg(feature = "parallel")] {
+//! use rs2::threads::ThreadConfig;
+//!
+//! let pool = ThreadConfig::with_threads(2).build_pool().unwrap();
+//! let blocks = rs2::correct_messages_par_in(&pool, &[0u8; 255 * 8]);
+//! assert_eq!(blocks.len(), 8);
+//! # }
+//! ```
+use std::thread::{self, JoinHandle};
+
+/// Worker count and CPU pinning of a thread pool or pipeline.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ThreadConfig {
+    /// Number of worker threads of a pool. If None, one for each CPU. The
+    /// [crate::pipeline::ThreadedPipeline] always has a thread for each stage, so
+    /// ignores this.
+    pub threads: Option<usize>,
+    /// CPU core ids to pin the threads to, where thread `i` is pinned to core
+    /// `i % cores.len()`, e.g., worker `i` of a pool, or stage `i` of a pipeline in
+    /// stream order. If empty, threads are not pinned. Pinning is best effort, as
+    /// allowed by the OS, and a core id that does not exist is ignored. Requires the
+    /// `affinity` feature.
+    #[cfg(feature = "affinity")]
+    pub cores: Vec<usize>,
+}
+
+impl ThreadConfig {
+    /// `threads` workers, without pinning.
+    // the update is needed for the cores of the affinity feature
+    #[allow(clippy::needless_update)]
+    pub fn with_threads(threads: usize) -> Self {
+        ThreadConfig {
+            threads: Some(threads),
+            ..Default::default()
+        }
+    }
+
+    /// Build a rayon pool, e.g., for [crate::correct_messages_par_in], with the
+    /// configured number of workers and pinning. Requires the `parallel` feature.
+    #[cfg(feature = "parallel")]
+    pub fn build_pool(&self) -> Result<rayon::ThreadPool, rayon::ThreadPoolBuildError> {
+        let config = self.clone();
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads.unwrap_or(0))
+            .start_handler(move |index| config.pin(index))
+            .build()
+    }
+
+    /// Spawn thread `index`, pinned as configured.
+    pub(crate) fn spawn<F, T>(&self, index: usize, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let config = self.clone();
+        thread::spawn(move || {
+            config.pin(index);
+            f()
+        })
+    }
+
+    /// Pin the current thread as thread `index`.
+    #[cfg_attr(not(feature = "affinity"), allow(unused_variables))]
+    fn pin(&self, index: usize) {
+        #[cfg(feature = "affinity")]
+        if !self.cores.is_empty() {
+            let id = self.cores[index % self.cores.len()];
+            core_affinity::set_for_current(core_affinity::CoreId { id });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn() {
+        let handle = ThreadConfig::default().spawn(0, || 7);
+        assert_eq!(handle.join().unwrap(), 7);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_build_pool() {
+        let pool = ThreadConfig::with_threads(3).build_pool().unwrap();
+        assert_eq!(pool.current_num_threads(), 3);
+    }
+
+    #[cfg(feature = "affinity")]
+    #[test]
+    fn test_pinned() {
+        let Some(cores) = core_affinity::get_core_ids() else {
+            return;
+        };
+        let config = ThreadConfig {
+            cores: vec![cores[0].id],
+            ..Default::default()
+        };
+        // every thread is pinned to the single core, whatever its index
+        let handle = config.spawn(5, || core_affinity::get_core_ids().map(|ids| ids.len()));
+        assert_eq!(handle.join().unwrap(), Some(1));
+    }
+}
@@ -0,0 +1,293 @@
+//! Ingest of CADUs delivered over UDP, as by many frame synchronizer appliances.
+//!
+//! Each datagram carries either a single CADU or any number of CADUs each prefixed
+//! with its length; see [Framing]. CADUs are decoded using the same [Config] as a
+//! [crate::pipeline::Pipeline], but no sync search is done since the datagrams
+//! already delimit the CADUs.
+//!
+//! ```no_run
+//! use rs2::pipeline::Config;
+//! use rs2::udp::{Framing, UdpSource};
+//!
+//! let mut source = UdpSource::bind("0.0.0.0:5000", Config::default(), Framing::Datagram).unwrap();
+//! while let Some(frame) = source.next() {
+//!     println!("{:?} {:?}", frame.unwrap().states, source.counters());
+//! }
+//! ```
+//!
+//! Datagrams may be lost or reordered in transit, which is detected using the virtual
+//! channel frame count in the primary header of each corrected transfer frame and
+//! reported in the [Counters].
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind, Result};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::pipeline::{self, Config, Frame};
+use crate::sync;
+
+/// Maximum UDP payload length.
+const MAX_DATAGRAM: usize = 65_507;
+
+/// Modulus of the 24 bit virtual channel frame count.
+const COUNT_MOD: u32 = 1 << 24;
+
+/// How CADUs are packed into datagrams.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Framing {
+    /// Each datagram is exactly one CADU.
+    Datagram,
+    /// Each datagram is a sequence of CADUs, each preceded by its length as a 4 byte
+    /// big-endian integer.
+    LengthPrefixed,
+}
+
+/// Counts of the datagrams and CADUs received by a [UdpSource].
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Counters {
+    /// Datagrams received.
+    pub datagrams: u64,
+    /// CADUs decoded.
+    pub cadus: u64,
+    /// CADUs, or datagrams for [Framing::LengthPrefixed], that were discarded because
+    /// they were not the expected length.
+    pub invalid: u64,
+    /// Frames missing according to gaps in the virtual channel frame counts.
+    pub dropped: u64,
+    /// Frames received with a virtual channel frame count earlier than one already
+    /// received.
+    pub reordered: u64,
+}
+
+/// Receives CADUs from a UDP socket and yields the decoded [Frame]s.
+///
+/// Iteration blocks until a datagram is received, so use
+/// [UdpSocket::set_read_timeout] on the socket passed to [UdpSource::new] to bound
+/// the wait, in which case the timeout is produced as an error.
+pub struct UdpSource {
+    socket: UdpSocket,
+    config: Config,
+    framing: Framing,
+    buf: Vec<u8>,
+    pending: VecDeque<Frame>,
+    counters: Counters,
+    /// Last frame count for each virtual channel, keyed by the first two header
+    /// bytes less the version number.
+    last_count: HashMap<u16, u32>,
+    offset: u64,
+}
+
+impl UdpSource {
+    pub fn new(socket: UdpSocket, config: Config, framing: Framing) -> Self {
+        UdpSource {
+            socket,
+            config,
+            framing,
+            buf: vec![0u8; MAX_DATAGRAM],
+            pending: VecDeque::new(),
+            counters: Counters::default(),
+            last_count: HashMap::new(),
+            offset: 0,
+        }
+    }
+
+    /// Create a source receiving on a socket bound to `addr`.
+    pub fn bind<A: ToSocketAddrs>(addr: A, config: Config, framing: Framing) -> Result<Self> {
+        Ok(Self::new(UdpSocket::bind(addr)?, config, framing))
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    /// Decode a CADU, with or without its ASM, and queue the frame.
+    fn push_cadu(&mut self, cadu: &[u8]) {
+        let block_len = self.config.block_len();
+        let data = if cadu.len() == self.config.asm.len() + block_len {
+            &cadu[self.config.asm.len()..]
+        } else if cadu.len() == block_len {
+            cadu
+        } else {
+            self.counters.invalid += 1;
+            return;
+        };
+        let frame = pipeline::decode(
+            &self.config,
+            sync::Cadu {
+                offset: self.offset,
+                data: data.to_vec(),
+                flywheel: false,
+                inverted: false,
+            },
+        );
+        self.offset += cadu.len() as u64;
+        self.counters.cadus += 1;
+        if let Some(data) = &frame.data {
+            self.check_count(data);
+        }
+        self.pending.push_back(frame);
+    }
+
+    fn check_count(&mut self, frame: &[u8]) {
+        if frame.len() < 5 {
+            return;
+        }
+        let key = u16::from_be_bytes([frame[0] & 0x3f, frame[1]]);
+        let count = u32::from_be_bytes([0, frame[2], frame[3], frame[4]]);
+        let Some(last) = self.last_count.get(&key).copied() else {
+            self.last_count.insert(key, count);
+            return;
+        };
+        let gap = count.wrapping_sub(last).wrapping_sub(1) % COUNT_MOD;
+        if gap < COUNT_MOD / 2 {
+            self.counters.dropped += gap as u64;
+            self.last_count.insert(key, count);
+        } else {
+            self.counters.reordered += 1;
+        }
+    }
+
+    fn push_datagram(&mut self, len: usize) {
+        self.counters.datagrams += 1;
+        let datagram = std::mem::take(&mut self.buf);
+        match self.framing {
+            Framing::Datagram => self.push_cadu(&datagram[..len]),
+            Framing::LengthPrefixed => {
+                let mut rest = &datagram[..len];
+                while !rest.is_empty() {
+                    let Some((prefix, tail)) = rest.split_first_chunk::<4>() else {
+                        self.counters.invalid += 1;
+                        break;
+                    };
+                    let cadu_len = u32::from_be_bytes(*prefix) as usize;
+                    if cadu_len > tail.len() {
+                        self.counters.invalid += 1;
+                        break;
+                    }
+                    self.push_cadu(&tail[..cadu_len]);
+                    rest = &tail[cadu_len..];
+                }
+            }
+        }
+        self.buf = datagram;
+    }
+}
+
+impl Iterator for UdpSource {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(frame) = self.pending.pop_front() {
+                return Some(Ok(frame));
+            }
+            match self.socket.recv(&mut self.buf) {
+                Ok(len) => self.push_datagram(len),
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen;
+    use crate::RSState;
+
+    fn cadu(config: &Config, count: u32) -> Vec<u8> {
+        let mut frame = vec![0u8; config.frame_len()];
+        frame[0] = 0x40;
+        frame[1] = 0x10;
+        frame[2..5].copy_from_slice(&count.to_be_bytes()[1..]);
+        gen::cadu(config, &frame).unwrap()
+    }
+
+    #[test]
+    fn test_udp_source() {
+        let config = Config {
+            interleave: 1,
+            ..Default::default()
+        };
+        let mut source = UdpSource::bind("127.0.0.1:0", config.clone(), Framing::Datagram).unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.connect(source.local_addr().unwrap()).unwrap();
+
+        let mut corrupt = cadu(&config, 1);
+        corrupt[100] ^= 0xff;
+        for datagram in [
+            cadu(&config, 0),
+            corrupt,
+            cadu(&config, 4),
+            vec![0u8; 10],
+            cadu(&config, 3),
+            cadu(&config, 5)[4..].to_vec(),
+        ] {
+            sender.send(&datagram).unwrap();
+        }
+
+        let frames = (0..5)
+            .map(|_| source.next().unwrap().unwrap())
+            .collect::<Vec<Frame>>();
+
+        assert_eq!(frames[1].states, vec![RSState::Corrected(1)]);
+        assert_eq!(frames[4].offset, 4 * 259);
+        assert_eq!(frames[4].data.as_ref().unwrap()[2..5], [0, 0, 5]);
+        assert_eq!(
+            source.counters(),
+            &Counters {
+                datagrams: 6,
+                cadus: 5,
+                invalid: 1,
+                dropped: 2,
+                reordered: 1,
+            }
+        );
+
+        // the frame count wraps, here on another virtual channel
+        let mut header = [0x40, 0x11, 0xff, 0xff, 0xff];
+        source.check_count(&header);
+        header[2..].fill(0);
+        source.check_count(&header);
+        assert_eq!(source.counters().dropped, 2);
+        assert_eq!(source.counters().reordered, 1);
+    }
+
+    #[test]
+    fn test_udp_length_prefixed() {
+        let config = Config {
+            interleave: 1,
+            ..Default::default()
+        };
+        let mut source =
+            UdpSource::bind("127.0.0.1:0", config.clone(), Framing::LengthPrefixed).unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.connect(source.local_addr().unwrap()).unwrap();
+
+        let mut datagram = Vec::new();
+        for count in 0..3 {
+            datagram.extend(259u32.to_be_bytes());
+            datagram.extend(cadu(&config, count));
+        }
+        datagram.extend(300u32.to_be_bytes());
+        sender.send(&datagram).unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(source.next().unwrap().unwrap().states, vec![RSState::Ok]);
+        }
+        assert_eq!(
+            source.counters(),
+            &Counters {
+                datagrams: 1,
+                cadus: 3,
+                invalid: 1,
+                dropped: 0,
+                reordered: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! WebAssembly bindings using wasm-bindgen. Requires the `wasm` feature.
+//!
+//! Build the module as a cdylib and generate the JavaScript glue with `wasm-bindgen`,
+//! e.g.,
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/rs2.wasm
+//! ```
+//!
+//! Inputs are `Uint8Array`s and results are returned as objects with read-only
+//! properties.
+//!
+//! ```js
+//! import init, { correct_message } from "./pkg/rs2.js";
+//!
+//! await init();
+//! const block = correct_message(data);
+//! console.log(block.state, block.num_corrected, block.message);
+//! ```
+use wasm_bindgen::prelude::*;
+
+use crate::{Code, RSState};
+
+fn state_name(state: &RSState) -> String {
+    match state {
+        RSState::Ok => "ok",
+        RSState::Corrected(_) => "corrected",
+        RSState::Uncorrectable(_) => "uncorrectable",
+        RSState::NotPerformed => "not_performed",
+    }
+    .to_string()
+}
+
+fn num_corrected(state: &RSState) -> i32 {
+    match state {
+        RSState::Corrected(n) => *n,
+        _ => 0,
+    }
+}
+
+fn reason(state: &RSState) -> Option<String> {
+    match state {
+        RSState::Uncorrectable(reason) => Some(reason.to_string()),
+        _ => None,
+    }
+}
+
+/// Result of correcting a single message.
+#[wasm_bindgen]
+pub struct Block {
+    block: crate::Block,
+}
+
+#[wasm_bindgen]
+impl Block {
+    /// One of "ok", "corrected", "uncorrectable" or "not_performed".
+    #[wasm_bindgen(getter)]
+    pub fn state(&self) -> String {
+        state_name(&self.block.state)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn num_corrected(&self) -> i32 {
+        num_corrected(&self.block.state)
+    }
+
+    /// Reason the message was uncorrectable, if it was.
+    #[wasm_bindgen(getter)]
+    pub fn reason(&self) -> Option<String> {
+        reason(&self.block.state)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> Option<Vec<u8>> {
+        self.block.message.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn error_positions(&self) -> Vec<u32> {
+        self.block
+            .error_positions
+            .iter()
+            .map(|p| *p as u32)
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn error_values(&self) -> Vec<u8> {
+        self.block.error_values.clone()
+    }
+
+    /// Number of bits corrected.
+    #[wasm_bindgen(getter)]
+    pub fn corrected_bits(&self) -> u32 {
+        self.block.corrected_bits()
+    }
+}
+
+/// Result of correcting an interleaved codeblock.
+#[wasm_bindgen]
+pub struct Codeblock {
+    codeblock: crate::Codeblock,
+}
+
+#[wasm_bindgen]
+impl Codeblock {
+    /// State of each interleaved message.
+    #[wasm_bindgen(getter)]
+    pub fn states(&self) -> Vec<String> {
+        self.codeblock.states.iter().map(state_name).collect()
+    }
+
+    /// Number of symbols corrected in each interleaved message.
+    #[wasm_bindgen(getter)]
+    pub fn num_corrected(&self) -> Vec<i32> {
+        self.codeblock.states.iter().map(num_corrected).collect()
+    }
+
+    /// The corrected codeblock, or undefined if any message was uncorrectable.
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> Option<Vec<u8>> {
+        self.codeblock.message.clone()
+    }
+}
+
+fn parse_code(e8: bool) -> Code {
+    if e8 {
+        Code::E8
+    } else {
+        Code::E16
+    }
+}
+
+/// Correct a 255 byte dual basis message, using RS(255,239) if `e8` is true and
+/// RS(255,223) otherwise.
+#[wasm_bindgen]
+pub fn correct_message(input: &[u8], e8: Option<bool>) -> Block {
+    Block {
+        block: parse_code(e8.unwrap_or_default()).correct_message(input),
+    }
+}
+
+/// Correct a codeblock of `interleave` messages; see [correct_message].
+#[wasm_bindgen]
+pub fn correct_codeblock(input: &[u8], interleave: u8, e8: Option<bool>) -> Codeblock {
+    Codeblock {
+        codeblock: parse_code(e8.unwrap_or_default()).correct_codeblock(input, interleave),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{interleave, FIXTURE_MSG};
+
+    #[test]
+    fn test_wasm_bindings() {
+        let mut msg = *FIXTURE_MSG;
+        msg[77] ^= 0x01;
+
+        let block = correct_message(&msg, None);
+        assert_eq!(block.state(), "corrected");
+        assert_eq!(block.num_corrected(), 1);
+        assert_eq!(block.error_positions(), vec![77]);
+        assert_eq!(block.message().unwrap(), FIXTURE_MSG.to_vec());
+
+        let block = correct_message(&msg[..10], Some(false));
+        assert_eq!(block.state(), "uncorrectable");
+        assert!(block.reason().is_some());
+
+        let codeblock = correct_codeblock(&interleave(&[FIXTURE_MSG, &msg]), 2, None);
+        assert_eq!(codeblock.states(), vec!["ok", "corrected"]);
+        assert_eq!(codeblock.num_corrected(), vec![0, 1]);
+    }
+}